@@ -0,0 +1,120 @@
+//! Simple milestones that are unlocked as the user studies.
+
+use crate::db::Database;
+use crate::locale::*;
+
+/// An achievement that can be unlocked
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Achievement {
+    /// The first 100 reviews were done
+    HundredReviews,
+
+    /// A word was reviewed on 7 consecutive days
+    WeekStreak,
+
+    /// Every word of a deck is on the longest timeout
+    DeckMastered,
+}
+
+impl Achievement {
+    /// All the achievements there are
+    pub const ALL: [Achievement; 3] = [
+        Achievement::HundredReviews,
+        Achievement::WeekStreak,
+        Achievement::DeckMastered,
+    ];
+
+    /// Returns the name under which the achievement is stored
+    pub fn name(&self) -> &'static str {
+        match self {
+            Achievement::HundredReviews => "hundred_reviews",
+            Achievement::WeekStreak     => "week_streak",
+            Achievement::DeckMastered   => "deck_mastered",
+        }
+    }
+
+    /// Returns the achievement stored under `name`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|achievement| achievement.name() == name)
+    }
+
+    /// Returns the text shown to the user when the achievement is unlocked
+    pub fn description(&self) -> &'static str {
+        match self {
+            Achievement::HundredReviews => tr(ACHIEVEMENT_HUNDRED_REVIEWS),
+            Achievement::WeekStreak     => tr(ACHIEVEMENT_WEEK_STREAK),
+            Achievement::DeckMastered   => tr(ACHIEVEMENT_DECK_MASTERED),
+        }
+    }
+
+    /// Returns whether the achievement has been earned in `db`
+    pub fn is_earned(&self, db: &Database) -> bool {
+        match self {
+            Achievement::HundredReviews => db.meta.reviews >= 100,
+            Achievement::WeekStreak     => db.meta.streak >= 7,
+            // The words that don't belong to any deck are a deck of their
+            // own
+            Achievement::DeckMastered   => db.decks().iter()
+                .map(String::as_str)
+                .chain(std::iter::once(""))
+                .any(|deck| db.is_mastered(deck)),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn crossing_a_milestone_unlocks_it_exactly_once() {
+        let dir    = TempDir::new("achievements");
+        let path   = dir.write("words.txt", b"Hund;; dog;; 0;; 0\n");
+        let mut db = Database::open(path).unwrap();
+
+        db.meta.reviews = 99;
+        assert_eq!(db.check_achievements(), Vec::new());
+
+        db.meta.reviews = 100;
+        assert_eq!(db.check_achievements(), vec![Achievement::HundredReviews]);
+        assert_eq!(db.check_achievements(), Vec::new());
+
+        db.meta.reviews = 150;
+        db.meta.streak  = 7;
+        assert_eq!(db.check_achievements(), vec![Achievement::WeekStreak]);
+        assert_eq!(db.check_achievements(), Vec::new());
+    }
+
+    #[test]
+    fn mastering_any_deck_is_mastering_a_deck() {
+        let dir    = TempDir::new("deck-mastered");
+        let path   = dir.write("words.txt",
+                               b"Hund;; dog;; 99;; 1;; deck=German\n\
+                                 pes;; dog;; 0;; 0;; deck=Czech\n");
+        let mut db = Database::open(path).unwrap();
+        assert!(db.is_mastered("German"));
+        assert!(!db.is_mastered("Czech"));
+        assert!(!db.is_mastered(""));
+        assert_eq!(db.check_achievements(), vec![Achievement::DeckMastered]);
+    }
+
+    #[test]
+    fn the_words_of_no_deck_are_a_deck_of_their_own() {
+        let dir  = TempDir::new("no-deck-mastered");
+        let path = dir.write("words.txt", b"Hund;; dog;; 99;; 1\n\
+                                            pes;; dog;; 0;; 0;; deck=Czech\n");
+        let db   = Database::open(path).unwrap();
+        assert!(Achievement::DeckMastered.is_earned(&db));
+    }
+
+    #[test]
+    fn the_names_of_the_achievements_round_trip() {
+        for &achievement in Achievement::ALL.iter() {
+            assert_eq!(Achievement::from_name(achievement.name()),
+                       Some(achievement));
+        }
+        assert_eq!(Achievement::from_name("unknown"), None);
+    }
+}
@@ -0,0 +1,73 @@
+//! Debouncing of the writes of the database.
+//! Writing the database after every single answer is wasteful, so the changes
+//! are only counted and written in batches; once enough of them piled up or
+//! enough time passed since the last write. Whatever is left is written when
+//! the database is closed.
+
+/// The longest time (in seconds) between two writes of pending changes
+pub const FLUSH_INTERVAL: u64 = 30;
+
+/// The number of changes that are written at once
+pub const FLUSH_BATCH: usize = 10;
+
+
+/// Decides when the pending changes of the database should be written
+#[derive(Clone, Debug)]
+pub struct AutoSave {
+    /// The longest time (in seconds) between two writes
+    pub interval: u64,
+
+    /// The number of changes that are written at once
+    pub batch: usize,
+
+    /// The number of changes since the last write
+    pending: usize,
+
+    /// When (in seconds since the epoch) the changes were last written
+    last_flush: u64,
+}
+
+impl Default for AutoSave {
+    fn default() -> Self {
+        Self::new(FLUSH_INTERVAL, FLUSH_BATCH)
+    }
+}
+
+impl AutoSave {
+    /// Creates a new debouncer with a given interval (in seconds) and batch
+    pub fn new(interval: u64, batch: usize) -> Self {
+        Self {
+            interval,
+            batch,
+            pending:    0,
+            last_flush: 0,
+        }
+    }
+
+    /// Counts a change made at `now` (in seconds since the epoch).
+    /// Returns whether the changes should be written now.
+    pub fn record(&mut self, now: u64) -> bool {
+        self.pending += 1;
+        self.is_due(now)
+    }
+
+    /// Returns whether there are pending changes that should be written at
+    /// `now` (in seconds since the epoch)
+    pub fn is_due(&self, now: u64) -> bool {
+        self.pending > 0
+            && (self.pending >= self.batch
+                || now.saturating_sub(self.last_flush) >= self.interval)
+    }
+
+    /// Returns whether there are changes that haven't been written yet
+    pub fn is_pending(&self) -> bool {
+        self.pending > 0
+    }
+
+    /// Forgets the pending changes; they were written at `now` (in seconds
+    /// since the epoch)
+    pub fn flushed(&mut self, now: u64) {
+        self.pending    = 0;
+        self.last_flush = now;
+    }
+}
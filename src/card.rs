@@ -0,0 +1,143 @@
+//! The types of the cards the entries are reviewed as.
+//! A basic card shows the word and hides its translation. A cloze card is a
+//! sentence with some of its parts in double braces (e.g.
+//! `Ich {{gehe}} nach Hause`); the sentence is shown with the parts blanked
+//! out and the parts are what's hidden.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Opens a part of a cloze sentence that is blanked out
+const GAP_START: &str = "{{";
+
+/// Closes a part of a cloze sentence that is blanked out
+const GAP_END: &str = "}}";
+
+/// What the blanked out parts of a cloze sentence are shown as
+pub const BLANK: &str = "[...]";
+
+
+/// How an entry is shown when it's reviewed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CardType {
+    /// The word is shown, the translation hidden
+    #[default]
+    Basic,
+
+    /// The word is a sentence with some of its parts hidden
+    Cloze,
+}
+
+impl CardType {
+    /// Returns the card type fitting for `word`; a cloze card if it has any
+    /// blanked out parts
+    pub fn detect(word: &str) -> Self {
+        match Cloze::parse(word) {
+            Some(_) => CardType::Cloze,
+            None    => CardType::Basic,
+        }
+    }
+}
+
+impl fmt::Display for CardType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CardType::Basic => write!(f, "basic"),
+            CardType::Cloze => write!(f, "cloze"),
+        }
+    }
+}
+
+impl FromStr for CardType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "basic" => Ok(CardType::Basic),
+            "cloze" => Ok(CardType::Cloze),
+            _       => Err(()),
+        }
+    }
+}
+
+
+/// A part of a cloze sentence
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    /// A part that is always shown
+    Text(String),
+
+    /// A part that is blanked out until it's revealed
+    Gap(String),
+}
+
+
+/// A sentence with some of its parts blanked out
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cloze {
+    parts: Vec<Part>,
+}
+
+impl Cloze {
+    /// Parses a sentence with its hidden parts in double braces.
+    /// Returns `None` if there are no hidden parts, a brace isn't closed or a
+    /// hidden part is blank.
+    pub fn parse(sentence: &str) -> Option<Self> {
+        let mut parts = Vec::new();
+        let mut rest  = sentence;
+
+        while let Some(start) = rest.find(GAP_START) {
+            let after = &rest[start + GAP_START.len()..];
+            let end   = after.find(GAP_END)?;
+            let gap   = after[..end].trim();
+            if gap.is_empty() {
+                return None;
+            }
+
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            parts.push(Part::Gap(gap.to_string()));
+            rest = &after[end + GAP_END.len()..];
+        }
+
+        if rest.contains(GAP_END) || parts.is_empty() {
+            return None;
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+        Some(Self { parts })
+    }
+
+    /// Returns the sentence with the hidden parts replaced by `BLANK`
+    pub fn blanked(&self) -> String {
+        self.parts.iter()
+            .map(|part| match part {
+                Part::Text(text) => text.as_str(),
+                Part::Gap(_)     => BLANK,
+            })
+            .collect()
+    }
+
+    /// Returns the whole sentence, without the braces
+    pub fn revealed(&self) -> String {
+        self.parts.iter()
+            .map(|part| match part {
+                Part::Text(text) | Part::Gap(text) => text.as_str(),
+            })
+            .collect()
+    }
+
+    /// Returns the hidden parts, joined by spaces; what the user is expected
+    /// to type
+    pub fn hidden(&self) -> String {
+        let gaps: Vec<&str> = self.parts.iter()
+            .filter_map(|part| match part {
+                Part::Gap(gap) => Some(gap.as_str()),
+                _              => None,
+            })
+            .collect();
+        gaps.join(" ")
+    }
+}
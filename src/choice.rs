@@ -0,0 +1,133 @@
+//! Multiple-choice questions; the word is shown with `CHOICE_COUNT`
+//! candidate answers, one of which is right, and the user picks one.
+//!
+//! The wrong candidates (the distractors) are the answers of other entries,
+//! drawn at random, preferably from the deck of the word so that they're in
+//! the same language. A candidate that's (nearly) the same as the answer or
+//! as another candidate would make the question unfair or give it away, so
+//! the candidates differing by `NEAR_DUPLICATE_TYPOS` or fewer typos (after
+//! `grading::fold`) are skipped:
+//!
+//! ```text
+//! Hund -> dog, hound
+//!
+//! 1: cat         <- another entry of the deck
+//! 2: dog, hound  <- the answer
+//! 3: horse
+//! 4: bird        <- "dogs" or "Hound" would be skipped
+//! ```
+
+use crate::db::{Direction, Entry};
+use crate::diff::diff;
+use crate::grading::fold;
+use crate::queue::shuffle;
+use crate::rng::Rng;
+
+/// The number of candidate answers of a question, the right one included
+pub const CHOICE_COUNT: usize = 4;
+
+/// The candidates differing from each other by this many typos or fewer are
+/// too similar to be told apart
+const NEAR_DUPLICATE_TYPOS: usize = 1;
+
+
+/// A multiple-choice question of an entry
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Question {
+    /// The candidate answers, as they're shown
+    pub choices: Vec<String>,
+
+    /// The index of the right answer among `choices`
+    pub answer: usize,
+}
+
+impl Question {
+    /// Returns whether the `choice`th candidate is the right answer
+    pub fn is_correct(&self, choice: usize) -> bool {
+        choice == self.answer
+    }
+}
+
+
+/// Returns the answers of `entry` shown in `direction`, each on its own; its
+/// translations, or its original word in reverse
+fn answers(entry: &Entry, direction: Direction) -> Vec<String> {
+    match direction {
+        Direction::Forward => entry.tr_word.clone(),
+        Direction::Reverse => vec![entry.word.clone()],
+    }
+}
+
+/// Returns the answers of `entry` shown in `direction` as a candidate
+fn choice(entry: &Entry, direction: Direction) -> String {
+    match direction {
+        Direction::Forward => entry.translations(),
+        Direction::Reverse => entry.word.clone(),
+    }
+}
+
+/// Returns whether any of the (folded) `answers` is nearly the same as any
+/// of the `others`
+fn is_near_duplicate(answers: &[String], others: &[String]) -> bool {
+    answers.iter().any(|answer| {
+        others.iter()
+            .any(|other| diff(answer, other).len() <= NEAR_DUPLICATE_TYPOS)
+    })
+}
+
+/// Returns the folded `answers`, without the empty ones
+fn folded(answers: Vec<String>) -> Vec<String> {
+    answers.iter()
+        .map(|answer| fold(answer))
+        .filter(|answer| !answer.is_empty())
+        .collect()
+}
+
+/// Deals a question of `entry` shown in `direction`, with the distractors
+/// drawn by `rng` from the `entries` (the entry itself may be among them).
+/// Returns `None` if `entry` is a cloze card (its answer isn't a word of
+/// its own) or if there aren't enough distractors different enough.
+pub fn deal<'a, I>(entry: &Entry, direction: Direction, entries: I,
+                   rng: &mut Rng) -> Option<Question>
+        where I: IntoIterator<Item = &'a Entry> {
+    if entry.cloze().is_some() {
+        return None;
+    }
+    let mut taken = vec![folded(answers(entry, direction))];
+    if taken[0].is_empty() {
+        return None;
+    }
+
+    // The sort is stable, so the entries of the same deck come first in
+    // a random order
+    let mut candidates: Vec<&Entry> = entries.into_iter()
+        .filter(|other| other.cloze().is_none())
+        .collect();
+    shuffle(&mut candidates, rng);
+    candidates.sort_by_key(|other| other.deck != entry.deck);
+
+    let mut choices = vec![choice(entry, direction)];
+    for other in candidates {
+        if choices.len() == CHOICE_COUNT {
+            break;
+        }
+        let answers = folded(answers(other, direction));
+        if answers.is_empty() || taken.iter()
+                .any(|taken| is_near_duplicate(&answers, taken)) {
+            continue;
+        }
+        choices.push(choice(other, direction));
+        taken.push(answers);
+    }
+    if choices.len() < CHOICE_COUNT {
+        return None;
+    }
+
+    // The right answer is the first one before the shuffle
+    let mut order: Vec<usize> = (0..CHOICE_COUNT).collect();
+    shuffle(&mut order, rng);
+    Some(Question {
+        answer:  order.iter().position(|&idx| idx == 0)?,
+        choices: order.iter().map(|&idx| choices[idx].clone()).collect(),
+    })
+}
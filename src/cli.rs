@@ -0,0 +1,528 @@
+//! Reviewing in the terminal, for when there's no display (e.g. over ssh).
+//! The words are served and scheduled by the same `Database` and `Session`
+//! as in the ui, so both can be used to review the same database.
+//!
+//! There are also a few commands for maintaining a database from the shell
+//! (see `Command`). Their lists print a line per word with tab separated
+//! columns, so that they can be piped into `cut`, `sort` and the like:
+//!
+//! ```text
+//! word    translation|translation    deck    tag, tag    2024-05-01
+//! ```
+//!
+//! The last column is the date the word is due on, or `-` if it's new.
+//!
+//! The arguments of the program tell whether it runs in the terminal at all
+//! (see `Invocation`); the window is left to the frontend.
+
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+use crate::config::Config;
+use crate::db::{Database, Direction, Entry, Quarantined, DAY, TR_DELIMITER,
+                join_tags, now};
+use crate::db::backup::BACKUP_COUNT;
+use crate::error::{Error, Result};
+use crate::export::history::{self, HistoryFormat};
+use crate::locale::*;
+use crate::paths;
+use crate::session::{self, Session, Summary};
+use crate::stats::{self, Stats};
+
+/// The number of days of the history the statistics are computed for
+const STATS_DAYS: u64 = 14;
+
+/// The number of quarantined lines listed when a database is opened; the
+/// rest are only counted
+const SKIPPED_LINES_SHOWN: usize = 10;
+
+
+/// A command maintaining a database from the shell
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Adds a word with its translations (separated by `TR_DELIMITER`)
+    Add {
+        word:    String,
+        tr_word: String,
+    },
+
+    /// Lists all the words
+    List,
+
+    /// Lists the words that are due
+    Due,
+
+    /// Prints the statistics
+    Stats,
+}
+
+impl Command {
+    /// Parses the command `name` with its `args` (the ones after the
+    /// database), or returns `None` if there's no such command or the
+    /// arguments don't fit it
+    pub fn parse(name: &str, args: &[&str]) -> Option<Self> {
+        match (name, args) {
+            ("add", [word, tr_word]) => Some(Command::Add {
+                word:    word.to_string(),
+                tr_word: tr_word.to_string(),
+            }),
+            ("list",  []) => Some(Command::List),
+            ("due",   []) => Some(Command::Due),
+            ("stats", []) => Some(Command::Stats),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the command only reads the database, so that it can
+    /// run while the database is open somewhere else
+    pub fn is_read_only(&self) -> bool {
+        !matches!(self, Command::Add { .. })
+    }
+}
+
+
+/// What the program is asked to do by its arguments
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Invocation {
+    /// Opens the window; with the database to open read-only, if any
+    /// (`--read-only <database>`)
+    Window(Option<PathBuf>),
+
+    /// Reviews the database in the terminal (`--cli <database>`)
+    Review(PathBuf),
+
+    /// Exports the history of the database into the file and quits
+    /// (`--export-stats <database> <file>`)
+    ExportStats(PathBuf, PathBuf),
+
+    /// Runs the command on the database (`<command> <database> [args]`)
+    Command(PathBuf, Command),
+}
+
+impl Invocation {
+    /// Parses the arguments of the program (without its name), or returns
+    /// `None` if they don't make sense
+    pub fn parse(args: &[&str]) -> Option<Self> {
+        match args {
+            [] => Some(Invocation::Window(None)),
+            ["--cli", path] => Some(Invocation::Review(path.into())),
+            ["--read-only", path] => {
+                Some(Invocation::Window(Some(path.into())))
+            },
+            ["--export-stats", path, output] => {
+                Some(Invocation::ExportStats(path.into(), output.into()))
+            },
+            [name, path, rest @ ..] => Command::parse(name, rest)
+                .map(|command| Invocation::Command(path.into(), command)),
+            _ => None,
+        }
+    }
+
+    /// Runs the invocation in the terminal and returns the exit code.
+    /// The errors are printed instead of shown in dialogs. The window is up
+    /// to the frontend; `Window` does nothing.
+    /// Note that on Windows, the release builds aren't attached to any
+    /// console.
+    pub fn run(self) -> i32 {
+        match self {
+            Invocation::Window(_)                 => 0,
+            Invocation::Review(path)              => review_in_terminal(path),
+            Invocation::ExportStats(path, output) => export_stats(path, output),
+            Invocation::Command(path, command)    => {
+                run_command(path, &command)
+            },
+        }
+    }
+}
+
+/// Prints how the program is run.
+/// Returns the exit code of a program run the wrong way.
+pub fn usage() -> i32 {
+    eprintln!("{}", tr(CLI_USAGE));
+    2
+}
+
+
+/// Reviews the words of `db` in `session`, reading the answers from `input`
+/// and printing everything to `output`.
+/// Every word is printed first; Enter reveals the translation and `y` or `n`
+/// grades it. New words that are only being presented in a batch are shown
+/// with their translation and aren't graded.
+/// The review ends with a summary once the session is over, after `q` or at
+/// the end of the input. The database is written after every answer.
+pub fn review<R, W>(db: &mut Database, session: &mut Session, mut input: R,
+                    mut output: W) -> Result<()>
+        where R: BufRead, W: Write {
+    while let Some((entry, index)) = session.next_entry(db, now()) {
+        // In reverse, the translations are shown first.
+        // A cloze sentence is shown with the blanks and revealed whole.
+        let direction = db.card(&entry).direction;
+        let (word, tr_word) = match (entry.cloze(), direction) {
+            (Some(cloze), _) => {
+                (cloze.blanked(),
+                 format!("{}\n{}", cloze.revealed(), entry.translations()))
+            },
+            (None, Direction::Forward) => {
+                (entry.word.clone(), entry.translations())
+            },
+            (None, Direction::Reverse) => {
+                (entry.translations(), entry.word.clone())
+            },
+        };
+        writeln!(output, "\n{}", word)?;
+        let shown = Instant::now();
+
+        if db.is_learning() {
+            writeln!(output, "{}", tr_word)?;
+            if read_line(&mut input, &mut output, tr(CLI_CONTINUE_PROMPT))?
+                    .is_none() {
+                break;
+            }
+            continue;
+        }
+
+        match read_line(&mut input, &mut output, tr(CLI_REVEAL_PROMPT))? {
+            Some(answer) if answer != "q" => {},
+            _ => break,
+        }
+        writeln!(output, "{}", tr_word)?;
+        if !entry.notes.is_empty() {
+            writeln!(output, "{}", entry.notes)?;
+        }
+
+        let correct = match read_grade(&mut input, &mut output)? {
+            Some(correct) => correct,
+            None          => break,
+        };
+
+        let response = shown.elapsed().as_millis() as u64;
+        let answered = session.answer(db, index, correct.into(),
+                                      Some(response));
+        if answered.leech {
+            writeln!(output, "{}", tr(LEECH_SUSPENDED_MESSAGE))?;
+        }
+        for achievement in answered.achievements {
+            writeln!(output, "{}", achievement.description())?;
+        }
+        db.write_db()?;
+    }
+
+    print_summary(&mut output, &session.summary(now()))?;
+    Ok(())
+}
+
+/// Prints `prompt` and reads a line of `input`; trimmed and in lowercase.
+/// Returns `None` at the end of the input.
+fn read_line<R, W>(input: &mut R, output: &mut W, prompt: &str)
+        -> io::Result<Option<String>>
+        where R: BufRead, W: Write {
+    write!(output, "{} ", prompt)?;
+    output.flush()?;
+
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_lowercase()))
+}
+
+/// Asks whether the user knew the word until they answer `y` or `n`.
+/// Returns `None` if they'd rather quit (`q`) or the input ended.
+fn read_grade<R, W>(input: &mut R, output: &mut W) -> io::Result<Option<bool>>
+        where R: BufRead, W: Write {
+    loop {
+        match read_line(input, output, tr(CLI_GRADE_PROMPT))?.as_deref() {
+            Some("y")        => return Ok(Some(true)),
+            Some("n")        => return Ok(Some(false)),
+            Some("q") | None => return Ok(None),
+            Some(_)          => continue,
+        }
+    }
+}
+
+/// Prints the summary of a finished session
+fn print_summary<W: Write>(output: &mut W, summary: &Summary)
+        -> io::Result<()> {
+    let accuracy = match summary.accuracy {
+        Some(accuracy) => format!("{:.0} %", accuracy * 100.),
+        None           => "-".to_string(),
+    };
+    let duration = format!("{} min {} s",
+                           summary.duration / 60, summary.duration % 60);
+
+    writeln!(output, "\n{}", tr(SUMMARY_TITLE))?;
+    writeln!(output, "{}: {}", tr(SUMMARY_REVIEWED), summary.reviewed)?;
+    writeln!(output, "{}: {}", tr(SUMMARY_ACCURACY), accuracy)?;
+    writeln!(output, "{}: {}", tr(SUMMARY_DURATION), duration)
+}
+
+/// Runs `command` on `db`, printing its output to `output`.
+/// Returns false if a word couldn't be added; it's blank, it has no
+/// translation or it's in the database already.
+/// The changes are persisted by the next `write_db`.
+pub fn run<W: Write>(db: &mut Database, command: &Command, mut output: W)
+        -> Result<bool> {
+    let now = now();
+    match command {
+        Command::Add { word, tr_word } => {
+            return Ok(!db.contains_word(word)
+                      && db.add_entry(word, tr_word, "", ""));
+        },
+        Command::List => {
+            for entry in db.search("") {
+                print_entry(&mut output, entry)?;
+            }
+        },
+        Command::Due => {
+            let mut due: Vec<&Entry> = db.due_entries(now).collect();
+            due.sort_by_key(|entry| entry.timeout);
+            for entry in due {
+                print_entry(&mut output, entry)?;
+            }
+        },
+        Command::Stats => {
+            let history = db.history.load()?;
+            let stats   = Stats::compute(db, &history, now, STATS_DAYS);
+            print_stats(&mut output, &stats, db.due_count(now))?;
+        },
+    }
+    Ok(true)
+}
+
+/// Prints `entry` as a line of tab separated columns
+fn print_entry<W: Write>(output: &mut W, entry: &Entry) -> io::Result<()> {
+    let due = match entry.is_new() {
+        true  => "-".to_string(),
+        false => {
+            let (year, month, day) = stats::date(entry.timeout / DAY);
+            format!("{:04}-{:02}-{:02}", year, month, day)
+        },
+    };
+    writeln!(output, "{}\t{}\t{}\t{}\t{}",
+             entry.word, entry.tr_word.join(&TR_DELIMITER.to_string()),
+             entry.deck, join_tags(&entry.tags), due)
+}
+
+/// Prints the statistics with the number of the `due` reviews
+fn print_stats<W: Write>(output: &mut W, stats: &Stats, due: usize)
+        -> io::Result<()> {
+    let accuracy = match stats.accuracy() {
+        Some(accuracy) => format!("{:.0} %", accuracy * 100.),
+        None           => "-".to_string(),
+    };
+    let response = match stats.average_response() {
+        Some(response) => stats::seconds_label(response),
+        None           => "-".to_string(),
+    };
+
+    writeln!(output, "{}: {}", tr(CLI_DUE), due)?;
+    writeln!(output, "{}: {}", tr(STATS_NEW), stats.new)?;
+    writeln!(output, "{}: {}", tr(STATS_YOUNG), stats.young)?;
+    writeln!(output, "{}: {}", tr(STATS_MATURE), stats.mature)?;
+    writeln!(output, "{}: {}", tr(STATS_ACCURACY), accuracy)?;
+    writeln!(output, "{}: {}", tr(STATS_RESPONSE), response)
+}
+
+/// Reviews the database at `path` in the terminal (see `review`), with the
+/// same settings as the window.
+/// Returns the exit code.
+fn review_in_terminal(path: PathBuf) -> i32 {
+    let config = Config::load().unwrap_or_else(|err| {
+        eprintln!("{}\n\n({})", tr(FAILED_CONFIG_READ_MESSAGE), err);
+        Config::default()
+    });
+    set_language(config.language);
+
+    let mut db = match open_in_terminal(Database::open(path)) {
+        Some(db) => db,
+        None     => return 1,
+    };
+    if let Err(err) = db.backup(BACKUP_COUNT) {
+        eprintln!("{}\n\n({})", tr(FAILED_BACKUP_MESSAGE), err);
+    }
+
+    let mut session = session::prepare(&mut db, &config);
+    let stdin       = io::stdin();
+    let result      = review(&mut db, &mut session, stdin.lock(),
+                             io::stdout())
+        .and_then(|_| db.flush_and_close());
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("{}\n\n({})", tr(FAILED_DB_WRITE_MESSAGE), err);
+            1
+        },
+    }
+}
+
+/// Runs `command` on the database at `path`,
+/// printing its output to the standard output.
+/// The commands that only read the database open it read-only, so they may
+/// run while it's open in the window.
+/// Returns the exit code.
+fn run_command(path: PathBuf, command: &Command) -> i32 {
+    let config = Config::load().unwrap_or_default();
+    set_language(config.language);
+
+    let db = match command.is_read_only() {
+        true  => Database::open_read_only(path),
+        false => Database::open(path),
+    };
+    let mut db = match open_in_terminal(db) {
+        Some(db) => db,
+        None     => return 1,
+    };
+
+    match run(&mut db, command, io::stdout()) {
+        Ok(true) => {},
+        Ok(false) => {
+            eprintln!("{}", tr(CLI_ADD_FAILED_MESSAGE));
+            return 1;
+        },
+        // Whatever reads the output (e.g. `head`) has read enough
+        Err(Error::Io(err)) if err.kind() == io::ErrorKind::BrokenPipe => {},
+        Err(err) => {
+            eprintln!("{}\n\n({})", tr(FAILED_STATS_MESSAGE), err);
+            return 1;
+        },
+    }
+    if let Err(err) = db.flush_and_close() {
+        eprintln!("{}\n\n({})", tr(FAILED_DB_WRITE_MESSAGE), err);
+        return 1;
+    }
+    0
+}
+
+/// Prints an error if the database failed to open and the lines that were
+/// skipped if it did.
+/// Returns the database, or `None` if it failed to open.
+fn open_in_terminal(db: Result<Database>) -> Option<Database> {
+    let db = match db {
+        Ok(db) => db,
+        Err(Error::Locked(path)) => {
+            eprintln!("{}\n\n{}", tr(CLI_LOCKED_MESSAGE),
+                      paths::lock_path(&path).display());
+            return None;
+        },
+        Err(Error::UnknownFormat) => {
+            eprintln!("{}", tr(FAILED_DB_FORMAT_MESSAGE));
+            return None;
+        },
+        Err(err) => {
+            eprintln!("{}\n\n({})", tr(FAILED_DB_INIT_MESSAGE), err);
+            return None;
+        },
+    };
+    if !db.quarantined.is_empty() {
+        eprintln!("{}", quarantined_lines(&db.quarantined));
+    }
+    Some(db)
+}
+
+/// Exports the review history and the entries of the database at `path` to
+/// `output` (see `export::history`), as CSV or JSON by the
+/// extension, and prints the paths of the written files.
+/// The database is only read, so it may be open in the window meanwhile.
+/// Returns the exit code.
+fn export_stats(path: PathBuf, output: PathBuf) -> i32 {
+    let config = Config::load().unwrap_or_default();
+    set_language(config.language);
+
+    let db = match open_in_terminal(Database::open_read_only(path)) {
+        Some(db) => db,
+        None     => return 1,
+    };
+
+    let format = HistoryFormat::for_path(&output);
+    match history::export(&db, &output, format) {
+        Ok(written) => {
+            for path in written {
+                println!("{}", path.display());
+            }
+            0
+        },
+        Err(err) => {
+            eprintln!("{}\n\n({})", tr(FAILED_EXPORT_MESSAGE), err);
+            1
+        },
+    }
+}
+
+/// Returns the message listing the lines that were quarantined when a
+/// database was opened, so that the user can find and fix them; the
+/// window shows it as well
+pub fn quarantined_lines(quarantined: &[Quarantined]) -> String {
+    let mut message = tr(SKIPPED_LINES_MESSAGE).to_string();
+    message.push('\n');
+    for line in quarantined.iter().take(SKIPPED_LINES_SHOWN) {
+        message.push_str(&format!("\n{}", line));
+    }
+    if quarantined.len() > SKIPPED_LINES_SHOWN {
+        message.push_str(&format!("\n{} {}", tr(SKIPPED_LINES_MORE),
+                                  quarantined.len() - SKIPPED_LINES_SHOWN));
+    }
+    message
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseError;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn parses_the_invocations() {
+        let path = || PathBuf::from("words.txt");
+        assert_eq!(Invocation::parse(&[]), Some(Invocation::Window(None)));
+        assert_eq!(Invocation::parse(&["--read-only", "words.txt"]),
+                   Some(Invocation::Window(Some(path()))));
+        assert_eq!(Invocation::parse(&["--cli", "words.txt"]),
+                   Some(Invocation::Review(path())));
+        assert_eq!(Invocation::parse(&["--export-stats", "words.txt", "a.csv"]),
+                   Some(Invocation::ExportStats(path(), "a.csv".into())));
+        assert_eq!(Invocation::parse(&["add", "words.txt", "Hund", "dog"]),
+                   Some(Invocation::Command(path(), Command::Add {
+                       word:    "Hund".to_string(),
+                       tr_word: "dog".to_string(),
+                   })));
+        assert_eq!(Invocation::parse(&["add", "words.txt", "Hund"]), None);
+        assert_eq!(Invocation::parse(&["--cli"]), None);
+    }
+
+    #[test]
+    fn reviews_a_word_by_the_typed_answers() {
+        let dir         = TempDir::new("cli-review");
+        let path        = dir.write("words.txt", b"Hund;; dog;; 0;; 0\n");
+        let mut db      = Database::open(path.clone()).unwrap();
+        let mut session = session::prepare(&mut db, &Config::default());
+
+        let mut output = Vec::new();
+        review(&mut db, &mut session, &b"\ny\n"[..], &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Hund"));
+        assert!(output.contains("dog"));
+        assert_eq!(session.answers, vec![true]);
+        db.flush_and_close().unwrap();
+
+        let db = Database::open(path).unwrap();
+        assert_eq!(db.unusable[0].cur_iter, 1);
+    }
+
+    #[test]
+    fn lists_only_the_first_quarantined_lines() {
+        let quarantined: Vec<Quarantined> = (1..=SKIPPED_LINES_SHOWN + 5)
+            .map(|line| Quarantined {
+                line,
+                text:  "Hund".to_string(),
+                error: ParseError::ColumnCount(1),
+            })
+            .collect();
+        let message = quarantined_lines(&quarantined);
+        let listed  = message.lines()
+            .filter(|line| line.starts_with("line "))
+            .count();
+        assert_eq!(listed, SKIPPED_LINES_SHOWN);
+        assert!(message.ends_with(" 5"));
+    }
+}
@@ -0,0 +1,379 @@
+//! This module handles the application config; the preferences that aren't
+//! tied to any database. It is stored as TOML in the config directory of the
+//! platform (e.g. `~/.config/wordpal/config.toml` on Linux), or next to the
+//! program in portable mode (see `paths`).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::db::{Direction, write_atomic};
+use crate::locale::Language;
+use crate::normalize::Normalization;
+use crate::layout::{DisplayConfig, FontSizes};
+use crate::paths;
+use crate::scheduler::{TIMEOUT_DELAYS, DEFAULT_DAY_START_HOUR, Algorithm,
+                       GradeLabels, is_valid_ladder};
+use crate::fsrs::{DEFAULT_RETENTION, is_valid_retention};
+use crate::theme::Theme;
+
+/// The number of times a word can be forgotten before it's suspended as a
+/// leech, unless the config says otherwise
+const DEFAULT_LEECH_THRESHOLD: u32 = 8;
+
+/// The delays (in minutes) of the steps a forgotten word is relearned in
+/// within the session, unless the config says otherwise
+const DEFAULT_RELEARN_STEPS: [u64; 2] = [1, 10];
+
+/// The number of times a word is relearned in a session before it's
+/// scheduled out anyway, unless the config says otherwise
+const DEFAULT_RELEARN_CAP: u32 = 3;
+
+/// The number of answers per day the user aims for, unless the config says
+/// otherwise
+const DEFAULT_DAILY_GOAL: u64 = 50;
+
+/// The hours ahead the words can be studied once there's nothing due, unless
+/// the config says otherwise
+const DEFAULT_AHEAD_HOURS: u64 = 4;
+
+/// The scale (in percent) of the user interface, unless the config says
+/// otherwise
+const DEFAULT_UI_SCALE: u16 = 100;
+
+/// The range of the scales (in percent) of the user interface
+const UI_SCALES: std::ops::RangeInclusive<u16> = 50..=300;
+
+/// The largest font size of the words that can be set
+const MAX_FONT_SIZE: u16 = 300;
+
+/// The number of the databases opened last that are remembered
+pub const RECENT_DATABASES: usize = 5;
+
+
+/// The application config
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// The language of the user interface
+    pub language: Language,
+
+    /// The color scheme of the user interface
+    pub theme: Theme,
+
+    /// The scale (in percent) of the whole user interface, e.g. for the
+    /// screens with a high density of pixels
+    pub ui_scale: u16,
+
+    /// The font size of the longest words; the font never gets smaller
+    pub min_font_size: u16,
+
+    /// The font size of the short original words; the translations are a
+    /// bit smaller
+    pub max_font_size: u16,
+
+    /// The database opened last; it's reopened when the application starts
+    pub last_database: Option<PathBuf>,
+
+    /// The databases opened last, the latest one first; see
+    /// `remember_database`
+    pub recent_databases: Vec<PathBuf>,
+
+    /// The direction the databases are reviewed in
+    pub direction: Direction,
+
+    /// Whether the reverse direction has a schedule of its own instead of
+    /// sharing the one of the forward direction
+    pub separate_directions: bool,
+
+    /// If set, at most this many new words are introduced per day in the
+    /// databases that don't set a limit of their own
+    pub new_limit: Option<u64>,
+
+    /// If set, at most this many reviews are done per day in the databases
+    /// that don't set a limit of their own
+    pub review_limit: Option<u64>,
+
+    /// If set, new words are presented and quizzed in batches of this size
+    /// before returning to the reviews
+    pub new_word_batch_size: Option<usize>,
+
+    /// If set, a review session ends with a summary after this many answers;
+    /// it always ends once there's nothing left to review
+    pub session_size: Option<usize>,
+
+    /// Words are suspended as leeches once they were forgotten this many
+    /// times; 0 never suspends them
+    pub leech_threshold: u32,
+
+    /// The delays (in minutes) after which a forgotten word is served again
+    /// in the session, one per correct answer it takes to relearn it; empty
+    /// to schedule it out right away
+    pub relearn_steps: Vec<u64>,
+
+    /// A word is relearned at most this many times in a session; once it's
+    /// forgotten again, it's scheduled out anyway
+    pub relearn_cap: u32,
+
+    /// The number of answers per day the user aims for; 0 for no goal
+    pub daily_goal: u64,
+
+    /// Once there's nothing due, the words that become due in this many
+    /// hours can be studied ahead; 0 never offers it
+    pub ahead_hours: u64,
+
+    /// Whether the first word is drawn as soon as a database is opened;
+    /// otherwise it's drawn by the first answer, which doesn't grade anything
+    pub preload_first_entry: bool,
+
+    /// Whether a word recalled with a hint is graded as recalled with
+    /// difficulty at best; see `hint::penalize`
+    pub hint_penalty: bool,
+
+    /// The differences between the typed answers and the words that don't
+    /// matter in the decks without a normalization of their own
+    pub normalization: Normalization,
+
+    /// The normalizations of the typed answers of the decks that have their
+    /// own, by the names of the decks
+    pub deck_normalizations: BTreeMap<String, Normalization>,
+
+    /// What the buttons of the grades are labeled with
+    pub grade_labels: GradeLabels,
+
+    /// Whether the buttons of the grades are marked with icons as well
+    pub grade_icons: bool,
+
+    /// Whether the user is asked before an answer moves a word to the longest
+    /// interval of its ladder
+    pub confirm_longest: bool,
+
+    /// Whether the user is notified of the words that became due while the
+    /// window is in the background
+    pub reminders: bool,
+
+    /// Whether the window hides to the system tray when it's closed (only
+    /// with the `tray` feature)
+    pub tray: bool,
+
+    /// The URL of the folder on a WebDAV server (e.g. Nextcloud) the
+    /// databases are synced with, each with the file of its name (only with
+    /// the `sync` feature); "" if they aren't synced
+    pub sync_url: String,
+
+    /// The user signing in to the WebDAV server, or "" if it doesn't need one
+    pub sync_user: String,
+
+    /// The password of the user on the WebDAV server; preferably an app
+    /// password that can be revoked on its own
+    pub sync_password: String,
+
+    /// The hour (0-23, local time) the days of the reviews start at
+    pub day_start_hour: u8,
+
+    /// The offset (in hours) of the local time from UTC, which the day start
+    /// is in
+    pub utc_offset: i8,
+
+    /// The algorithm the reviews are scheduled with
+    pub scheduler: Algorithm,
+
+    /// The chance of recalling a word the FSRS scheduler schedules the
+    /// reviews for, between 0.7 and 0.99
+    pub retention: f64,
+
+    /// The timeout ladder (in days) of the fixed scheduler
+    pub ladder: Vec<u64>,
+
+    /// Whether an entry (outside of the two-way decks) only advances to the
+    /// next rung once it was recalled in both directions in the session; only
+    /// if the directions share their schedule (see `separate_directions`)
+    pub both_directions: bool,
+
+    /// The decks whose entries are reviewed in both directions, each with its
+    /// own schedule, no matter `direction`
+    pub two_way_decks: BTreeSet<String>,
+
+    /// The timeout ladders of the decks that don't use `ladder`, by the names
+    /// of the decks.
+    /// This and `deck_new_limits` are tables, so they have to stay the last
+    /// fields.
+    pub deck_ladders: BTreeMap<String, Vec<u64>>,
+
+    /// The numbers of new words introduced per day in the decks that limit
+    /// them, by the names of the decks; on top of `new_limit`
+    pub deck_new_limits: BTreeMap<String, u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            language:            Language::default(),
+            theme:               Theme::default(),
+            ui_scale:            DEFAULT_UI_SCALE,
+            min_font_size:       FontSizes::WORD.min as u16,
+            max_font_size:       FontSizes::WORD.max as u16,
+            last_database:       None,
+            recent_databases:    Vec::new(),
+            direction:           Direction::default(),
+            separate_directions: true,
+            new_limit:           None,
+            review_limit:        None,
+            new_word_batch_size: None,
+            session_size:        None,
+            leech_threshold:     DEFAULT_LEECH_THRESHOLD,
+            relearn_steps:       DEFAULT_RELEARN_STEPS.to_vec(),
+            relearn_cap:         DEFAULT_RELEARN_CAP,
+            daily_goal:          DEFAULT_DAILY_GOAL,
+            ahead_hours:         DEFAULT_AHEAD_HOURS,
+            preload_first_entry: true,
+            hint_penalty:        true,
+            normalization:       Normalization::default(),
+            deck_normalizations: BTreeMap::new(),
+            grade_labels:        GradeLabels::default(),
+            grade_icons:         false,
+            confirm_longest:     false,
+            reminders:           false,
+            tray:                false,
+            sync_url:            String::new(),
+            sync_user:           String::new(),
+            sync_password:       String::new(),
+            day_start_hour:      DEFAULT_DAY_START_HOUR,
+            utc_offset:          0,
+            scheduler:           Algorithm::default(),
+            retention:           DEFAULT_RETENTION,
+            ladder:              TIMEOUT_DELAYS.to_vec(),
+            both_directions:     false,
+            two_way_decks:       BTreeSet::new(),
+            deck_ladders:        BTreeMap::new(),
+            deck_new_limits:     BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Returns the path of the config file, or `None` if the platform has no
+    /// config directory (e.g. there's no home directory); see
+    /// `paths::config_path`
+    pub fn path() -> Option<PathBuf> {
+        paths::config_path()
+    }
+
+    /// Loads the config.
+    /// If there's no config file yet, the default config is returned.
+    /// Unknown keys are ignored and missing keys are set to their defaults.
+    /// Invalid ladders are replaced by the default one (or dropped, if they
+    /// belong to a deck), and so are invalid hours, retentions, scales and
+    /// font sizes. Empty batches of new words and empty sessions turn the
+    /// batches and the session sizes off.
+    pub fn load() -> std::io::Result<Self> {
+        let path = match Self::path() {
+            Some(path) => path,
+            None       => return Ok(Self::default()),
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+
+        let mut config: Self = toml::from_str(&contents)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        if !is_valid_ladder(&config.ladder) {
+            config.ladder = TIMEOUT_DELAYS.to_vec();
+        }
+        config.deck_ladders.retain(|_, ladder| is_valid_ladder(ladder));
+        if config.day_start_hour >= 24 {
+            config.day_start_hour = DEFAULT_DAY_START_HOUR;
+        }
+        if !(-12..=14).contains(&config.utc_offset) {
+            config.utc_offset = 0;
+        }
+        if config.new_word_batch_size == Some(0) {
+            config.new_word_batch_size = None;
+        }
+        if config.session_size == Some(0) {
+            config.session_size = None;
+        }
+        if !is_valid_retention(config.retention) {
+            config.retention = DEFAULT_RETENTION;
+        }
+        if !UI_SCALES.contains(&config.ui_scale) {
+            config.ui_scale = DEFAULT_UI_SCALE;
+        }
+        if !are_valid_font_sizes(config.min_font_size, config.max_font_size) {
+            config.min_font_size = FontSizes::WORD.min as u16;
+            config.max_font_size = FontSizes::WORD.max as u16;
+        }
+
+        // Configs older than the list only know the database opened last
+        if config.recent_databases.is_empty() {
+            config.recent_databases.extend(config.last_database.clone());
+        }
+        config.last_database = config.last_database
+            .map(|path| paths::resolve(&path));
+        for path in config.recent_databases.iter_mut() {
+            *path = paths::resolve(path);
+        }
+        Ok(config)
+    }
+
+    /// Returns the factor the user interface is scaled by
+    pub fn scale_factor(&self) -> f64 {
+        f64::from(self.ui_scale) / 100.
+    }
+
+    /// Returns how the words are displayed with the font sizes of the config
+    pub fn display(&self) -> DisplayConfig {
+        DisplayConfig::with_font_sizes(f32::from(self.min_font_size),
+                                       f32::from(self.max_font_size))
+    }
+
+    /// Returns the normalization of the typed answers of `deck`; its own one,
+    /// if it has one
+    pub fn normalization(&self, deck: &str) -> Normalization {
+        self.deck_normalizations.get(deck)
+            .copied()
+            .unwrap_or(self.normalization)
+    }
+
+    /// Remembers `path` as the database opened last, keeping the last
+    /// `RECENT_DATABASES` of them
+    pub fn remember_database(&mut self, path: &Path) {
+        self.recent_databases.retain(|recent| recent != path);
+        self.recent_databases.insert(0, path.to_path_buf());
+        self.recent_databases.truncate(RECENT_DATABASES);
+        self.last_database = Some(path.to_path_buf());
+    }
+
+    /// Writes the config to the config file, creating the config directory
+    /// if needed
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::path().ok_or_else(|| {
+            Error::new(ErrorKind::NotFound, "no config directory")
+        })?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // The databases are remembered relative to the portable directory
+        let mut config = self.clone();
+        config.last_database = config.last_database
+            .map(|path| paths::remembered(&path));
+        for path in config.recent_databases.iter_mut() {
+            *path = paths::remembered(path);
+        }
+
+        let contents = toml::to_string(&config)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        write_atomic(&path, contents.as_bytes())
+    }
+}
+
+
+/// Returns whether the words can be displayed with the font sizes between
+/// `min` and `max`
+pub fn are_valid_font_sizes(min: u16, max: u16) -> bool {
+    0 < min && min <= max && max <= MAX_FONT_SIZE
+}
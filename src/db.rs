@@ -1,28 +1,429 @@
 //! This module handles the database;
 //! loading, parsing, writing, etc. etc.
+//!
+//! The entries are stored by a backend (see `backend`); in a text file, in
+//! a JSON file (see `backend::JsonBackend`) or in an SQLite one. The format
+//! can be changed with `Database::save_as`.
+//!
+//! # The text format
+//!
+//! A text database is a UTF-8 file (see `encoding` for the ones that aren't)
+//! of lines, one entry per line:
+//!
+//! ```text
+//! file         = { line } [ "--- journal ---" "\n" { line } ]
+//! line         = [ entry ] "\n"
+//! entry        = word DELIMITER translations
+//!                [ DELIMITER iteration DELIMITER timeout
+//!                  { DELIMITER key "=" value } ]
+//! translations = translation { "|" translation }
+//! iteration    = [ "-" ] digit { digit }
+//! timeout      = digit { digit }
+//! DELIMITER    = ";; "
+//! ```
+//!
+//! - The columns are escaped (see `escape`): `\\` is a backslash, `\n` and
+//!   `\r` are line breaks and `\;` is a semicolon. Any other backslash is
+//!   just a backslash.
+//! - The words, translations and values are trimmed. Blank translations are
+//!   dropped and a line without a word or a translation is broken.
+//! - The keys are those of `Entry::parse`. Unknown keys are ignored, so that
+//!   older versions can read the files of newer ones; a known key with
+//!   a value that doesn't parse makes the line broken.
+//! - A broken line is quarantined (see `Quarantined`); it's kept in the file
+//!   as it is, even when the file is rewritten, until it's fixed by hand.
+//! - An entry in the journal replaces the earlier one with the same word and
+//!   translations.
+//!
+//! `Entry::db_repr` writes the lines and `Entry::parse` reads them. An entry
+//! in its canonical form (see `Entry::normalize`) always reads back as the
+//! very same entry, whatever characters its columns hold.
 
-use std::fs::{File, OpenOptions};
+pub mod import;
+pub mod deck;
+pub mod backup;
+pub mod backend;
+pub mod lock;
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::str::FromStr;
 use std::io::prelude::*;
-use std::io::SeekFrom;
-use std::time::{UNIX_EPOCH, SystemTime, Duration};
-use std::path::PathBuf;
+use std::time::{UNIX_EPOCH, SystemTime};
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use serde::{Deserialize, Serialize};
 use crate::rng::Rng;
+use crate::queue::ReviewQueue;
+use crate::meta::Meta;
+use crate::stats::{History, Review};
+use crate::achievements::Achievement;
+use crate::card::{CardType, Cloze};
+use crate::error::{Error, ParseError, Result};
+use crate::scheduler::{Scheduler, FixedScheduler, Grade, DayStart,
+                       TIMEOUT_DELAYS, MAX_LADDER_STEPS, DEFAULT_EASE,
+                       fuzz, fuzz_seed, is_valid_ladder};
+use crate::stats;
+use crate::encoding;
+use crate::grading;
+use crate::paths;
+use crate::fsrs::{self, Fit};
+use backend::Backend;
+use lock::Lock;
 
 /// 24 hours in seconds
-const DAY: u64 = 86400;
+pub const DAY: u64 = 86400;
+
+/// An hour in seconds
+pub const HOUR: u64 = 3600;
 
-/// The word timeout values (in days)
-const TIMEOUT_DELAYS: [u64; 5] = [0, 1, 7, 14, 30];
+/// A minute in seconds
+pub const MINUTE: u64 = 60;
 
 /// Column delimiter in the database
-const DELIMITER: &str = ";; ";
+pub const DELIMITER: &str = ";; ";
+
+/// Key/value delimiter of the optional columns following the fixed ones
+const FIELD_DELIMITER: char = '=';
+
+/// Delimiter of the translations within the translation column; also used
+/// to enter multiple translations in the ui
+pub const TR_DELIMITER: char = '|';
+
+/// Delimiter of the tags within the tag column; also used to enter multiple
+/// tags in the ui
+pub const TAG_DELIMITER: char = ',';
+
+/// Escapes characters in the columns that would otherwise break the line
+/// apart; see `escape` and `split_line`
+const ESCAPE: char = '\\';
+
+/// Marks the start of the journal in the database file.
+/// Every line after it is a changed entry that was appended to the file
+/// instead of rewriting the whole database.
+const JOURNAL_MARKER: &str = "--- journal ---";
+
+/// The number of journal entries after which the database is compacted
+const JOURNAL_MAX_LEN: usize = 1000;
+
+/// The number of answers that can be undone
+pub const UNDO_DEPTH: usize = 20;
+
+/// Extensions of the databases stored in SQLite files
+const SQLITE_EXTENSIONS: [&str; 2] = ["sqlite", "sqlite3"];
+
+/// The first bytes of every SQLite file
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+/// Extension of the databases stored as a JSON array
+const JSON_EXTENSION: &str = "json";
+
+/// Extensions of the databases stored as JSON lines
+const JSON_LINES_EXTENSIONS: [&str; 2] = ["jsonl", "ndjson"];
+
+/// Parses the timeout delay iteration of an entry.
+/// Hand-edited files are treated tolerantly: negative values (e.g. `-1` meant
+/// as "reset") become 0 and values past the end of the longest possible
+/// ladder become its last rung. Values past the end of the ladder the entry
+/// is actually scheduled with are treated as its last rung.
+/// Returns `None` only if the value isn't a number at all.
+fn parse_cur_iter(value: &str) -> Option<usize> {
+    let value = value.trim();
+    let top   = MAX_LADDER_STEPS - 1;
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None         => (false, value),
+    };
+
+    if digits.is_empty() || !digits.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    if negative {
+        return Some(0);
+    }
+
+    // Numbers too large to parse are past the end of the ladder anyway
+    Some(digits.parse::<usize>().map_or(top, |iter| iter.min(top)))
+}
+
+/// Parses the `value` of the optional column `field`
+fn parse_field<T: FromStr>(field: &str, value: &str)
+        -> std::result::Result<T, ParseError> {
+    value.parse().map_err(|_| ParseError::Field(field.to_string()))
+}
+
+/// Escapes `column` so that it can be written into the database.
+/// Backslashes and line breaks are always escaped, semicolons only if they
+/// could be mistaken for a part of `DELIMITER`, so that most words are
+/// written as they are.
+pub fn escape(column: &str) -> String {
+    let mut escaped = String::with_capacity(column.len());
+    let mut chars   = column.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            ESCAPE => escaped.push_str("\\\\"),
+            '\n'   => escaped.push_str("\\n"),
+            '\r'   => escaped.push_str("\\r"),
+            ';' if matches!(chars.peek(), None | Some(';')) => {
+                escaped.push(ESCAPE);
+                escaped.push(ch);
+            },
+            _ => escaped.push(ch),
+        }
+    }
+
+    escaped
+}
+
+/// Splits a line of the database into its columns and undoes `escape`.
+/// Unknown escape sequences are kept as they are; files written before
+/// escaping was introduced hardly ever contain a backslash followed by one
+/// of the escaped characters, so they are read just like they always were.
+pub fn split_line(line: &str) -> Vec<String> {
+    let mut columns = vec![String::new()];
+    let mut rest    = line;
+
+    while let Some(ch) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix(DELIMITER) {
+            columns.push(String::new());
+            rest = after;
+            continue;
+        }
+        rest = &rest[ch.len_utf8()..];
+
+        let column = columns.last_mut().unwrap();
+        if ch != ESCAPE {
+            column.push(ch);
+            continue;
+        }
+
+        match rest.chars().next() {
+            Some('n') => column.push('\n'),
+            Some('r') => column.push('\r'),
+            Some(escaped) if escaped == ESCAPE || escaped == ';' => {
+                column.push(escaped);
+            },
+            _ => {
+                column.push(ch);
+                continue;
+            },
+        }
+        rest = &rest[1..];
+    }
+
+    columns
+}
+
+/// Splits a translation column into the translations.
+/// The translations are trimmed and the blank ones are dropped.
+pub fn split_translations(column: &str) -> Vec<String> {
+    column.split(TR_DELIMITER)
+        .map(|translation| translation.trim())
+        .filter(|translation| !translation.is_empty())
+        .map(|translation| translation.to_string())
+        .collect()
+}
+
+/// Joins `translations` into a translation column; the opposite of
+/// `split_translations`
+pub fn join_translations(translations: &[String]) -> String {
+    translations.join(&format!(" {} ", TR_DELIMITER))
+}
+
+/// Splits a tag column into the tags.
+/// The tags are trimmed, the blank ones are dropped and every tag is kept
+/// only once.
+pub fn split_tags(column: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in column.split(TAG_DELIMITER).map(|tag| tag.trim()) {
+        if !tag.is_empty() && !tags.iter().any(|other| other == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Joins `tags` into a tag column; the opposite of `split_tags`
+pub fn join_tags(tags: &[String]) -> String {
+    tags.join(&format!("{} ", TAG_DELIMITER))
+}
+
+/// Returns a new random id of an entry; a version 4 UUID, e.g.
+/// `0f8e2c1a-5b3d-4e6f-9a7b-1c2d3e4f5a6b`.
+/// Every id is drawn from an RNG of its own, seeded from system entropy, so
+/// that the ids don't repeat even if the RNG of a database is seeded.
+pub fn new_id() -> String {
+    let mut rng = Rng::new();
+    let high    = (rng.rand() & !0xf000) | 0x4000;
+    let low     = (rng.rand() & !(0xc << 60)) | (0x8 << 60);
+    format!("{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            high >> 32, high >> 16 & 0xffff, high & 0xffff,
+            low >> 48, low & 0xffff_ffff_ffff)
+}
+
+/// Atomically replaces the contents of the file at `path`.
+/// The contents are written into a temporary file next to it first, which is
+/// then renamed over the original, so a crash in the middle of the write
+/// never leaves a half-written file behind.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let tmp      = paths::tmp_path(path);
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Returns the current time in seconds since the epoch
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// Returns the current time in milliseconds since the epoch, e.g. to time
+/// the answers
+pub fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+}
+
+/// Returns until when (in seconds since the epoch) the words are studied
+/// `hours` hours ahead (see `Database::study_ahead`)
+pub fn ahead_until(hours: u64) -> u64 {
+    now().saturating_add(hours.saturating_mul(HOUR))
+}
+
+
+/// The on-disk formats of the database
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Lines of `DELIMITER` separated columns
+    Text,
+
+    /// An SQLite file; only supported with the `sqlite` feature
+    Sqlite,
+
+    /// A JSON array of entry objects; see `backend`
+    Json,
+
+    /// An entry object of JSON on every line; see `backend`
+    JsonLines,
+}
+
+impl Format {
+    /// Returns the format a database at `path` is stored in, judging by its
+    /// extension. Anything that isn't an SQLite or a JSON file is a text
+    /// file.
+    pub fn for_path(path: &Path) -> Self {
+        let extension = path.extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+        match extension.as_deref() {
+            Some(ext) if SQLITE_EXTENSIONS.contains(&ext) => Format::Sqlite,
+            Some(ext) if JSON_LINES_EXTENSIONS.contains(&ext) => {
+                Format::JsonLines
+            },
+            Some(JSON_EXTENSION) => Format::Json,
+            _                    => Format::Text,
+        }
+    }
+
+    /// Recognizes the formats whose files start unmistakably (SQLite and
+    /// JSON) by the first bytes of a database.
+    /// Returns `None` for anything else, e.g. a text database.
+    pub fn signature(start: &[u8]) -> Option<Self> {
+        if start.starts_with(SQLITE_HEADER) {
+            return Some(Format::Sqlite);
+        }
+
+        // A JSON array opens with an object (or closes right away), and the
+        // JSON lines start with a key. A line of a text database can't
+        // start like that, not even a cloze sentence.
+        let start = start.strip_prefix(b"\xef\xbb\xbf").unwrap_or(start);
+        let mut bytes = start.iter()
+            .copied()
+            .filter(|byte| !byte.is_ascii_whitespace());
+        match (bytes.next(), bytes.next()) {
+            (Some(b'['), Some(b'{')) | (Some(b'['), Some(b']')) => {
+                Some(Format::Json)
+            },
+            (Some(b'{'), Some(b'"')) => Some(Format::JsonLines),
+            _                        => None,
+        }
+    }
+
+    /// Guesses the format of the database by sniffing its contents rather than
+    /// trusting the file extension.
+    /// Anything that reads as text is a text database, even if none of its
+    /// lines parse; it's up to the parser to tell what's wrong with them.
+    /// Returns `None` if the contents don't look like any known format,
+    /// e.g. binary data.
+    pub fn detect(contents: &[u8]) -> Option<Self> {
+        if let Some(format) = Self::signature(contents) {
+            return Some(format);
+        }
+        let (text, _) = encoding::decode(contents);
+
+        let is_binary = |c: char| {
+            c == char::REPLACEMENT_CHARACTER
+                || (c.is_control() && !c.is_whitespace())
+        };
+        match text.chars().any(is_binary) {
+            true  => None,
+            false => Some(Format::Text),
+        }
+    }
+}
+
+
+/// A line of the database file that couldn't be parsed.
+/// Its entry isn't served, but the line is written back as it is whenever
+/// the file is rewritten, so that the word isn't lost before the user fixes
+/// the line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Quarantined {
+    /// The number of the line (counted from 1) when the file was read
+    pub line: usize,
+
+    /// The line as it is in the file
+    pub text: String,
+
+    /// Why the line couldn't be parsed
+    pub error: ParseError,
+}
+
+impl From<Quarantined> for Error {
+    fn from(quarantined: Quarantined) -> Self {
+        Error::Parse {
+            line:  quarantined.line,
+            error: quarantined.error,
+        }
+    }
+}
+
+impl std::fmt::Display for Quarantined {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
 
 
 /// This struct keeps track of the open database file and of its internal
 /// in-memory representation.
 pub struct Database {
-    /// The handle to the database file
-    pub file: File,
+    /// Loads and stores the entries
+    backend: Box<dyn Backend>,
+
+    /// The lines that couldn't be parsed when the database was opened, so
+    /// that the user can fix them; they're kept in the file meanwhile
+    pub quarantined: Vec<Quarantined>,
+
+    /// The path to the database file
+    pub path: PathBuf,
+
+    /// The metadata stored next to the database
+    pub meta: Meta,
+
+    /// The review history stored next to the database
+    pub history: History,
 
     /// The vector of usable (not timed-out) database entries
     pub usable: Vec<Entry>,
@@ -32,191 +433,3087 @@ pub struct Database {
 
     /// The RNG used to get random entries from the database
     pub rng: Rng,
+
+    /// How the next entry is selected
+    pub selection: Selection,
+
+    /// The shuffled entries yet to be served when selecting randomly
+    queue: ReviewQueue,
+
+    /// If set, only the entries of this deck are served
+    pub active_deck: Option<String>,
+
+    /// If set, only the entries tagged with this tag are served; whatever
+    /// deck they're in, unless `active_deck` is set as well
+    pub active_tag: Option<String>,
+
+    /// Whether only the starred entries are served; within the active deck
+    /// and tag, if any
+    pub starred_only: bool,
+
+    /// Which of the words of the entries is shown first
+    direction: Direction,
+
+    /// Whether the reverse direction is scheduled independently.
+    /// If so, the reverse schedules are swapped into the entries while the
+    /// database is reviewed in reverse.
+    separate_directions: bool,
+
+    /// Whether an entry reviewed with the schedule shared by both directions
+    /// only advances once it was recalled in both of them in the session;
+    /// see `update_timeout`
+    pub both_directions: bool,
+
+    /// The directions the entries waiting for the other direction (see
+    /// `both_directions`) were recalled in, by their words and translations
+    recalled: HashMap<(String, Vec<String>), HashSet<Direction>>,
+
+    /// The decks whose entries are reviewed in both directions, as two cards
+    /// with their own schedules; see `card`
+    two_way_decks: HashSet<String>,
+
+    /// Decides when the reviewed entries are due again
+    pub scheduler: Box<dyn Scheduler>,
+
+    /// The schedulers of the decks with a preset of their own (see
+    /// `Meta::deck_presets`), by the names of the decks; the other decks use
+    /// `scheduler`
+    pub deck_schedulers: HashMap<String, Box<dyn Scheduler>>,
+
+    /// When the days start; the timeouts are rounded down to the start of
+    /// their day
+    pub day_start: DayStart,
+
+    /// The timeout ladder (in days) of the decks without a ladder of their
+    /// own; see `set_ladders`
+    ladder: Vec<u64>,
+
+    /// The timeout ladders of the decks that have their own, by the names of
+    /// the decks
+    deck_ladders: HashMap<String, Vec<u64>>,
+
+    /// The daily limits of new words of the decks that have one, by the names
+    /// of the decks; see `set_deck_new_limits`
+    deck_new_limits: HashMap<String, u64>,
+
+    /// If set, new words are introduced in batches instead of one by one
+    pub batches: Option<Batches>,
+
+    /// Whether new words are served; see `session`
+    pub serve_new: bool,
+
+    /// Whether reviews (of words that aren't new) are served
+    pub serve_reviews: bool,
+
+    /// If set, entries are suspended as leeches once they were forgotten
+    /// this many times
+    pub leech_threshold: Option<u32>,
+
+    /// The delays (in minutes) after which a forgotten entry is served again
+    /// in the session, one per correct answer it takes to relearn it; see
+    /// `update_timeout`
+    pub relearn_steps: Vec<u64>,
+
+    /// An entry is relearned at most this many times in a session
+    pub relearn_cap: u32,
+
+    /// The entries being relearned in the session
+    relearning: Vec<Relearning>,
+
+    /// Whether all the entries are served no matter their timeouts; see
+    /// `set_cram`
+    cram: bool,
+
+    /// The profile whose schedule is loaded.
+    /// If set, the database file only holds the words and the schedule is
+    /// kept in a separate file of the profile.
+    pub profile: Option<String>,
+
+    /// The state before the last few answers, the latest one last
+    undo: Vec<Update>,
+
+    /// Whether the whole database has to be rewritten on the next write,
+    /// because the entries can't be matched to their journal records anymore
+    needs_compaction: bool,
+
+    /// The start of the day (in seconds since the epoch) the daily snapshot
+    /// of the database file was taken on; see `snapshot_daily`
+    snapshotted: u64,
+
+    /// The lock of the database; `None` if it's opened read-only
+    lock: Option<Lock>,
 }
 
 impl Database {
-    /// Opens the database, parses it and returns it
-    pub fn open(filename: PathBuf) -> std::io::Result<Self> {
-        // Read the contents of the file
-        let mut file     = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(false)
-            .open(filename)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-
-        // And create vectors of entries from the lines of the file
-        let numlines     = contents.lines().count();
-        let mut usable   = Vec::with_capacity(numlines);
-        let mut unusable = Vec::with_capacity(numlines);
-
-        for line in contents.lines() {
-            if let Some(entry) = Entry::parse_from_line(line) {
-                if entry.timed_out {
-                    unusable.push(entry);
-                } else {
-                    usable.push(entry);
-                }
+    /// Opens the database, parses it and returns it.
+    /// The backend is chosen by the extension of the file; see `backend`.
+    /// Broken lines are skipped and kept in `quarantined`.
+    /// The database is locked until it's dropped; it fails to open with
+    /// `Error::Locked` if it's open somewhere else already, but not if the
+    /// lock was left behind by a crash (see `lock`). If the lock can't
+    /// be created at all (e.g. the directory is read-only), the database is
+    /// opened read-only; see `open_read_only`.
+    pub fn open(filename: PathBuf) -> Result<Self> {
+        let lock = match Lock::acquire(paths::lock_path(&filename)) {
+            Ok(lock) => Some(lock),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                return Err(Error::Locked(filename));
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::PermissionDenied => {
+                None
+            },
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut db = Self::load(filename)?;
+        db.lock    = lock;
+        db.set_cram(db.is_read_only());
+        Ok(db)
+    }
+
+    /// Opens the database without locking it, e.g. because it's open
+    /// somewhere else already or it's someone else's.
+    /// Nothing is ever written to it, so its entries are only crammed and
+    /// their schedules stay as they are.
+    pub fn open_read_only(filename: PathBuf) -> Result<Self> {
+        let mut db = Self::load(filename)?;
+        db.set_cram(true);
+        Ok(db)
+    }
+
+    /// Loads the database without locking it
+    fn load(filename: PathBuf) -> Result<Self> {
+        let mut backend                = backend::open(&filename)?;
+        let (mut entries, quarantined) = backend.load_entries()?;
+
+        // The entries of the older versions have no ids yet, and the copied
+        // lines share theirs; they're given new ones and the file is
+        // rewritten with them
+        let mut ids              = HashSet::new();
+        let mut needs_compaction = false;
+        for entry in entries.iter_mut() {
+            if entry.id.is_empty() || !ids.insert(entry.id.clone()) {
+                entry.id         = new_id();
+                needs_compaction = true;
             }
         }
 
+        // And create vectors of entries
+        let (unusable, usable): (Vec<Entry>, Vec<Entry>) = entries.into_iter()
+            .partition(|entry| entry.timed_out);
+
+        let meta    = Meta::load(&filename)?;
+        let history = History::new(&filename);
+
         Ok(Self {
-            file,
+            backend,
+            quarantined,
+            path: filename,
+            meta,
+            history,
             usable,
             unusable,
             rng: Rng::new(),
+            selection: Selection::Random,
+            queue: ReviewQueue::new(),
+            active_deck: None,
+            active_tag: None,
+            starred_only: false,
+            direction: Direction::Forward,
+            separate_directions: false,
+            both_directions: false,
+            recalled: HashMap::new(),
+            two_way_decks: HashSet::new(),
+            scheduler: Box::new(FixedScheduler),
+            deck_schedulers: HashMap::new(),
+            day_start: DayStart::default(),
+            ladder: TIMEOUT_DELAYS.to_vec(),
+            deck_ladders: HashMap::new(),
+            deck_new_limits: HashMap::new(),
+            batches: None,
+            serve_new: true,
+            serve_reviews: true,
+            leech_threshold: None,
+            relearn_steps: Vec::new(),
+            relearn_cap: 0,
+            relearning: Vec::new(),
+            cram: false,
+            profile: None,
+            undo: Vec::new(),
+            needs_compaction,
+            snapshotted: 0,
+            lock: None,
         })
     }
 
-    /// Writes the internal database representation to the file
-    pub fn write_db(&mut self) -> std::io::Result<()> {
-        self.file.seek(SeekFrom::Start(0))?;
+    /// Returns whether the database was opened read-only, so that nothing is
+    /// written to it
+    pub fn is_read_only(&self) -> bool {
+        self.lock.is_none()
+    }
 
-        let entries = self.usable.iter().chain(self.unusable.iter());
+    /// Copies the database file into a new backup and keeps only the `keep`
+    /// newest backups; the older ones are shifted down and the oldest one is
+    /// deleted.
+    pub fn backup(&self, keep: usize) -> Result<()> {
+        // Whoever has the database open writes its backups
+        if keep == 0 || self.is_read_only() {
+            return Ok(());
+        }
+
+        match std::fs::remove_file(paths::backup_path(&self.path, keep)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                return Err(err.into());
+            },
+            _ => {},
+        }
+        for n in (1..keep).rev() {
+            let from = paths::backup_path(&self.path, n);
+            if from.exists() {
+                std::fs::rename(from, paths::backup_path(&self.path, n + 1))?;
+            }
+        }
+
+        let contents = std::fs::read(&self.path)?;
+        Ok(write_atomic(&paths::backup_path(&self.path, 1), &contents)?)
+    }
+
+    /// Opens the database with the schedule of `profile`.
+    /// The words are shared by all the profiles, but each profile has its own
+    /// schedule file next to the database. Words that aren't in the schedule
+    /// of the profile yet are new to it.
+    pub fn open_profile(filename: PathBuf, profile: &str) -> Result<Self> {
+        let mut db   = Self::open(filename)?;
+        let sched    = paths::profile_path(&db.path, profile);
+        let contents = match std::fs::read_to_string(&sched) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                String::new()
+            },
+            Err(err) => return Err(err.into()),
+        };
+
+        // The schedule is matched to the words by both of the words
+        let mut schedules: HashMap<(String, Vec<String>), Entry> = contents
+            .lines()
+            .filter_map(Entry::parse_from_line)
+            .map(|entry| ((entry.word.clone(), entry.tr_word.clone()), entry))
+            .collect();
+
+        let entries: Vec<Entry> = db.usable.drain(..)
+            .chain(db.unusable.drain(..))
+            .collect();
         for entry in entries {
-            self.file.write_all(format!("{}\n", entry.db_repr()).as_bytes())?;
+            // The words, the ids and the rest of the content are those of the
+            // shared words
+            let key      = (entry.word.clone(), entry.tr_word.clone());
+            let schedule = schedules.remove(&key).unwrap_or_else(|| {
+                Entry::new("", "")
+            });
+            let entry = entry.with_schedule(schedule);
+            if entry.timed_out {
+                db.unusable.push(entry);
+            } else {
+                db.usable.push(entry);
+            }
+        }
+
+        db.meta    = Meta::load(&sched)?;
+        db.history = History::new(&sched);
+        db.profile = Some(profile.to_string());
+        Ok(db)
+    }
+
+    /// Creates a new empty database and opens it.
+    /// Fails if the file already exists.
+    pub fn create(filename: PathBuf) -> Result<Self> {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&filename)?;
+        Self::open(filename)
+    }
+
+    /// Saves the database as `filename` in the format of its extension (see
+    /// `Format::for_path`) and continues with that file from then on, like
+    /// "save as" in an editor; e.g. a text database is converted into a JSON
+    /// one. The file is replaced if it exists already.
+    /// The metadata and the history (of the loaded profile, if any) move
+    /// along; the backups and the other profiles stay with the old file.
+    pub fn save_as(&mut self, filename: PathBuf) -> Result<()> {
+        if filename == self.path {
+            self.needs_compaction = true;
+            return self.write_db();
+        }
+        let lock = match Lock::acquire(paths::lock_path(&filename)) {
+            Ok(lock) => lock,
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                return Err(Error::Locked(filename));
+            },
+            Err(err) => return Err(err.into()),
+        };
+
+        // Whatever wasn't written yet goes to the old file first, so that
+        // the history is complete when it's copied
+        self.write_db()?;
+
+        // The metadata and the history of a profile are named after its
+        // schedule file
+        let sidecars = |db: &Path| match &self.profile {
+            Some(profile) => paths::profile_path(db, profile),
+            None          => db.to_path_buf(),
+        };
+        let (old, new) = (sidecars(&self.path), sidecars(&filename));
+        match std::fs::copy(paths::history_path(&old),
+                            paths::history_path(&new)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                return Err(err.into());
+            },
+            _ => {},
+        }
+
+        // An empty file is a new database in every format.
+        // The quarantined lines stay in the old file, which may well be of
+        // another format.
+        write_atomic(&filename, b"")?;
+        self.backend     = backend::open(&filename)?;
+        self.meta.path   = paths::meta_path(&new);
+        self.history     = History::new(&new);
+        self.path        = filename;
+        self.lock        = Some(lock);
+        self.quarantined = Vec::new();
+
+        self.needs_compaction = true;
+        self.snapshotted      = 0;
+        self.write_db()
+    }
+
+    /// Writes the internal database representation to the file.
+    /// Only the changed entries are stored (e.g. appended to the journal at
+    /// the end of a text file); all of them are rewritten once the backend
+    /// wants it or they can't be matched to the stored ones anymore.
+    pub fn write_db(&mut self) -> Result<()> {
+        if self.is_read_only() {
+            return Ok(());
+        }
+        self.history.save()?;
+
+        if let Some(profile) = &self.profile {
+            return self.write_profile(&profile.clone());
+        }
+
+        if self.needs_compaction || self.backend.wants_rewrite() {
+            return self.compact();
+        }
+
+        // Only the changed entries are stored
+        self.snapshot_daily()?;
+        let entries = self.usable.iter_mut().chain(self.unusable.iter_mut());
+        for entry in entries.filter(|entry| entry.dirty) {
+            self.backend.persist_entry(entry)?;
+            entry.dirty = false;
+        }
+        Ok(self.meta.save()?)
+    }
+
+    /// Takes a snapshot of the database file before it's written for the
+    /// first time on a day, so that it can be restored; see `backup`
+    fn snapshot_daily(&mut self) -> Result<()> {
+        let today = self.day_start.start(now());
+        if self.snapshotted != today {
+            backup::snapshot_daily(&self.path, today)?;
+            self.snapshotted = today;
         }
         Ok(())
     }
 
+    /// Rewrites all the entries of the database (e.g. the whole text file
+    /// without the journal)
+    pub fn compact(&mut self) -> Result<()> {
+        if self.is_read_only() {
+            return Ok(());
+        }
+
+        self.snapshot_daily()?;
+        let entries: Vec<&Entry> = self.usable.iter()
+            .chain(self.unusable.iter())
+            .collect();
+        self.backend.persist_all(&entries, &self.quarantined)?;
+
+        for entry in self.usable.iter_mut().chain(self.unusable.iter_mut()) {
+            entry.dirty = false;
+        }
+        self.needs_compaction = false;
+        Ok(self.meta.save()?)
+    }
+
+    /// Writes the words to the database file and the schedule to the schedule
+    /// file of `profile`
+    fn write_profile(&mut self, profile: &str) -> Result<()> {
+        let mut schedule = String::new();
+        let mut words    = Vec::new();
+
+        let entries = self.usable.iter_mut().chain(self.unusable.iter_mut());
+        for entry in entries {
+            schedule.push_str(&format!("{}\n", entry.schedule().db_repr()));
+            words.push(entry.clone().with_schedule(Entry::new("", "")));
+            entry.dirty = false;
+        }
+
+        let words: Vec<&Entry> = words.iter().collect();
+        self.backend.persist_all(&words, &self.quarantined)?;
+        write_atomic(&paths::profile_path(&self.path, profile),
+                     schedule.as_bytes())?;
+        Ok(self.meta.save()?)
+    }
+
+    /// Returns the entries that changed since the database was last written
+    pub fn dirty_entries(&self) -> Vec<&Entry> {
+        self.usable.iter()
+            .chain(self.unusable.iter())
+            .filter(|entry| entry.dirty)
+            .collect()
+    }
+
+    /// Returns whether there are changes that haven't been written yet
+    pub fn is_dirty(&self) -> bool {
+        self.needs_compaction || !self.dirty_entries().is_empty()
+    }
+
+    /// Writes the database one last time, makes sure it actually hit the disk
+    /// and closes the file.
+    pub fn flush_and_close(mut self) -> Result<()> {
+        if self.is_read_only() {
+            return Ok(());
+        }
+        self.write_db()?;
+        Ok(self.backend.flush()?)
+    }
+
     /// Returns a random usable entry and its index in the database.
+    /// The entries are dealt from a shuffled queue, so no entry is served
+    /// twice before all the others were served; the overdue and the recently
+    /// forgotten ones tend to be dealt first (see `queue::weight`).
     /// If all entries are timed out (that is, unusable), `None` is returned.
     pub fn random_entry(&mut self) -> Option<(Entry, usize)> {
-        if self.usable.len() == 0 {
+        self.release_relearned();
+        let candidates = self.candidates();
+        if candidates.is_empty() {
             return None;
         }
 
-        let num = self.rng.range(0, (self.usable.len()-1) as u64) as usize;
+        // Cramming drills all the entries in a random order
+        if self.batches.is_some() && !self.cram {
+            return self.batched_entry(&candidates);
+        }
 
-        if let Some(entry) = self.usable.get(num) {
-            Some((entry.clone(), num))
-        } else {
-            None
+        // Serve the most overdue review, falling back to random new words
+        // once there are no reviews left
+        if self.selection == Selection::MostOverdue && !self.cram {
+            let most_overdue = candidates.iter()
+                .copied()
+                .filter(|&idx| !self.usable[idx].is_new())
+                .min_by_key(|&idx| self.usable[idx].timeout);
+            if let Some(idx) = most_overdue {
+                return Some((self.usable[idx].clone(), idx));
+            }
         }
+
+        let idx = self.queue.deal(&self.usable, &candidates, &mut self.rng)?;
+        Some((self.usable[idx].clone(), idx))
     }
 
-    /// Updates the timeout value of the `index`th entry and moves it from the
-    /// inner `usable` vec into the `unusable` one.
-    /// If `next` is true, `cur_iter` in the entry is incremented.
-    /// If it's false, it is decremented.
-    pub fn update_timeout(&mut self, index: usize, next: bool) {
-        if index >= self.usable.len() {
+    /// Makes the entries being relearned usable again once their steps are
+    /// over. If there's nothing else to serve, the one due first is served
+    /// early rather than ending the session.
+    fn release_relearned(&mut self) {
+        if self.relearning.is_empty() || self.cram {
             return;
-        } else {
-            self.usable[index].update_timeout(next);
-            self.unusable.push(self.usable[index].clone());
-            self.usable.swap_remove(index);
+        }
+
+        let now       = now();
+        let mut due   = self.relearning.iter()
+            .filter(|relearning| relearning.due <= now)
+            .peekable();
+        let mut early = None;
+        if due.peek().is_none() && self.candidates().is_empty() {
+            early = self.relearning.iter()
+                .min_by_key(|relearning| relearning.due);
+        }
+        let released: Vec<Relearning> = due.chain(early)
+            .cloned()
+            .collect();
+
+        for relearning in released.iter() {
+            let idx = self.unusable.iter()
+                .position(|entry| relearning.is(entry));
+            if let Some(idx) = idx {
+                let mut entry   = self.unusable.swap_remove(idx);
+                entry.timed_out = false;
+                self.usable.push(entry);
+            }
         }
     }
-}
 
+    /// Returns the indices of the usable entries that can be served
+    fn candidates(&self) -> Vec<usize> {
+        (0..self.usable.len())
+            .filter(|&idx| self.is_candidate(&self.usable[idx]))
+            .collect()
+    }
 
-/// An entry in the database struct
-#[derive(Clone, Debug)]
-pub struct Entry {
-    /// The original word.
-    pub word: String,
+    /// Returns whether `entry` passes the filters (see `is_filtered_in`),
+    /// isn't suspended or buried, is due (unless cramming) and is of a kind
+    /// (new or not) that is being served
+    pub fn is_candidate(&self, entry: &Entry) -> bool {
+        // Entries that aren't due can be among the usable ones while they
+        // are changed in the browser
+        self.is_served(entry) && (self.cram || !entry.timed_out)
+    }
 
-    /// The translated word.
-    pub tr_word: String,
+    /// Returns whether `entry` passes the filters (see `is_filtered_in`),
+    /// isn't suspended or buried and is of a kind (new or not) that is being
+    /// served; whether it's due or not.
+    /// New entries of decks that reached their daily limit aren't served,
+    /// unless cramming.
+    fn is_served(&self, entry: &Entry) -> bool {
+        let now    = now();
+        let served = match entry.is_new() {
+            true  => self.serve_new
+                && (self.cram || !self.is_deck_full(&entry.deck, now)),
+            false => self.serve_reviews,
+        };
+        served && !entry.suspended && !entry.is_buried(now)
+            && self.is_filtered_in(entry)
+    }
 
-    /// Current timeout delay iteration
-    pub cur_iter: usize,
+    /// Returns whether `entry` belongs to the active deck, carries the
+    /// active tag and is starred if only the starred entries are served; the
+    /// filters that aren't set let every entry in
+    pub fn is_filtered_in(&self, entry: &Entry) -> bool {
+        self.active_deck.as_ref().is_none_or(|deck| &entry.deck == deck)
+            && self.active_tag.as_ref().is_none_or(|tag| entry.has_tag(tag))
+            && (entry.starred || !self.starred_only)
+    }
 
-    /// The word can't be used until this time
-    pub timeout: u64,
+    /// Returns the number of entries that can be served right now
+    pub fn usable_count(&self) -> usize {
+        self.candidates().len()
+    }
 
-    /// Whether the word is usable already or still on a timeout.
-    /// This value isn't really needed - it can be calculated on the run,
-    /// but it makes the code prettier and _very slightly_ faster.
-    pub timed_out: bool,
-}
+    /// Returns the number of entries that can be served and are due before
+    /// the end of the day of `now` (seconds since the epoch); the ones that
+    /// are usable and the ones that become due later that day
+    pub fn due_today_count(&self, now: u64) -> usize {
+        let tomorrow = self.day_start.next(now);
+        self.usable.iter()
+            .chain(self.unusable.iter())
+            .filter(|entry| self.is_served(entry))
+            .filter(|entry| !entry.timed_out || entry.timeout < tomorrow)
+            .count()
+    }
 
-impl Entry {
-    /// Parses a line taken from a textfile and returns a corresponding Entry.
-    /// Returns `None` if the entry is timed out or if an error occurs.
-    pub fn parse_from_line(line: &str) -> Option<Self> {
-        // Extract the elements from the line
-        let split               = line.split(DELIMITER);
-        let elements: Vec<&str> = split.into_iter().collect();
+    /// Returns whether `entry` can be served but isn't due yet, and becomes
+    /// due before `until` (seconds since the epoch); see `study_ahead`
+    fn is_due_ahead(&self, entry: &Entry, until: u64) -> bool {
+        entry.timed_out && entry.timeout <= until && self.is_served(entry)
+    }
 
-        let mut word      = String::new();
-        let mut tr_word   = String::new();
-        let mut cur_iter  = 0;
-        let mut timeout   = Duration::from_secs(0);
-        let mut timed_out = false;
+    /// Returns the number of entries that can be studied ahead because they
+    /// become due before `until` (seconds since the epoch)
+    pub fn due_ahead_count(&self, until: u64) -> usize {
+        self.unusable.iter()
+            .filter(|entry| self.is_due_ahead(entry, until))
+            .count()
+    }
 
-        // If there's 4 elements, the entry is valid.
-        // If there's 2 elements, the entry is new (no time info) but valid.
-        if elements.len() != 2 && elements.len() != 4 {
-            return None;
+    /// Makes the entries that become due before `until` (seconds since the
+    /// epoch) usable right away, e.g. because there's nothing due left.
+    /// They're graded like the due ones; the schedulers count with the time
+    /// since their last review, so that their next intervals don't grow more
+    /// than they would have when the entries were due.
+    /// Returns the number of the entries studied ahead.
+    pub fn study_ahead(&mut self, until: u64) -> usize {
+        let entries = std::mem::take(&mut self.unusable);
+        let (mut ahead, unusable): (Vec<Entry>, Vec<Entry>) = entries
+            .into_iter()
+            .partition(|entry| self.is_due_ahead(entry, until));
+        for entry in ahead.iter_mut() {
+            entry.timed_out = false;
         }
 
-        // All entries
-        if elements.len() >= 2 {
-            word    = elements.get(0)?.to_string();
-            tr_word = elements.get(1)?.to_string();
-        }
+        let count     = ahead.len();
+        self.unusable = unusable;
+        self.usable.extend(ahead);
+        count
+    }
 
-        // Already initialized entries
-        if elements.len() == 4 {
-            cur_iter = elements.get(2)?.parse::<usize>().ok()?;
+    /// Returns the next entry when new words are introduced in batches.
+    /// Words of the current batch are presented first, then quizzed, and then
+    /// the reviews are served. A new batch is only started once there are no
+    /// reviews left.
+    fn batched_entry(&mut self, candidates: &[usize])
+            -> Option<(Entry, usize)> {
+        let mut batches = self.batches.take()?;
+        let reviews: Vec<usize> = candidates.iter()
+            .copied()
+            .filter(|&idx| !self.usable[idx].is_new())
+            .collect();
 
-            // Try to parse the timeout into an integer and compare it
-            // to current time. If it's greater than current time,
-            // the word is on a timeout.
-            let timeout_parse = elements.get(3)?;
-            let timeout_parse = timeout_parse.parse::<u64>().ok()?;
-            timeout = Duration::from_secs(timeout_parse);
+        // Start a new batch if the last one is done and there's nothing
+        // to review
+        if batches.is_done() && reviews.is_empty() {
+            let new_words = candidates.iter()
+                .map(|&idx| &self.usable[idx])
+                .filter(|entry| entry.is_new())
+                .take(batches.size)
+                .map(|entry| entry.word.clone());
+            batches.presenting.extend(new_words);
+            batches.presenting.reverse();
+            batches.quizzing = batches.presenting.clone();
+        }
+
+        // Present the words of the batch first and then quiz them.
+        // Words that aren't usable anymore are skipped.
+        let usable   = &self.usable;
+        let position = |word: &str| usable.iter().position(|e| e.word == word);
 
-            if timeout > SystemTime::now().duration_since(UNIX_EPOCH).ok()? {
-                timed_out = true;
+        let mut found    = None;
+        batches.learning = false;
+        while let Some(word) = batches.presenting.pop() {
+            if let Some(idx) = position(&word) {
+                found            = Some(idx);
+                batches.learning = true;
+                break;
+            }
+        }
+        while found.is_none() {
+            match batches.quizzing.pop() {
+                Some(word) => found = position(&word),
+                None       => break,
             }
         }
 
-        Some(Self {
-            word,
-            tr_word,
-            cur_iter,
-            timeout: timeout.as_secs(),
-            timed_out,
-        })
+        // Fall back to the reviews once the batch is done
+        let found = found.or_else(|| {
+            self.queue.deal(&self.usable, &reviews, &mut self.rng)
+        });
+
+        self.batches = Some(batches);
+        found.map(|idx| (self.usable[idx].clone(), idx))
     }
 
-    /// Updates the timeout of this entry.
-    /// If `next` is true, `cur_iter` is incremented.
-    /// If it's false, it is decremented.
-    pub fn update_timeout(&mut self, next: bool) {
-        if self.timed_out {
-            return;
-        }
+    /// Returns whether the last entry returned by `random_entry` should only
+    /// be presented to the user (and not graded) because it's a new word
+    /// being learned in a batch.
+    pub fn is_learning(&self) -> bool {
+        !self.cram
+            && self.batches.as_ref().is_some_and(|batches| batches.learning)
+    }
 
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    /// Returns the sorted names of all the decks in the database.
+    /// Entries without a deck don't make up a deck of their own.
+    pub fn decks(&self) -> Vec<String> {
+        let mut decks: Vec<String> = self.usable.iter()
+            .chain(self.unusable.iter())
+            .filter(|entry| !entry.deck.is_empty())
+            .map(|entry| entry.deck.clone())
+            .collect();
+        decks.sort();
+        decks.dedup();
+        decks
+    }
 
-        // Update `cur_iter` and also don't overflow
-        if next {
-            if self.cur_iter != TIMEOUT_DELAYS.len()-1 {
-                self.cur_iter += 1;
+    /// Returns the sorted tags of all the entries in the database, each with
+    /// the number of the entries carrying it
+    pub fn tags(&self) -> Vec<(String, usize)> {
+        let mut tags: BTreeMap<&str, usize> = BTreeMap::new();
+        for entry in self.usable.iter().chain(self.unusable.iter()) {
+            for tag in entry.tags.iter() {
+                *tags.entry(tag).or_insert(0) += 1;
             }
-        } else if self.cur_iter != 0 {
-            self.cur_iter -= 1;
         }
+        tags.into_iter()
+            .map(|(tag, count)| (tag.to_string(), count))
+            .collect()
+    }
 
-        // Update `timeout` based on `cur_iter`.
-        // `timed_out` is also set to true.
-        let timeout = Duration::from_secs(TIMEOUT_DELAYS[self.cur_iter] * DAY);
+    /// Returns the number of the starred entries in the database
+    pub fn starred_count(&self) -> usize {
+        self.usable.iter()
+            .chain(self.unusable.iter())
+            .filter(|entry| entry.starred)
+            .count()
+    }
 
-        self.timeout   = now.as_secs() + timeout.as_secs();
-        self.timed_out = true;
+    /// Returns whether there's anything to pick before reviewing; any decks,
+    /// tags or starred entries
+    pub fn is_pickable(&self) -> bool {
+        !self.decks().is_empty() || !self.tags().is_empty()
+            || self.starred_count() != 0
     }
 
-    /// Returns the in-database representation of this entry
-    pub fn db_repr(&self) -> String {
-        format!("{}{}{}{}{}{}{}",
-                self.word,     DELIMITER,
-                self.tr_word,  DELIMITER,
-                self.cur_iter, DELIMITER,
-                self.timeout)
+    /// Guesses whether the word and translation columns are swapped in the
+    /// whole database. `is_source_word` should return whether a word belongs
+    /// to the language of the original words (e.g. by looking it up in
+    /// a wordlist).
+    pub fn detect_swapped<F>(&self, is_source_word: F) -> bool
+    where
+        F: Fn(&str) -> bool,
+    {
+        let entries = self.usable.iter().chain(self.unusable.iter());
+        let (mut words, mut tr_words) = (0, 0);
+        for entry in entries {
+            words    += is_source_word(&entry.word) as usize;
+            tr_words += entry.tr_word.iter()
+                .any(|tr_word| is_source_word(tr_word)) as usize;
+        }
+        tr_words > words
+    }
+
+    /// Swaps the word and translation of every entry.
+    /// The fix is persisted by the next `write_db`.
+    pub fn swap_all_columns(&mut self) {
+        self.undo.clear();
+        for entry in self.usable.iter_mut().chain(self.unusable.iter_mut()) {
+            let word      = join_translations(&entry.tr_word);
+            entry.tr_word = split_translations(&entry.word);
+            entry.word    = word;
+            entry.touch();
+        }
+        self.needs_compaction = true;
+    }
+
+    /// Returns whether every entry of `deck` ("" for the entries that don't
+    /// belong to any) is on the longest timeout of its ladder; false if
+    /// there are no such entries
+    pub fn is_mastered(&self, deck: &str) -> bool {
+        let mut entries = self.usable.iter().chain(self.unusable.iter())
+            .filter(|entry| entry.deck == deck)
+            .peekable();
+        let longest = self.ladder(deck).len() - 1;
+        entries.peek().is_some()
+            && entries.all(|entry| entry.cur_iter >= longest)
+    }
+
+    /// Sets the timeout ladders (in days) of the fixed scheduler; `ladder`
+    /// for all the decks but the ones in `deck_ladders`.
+    /// The ladders of the deck presets in the metadata take precedence over
+    /// `deck_ladders`, so they have to be set up before.
+    /// Invalid ladders (see `scheduler::is_valid_ladder`) are ignored; the
+    /// decks use the default ladder instead.
+    pub fn set_ladders(&mut self, ladder: &[u64],
+                       deck_ladders: &BTreeMap<String, Vec<u64>>) {
+        self.ladder = match is_valid_ladder(ladder) {
+            true  => ladder.to_vec(),
+            false => TIMEOUT_DELAYS.to_vec(),
+        };
+        let presets = self.meta.deck_presets.iter()
+            .filter_map(|(deck, preset)| Some((deck, preset.ladder.as_ref()?)));
+        self.deck_ladders = deck_ladders.iter()
+            .chain(presets)
+            .filter(|(_, ladder)| is_valid_ladder(ladder))
+            .map(|(deck, ladder)| (deck.clone(), ladder.clone()))
+            .collect();
+    }
+
+    /// Returns the scheduler the entries of `deck` are scheduled with
+    pub fn scheduler(&self, deck: &str) -> &dyn Scheduler {
+        self.deck_schedulers.get(deck).unwrap_or(&self.scheduler).as_ref()
+    }
+
+    /// Sets the daily limits of new words of the decks in `limits`.
+    /// The new words of a deck aren't served anymore once the deck introduced
+    /// its limit of them that day, so that a big pile of new words is spread
+    /// over the days; the daily limit of the whole database (see `session`)
+    /// applies on top of that.
+    pub fn set_deck_new_limits(&mut self, limits: &BTreeMap<String, u64>) {
+        self.deck_new_limits = limits.iter()
+            .map(|(deck, &limit)| (deck.clone(), limit))
+            .collect();
+    }
+
+    /// Returns whether `deck` introduced as many new words as its daily limit
+    /// allows on the day of `now` (seconds since the epoch)
+    fn is_deck_full(&self, deck: &str, now: u64) -> bool {
+        let limit = match self.deck_new_limits.get(deck) {
+            Some(&limit) => limit,
+            None         => return false,
+        };
+        let today      = self.day_start.start(now);
+        let introduced = self.usable.iter()
+            .chain(self.unusable.iter())
+            .filter(|entry| entry.deck == deck && entry.introduced >= today)
+            .count();
+        introduced as u64 >= limit
+    }
+
+    /// Returns the timeout ladder (in days) the entries of `deck` are
+    /// scheduled with
+    pub fn ladder(&self, deck: &str) -> &[u64] {
+        self.deck_ladders.get(deck).unwrap_or(&self.ladder)
+    }
+
+    /// Returns the current interval (in days) of `entry`, see
+    /// `stats::interval`
+    pub fn interval(&self, entry: &Entry) -> u64 {
+        stats::interval(entry, self.ladder(&entry.deck))
+    }
+
+    /// Returns whether grading the `index`th usable entry with `grade` would
+    /// move it to the longest interval of the ladder of its deck from a
+    /// shorter one; the entry itself doesn't change
+    pub fn reaches_longest_interval(&self, index: usize, grade: Grade)
+            -> bool {
+        let entry = match self.usable.get(index) {
+            Some(entry) => entry,
+            None        => return false,
+        };
+        let ladder  = self.ladder(&entry.deck);
+        let longest = ladder[ladder.len() - 1];
+
+        let mut graded = entry.clone();
+        self.scheduler(&entry.deck).schedule(&mut graded, grade, ladder,
+                                             now());
+        self.interval(entry) < longest && self.interval(&graded) >= longest
+    }
+
+    /// Fits the weights of the FSRS scheduler to the review history (see
+    /// `fsrs::optimize`) and remembers them in the metadata, which is written
+    /// by the next `write_db`. The scheduler of the database doesn't change;
+    /// `session::set_scheduler` picks the weights up.
+    /// Returns `None` if there are too few reviews to fit the weights to.
+    pub fn optimize_fsrs(&mut self) -> Result<Option<Fit>> {
+        let fit = match fsrs::optimize(&self.history.load()?) {
+            Some(fit) => fit,
+            None      => return Ok(None),
+        };
+        self.meta.fsrs_weights = Some(fit.weights);
+        Ok(Some(fit))
+    }
+
+    /// Returns the achievements that were unlocked since the last check.
+    /// They are remembered in the metadata, so each of them is only ever
+    /// returned once.
+    pub fn check_achievements(&mut self) -> Vec<Achievement> {
+        let unlocked: Vec<Achievement> = Achievement::ALL.iter()
+            .copied()
+            .filter(|a| !self.meta.achievements.contains(a))
+            .filter(|a| a.is_earned(self))
+            .collect();
+        self.meta.achievements.extend(&unlocked);
+        unlocked
+    }
+
+    /// Returns up to `n` reviews that are due at `now` (seconds since the
+    /// epoch), the most overdue first.
+    /// New words were never scheduled, so they aren't overdue.
+    pub fn most_overdue(&self, now: u64, n: usize) -> Vec<&Entry> {
+        let mut due: Vec<&Entry> = self.usable.iter()
+            .chain(self.unusable.iter())
+            .filter(|entry| !entry.is_new() && entry.timeout <= now)
+            .collect();
+        due.sort_by_key(|entry| entry.timeout);
+        due.truncate(n);
+        due
+    }
+
+    /// Returns the entries passing the filters (see `is_filtered_in`) that
+    /// are scheduled; new words and leeches aren't
+    fn scheduled(&self) -> impl Iterator<Item = &Entry> {
+        self.usable.iter()
+            .chain(self.unusable.iter())
+            .filter(|entry| !entry.is_new() && !entry.suspended)
+            .filter(move |entry| self.is_filtered_in(entry))
+    }
+
+    /// Returns the number of reviews that are due at `now` (seconds since the
+    /// epoch); see `scheduled`
+    pub fn due_count(&self, now: u64) -> usize {
+        self.due_entries(now).count()
+    }
+
+    /// Returns the entries whose reviews are due at `now` (seconds since the
+    /// epoch); see `scheduled`
+    pub fn due_entries(&self, now: u64) -> impl Iterator<Item = &Entry> {
+        self.scheduled().filter(move |entry| entry.timeout <= now)
+    }
+
+    /// Returns when (in seconds since the epoch) the next review becomes due
+    /// after `now`, or `None` if none will; see `scheduled`
+    pub fn next_due(&self, now: u64) -> Option<u64> {
+        self.scheduled()
+            .map(|entry| entry.timeout)
+            .filter(|&timeout| timeout > now)
+            .min()
+    }
+
+    /// Returns the number of reviews that become due on each of the `days`
+    /// days starting with the day of `now` (seconds since the epoch).
+    /// The days start when `day_start` says, just like the days of the
+    /// daily limits.
+    /// The reviews that are overdue already count into the first day.
+    /// Only the scheduled entries are counted; see `scheduled`.
+    pub fn due_histogram(&self, now: u64, days: u64) -> Vec<usize> {
+        let today         = self.day_start.start(now);
+        let mut histogram = vec![0; days as usize];
+
+        for entry in self.scheduled() {
+            let due = self.day_start.start(entry.timeout);
+            let day = due.saturating_sub(today) / DAY;
+            if let Some(count) = histogram.get_mut(day as usize) {
+                *count += 1;
+            }
+        }
+        histogram
+    }
+
+    /// Returns a random `fraction` of all the entries (usable or not) for a
+    /// quick spot-check of the database. The same `seed` always yields the
+    /// same sample.
+    /// Returns `None` if `fraction` isn't within `(0, 1]`.
+    pub fn sample(&self, fraction: f32, seed: u64) -> Option<Vec<&Entry>> {
+        if !(fraction > 0. && fraction <= 1.) {
+            return None;
+        }
+
+        let mut entries: Vec<&Entry> = self.usable.iter()
+            .chain(self.unusable.iter())
+            .collect();
+        let count = ((entries.len() as f32 * fraction).ceil() as usize)
+            .min(entries.len());
+
+        // Partial Fisher-Yates; only the first `count` entries are shuffled
+        let mut rng = Rng::with_seed(seed);
+        for idx in 0..count {
+            let swap = rng.range(idx as u64, (entries.len()-1) as u64) as usize;
+            entries.swap(idx, swap);
+        }
+
+        entries.truncate(count);
+        Some(entries)
+    }
+
+    /// Updates the timeout value of the `index`th entry and moves it from the
+    /// inner `usable` vec into the `unusable` one.
+    /// `grade` is how well the user knew the word and `response` how long (in
+    /// milliseconds) the entry was shown before it was answered, if known.
+    /// Returns true if the entry was forgotten too many times and has just
+    /// been suspended as a leech.
+    ///
+    /// A forgotten entry is scheduled as usual, but it's relearned in the
+    /// session as well; it's served again after each of the `relearn_steps`
+    /// until it's recalled once per step (or easily once). The answers while
+    /// it's relearned don't change its schedule, and forgetting it again
+    /// starts the steps over, at most `relearn_cap` times.
+    ///
+    /// If `both_directions` is set, an entry recalled in one direction only
+    /// keeps its schedule (and its rung); it stays usable and it's reviewed
+    /// in the other direction next (see `card`). It's scheduled once it was
+    /// recalled in both of them, or as soon as it's forgotten.
+    pub fn update_timeout(&mut self, index: usize, grade: Grade,
+                          response: Option<u64>) -> bool {
+        if index >= self.usable.len() {
+            false
+        } else {
+            let now   = now();
+            let entry = &mut self.usable[index];
+            let new   = entry.is_new();
+            if entry.introduced == 0 {
+                entry.introduced = now;
+            }
+
+            // Remember the state before the answer so that it can be undone
+            if self.undo.len() == UNDO_DEPTH {
+                self.undo.remove(0);
+            }
+            self.undo.push(Update {
+                entry:      entry.clone(),
+                meta:       self.meta.clone(),
+                relearning: self.relearning.clone(),
+                recalled:   self.recalled.clone(),
+            });
+
+            let waiting    = self.awaits_other_direction(index, grade);
+            let entry      = &mut self.usable[index];
+            entry.reviewed = now;
+            let position   = self.relearning.iter()
+                .position(|relearning| relearning.is(entry));
+            let mut leech  = false;
+            if position.is_none() && !waiting {
+                // Each deck may be scheduled with its own preset
+                let ladder    = self.deck_ladders.get(&entry.deck)
+                    .unwrap_or(&self.ladder);
+                let scheduler = self.deck_schedulers.get(&entry.deck)
+                    .unwrap_or(&self.scheduler);
+                entry.update_timeout(grade, scheduler.as_ref(), ladder,
+                                     self.day_start);
+                leech = !grade.is_correct() && self.leech_threshold
+                    .is_some_and(|threshold| entry.lapses >= threshold);
+                if leech {
+                    entry.suspended = true;
+                }
+            }
+
+            self.meta.record_review(now, new);
+            self.history.record(Review {
+                time:     now,
+                correct:  grade.is_correct(),
+                interval: entry.days_until_due(now),
+                word:     entry.word.clone(),
+                tr_word:  join_translations(&entry.tr_word),
+                response,
+            });
+
+            let mut entry = self.usable.swap_remove(index);
+            entry.touch();
+
+            // An entry served for a step is scheduled already
+            if position.is_some() {
+                entry.timed_out = true;
+            }
+            let relearned = match position {
+                Some(position) => self.relearn(position, grade, now),
+                None if !grade.is_correct() && !leech => {
+                    self.start_relearning(&entry, now)
+                },
+                None => false,
+            };
+            if relearned {
+                self.unusable.push(entry);
+                return leech;
+            }
+
+            // The other card of a two-way entry is served next if it's due
+            // first, but not on the same day; the answer would be fresh
+            if self.orient(&mut entry, now) && !entry.timed_out {
+                entry.buried_until = self.day_start.next(now);
+            }
+            match entry.timed_out {
+                true  => self.unusable.push(entry),
+                false => self.usable.push(entry),
+            }
+            leech
+        }
+    }
+
+    /// Records the direction the `index`th usable entry was just recalled in
+    /// with `grade`, if it needs both of them (see `both_directions`); the
+    /// entries with their own schedule in each direction and the ones being
+    /// relearned don't.
+    /// Returns whether the entry still waits for the other direction, so it
+    /// shouldn't be scheduled yet.
+    fn awaits_other_direction(&mut self, index: usize, grade: Grade) -> bool {
+        let entry      = &self.usable[index];
+        let own        = self.separate_directions
+            || self.is_two_way(&entry.deck);
+        let relearning = self.relearning.iter()
+            .any(|relearning| relearning.is(entry));
+        if !self.both_directions || own || relearning {
+            return false;
+        }
+
+        // Forgetting the entry in either direction starts it over
+        let direction = self.card(entry).direction;
+        let key       = (entry.word.clone(), entry.tr_word.clone());
+        if !grade.is_correct() {
+            self.recalled.remove(&key);
+            return false;
+        }
+
+        let recalled = self.recalled.entry(key.clone()).or_default();
+        recalled.insert(direction);
+        if recalled.len() < 2 {
+            return true;
+        }
+        self.recalled.remove(&key);
+        false
+    }
+
+    /// Starts relearning the just forgotten `entry` in the session, unless
+    /// there are no steps or it was relearned too many times already.
+    /// Returns whether it's being relearned.
+    fn start_relearning(&mut self, entry: &Entry, now: u64) -> bool {
+        let first = match self.relearn_steps.first() {
+            Some(&first) if self.relearn_cap > 0 => first,
+            _                                    => return false,
+        };
+        self.relearning.push(Relearning {
+            word:    entry.word.clone(),
+            tr_word: entry.tr_word.clone(),
+            step:    0,
+            due:     now + first * MINUTE,
+            lapses:  1,
+        });
+        true
+    }
+
+    /// Moves the `position`th entry being relearned to its next step after
+    /// an answer of `grade`; back to the first one if it was forgotten again.
+    /// Returns whether it's still being relearned; it's done once it's past
+    /// the last step, recalled easily or forgotten too many times.
+    fn relearn(&mut self, position: usize, grade: Grade, now: u64) -> bool {
+        let relearning = &mut self.relearning[position];
+        match grade {
+            Grade::Again => {
+                relearning.step    = 0;
+                relearning.lapses += 1;
+            },
+            Grade::Easy => relearning.step = self.relearn_steps.len(),
+            _           => relearning.step += 1,
+        }
+
+        let done = relearning.lapses > self.relearn_cap;
+        match self.relearn_steps.get(relearning.step) {
+            Some(&delay) if !done => {
+                relearning.due = now + delay * MINUTE;
+                true
+            },
+            _ => {
+                self.relearning.remove(position);
+                false
+            },
+        }
+    }
+
+    /// Returns which of the words of the entries is shown first
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns whether each direction of an entry has its own schedule; see
+    /// `set_direction`
+    pub fn separates_directions(&self) -> bool {
+        self.separate_directions
+    }
+
+    /// Changes the direction the entries are reviewed in.
+    /// If `separate` is true, each direction of an entry has its own schedule;
+    /// otherwise both directions share the same one.
+    /// The entries of the two-way decks are reviewed in both directions no
+    /// matter the direction of the database.
+    pub fn set_direction(&mut self, direction: Direction, separate: bool) {
+        let swapped = |direction, separate| {
+            direction == Direction::Reverse && separate
+        };
+        let swap = swapped(self.direction, self.separate_directions)
+            != swapped(direction, separate);
+
+        self.direction           = direction;
+        self.separate_directions = separate;
+        if swap {
+            self.reorient();
+        }
+    }
+
+    /// Sets the decks whose entries are reviewed in both directions.
+    /// Each direction of such an entry is a card of its own (see `card`) with
+    /// its own schedule, as if the entry was in the database twice; the card
+    /// that's due first is the one that's served.
+    pub fn set_two_way_decks(&mut self, decks: &BTreeSet<String>) {
+        let decks: HashSet<String> = decks.iter().cloned().collect();
+        if decks != self.two_way_decks {
+            self.two_way_decks = decks;
+            self.reorient();
+        }
+    }
+
+    /// Returns whether the entries of `deck` are reviewed in both directions
+    pub fn is_two_way(&self, deck: &str) -> bool {
+        self.two_way_decks.contains(deck)
+    }
+
+    /// Returns the card `entry` is reviewed as right now; the direction of
+    /// its schedule that's swapped in for the two-way decks, the direction
+    /// of the database otherwise. An entry that was recalled in the direction
+    /// of the database and waits for the other one (see `both_directions`)
+    /// is reviewed in the other one.
+    pub fn card(&self, entry: &Entry) -> CardId {
+        let key      = (entry.word.clone(), entry.tr_word.clone());
+        let recalled = self.recalled.get(&key)
+            .is_some_and(|recalled| recalled.contains(&self.direction));
+        let direction = match (self.is_two_way(&entry.deck), entry.reversed) {
+            (true, false)          => Direction::Forward,
+            (true, true)           => Direction::Reverse,
+            (false, _) if recalled => self.direction.flip(),
+            (false, _)             => self.direction,
+        };
+        CardId {
+            word: entry.word.clone(),
+            direction,
+        }
+    }
+
+    /// Swaps the schedule that should be reviewed into `entry` at `now`
+    /// (seconds since the epoch); the one of the direction of the database,
+    /// or the one that's due first for the entries of the two-way decks.
+    /// Returns whether the schedules were swapped.
+    fn orient(&self, entry: &mut Entry, now: u64) -> bool {
+        let swap = match self.is_two_way(&entry.deck) {
+            true  => entry.reverse.timeout < entry.timeout,
+            false => entry.reversed != (self.direction == Direction::Reverse
+                                        && self.separate_directions),
+        };
+        if swap {
+            entry.swap_schedules(now);
+        }
+        swap
+    }
+
+    /// Swaps the schedules that should be reviewed into all the entries
+    /// (see `orient`) after the directions changed
+    fn reorient(&mut self) {
+        // The saved states have the other schedule swapped in
+        self.undo.clear();
+
+        // The entries that are due differ between the schedules
+        let entries: Vec<Entry> = self.usable.drain(..)
+            .chain(self.unusable.drain(..))
+            .collect();
+        self.distribute(entries);
+    }
+
+    /// Returns whether all the entries are being crammed
+    pub fn is_cramming(&self) -> bool {
+        self.cram
+    }
+
+    /// Starts or stops cramming.
+    /// While cramming, all the entries are usable and served in a random
+    /// order, even the ones that aren't due yet. The answers shouldn't be
+    /// scheduled (with `update_timeout`) then, so that the schedules stay
+    /// untouched. A read-only database is crammed until it's closed.
+    pub fn set_cram(&mut self, cram: bool) {
+        let cram = cram || self.is_read_only();
+        if self.cram == cram {
+            return;
+        }
+        self.cram = cram;
+
+        let entries: Vec<Entry> = self.usable.drain(..)
+            .chain(self.unusable.drain(..))
+            .collect();
+        self.distribute(entries);
+    }
+
+    /// Returns whether there's an answer that can be undone
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Reverts the last answer (the last `update_timeout`); the schedule of
+    /// the entry and the metadata are restored and the entry is usable again.
+    /// Returns the restored entry and its index, just like `random_entry`,
+    /// or `None` if there's nothing to undo.
+    pub fn undo_last_update(&mut self) -> Result<Option<(Entry, usize)>> {
+        let update = match self.undo.pop() {
+            Some(update) => update,
+            None         => return Ok(None),
+        };
+
+        // The entry is most likely still timed out, but look everywhere
+        let same = |entry: &Entry| {
+            entry.word == update.entry.word
+                && entry.tr_word == update.entry.tr_word
+        };
+        if let Some(idx) = self.unusable.iter().position(same) {
+            self.unusable.swap_remove(idx);
+        } else if let Some(idx) = self.usable.iter().position(same) {
+            self.usable.swap_remove(idx);
+        } else {
+            // The entry is gone; the answer can't be undone anymore
+            return Ok(None);
+        }
+
+        let mut entry = update.entry;
+        entry.touch();
+        self.usable.push(entry.clone());
+        self.meta       = update.meta;
+        self.relearning = update.relearning;
+        self.recalled   = update.recalled;
+        self.history.remove_last()?;
+        Ok(Some((entry, self.usable.len() - 1)))
+    }
+
+    /// Returns whether there already is an entry with the original `word`
+    pub fn contains_word(&self, word: &str) -> bool {
+        let word = word.trim();
+        self.usable.iter()
+            .chain(self.unusable.iter())
+            .any(|entry| entry.word == word)
+    }
+
+    /// Adds a new word to the database (into the active deck, if any).
+    /// `tr_word` holds the translations separated by `TR_DELIMITER`, `tags`
+    /// the tags separated by `TAG_DELIMITER`.
+    /// A word with parts in double braces becomes a cloze card.
+    /// Returns false if the word is blank or there's no translation.
+    pub fn add_entry(&mut self, word: &str, tr_word: &str, notes: &str,
+                     tags: &str) -> bool {
+        let mut entry = Entry::new(word.trim(), tr_word);
+        if entry.word.is_empty() || entry.tr_word.is_empty() {
+            return false;
+        }
+
+        entry.notes   = notes.trim().to_string();
+        entry.tags    = split_tags(tags);
+        entry.deck    = self.active_deck.clone().unwrap_or_default();
+        entry.card    = CardType::detect(&entry.word);
+        entry.added   = now();
+        entry.id      = new_id();
+        entry.touch();
+        self.orient(&mut entry, now());
+        self.usable.push(entry);
+        true
+    }
+
+    /// Adds `entry` to the database as it is, e.g. an entry of a shared deck
+    /// (see `deck`); it's added now if it doesn't say when it was.
+    /// Returns false if the word is in the database already.
+    pub fn insert_entry(&mut self, mut entry: Entry) -> bool {
+        if self.contains_word(&entry.word) {
+            return false;
+        }
+
+        let now = now();
+        if entry.added == 0 {
+            entry.added = now;
+        }
+        if entry.id.is_empty() {
+            entry.id = new_id();
+        }
+        entry.timed_out = entry.timeout > now;
+        entry.touch();
+        self.orient(&mut entry, now);
+        match entry.timed_out && !self.cram {
+            true  => self.unusable.push(entry),
+            false => self.usable.push(entry),
+        }
+        true
+    }
+
+    /// Changes the words, the notes and the tags of the `index`th usable
+    /// entry, keeping its schedule.
+    /// `tr_word` and `tags` are separated like in `add_entry`.
+    /// The card type follows the word, like in `add_entry`.
+    /// Returns false if there's no such entry, the word is blank or there's
+    /// no translation.
+    pub fn edit_entry(&mut self, index: usize, word: &str, tr_word: &str,
+                      notes: &str, tags: &str) -> bool {
+        let (word, tr_word) = (word.trim(), split_translations(tr_word));
+        if word.is_empty() || tr_word.is_empty() {
+            return false;
+        }
+
+        match self.usable.get_mut(index) {
+            Some(entry) => {
+                entry.word    = word.to_string();
+                entry.tr_word = tr_word;
+                entry.notes   = notes.trim().to_string();
+                entry.tags    = split_tags(tags);
+                entry.card    = CardType::detect(word);
+                entry.touch();
+
+                // The journal records are matched to the entries by the words
+                self.needs_compaction = true;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Returns the leeches; the suspended entries, sorted by their words
+    pub fn leeches(&self) -> Vec<&Entry> {
+        let mut leeches: Vec<&Entry> = self.usable.iter()
+            .chain(self.unusable.iter())
+            .filter(|entry| entry.suspended)
+            .collect();
+        leeches.sort_by(|a, b| {
+            (&a.word, &a.tr_word).cmp(&(&b.word, &b.tr_word))
+        });
+        leeches
+    }
+
+    /// Returns the index of the leech with the word `word` and translations
+    /// `tr_word` in the usable entries, so that it can be edited like any
+    /// other entry.
+    /// A timed out leech is moved among the usable entries; being suspended,
+    /// it isn't served anyway.
+    pub fn leech_index(&mut self, word: &str, tr_word: &[String])
+            -> Option<usize> {
+        self.usable_position(|entry| {
+            entry.suspended && entry.word == word && entry.tr_word == tr_word
+        })
+    }
+
+    /// Returns the index of the entry with the word `word` and translations
+    /// `tr_word` in the usable entries, so that it can be changed like the
+    /// served ones.
+    /// An entry that isn't due is moved among the usable entries; it isn't
+    /// served before it's due anyway.
+    pub fn entry_index(&mut self, word: &str, tr_word: &[String])
+            -> Option<usize> {
+        self.usable_position(|entry| {
+            entry.word == word && entry.tr_word == tr_word
+        })
+    }
+
+    /// Changes all the entries with the words and translations in `keys` with
+    /// `change` (given the database and the index of the entry among the
+    /// usable ones, see `entry_index`), e.g. with `reset_entry`.
+    /// Returns the number of entries `change` returned true for. Nothing is
+    /// written; the changes are persisted by the next `write_db`, all at once.
+    pub fn change_entries<F>(&mut self, keys: &[(String, Vec<String>)],
+                             mut change: F) -> usize
+            where F: FnMut(&mut Self, usize) -> bool {
+        let mut changed = 0;
+        for (word, tr_word) in keys {
+            if let Some(index) = self.entry_index(word, tr_word) {
+                changed += change(self, index) as usize;
+            }
+        }
+        changed
+    }
+
+    /// Returns the index of the first entry matching `same` in the usable
+    /// entries, moving it there from the unusable ones if needed
+    fn usable_position<F>(&mut self, same: F) -> Option<usize>
+            where F: Fn(&Entry) -> bool {
+        if let Some(idx) = self.usable.iter().position(&same) {
+            return Some(idx);
+        }
+
+        let idx = self.unusable.iter().position(&same)?;
+        self.usable.push(self.unusable.swap_remove(idx));
+        Some(self.usable.len() - 1)
+    }
+
+    /// Returns the entries whose word or any of the translations contain
+    /// `query` (ignoring the case), sorted by their words.
+    /// All the entries are returned if the query is blank.
+    pub fn search(&self, query: &str) -> Vec<&Entry> {
+        let query   = query.trim().to_lowercase();
+        let matches = |text: &str| text.to_lowercase().contains(&query);
+
+        let mut found: Vec<&Entry> = self.usable.iter()
+            .chain(self.unusable.iter())
+            .filter(|entry| {
+                matches(&entry.word)
+                    || entry.tr_word.iter().any(|tr_word| matches(tr_word))
+            })
+            .collect();
+        found.sort_by(|a, b| {
+            (&a.word, &a.tr_word).cmp(&(&b.word, &b.tr_word))
+        });
+        found
+    }
+
+    /// Suspends the `index`th usable entry, so that it isn't served until
+    /// it's unsuspended.
+    /// Returns false if there's no such entry.
+    pub fn suspend(&mut self, index: usize) -> bool {
+        match self.usable.get_mut(index) {
+            Some(entry) => {
+                entry.suspended = true;
+                entry.touch();
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Hides the `index`th usable entry until tomorrow, keeping its schedule.
+    /// Returns false if there's no such entry.
+    pub fn bury(&mut self, index: usize) -> bool {
+        match self.usable.get_mut(index) {
+            Some(entry) => {
+                entry.buried_until = self.day_start.next(now());
+                entry.touch();
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Stars the `index`th usable entry, or unstars it if it's starred
+    /// already; unlike burying, it stays where it is.
+    /// Returns false if there's no such entry.
+    pub fn toggle_star(&mut self, index: usize) -> bool {
+        match self.usable.get_mut(index) {
+            Some(entry) => {
+                entry.starred = !entry.starred;
+                entry.touch();
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Postpones the `index`th usable entry by `days` days from its timeout,
+    /// or from now if it's due already. New entries have no timeout to
+    /// postpone.
+    /// Returns false if there's no such entry or it's new.
+    pub fn postpone(&mut self, index: usize, days: u64) -> bool {
+        let now = now();
+        match self.usable.get_mut(index) {
+            Some(entry) if !entry.is_new() => {
+                entry.timeout   = entry.timeout.max(now) + days * DAY;
+                entry.timed_out = entry.timeout > now;
+                entry.touch();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Moves the `index`th usable entry into `deck`; "" for no deck.
+    /// Returns false if there's no such entry.
+    pub fn set_deck(&mut self, index: usize, deck: &str) -> bool {
+        match self.usable.get_mut(index) {
+            Some(entry) => {
+                entry.deck = deck.trim().to_string();
+                entry.touch();
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Forgets the schedules (of both directions), the lapses and the
+    /// suspension of the `index`th usable entry, so that it's new again.
+    /// Returns false if there's no such entry.
+    pub fn reset_entry(&mut self, index: usize) -> bool {
+        match self.usable.get_mut(index) {
+            Some(entry) => {
+                *entry = Entry {
+                    tr_word:  entry.tr_word.clone(),
+                    deck:     entry.deck.clone(),
+                    notes:    entry.notes.clone(),
+                    audio:    entry.audio.clone(),
+                    image:    entry.image.clone(),
+                    tags:     entry.tags.clone(),
+                    card:     entry.card,
+                    starred:  entry.starred,
+                    added:    entry.added,
+                    id:       entry.id.clone(),
+                    reversed: entry.reversed,
+                    ..Entry::new(&entry.word, "")
+                };
+                entry.touch();
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Unsuspends the `index`th usable entry and forgets its lapses.
+    /// The entry is served again right away, no matter its timeout.
+    /// Returns false if there's no such entry.
+    pub fn unsuspend(&mut self, index: usize) -> bool {
+        match self.usable.get_mut(index) {
+            Some(entry) => {
+                entry.suspended = false;
+                entry.lapses    = 0;
+                entry.timed_out = false;
+                entry.touch();
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Removes the `index`th usable entry from the database and returns it
+    pub fn remove_entry(&mut self, index: usize) -> Option<Entry> {
+        if index >= self.usable.len() {
+            return None;
+        }
+
+        // A removal can't be expressed in the journal
+        self.needs_compaction = true;
+        Some(self.usable.swap_remove(index))
+    }
+
+    /// Merges the entries of `other` (e.g. a copy of this database from
+    /// another machine) into this database.
+    /// The entries are matched by their ids, or by their original words if
+    /// the ids differ (e.g. the same word was added on both machines). The
+    /// entries missing in this database are added; of the entries in both,
+    /// the one that was changed last is kept (see `Entry::is_newer`), so
+    /// merging the databases both ways leaves them the same.
+    pub fn merge(&mut self, other: &Database) -> MergeSummary {
+        let now         = now();
+        let mut summary = MergeSummary::default();
+        let mut entries: Vec<Entry> = self.usable.drain(..)
+            .chain(self.unusable.drain(..))
+            .collect();
+        let mut ids: HashMap<String, usize>   = HashMap::new();
+        let mut words: HashMap<String, usize> = HashMap::new();
+        for (idx, entry) in entries.iter().enumerate() {
+            ids.insert(entry.id.clone(), idx);
+            words.entry(entry.word.clone()).or_insert(idx);
+        }
+
+        // The schedules are compared as they're stored, no matter the
+        // directions the databases are reviewed in.
+        // The entries keep when they were changed; the merge changes nothing.
+        for theirs in other.usable.iter().chain(other.unusable.iter()) {
+            let mut theirs = theirs.stored().into_owned();
+            let idx = ids.get(&theirs.id)
+                .or_else(|| words.get(&theirs.word))
+                .copied();
+            if idx.is_some_and(|idx| {
+                !theirs.is_newer(&entries[idx].stored())
+            }) {
+                summary.unchanged += 1;
+                continue;
+            }
+
+            theirs.timed_out = theirs.timeout > now;
+            theirs.dirty     = true;
+            match idx {
+                Some(idx) => {
+                    ids.insert(theirs.id.clone(), idx);
+                    words.insert(theirs.word.clone(), idx);
+                    entries[idx] = theirs;
+                    summary.updated += 1;
+                },
+                None => {
+                    ids.insert(theirs.id.clone(), entries.len());
+                    words.insert(theirs.word.clone(), entries.len());
+                    entries.push(theirs);
+                    summary.added += 1;
+                },
+            }
+        }
+        self.distribute(entries);
+
+        // The saved states and the journal records are matched to the entries
+        // by the words, which may belong to other entries now
+        if summary.added != 0 || summary.updated != 0 {
+            self.undo.clear();
+            self.needs_compaction = true;
+        }
+        summary
+    }
+
+    /// Returns the groups of entries whose original words only differ in
+    /// case, diacritics or whitespace (see `grading::fold`), e.g. `čaj` and
+    /// `Caj`; each with at least two entries, sorted by the words
+    pub fn find_duplicates(&self) -> Vec<Vec<&Entry>> {
+        let mut groups: BTreeMap<String, Vec<&Entry>> = BTreeMap::new();
+        for entry in self.usable.iter().chain(self.unusable.iter()) {
+            groups.entry(grading::fold(&entry.word))
+                .or_default()
+                .push(entry);
+        }
+
+        groups.into_values()
+            .filter(|group| group.len() > 1)
+            .map(|mut group| {
+                group.sort_by(|a, b| {
+                    (&a.word, &a.tr_word).cmp(&(&b.word, &b.tr_word))
+                });
+                group
+            })
+            .collect()
+    }
+
+    /// Merges the entries with the words and translations in `keys` (e.g. a
+    /// group returned by `find_duplicates`) into one.
+    /// The entry that got the furthest (the longest interval, then the
+    /// latest timeout) is kept with its schedule; it gets the translations
+    /// of the others and their notes, audio, image and deck if it has none,
+    /// and it's starred if any of them is.
+    /// Returns false if fewer than two of the entries were found; nothing
+    /// is merged then.
+    pub fn merge_duplicates(&mut self, keys: &[(String, Vec<String>)])
+            -> bool {
+        let is_key = |entry: &Entry| {
+            keys.iter().any(|(word, tr_word)| {
+                entry.word == *word && entry.tr_word == *tr_word
+            })
+        };
+        let (mut merged, mut entries): (Vec<Entry>, Vec<Entry>) =
+            self.usable.drain(..)
+                .chain(self.unusable.drain(..))
+                .partition(is_key);
+        if merged.len() < 2 {
+            entries.append(&mut merged);
+            self.distribute(entries);
+            return false;
+        }
+
+        let best = (0..merged.len())
+            .max_by_key(|&idx| {
+                let entry = &merged[idx];
+                (!entry.is_new(), self.interval(entry), entry.timeout)
+            })
+            .unwrap_or(0);
+        let mut kept = merged.swap_remove(best);
+        for other in merged {
+            for tr_word in other.tr_word {
+                if !kept.tr_word.contains(&tr_word) {
+                    kept.tr_word.push(tr_word);
+                }
+            }
+            let fields = [
+                (&mut kept.notes, other.notes),
+                (&mut kept.audio, other.audio),
+                (&mut kept.image, other.image),
+                (&mut kept.deck,  other.deck),
+            ];
+            for (field, theirs) in fields {
+                if field.is_empty() {
+                    *field = theirs;
+                }
+            }
+            kept.starred |= other.starred;
+        }
+        kept.touch();
+        entries.push(kept);
+        self.distribute(entries);
+
+        // The saved states and the journal records are matched to the entries
+        // by the words, and a removal can't be expressed in the journal
+        self.undo.clear();
+        self.needs_compaction = true;
+        true
+    }
+
+    /// Puts `entries` among the usable or the unusable ones by whether
+    /// they're due; all of them are usable while cramming.
+    /// The schedules that should be reviewed are swapped in first.
+    fn distribute(&mut self, mut entries: Vec<Entry>) {
+        let now = now();
+        for entry in entries.iter_mut() {
+            self.orient(entry, now);
+        }
+
+        if self.cram {
+            self.usable   = entries;
+            self.unusable = Vec::new();
+            return;
+        }
+
+        let (unusable, usable) = entries.into_iter()
+            .partition(|entry| entry.timed_out);
+        self.usable   = usable;
+        self.unusable = unusable;
+    }
+}
+
+
+/// What happened during a merge of two databases
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    /// The number of words added to the database
+    pub added: usize,
+
+    /// The number of entries replaced by the more recent ones of the other
+    /// database
+    pub updated: usize,
+
+    /// The number of entries of the other database that weren't newer
+    pub unchanged: usize,
+}
+
+
+/// The state of the database before an answer, used to undo it
+#[derive(Clone, Debug)]
+struct Update {
+    /// The answered entry as it was before the answer
+    entry: Entry,
+
+    /// The metadata as it was before the answer
+    meta: Meta,
+
+    /// The entries being relearned before the answer
+    relearning: Vec<Relearning>,
+
+    /// The directions the entries were recalled in before the answer
+    recalled: HashMap<(String, Vec<String>), HashSet<Direction>>,
+}
+
+
+/// An entry being relearned in the session after it was forgotten; see
+/// `Database::update_timeout`
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Relearning {
+    /// The original word of the entry
+    word: String,
+
+    /// The translations of the entry
+    tr_word: Vec<String>,
+
+    /// The index of the step of `Database::relearn_steps` the entry is at
+    step: usize,
+
+    /// When (in seconds since the epoch) the entry is served again
+    due: u64,
+
+    /// The number of times the entry was forgotten in the session
+    lapses: u32,
+}
+
+impl Relearning {
+    /// Returns whether `entry` is the entry being relearned
+    fn is(&self, entry: &Entry) -> bool {
+        entry.word == self.word && entry.tr_word == self.tr_word
+    }
+}
+
+
+/// A card an entry is reviewed as; the entry shown in one of the directions.
+/// The entries of the two-way decks are two cards, one in each direction,
+/// with their own schedules. The other entries are a single card in the
+/// direction of the database.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CardId {
+    /// The original word of the entry
+    pub word: String,
+
+    /// The direction the entry is shown in
+    pub direction: Direction,
+}
+
+
+/// Which of the words of an entry is shown first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize,
+         Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// The original word is shown and the translation is recalled
+    #[default]
+    Forward,
+
+    /// The translation is shown and the original word is recalled
+    Reverse,
+}
+
+impl Direction {
+    /// Returns the other direction
+    pub fn flip(self) -> Self {
+        match self {
+            Direction::Forward => Direction::Reverse,
+            Direction::Reverse => Direction::Forward,
+        }
+    }
+}
+
+
+/// How the next entry to review is selected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Selection {
+    /// Any usable entry at random; the overdue and the recently forgotten
+    /// ones are more likely
+    Random,
+
+    /// The review that has been due the longest
+    MostOverdue,
+}
+
+
+/// The state of the new word batches.
+/// New words are first presented to the user (with the translation shown)
+/// back to back, and only then is the whole batch quizzed.
+#[derive(Clone, Debug)]
+pub struct Batches {
+    /// The number of new words in one batch
+    pub size: usize,
+
+    /// Words of the current batch that are yet to be presented
+    presenting: Vec<String>,
+
+    /// Words of the current batch that are yet to be quizzed
+    quizzing: Vec<String>,
+
+    /// Whether the last served word was being presented
+    learning: bool,
+}
+
+impl Batches {
+    /// Creates a new batch state with batches of `size` words
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            presenting: Vec::new(),
+            quizzing:   Vec::new(),
+            learning:   false,
+        }
+    }
+
+    /// Returns whether all the words of the current batch have been quizzed
+    pub fn is_done(&self) -> bool {
+        self.presenting.is_empty() && self.quizzing.is_empty()
+    }
+}
+
+
+/// The schedule of the reverse direction of an entry.
+/// The fields mirror the scheduling fields of `Entry`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schedule {
+    pub cur_iter:   usize,
+    pub timeout:    u64,
+    pub ease:       f64,
+    pub interval:   u64,
+    pub reps:       u32,
+    pub stability:  f64,
+    pub difficulty: f64,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            cur_iter:   0,
+            timeout:    0,
+            ease:       DEFAULT_EASE,
+            interval:   0,
+            reps:       0,
+            stability:  0.,
+            difficulty: 0.,
+        }
+    }
+}
+
+
+/// An entry in the database struct
+#[derive(Clone, Debug)]
+pub struct Entry {
+    /// The original word.
+    pub word: String,
+
+    /// The translations of the word; there's always at least one in a valid
+    /// entry
+    pub tr_word: Vec<String>,
+
+    /// Current timeout delay iteration
+    pub cur_iter: usize,
+
+    /// The word can't be used until this time
+    pub timeout: u64,
+
+    /// Whether the word is usable already or still on a timeout.
+    /// This value isn't really needed - it can be calculated on the run,
+    /// but it makes the code prettier and _very slightly_ faster.
+    pub timed_out: bool,
+
+    /// The deck the entry belongs to, or "" if it doesn't belong to any
+    pub deck: String,
+
+    /// The ease factor of the SM-2 scheduler
+    pub ease: f64,
+
+    /// The last interval (in days) of the SM-2 and FSRS schedulers
+    pub interval: u64,
+
+    /// The number of consecutive correct answers of the SM-2 scheduler
+    pub reps: u32,
+
+    /// The stability (in days) of the memory of the word in the FSRS
+    /// scheduler; 0 if it was never scheduled by it
+    pub stability: f64,
+
+    /// The difficulty (1-10) of the word in the FSRS scheduler; 0 if it was
+    /// never scheduled by it
+    pub difficulty: f64,
+
+    /// The schedule of the direction that isn't being reviewed; only used if
+    /// the directions are scheduled separately
+    pub reverse: Schedule,
+
+    /// The number of times the word was forgotten after it was learned
+    pub lapses: u32,
+
+    /// Whether the entry is a leech that isn't served anymore
+    pub suspended: bool,
+
+    /// Whether the user starred the entry as a tricky one, to review the
+    /// starred entries on their own (see `Database::starred_only`)
+    pub starred: bool,
+
+    /// The entry isn't served until this time (in seconds since the epoch),
+    /// no matter its timeout; 0 if it was never buried
+    pub buried_until: u64,
+
+    /// When (in seconds since the epoch) the entry was first answered; 0 if
+    /// it's new
+    pub introduced: u64,
+
+    /// When (in seconds since the epoch) the entry was added to the
+    /// database; 0 if it isn't known, e.g. it was added by an older version
+    pub added: u64,
+
+    /// When (in seconds since the epoch) the entry was last answered; 0 if
+    /// it never was
+    pub reviewed: u64,
+
+    /// When (in seconds since the epoch) the entry was last changed, by an
+    /// answer or an edit; 0 if it isn't known, e.g. it was last changed by an
+    /// older version
+    pub modified: u64,
+
+    /// The id of the entry (see `new_id`); it stays the same when the words
+    /// are edited, and the copies of the entry in the copies of the database
+    /// share it. "" until the entry is given one by the database.
+    pub id: String,
+
+    /// Notes or example sentences shown with the translations, or "" if
+    /// there are none
+    pub notes: String,
+
+    /// The path of an audio file with the pronunciation of the word, relative
+    /// to the database, or "" if there's none
+    pub audio: String,
+
+    /// The path of an image shown with the word, relative to the database,
+    /// or "" if there's none
+    pub image: String,
+
+    /// The tags of the entry, independent of its deck; every tag is there
+    /// only once
+    pub tags: Vec<String>,
+
+    /// How the entry is shown when it's reviewed
+    pub card: CardType,
+
+    /// Whether the schedules of the directions are swapped
+    reversed: bool,
+
+    /// Whether the entry changed since it was last written to the file
+    pub dirty: bool,
+}
+
+impl Entry {
+    /// Creates a new entry that has never been reviewed.
+    /// `tr_word` holds the translations separated by `TR_DELIMITER`.
+    pub fn new(word: &str, tr_word: &str) -> Self {
+        Self {
+            word:         word.to_string(),
+            tr_word:      split_translations(tr_word),
+            cur_iter:     0,
+            timeout:      0,
+            timed_out:    false,
+            deck:         String::new(),
+            ease:         DEFAULT_EASE,
+            interval:     0,
+            reps:         0,
+            stability:    0.,
+            difficulty:   0.,
+            reverse:      Schedule::default(),
+            lapses:       0,
+            suspended:    false,
+            starred:      false,
+            buried_until: 0,
+            introduced:   0,
+            added:        0,
+            reviewed:     0,
+            modified:     0,
+            id:           String::new(),
+            notes:        String::new(),
+            audio:        String::new(),
+            image:        String::new(),
+            tags:         Vec::new(),
+            card:         CardType::Basic,
+            reversed:     false,
+            dirty:        false,
+        }
+    }
+
+    /// Returns the entry with the schedule of `schedule` and everything else,
+    /// the words included, of itself
+    fn with_schedule(self, schedule: Entry) -> Entry {
+        Entry {
+            word:     self.word,
+            tr_word:  self.tr_word,
+            deck:     self.deck,
+            added:    self.added,
+            modified: self.modified,
+            id:       self.id,
+            notes:    self.notes,
+            audio:    self.audio,
+            image:    self.image,
+            tags:     self.tags,
+            card:     self.card,
+            dirty:    self.dirty,
+            ..schedule
+        }
+    }
+
+    /// Returns just the words and the schedule of the entry; what the
+    /// schedule file of a profile keeps of it
+    fn schedule(&self) -> Entry {
+        let words = Entry {
+            tr_word: self.tr_word.clone(),
+            ..Entry::new(&self.word, "")
+        };
+        words.with_schedule(self.clone())
+    }
+
+    /// Brings the entry into its canonical form; the one it has after being
+    /// written with `db_repr` and parsed back.
+    /// The word, the translations and the text columns are trimmed,
+    /// translations holding `TR_DELIMITER` are split and the blank ones are
+    /// dropped, so are the tags, and the iterations are kept within the
+    /// longest ladder.
+    pub fn normalize(&mut self) {
+        let translations = join_translations(&self.tr_word);
+        self.tr_word = split_translations(&translations);
+        self.tags    = split_tags(&join_tags(&self.tags));
+        for column in [&mut self.word, &mut self.deck, &mut self.notes,
+                       &mut self.audio, &mut self.image, &mut self.id] {
+            *column = column.trim().to_string();
+        }
+        self.cur_iter         = self.cur_iter.min(MAX_LADDER_STEPS - 1);
+        self.reverse.cur_iter = self.reverse.cur_iter
+            .min(MAX_LADDER_STEPS - 1);
+    }
+
+    /// Parses a line taken from a textfile and returns a corresponding Entry.
+    /// Returns `None` if the line is broken; see `parse` for why.
+    pub fn parse_from_line(line: &str) -> Option<Self> {
+        Self::parse(line).ok()
+    }
+
+    /// Parses a line taken from a textfile and returns a corresponding Entry,
+    /// or what's wrong with the line.
+    pub fn parse(line: &str) -> std::result::Result<Self, ParseError> {
+        // Extract the elements from the line
+        let elements = split_line(line);
+
+        let mut entry = Self::new("", "");
+
+        // If there's 4 or more elements, the entry is valid.
+        // If there's 2 elements, the entry is new (no time info) but valid.
+        if elements.len() != 2 && elements.len() < 4 {
+            return Err(ParseError::ColumnCount(elements.len()));
+        }
+
+        // All entries.
+        // Whitespace-only words would show up as blank buttons in the ui,
+        // so they are treated as invalid.
+        entry.word    = elements[0].trim().to_string();
+        entry.tr_word = split_translations(&elements[1]);
+        if entry.word.is_empty() || entry.tr_word.is_empty() {
+            return Err(ParseError::BlankWord);
+        }
+
+        // Already initialized entries
+        if elements.len() >= 4 {
+            let iter       = elements[2].trim();
+            entry.cur_iter = parse_cur_iter(iter)
+                .ok_or_else(|| ParseError::Iteration(iter.to_string()))?;
+
+            // Try to parse the timeout into an integer and compare it
+            // to current time. If it's greater than current time,
+            // the word is on a timeout.
+            let timeout     = elements[3].trim();
+            entry.timeout   = timeout.parse::<u64>()
+                .map_err(|_| ParseError::Timeout(timeout.to_string()))?;
+            entry.timed_out = entry.timeout > now();
+        }
+
+        // The optional `key=value` columns.
+        // Unknown keys are ignored so that older versions can still read the
+        // files of newer ones.
+        for field in elements.iter().skip(4) {
+            let mut split = field.splitn(2, FIELD_DELIMITER);
+            let key       = split.next().unwrap_or("").trim();
+            let value     = split.next().unwrap_or("").trim();
+
+            match key {
+                "id"       => entry.id       = value.to_string(),
+                "deck"     => entry.deck     = value.to_string(),
+                "notes"    => entry.notes    = value.to_string(),
+                "audio"    => entry.audio    = value.to_string(),
+                "image"    => entry.image    = value.to_string(),
+                "tags"     => entry.tags     = split_tags(value),
+                "card"     => entry.card     = parse_field(field, value)?,
+                "ease"     => entry.ease     = parse_field(field, value)?,
+                "interval" => entry.interval = parse_field(field, value)?,
+                "reps"     => entry.reps     = parse_field(field, value)?,
+
+                // The memory state of the FSRS scheduler
+                "stability"  => entry.stability  = parse_field(field, value)?,
+                "difficulty" => entry.difficulty = parse_field(field, value)?,
+
+                // The leech detection
+                "lapses"    => entry.lapses    = parse_field(field, value)?,
+                "suspended" => entry.suspended = parse_field(field, value)?,
+                "starred"   => entry.starred   = parse_field(field, value)?,
+                "buried"    => entry.buried_until = parse_field(field, value)?,
+
+                // The new word limits of the decks
+                "introduced" => entry.introduced = parse_field(field, value)?,
+
+                // The timestamps of the entry
+                "added"    => entry.added    = parse_field(field, value)?,
+                "reviewed" => entry.reviewed = parse_field(field, value)?,
+                "modified" => entry.modified = parse_field(field, value)?,
+
+                // The schedule of the reverse direction
+                "rev_iter"     => {
+                    entry.reverse.cur_iter = parse_cur_iter(value)
+                        .ok_or_else(|| ParseError::Field(field.clone()))?;
+                },
+                "rev_timeout"  => {
+                    entry.reverse.timeout  = parse_field(field, value)?;
+                },
+                "rev_ease"     => {
+                    entry.reverse.ease     = parse_field(field, value)?;
+                },
+                "rev_interval" => {
+                    entry.reverse.interval = parse_field(field, value)?;
+                },
+                "rev_reps"     => {
+                    entry.reverse.reps     = parse_field(field, value)?;
+                },
+                "rev_stability"  => {
+                    entry.reverse.stability  = parse_field(field, value)?;
+                },
+                "rev_difficulty" => {
+                    entry.reverse.difficulty = parse_field(field, value)?;
+                },
+                _ => {},
+            }
+        }
+
+        Ok(entry)
+    }
+
+    /// Returns the number of seconds until this entry is usable again, or 0
+    /// if it's usable at `now` (seconds since the epoch)
+    pub fn time_until_due(&self, now: u64) -> u64 {
+        self.timeout.saturating_sub(now)
+    }
+
+    /// Returns the number of whole days (rounded up) until this entry is
+    /// usable again, or 0 if it's usable at `now` (seconds since the epoch)
+    pub fn days_until_due(&self, now: u64) -> u64 {
+        self.time_until_due(now).div_ceil(DAY)
+    }
+
+    /// Returns the translations joined for showing them to the user
+    pub fn translations(&self) -> String {
+        self.tr_word.join(", ")
+    }
+
+    /// Returns whether the entry is tagged with `tag`
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|other| other == tag)
+    }
+
+    /// Returns the sentence of this entry with its hidden parts if it's a
+    /// cloze card, or `None` if it isn't one or its sentence is broken
+    pub fn cloze(&self) -> Option<Cloze> {
+        match self.card {
+            CardType::Cloze => Cloze::parse(&self.word),
+            CardType::Basic => None,
+        }
+    }
+
+    /// Returns whether this entry is buried and not served at `now` (seconds
+    /// since the epoch)
+    pub fn is_buried(&self, now: u64) -> bool {
+        self.buried_until > now
+    }
+
+    /// Returns whether this entry has never been reviewed
+    pub fn is_new(&self) -> bool {
+        self.timeout == 0
+    }
+
+    /// Updates the timeout of this entry using `scheduler` with the timeout
+    /// `ladder` (in days) of its deck.
+    /// `grade` is how well the user knew the word.
+    pub fn update_timeout(&mut self, grade: Grade, scheduler: &dyn Scheduler,
+                          ladder: &[u64], day_start: DayStart) {
+        if self.timed_out {
+            return;
+        }
+
+        // Forgetting a word that was learned already is a lapse
+        if !grade.is_correct() && !self.is_new() {
+            self.lapses += 1;
+        }
+
+        // `timed_out` is also set to true.
+        let now = now();
+        scheduler.schedule(self, grade, ladder, now);
+        self.fuzz_timeout(now);
+        self.timeout   = day_start.start(self.timeout);
+        self.timed_out = true;
+        self.touch();
+    }
+
+    /// Fuzzes (see `scheduler::fuzz`) the interval the entry was just
+    /// scheduled at `now` (seconds since the epoch) with; the timeout, and
+    /// the interval if the scheduler remembers it
+    fn fuzz_timeout(&mut self, now: u64) {
+        let days   = self.timeout.saturating_sub(now) / DAY;
+        let fuzzed = fuzz(days, fuzz_seed(&self.id, self.timeout));
+        self.timeout = self.timeout - days * DAY + fuzzed * DAY;
+        if self.interval == days {
+            self.interval = fuzzed;
+        }
+    }
+
+    /// Marks the entry as changed just now; it's written by the next
+    /// `write_db` and wins over its older copies in a merge
+    pub fn touch(&mut self) {
+        self.modified = now();
+        self.dirty    = true;
+    }
+
+    /// Returns whether this entry was changed after `other`, a copy of it
+    /// (e.g. in another database); see `Database::merge`.
+    /// The entries are compared by when they were changed, then by their
+    /// timeouts for the ones that don't know it, then by their stored forms,
+    /// so that of two different copies, exactly one is the newer one on any
+    /// machine.
+    pub fn is_newer(&self, other: &Entry) -> bool {
+        let (this, other) = (self.stored(), other.stored());
+        (this.modified, this.timeout, this.db_repr())
+            > (other.modified, other.timeout, other.db_repr())
+    }
+
+    /// Swaps the schedule of the reviewed direction with the one in `reverse`.
+    /// `now` is used to tell whether the swapped in schedule is due.
+    pub fn swap_schedules(&mut self, now: u64) {
+        let reverse = &mut self.reverse;
+        std::mem::swap(&mut self.cur_iter,   &mut reverse.cur_iter);
+        std::mem::swap(&mut self.timeout,    &mut reverse.timeout);
+        std::mem::swap(&mut self.ease,       &mut reverse.ease);
+        std::mem::swap(&mut self.interval,   &mut reverse.interval);
+        std::mem::swap(&mut self.reps,       &mut reverse.reps);
+        std::mem::swap(&mut self.stability,  &mut reverse.stability);
+        std::mem::swap(&mut self.difficulty, &mut reverse.difficulty);
+        self.timed_out = self.timeout > now;
+        self.reversed  = !self.reversed;
+    }
+
+    /// Returns this entry as it's stored; with the forward schedule in the
+    /// scheduling fields, even while the entry is reviewed in reverse
+    fn stored(&self) -> Cow<'_, Self> {
+        if !self.reversed {
+            return Cow::Borrowed(self);
+        }
+
+        let mut entry = self.clone();
+        entry.swap_schedules(now());
+        Cow::Owned(entry)
+    }
+
+    /// Returns the in-database representation of this entry; a line of the
+    /// text format (see the module documentation), without the line break
+    pub fn db_repr(&self) -> String {
+        // The fixed columns always hold the forward schedule
+        if self.reversed {
+            return self.stored().db_repr();
+        }
+
+        let mut repr = format!("{}{}{}{}{}{}{}",
+                               escape(&self.word),    DELIMITER,
+                               escape(&join_translations(&self.tr_word)),
+                               DELIMITER,
+                               self.cur_iter, DELIMITER,
+                               self.timeout);
+
+        // The optional columns are only written if they aren't the defaults,
+        // so databases that don't use them look just like they always did
+        let mut field = |key: &str, value: String| {
+            repr.push_str(&format!("{}{}{}{}",
+                                   DELIMITER, key, FIELD_DELIMITER, value));
+        };
+        if !self.id.is_empty() {
+            field("id", escape(&self.id));
+        }
+        if !self.deck.is_empty() {
+            field("deck", escape(&self.deck));
+        }
+        if !self.notes.is_empty() {
+            field("notes", escape(&self.notes));
+        }
+        if !self.audio.is_empty() {
+            field("audio", escape(&self.audio));
+        }
+        if !self.image.is_empty() {
+            field("image", escape(&self.image));
+        }
+        if !self.tags.is_empty() {
+            field("tags", escape(&join_tags(&self.tags)));
+        }
+        if self.card != CardType::Basic {
+            field("card", self.card.to_string());
+        }
+        if self.ease != DEFAULT_EASE {
+            field("ease", self.ease.to_string());
+        }
+        if self.interval != 0 {
+            field("interval", self.interval.to_string());
+        }
+        if self.reps != 0 {
+            field("reps", self.reps.to_string());
+        }
+        if self.stability != 0. {
+            field("stability", self.stability.to_string());
+        }
+        if self.difficulty != 0. {
+            field("difficulty", self.difficulty.to_string());
+        }
+        if self.lapses != 0 {
+            field("lapses", self.lapses.to_string());
+        }
+        if self.suspended {
+            field("suspended", self.suspended.to_string());
+        }
+        if self.starred {
+            field("starred", self.starred.to_string());
+        }
+        if self.buried_until != 0 {
+            field("buried", self.buried_until.to_string());
+        }
+        if self.introduced != 0 {
+            field("introduced", self.introduced.to_string());
+        }
+        if self.added != 0 {
+            field("added", self.added.to_string());
+        }
+        if self.reviewed != 0 {
+            field("reviewed", self.reviewed.to_string());
+        }
+        if self.modified != 0 {
+            field("modified", self.modified.to_string());
+        }
+
+        let reverse = &self.reverse;
+        if reverse.cur_iter != 0 {
+            field("rev_iter", reverse.cur_iter.to_string());
+        }
+        if reverse.timeout != 0 {
+            field("rev_timeout", reverse.timeout.to_string());
+        }
+        if reverse.ease != DEFAULT_EASE {
+            field("rev_ease", reverse.ease.to_string());
+        }
+        if reverse.interval != 0 {
+            field("rev_interval", reverse.interval.to_string());
+        }
+        if reverse.reps != 0 {
+            field("rev_reps", reverse.reps.to_string());
+        }
+        if reverse.stability != 0. {
+            field("rev_stability", reverse.stability.to_string());
+        }
+        if reverse.difficulty != 0. {
+            field("rev_difficulty", reverse.difficulty.to_string());
+        }
+
+        repr
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn detects_the_format_by_the_contents() {
+        assert_eq!(Format::detect(b"Hund;; dog;; 0;; 0\n"), Some(Format::Text));
+        assert_eq!(Format::detect(b""), Some(Format::Text));
+        assert_eq!(Format::detect(b"[{\"word\": \"Hund\"}]"),
+                   Some(Format::Json));
+        assert_eq!(Format::detect(b"\x00\x01\x02\x03"), None);
+
+        // Text is text, however broken its lines
+        assert_eq!(Format::detect(b"just some notes\n"), Some(Format::Text));
+    }
+
+    #[test]
+    fn opens_a_text_database_with_a_json_extension() {
+        let dir  = TempDir::new("text-as-json");
+        let path = dir.write("words.json", b"Hund;; dog;; 0;; 0\n");
+        let db   = Database::open(path).unwrap();
+        assert_eq!(db.usable.len(), 1);
+        assert_eq!(db.usable[0].word, "Hund");
+        assert_eq!(db.usable[0].tr_word, vec!["dog".to_string()]);
+    }
+
+    #[test]
+    fn flush_and_close_writes_the_changes_and_unlocks() {
+        let dir    = TempDir::new("flush-and-close");
+        let path   = dir.write("words.txt", b"Hund;; dog;; 0;; 0\n");
+        let mut db = Database::open(path.clone()).unwrap();
+        assert!(paths::lock_path(&path).exists());
+
+        db.update_timeout(0, Grade::Good, None);
+        assert!(db.is_dirty());
+        db.flush_and_close().unwrap();
+        assert!(!paths::lock_path(&path).exists());
+
+        let db = Database::open(path).unwrap();
+        assert!(db.usable.is_empty());
+        assert_eq!(db.unusable[0].cur_iter, 1);
+    }
+
+    #[test]
+    fn only_the_graded_entries_are_appended() {
+        // The entries have their ids already, so none of them is changed
+        // just by opening the database
+        let dir      = TempDir::new("dirty-entries");
+        let contents = (0..10)
+            .map(|idx| format!("w{};; t{};; 0;; 0;; id={}\n", idx, idx, idx))
+            .collect::<String>();
+        let path   = dir.write("words.txt", contents.as_bytes());
+        let mut db = Database::open(path.clone()).unwrap();
+        assert!(db.dirty_entries().is_empty());
+
+        for _ in 0..2 {
+            let (_, idx) = db.random_entry().unwrap();
+            db.update_timeout(idx, Grade::Good, None);
+        }
+        let mut graded: Vec<String> = db.dirty_entries().iter()
+            .map(|entry| entry.db_repr())
+            .collect();
+        assert_eq!(graded.len(), 2);
+        db.write_db().unwrap();
+        assert!(!db.is_dirty());
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        let (start, journal) = written.split_at(contents.len());
+        assert_eq!(start, contents);
+        let mut journal: Vec<&str> = journal.lines().collect();
+        assert_eq!(journal.remove(0), JOURNAL_MARKER);
+        journal.sort();
+        graded.sort();
+        assert_eq!(journal, graded);
+    }
+
+    #[test]
+    fn profiles_keep_independent_schedules_of_the_same_words() {
+        let dir  = TempDir::new("profiles");
+        let path = dir.write("words.txt",
+                             b"Hund;; dog;; 0;; 0\nKatze;; cat;; 0;; 0\n");
+
+        // Each profile learns a different word
+        for (profile, word) in [("anna", "Hund"), ("ben", "Katze")].iter() {
+            let mut db = Database::open_profile(path.clone(), profile)
+                .unwrap();
+            let idx = db.usable.iter()
+                .position(|entry| entry.word == *word)
+                .unwrap();
+            db.update_timeout(idx, Grade::Good, None);
+            db.flush_and_close().unwrap();
+        }
+
+        for (profile, word) in [("anna", "Hund"), ("ben", "Katze")].iter() {
+            let db = Database::open_profile(path.clone(), profile).unwrap();
+            assert_eq!(db.unusable.len(), 1);
+            assert_eq!(db.unusable[0].word, *word);
+            assert_eq!(db.unusable[0].cur_iter, 1);
+            assert_eq!(db.usable.len(), 1);
+            assert!(db.usable[0].is_new());
+            db.flush_and_close().unwrap();
+        }
+
+        // The shared words don't carry any schedule
+        let db = Database::open(path).unwrap();
+        assert!(db.usable.iter().all(Entry::is_new));
+        assert_eq!(db.usable.len(), 2);
+    }
+
+    #[test]
+    fn profiles_keep_the_content_of_the_shared_words() {
+        let dir  = TempDir::new("profile_content");
+        let path = dir.write("words.txt",
+                             b"Hund;; dog;; 0;; 0;; deck=Animals;; \
+                               notes=Der Hund bellt.;; tags=pets\n");
+
+        for profile in ["anna", "ben"].iter() {
+            let mut db = Database::open_profile(path.clone(), profile)
+                .unwrap();
+            db.update_timeout(0, Grade::Good, None);
+            db.flush_and_close().unwrap();
+        }
+
+        let db    = Database::open_profile(path.clone(), "anna").unwrap();
+        let entry = &db.unusable[0];
+        assert_eq!(entry.deck, "Animals");
+        assert_eq!(entry.notes, "Der Hund bellt.");
+        assert_eq!(entry.tags, vec!["pets".to_string()]);
+        assert_eq!(entry.cur_iter, 1);
+        db.flush_and_close().unwrap();
+
+        // The schedule file holds nothing but the schedule
+        let schedule = std::fs::read_to_string(
+            paths::profile_path(&path, "anna")).unwrap();
+        assert!(!schedule.contains("Animals"));
+        assert!(!schedule.contains("bellt"));
+    }
+
+    #[test]
+    fn merges_the_newer_entries_matched_by_their_ids_or_words() {
+        let dir    = TempDir::new("merge");
+        let mine   = dir.write("mine.txt", b"\
+            Hund;; dog;; 1;; 0;; id=a;; modified=100\n\
+            Katze;; cat;; 2;; 0;; id=b;; modified=300\n\
+            Vogel;; bird;; 0;; 0;; id=c\n\
+            Fisch;; fish;; 0;; 0;; id=e\n");
+        let theirs = dir.write("theirs.txt", b"\
+            Hund;; hound;; 3;; 0;; id=a;; modified=200\n\
+            Katze;; kitty;; 1;; 0;; id=b;; modified=200\n\
+            Maus;; mouse;; 0;; 0;; id=d\n\
+            Fisch;; fish;; 4;; 0;; id=f;; modified=50\n");
+        let mut mine   = Database::open(mine).unwrap();
+        let mut theirs = Database::open(theirs).unwrap();
+
+        assert_eq!(mine.merge(&theirs), MergeSummary {
+            added:     1,
+            updated:   2,
+            unchanged: 1,
+        });
+
+        let entry = |db: &Database, word: &str| db.usable.iter()
+            .chain(db.unusable.iter())
+            .find(|entry| entry.word == word)
+            .cloned()
+            .unwrap();
+
+        // The newer one of the matched entries wins, by id or by word
+        assert_eq!(entry(&mine, "Hund").tr_word, vec!["hound"]);
+        assert_eq!(entry(&mine, "Hund").cur_iter, 3);
+        assert_eq!(entry(&mine, "Katze").tr_word, vec!["cat"]);
+        assert_eq!(entry(&mine, "Katze").cur_iter, 2);
+        assert_eq!(entry(&mine, "Fisch").cur_iter, 4);
+
+        // The entries on only one side are kept and added
+        assert_eq!(entry(&mine, "Vogel").id, "c");
+        assert_eq!(entry(&mine, "Maus").id, "d");
+        assert_eq!(mine.usable.len() + mine.unusable.len(), 5);
+
+        // Merging the other way around leaves both databases the same
+        theirs.merge(&mine);
+        let lines = |db: &Database| {
+            let mut lines: Vec<String> = db.usable.iter()
+                .chain(db.unusable.iter())
+                .map(Entry::db_repr)
+                .collect();
+            lines.sort();
+            lines
+        };
+        assert_eq!(lines(&theirs), lines(&mine));
+    }
+
+    #[test]
+    fn stops_relearning_a_word_once_it_reaches_the_cap() {
+        let dir    = TempDir::new("relearn-cap");
+        let path   = dir.write("words.txt", b"Hund;; dog;; 0;; 0\n");
+        let mut db = Database::open(path).unwrap();
+        db.relearn_steps = vec![1];
+        db.relearn_cap   = 2;
+
+        // The first failure schedules the word and starts relearning it, and
+        // each of the `relearn_cap` failures after it serves it again
+        let mut served = 0;
+        while let Some((_, idx)) = db.random_entry() {
+            served += 1;
+            assert!(served <= 3, "the word keeps coming back");
+            db.update_timeout(idx, Grade::Again, None);
+        }
+        assert_eq!(served, 3);
+        assert!(db.usable.is_empty());
+        assert!(db.unusable[0].timed_out);
+    }
+
+    /// Opens a database of a single due word in `dir` whose directions share
+    /// their schedule and have to be recalled both
+    fn open_both_directions(dir: &TempDir) -> Database {
+        let path   = dir.write("words.txt", b"Hund;; dog;; 2;; 1\n");
+        let mut db = Database::open(path).unwrap();
+        db.set_direction(Direction::Forward, false);
+        db.both_directions = true;
+        db
+    }
+
+    #[test]
+    fn a_single_direction_leaves_the_rung_unchanged() {
+        let dir    = TempDir::new("one-direction");
+        let mut db = open_both_directions(&dir);
+
+        let (entry, idx) = db.random_entry().unwrap();
+        assert_eq!(db.card(&entry).direction, Direction::Forward);
+        db.update_timeout(idx, Grade::Good, None);
+
+        let (entry, _) = db.random_entry().unwrap();
+        assert_eq!(entry.cur_iter, 2);
+        assert!(!entry.timed_out);
+        assert_eq!(db.card(&entry).direction, Direction::Reverse);
+    }
+
+    #[test]
+    fn both_directions_recalled_advance_the_rung() {
+        let dir    = TempDir::new("both-directions");
+        let mut db = open_both_directions(&dir);
+
+        for _ in 0..2 {
+            let (_, idx) = db.random_entry().unwrap();
+            db.update_timeout(idx, Grade::Good, None);
+        }
+        assert!(db.usable.is_empty());
+        assert_eq!(db.unusable[0].cur_iter, 3);
+        assert_eq!(db.card(&db.unusable[0]).direction, Direction::Forward);
+    }
+
+    #[test]
+    fn forgetting_the_other_direction_schedules_the_entry() {
+        let dir    = TempDir::new("other-direction-forgotten");
+        let mut db = open_both_directions(&dir);
+
+        let (_, idx) = db.random_entry().unwrap();
+        db.update_timeout(idx, Grade::Good, None);
+        let (_, idx) = db.random_entry().unwrap();
+        db.update_timeout(idx, Grade::Again, None);
+        assert!(db.usable.is_empty());
+        assert_eq!(db.unusable[0].lapses, 1);
+        assert_eq!(db.card(&db.unusable[0]).direction, Direction::Forward);
+    }
+
+    /// Returns the words and translations of all the entries of `db`, sorted
+    fn pairs(db: &Database) -> Vec<(String, Vec<String>)> {
+        let mut pairs: Vec<_> = db.usable.iter()
+            .chain(db.unusable.iter())
+            .map(|entry| (entry.word.clone(), entry.tr_word.clone()))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn swapping_all_columns_swaps_every_entry_and_round_trips() {
+        let dir    = TempDir::new("swap-columns");
+        let path   = dir.write("words.txt",
+                               b"dog;; Hund;; 0;; 0\ncat;; Katze;; 1;; 0\n");
+        let mut db = Database::open(path.clone()).unwrap();
+        let before = pairs(&db);
+        let german = |word: &str| word.starts_with(char::is_uppercase);
+        assert!(db.detect_swapped(german));
+
+        db.swap_all_columns();
+        let swapped = pairs(&db);
+        assert_eq!(swapped, vec![
+            ("Hund".to_string(), vec!["dog".to_string()]),
+            ("Katze".to_string(), vec!["cat".to_string()]),
+        ]);
+        assert!(!db.detect_swapped(german));
+
+        // The fix is persisted and the schedules stay with their entries
+        db.flush_and_close().unwrap();
+        let mut db = Database::open(path).unwrap();
+        assert_eq!(pairs(&db), swapped);
+        assert!(db.usable.iter()
+            .chain(db.unusable.iter())
+            .all(|entry| entry.cur_iter == (entry.word == "Katze") as usize));
+
+        db.swap_all_columns();
+        assert_eq!(pairs(&db), before);
+    }
+
+    #[test]
+    fn the_due_histogram_follows_the_start_of_the_day() {
+        // The days start at 4 o'clock in UTC
+        let day    = 19_844 * DAY;
+        let lines  = format!("Hund;; dog;; 1;; {}\nKatze;; cat;; 1;; {}\n\
+                              Maus;; mouse;; 1;; {}\n",
+                             day - DAY, day + DAY + 2 * HOUR,
+                             day + DAY + 5 * HOUR);
+        let dir    = TempDir::new("due-histogram");
+        let mut db = Database::open(dir.write("words.txt", lines.as_bytes()))
+            .unwrap();
+        db.day_start = DayStart::new(4, 0);
+
+        let now = day + 10 * HOUR;
+        assert_eq!(db.due_histogram(now, 3), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn decks_tags_and_stars_are_pickable() {
+        let dir = TempDir::new("pickable");
+        for (line, pickable) in [("", false), (";; deck=German", true),
+                                 (";; tags=pets", true),
+                                 (";; starred=true", true)].iter() {
+            let line = format!("Hund;; dog;; 0;; 0{}\n", line);
+            let db   = Database::open(dir.write("words.txt", line.as_bytes()))
+                .unwrap();
+            assert_eq!(db.is_pickable(), *pickable);
+            db.flush_and_close().unwrap();
+        }
+    }
+
+    #[test]
+    fn detects_swapped_columns_by_a_wordlist() {
+        let dir      = TempDir::new("swap-wordlist");
+        let list     = dir.write("german.txt", b"hund\n\nKATZE\r\nMaus\n");
+        let wordlist = import::read_wordlist(&list).unwrap();
+        let german   = |word: &str| wordlist.contains(&grading::fold(word));
+
+        let path = dir.write("words.txt",
+                             b"dog;; Hund;; 0;; 0\ncat;; Katze;; 0;; 0\n");
+        let mut db = Database::open(path).unwrap();
+        assert!(db.detect_swapped(german));
+        db.swap_all_columns();
+        assert!(!db.detect_swapped(german));
+    }
+
+    #[test]
+    fn an_entry_reads_back_from_its_line() {
+        let mut entry  = Entry::new("a;; b;", "c = d | e\\;");
+        entry.cur_iter = 3;
+        entry.timeout  = 1_600_000_000;
+        entry.id       = "id=1;;".to_string();
+        entry.deck     = "deck;; one".to_string();
+        entry.notes    = "key=value\nsecond line\r;".to_string();
+        entry.tags     = vec!["a=b".to_string(), "c".to_string()];
+        entry.card     = CardType::Cloze;
+        entry.ease     = 2.1;
+        entry.lapses   = 4;
+        entry.starred  = true;
+        entry.modified = 1_600_000_100;
+        entry.reverse  = Schedule {
+            cur_iter: 2,
+            timeout:  1_600_000_200,
+            ease:     1.9,
+            ..Schedule::default()
+        };
+
+        let line   = entry.db_repr();
+        let parsed = Entry::parse(&line).unwrap();
+        assert!(!line.contains('\n') && !line.contains('\r'));
+        assert_eq!(parsed.db_repr(), line);
+        assert_eq!(parsed.word, entry.word);
+        assert_eq!(parsed.tr_word, vec!["c = d", "e\\;"]);
+        assert_eq!(parsed.id, entry.id);
+        assert_eq!(parsed.deck, entry.deck);
+        assert_eq!(parsed.notes, entry.notes);
+        assert_eq!(parsed.tags, entry.tags);
+        assert_eq!(parsed.card, CardType::Cloze);
+        assert_eq!((parsed.cur_iter, parsed.timeout), (3, entry.timeout));
+        assert_eq!((parsed.lapses, parsed.modified), (4, entry.modified));
+        assert_eq!(parsed.ease, entry.ease);
+        assert!(parsed.starred);
+        assert_eq!(parsed.reverse, entry.reverse);
+    }
+
+    /// The characters the random columns are made of; mostly the ones that
+    /// mean something in the format
+    const FUZZ_CHARS: &[char] = &[
+        ';', ';', ' ', '\\', '\n', '\r', '=', '|', ',', 'n', 'r', 'a', 'ž',
+        '日', '\u{200f}', '\t',
+    ];
+
+    /// Returns a random string of up to `max_len` of `FUZZ_CHARS`
+    fn fuzz_string(rng: &mut Rng, max_len: u64) -> String {
+        let len = rng.range(0, max_len);
+        (0..len)
+            .map(|_| {
+                let idx = rng.range(0, FUZZ_CHARS.len() as u64 - 1) as usize;
+                FUZZ_CHARS[idx]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn random_lines_never_panic_and_read_back() {
+        let mut rng    = Rng::with_seed(793);
+        let mut parsed = 0;
+        for _ in 0..5000 {
+            let line = match rng.range(0, 1) {
+                0 => fuzz_string(&mut rng, 40),
+                _ => format!("{};; {};; {};; {};; {}",
+                             fuzz_string(&mut rng, 8),
+                             fuzz_string(&mut rng, 8),
+                             rng.range(0, 40), rng.range(0, 1 << 40),
+                             fuzz_string(&mut rng, 16)),
+            };
+            let _ = split_line(&line);
+
+            // Whatever is read, it's written so that it reads back the same
+            if let Ok(entry) = Entry::parse(&line) {
+                let repr = entry.db_repr();
+                assert_eq!(Entry::parse(&repr).unwrap().db_repr(), repr,
+                           "{:?}", line);
+                parsed += 1;
+            }
+        }
+        assert!(parsed > 500, "only {} lines were parsed", parsed);
+    }
+
+    #[test]
+    fn random_entries_read_back_from_their_lines() {
+        let mut rng     = Rng::with_seed(7930);
+        let mut checked = 0;
+        for _ in 0..5000 {
+            let mut entry = Entry::new(&fuzz_string(&mut rng, 12),
+                                       &fuzz_string(&mut rng, 12));
+            entry.deck     = fuzz_string(&mut rng, 8);
+            entry.notes    = fuzz_string(&mut rng, 16);
+            entry.id       = fuzz_string(&mut rng, 8);
+            entry.tags     = vec![fuzz_string(&mut rng, 6)];
+            entry.cur_iter = rng.range(0, 100) as usize;
+            entry.timeout  = rng.range(0, u64::MAX >> 1);
+            entry.normalize();
+            if entry.word.is_empty() || entry.tr_word.is_empty() {
+                continue;
+            }
+            checked += 1;
+
+            let line   = entry.db_repr();
+            let parsed = Entry::parse(&line).unwrap();
+            assert!(!line.contains('\n') && !line.contains('\r'));
+            assert_eq!(parsed.db_repr(), line);
+            assert_eq!(parsed.word, entry.word);
+            assert_eq!(parsed.tr_word, entry.tr_word);
+            assert_eq!(parsed.deck, entry.deck);
+            assert_eq!(parsed.notes, entry.notes);
+            assert_eq!(parsed.id, entry.id);
+            assert_eq!(parsed.tags, entry.tags);
+            assert_eq!((parsed.cur_iter, parsed.timeout),
+                       (entry.cur_iter, entry.timeout));
+        }
+        assert!(checked > 1000, "only {} entries were checked", checked);
+    }
+
+    #[test]
+    fn parses_the_iterations_tolerantly() {
+        let top = MAX_LADDER_STEPS - 1;
+        assert_eq!(parse_cur_iter("-1"), Some(0));
+        assert_eq!(parse_cur_iter("-99999999999999999999999"), Some(0));
+        assert_eq!(parse_cur_iter("0"), Some(0));
+        assert_eq!(parse_cur_iter(" 3 "), Some(3));
+        assert_eq!(parse_cur_iter("1000"), Some(top));
+        assert_eq!(parse_cur_iter("99999999999999999999999"), Some(top));
+        for value in ["abc", "", "-", "1.5", "+1", "--1"].iter() {
+            assert_eq!(parse_cur_iter(value), None, "{:?}", value);
+        }
+
+        // A negative iteration resets the entry instead of dropping it
+        assert_eq!(Entry::parse("Hund;; dog;; -1;; 0").unwrap().cur_iter, 0);
+        assert_eq!(Entry::parse("Hund;; dog;; abc;; 0").unwrap_err(),
+                   ParseError::Iteration("abc".to_string()));
+    }
+
+    #[test]
+    fn orders_the_due_reviews_by_how_overdue_they_are() {
+        let now   = now();
+        let lines = format!("new;; a;; 0;; 0\n\
+                             day;; b;; 1;; {}\n\
+                             week;; c;; 1;; {}\n\
+                             later;; d;; 1;; {}\n\
+                             hour;; e;; 1;; {}\n",
+                            now - DAY, now - 7 * DAY, now + DAY, now - 3600);
+        let dir    = TempDir::new("most-overdue");
+        let mut db = Database::open(dir.write("words.txt", lines.as_bytes()))
+            .unwrap();
+
+        let words = |n| db.most_overdue(now, n).iter()
+            .map(|entry| entry.word.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(words(10), vec!["week", "day", "hour"]);
+        assert_eq!(words(2), vec!["week", "day"]);
+        assert!(words(0).is_empty());
+
+        db.selection = Selection::MostOverdue;
+        assert_eq!(db.random_entry().unwrap().0.word, "week");
+    }
+
+    #[test]
+    fn counts_the_days_until_due_rounded_up() {
+        let now  = 1_000_000;
+        let days = |timeout| {
+            let mut entry = Entry::new("Hund", "dog");
+            entry.timeout = timeout;
+            entry.days_until_due(now)
+        };
+
+        assert_eq!(days(now + DAY), 1);
+        assert_eq!(days(now + 1), 1);
+        assert_eq!(days(now + 2 * DAY - 1), 2);
+
+        // Overdue entries are due right away
+        assert_eq!(days(now), 0);
+        assert_eq!(days(now - DAY), 0);
+    }
+
+    #[test]
+    fn samples_a_fraction_of_the_entries_reproducibly() {
+        let dir      = TempDir::new("sample");
+        let contents = (0..20)
+            .map(|idx| format!("word{};; tr{};; 0;; 0\n", idx, idx))
+            .collect::<String>();
+        let db = Database::open(dir.write("words.txt", contents.as_bytes()))
+            .unwrap();
+
+        let words = |fraction, seed| db.sample(fraction, seed)
+            .map(|sample| sample.iter()
+                .map(|entry| entry.word.clone())
+                .collect::<Vec<_>>());
+        let sample = words(0.25, 7).unwrap();
+        assert_eq!(sample.len(), 5);
+        let mut unique = sample.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), sample.len());
+        assert_eq!(words(0.25, 7), Some(sample));
+
+        assert_eq!(words(1., 7).unwrap().len(), 20);
+        assert_eq!(words(0.01, 7).unwrap().len(), 1);
+        for &fraction in [0., -0.5, 1.5, f32::NAN].iter() {
+            assert_eq!(words(fraction, 7), None);
+        }
+    }
+
+    #[test]
+    fn whitespace_only_words_and_translations_are_blank() {
+        for line in ["   ;; dog", "\t;; dog;; 0;; 0", "Hund;;    ",
+                     "Hund;;  |  ;; 0;; 0"].iter() {
+            assert!(matches!(Entry::parse(line), Err(ParseError::BlankWord)),
+                    "{:?} isn't blank", line);
+        }
+        assert_eq!(Entry::parse("  Hund ;;  dog ").unwrap().word, "Hund");
+    }
+
+    #[test]
+    fn quarantines_the_whitespace_only_lines_to_warn_about_them() {
+        let dir  = TempDir::new("whitespace-only");
+        let path = dir.write("words.txt",
+                             b"Hund;; dog;; 0;; 0\n   ;; cat\nMaus;;   \n");
+        let db   = Database::open(path).unwrap();
+        assert_eq!(db.usable.len(), 1);
+
+        let lines: Vec<_> = db.quarantined.iter()
+            .map(|line| (line.line, line.error.clone()))
+            .collect();
+        assert_eq!(lines, vec![(2, ParseError::BlankWord),
+                               (3, ParseError::BlankWord)]);
+    }
+
+    #[test]
+    fn refuses_a_file_of_an_unknown_format() {
+        let dir = TempDir::new("unknown-format");
+        for name in ["words.txt", "words.json"].iter() {
+            let path = dir.write(name, b"\x00\x01\x02\x03");
+            assert!(matches!(Database::open(path), Err(Error::UnknownFormat)));
+        }
+    }
+
+    #[test]
+    fn a_text_file_of_broken_lines_is_no_unknown_format() {
+        let dir = TempDir::new("broken-text");
+        for name in ["words.txt", "words.json"].iter() {
+            let path = dir.write(name, b"just some notes\nand more\n");
+            let db   = Database::open(path);
+            assert!(matches!(db, Err(Error::Parse { line: 1, .. })));
+        }
     }
 }
@@ -0,0 +1,92 @@
+//! The storage backends of the database.
+//! A backend only loads and stores the entries; everything else (the
+//! metadata, the history, the profiles, ...) stays in the files next to the
+//! database no matter the backend.
+
+mod text;
+mod json;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use super::{Entry, Format, Quarantined};
+use crate::error::{Error, Result};
+
+pub use text::TextBackend;
+pub use json::JsonBackend;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteBackend;
+
+/// The number of bytes of a database read to recognize its format
+const SIGNATURE_LEN: u64 = 64;
+
+
+/// Loads and stores the entries of a database
+pub trait Backend {
+    /// Reads all the entries of the database.
+    /// Returns the entries and the lines that were skipped because they
+    /// couldn't be read.
+    fn load_entries(&mut self) -> Result<(Vec<Entry>, Vec<Quarantined>)>;
+
+    /// Stores a single changed entry.
+    /// The entry replaces the stored entry with the same words, or is added
+    /// if there's no such entry.
+    fn persist_entry(&mut self, entry: &Entry) -> std::io::Result<()>;
+
+    /// Replaces all the stored entries with `entries`.
+    /// The `quarantined` lines are stored as they are after the entries, if
+    /// the storage is made of lines; they're never read as entries anyway.
+    fn persist_all(&mut self, entries: &[&Entry], quarantined: &[Quarantined])
+        -> std::io::Result<()>;
+
+    /// Returns whether the next write should rather `persist_all` the entries
+    /// than persist them one by one, e.g. because that keeps the storage
+    /// small
+    fn wants_rewrite(&self) -> bool {
+        false
+    }
+
+    /// Makes sure that everything stored so far actually hit the disk
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns the format of the database at `path` that doesn't start like
+/// a JSON (or an SQLite) file, starting with `start`; the one of the
+/// extension, unless it's a JSON extension on a text database (e.g. an old
+/// database renamed by hand).
+/// Returns `Error::UnknownFormat` if a file with a JSON extension is neither.
+fn sniff(path: &Path, start: &[u8]) -> Result<Format> {
+    let format = Format::for_path(path);
+    let empty  = start.iter().all(u8::is_ascii_whitespace);
+    match format {
+        Format::Json | Format::JsonLines if !empty => {
+            Format::detect(&fs::read(path)?).ok_or(Error::UnknownFormat)
+        },
+        _ => Ok(format),
+    }
+}
+
+/// Opens the backend of the database at `path`.
+/// The backend is chosen by the first bytes of the file (see
+/// `Format::signature`), so that e.g. a JSON database opens whatever it's
+/// called; by the file extension (see `Format::for_path`) if they don't tell.
+pub fn open(path: &Path) -> Result<Box<dyn Backend>> {
+    let mut start = Vec::new();
+    File::open(path)?.take(SIGNATURE_LEN).read_to_end(&mut start)?;
+    let format = match Format::signature(&start) {
+        Some(format) => format,
+        None         => sniff(path, &start)?,
+    };
+
+    match format {
+        #[cfg(feature = "sqlite")]
+        Format::Sqlite    => Ok(Box::new(SqliteBackend::open(path)?)),
+        Format::Json      => Ok(Box::new(JsonBackend::open(path, false)?)),
+        Format::JsonLines => Ok(Box::new(JsonBackend::open(path, true)?)),
+        _                 => Ok(Box::new(TextBackend::open(path)?)),
+    }
+}
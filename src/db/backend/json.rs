@@ -0,0 +1,406 @@
+//! The JSON backend; every entry is a JSON object, either in a single array
+//! (`.json`) or on a line of its own (JSON lines, `.jsonl`).
+//! Only the word and the translations are required. The other fields are
+//! optional and left out while they have their default values, so a new entry
+//! can be as short as
+//!
+//! ```text
+//! {"word":"Hund","translations":["dog","hound"],"notes":"der Hund"}
+//! ```
+//!
+//! and a reviewed one carries its schedule, with the schedule of the reverse
+//! direction in `reverse`. Unknown fields are ignored, just like the unknown
+//! keys of the text format.
+//!
+//! Changed entries are appended to the JSON lines like to the journal of a
+//! text file; the latest line of an entry wins. The array has to be rewritten
+//! as a whole on every write.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::card::CardType;
+use crate::db::{Entry, Quarantined, Schedule, JOURNAL_MAX_LEN, now,
+                write_atomic};
+use crate::error::{Error, ParseError, Result};
+use crate::scheduler::DEFAULT_EASE;
+use super::Backend;
+
+
+/// Returns whether `value` is the default of its type, for leaving it out
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// Returns whether `ease` is the ease of the words that were never reviewed
+fn is_default_ease(ease: &f64) -> bool {
+    *ease == DEFAULT_EASE
+}
+
+/// Returns the ease of the words that were never reviewed
+fn default_ease() -> f64 {
+    DEFAULT_EASE
+}
+
+
+/// The schedule of a direction of an entry, as it's stored
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct ScheduleRecord {
+    #[serde(default, skip_serializing_if = "is_default")]
+    iteration: usize,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    timeout: u64,
+
+    #[serde(default = "default_ease", skip_serializing_if = "is_default_ease")]
+    ease: f64,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    interval: u64,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    reps: u32,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    stability: f64,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    difficulty: f64,
+}
+
+impl Default for ScheduleRecord {
+    fn default() -> Self {
+        Self::from(&Schedule::default())
+    }
+}
+
+impl From<&Schedule> for ScheduleRecord {
+    fn from(schedule: &Schedule) -> Self {
+        Self {
+            iteration:  schedule.cur_iter,
+            timeout:    schedule.timeout,
+            ease:       schedule.ease,
+            interval:   schedule.interval,
+            reps:       schedule.reps,
+            stability:  schedule.stability,
+            difficulty: schedule.difficulty,
+        }
+    }
+}
+
+impl From<ScheduleRecord> for Schedule {
+    fn from(record: ScheduleRecord) -> Self {
+        Self {
+            cur_iter:   record.iteration,
+            timeout:    record.timeout,
+            ease:       record.ease,
+            interval:   record.interval,
+            reps:       record.reps,
+            stability:  record.stability,
+            difficulty: record.difficulty,
+        }
+    }
+}
+
+
+/// An entry as it's stored; the fields mirror the fields of `Entry`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Record {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    id: String,
+
+    word: String,
+
+    translations: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    deck: String,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    notes: String,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    audio: String,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    image: String,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+
+    /// The card type; unknown ones (of newer versions) are basic cards
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    card: String,
+
+    /// The schedule of the forward direction
+    #[serde(flatten)]
+    schedule: ScheduleRecord,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    reverse: ScheduleRecord,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    lapses: u32,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    suspended: bool,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    starred: bool,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    buried: u64,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    introduced: u64,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    added: u64,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    reviewed: u64,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    modified: u64,
+}
+
+impl From<&Entry> for Record {
+    fn from(entry: &Entry) -> Self {
+        // The fixed fields always hold the forward schedule
+        let entry = entry.stored();
+        let card  = match entry.card {
+            CardType::Basic => String::new(),
+            card            => card.to_string(),
+        };
+        let schedule = ScheduleRecord {
+            iteration:  entry.cur_iter,
+            timeout:    entry.timeout,
+            ease:       entry.ease,
+            interval:   entry.interval,
+            reps:       entry.reps,
+            stability:  entry.stability,
+            difficulty: entry.difficulty,
+        };
+        Self {
+            id:           entry.id.clone(),
+            word:         entry.word.clone(),
+            translations: entry.tr_word.clone(),
+            deck:         entry.deck.clone(),
+            notes:        entry.notes.clone(),
+            audio:        entry.audio.clone(),
+            image:        entry.image.clone(),
+            tags:         entry.tags.clone(),
+            card,
+            schedule,
+            reverse:      ScheduleRecord::from(&entry.reverse),
+            lapses:       entry.lapses,
+            suspended:    entry.suspended,
+            starred:      entry.starred,
+            buried:       entry.buried_until,
+            introduced:   entry.introduced,
+            added:        entry.added,
+            reviewed:     entry.reviewed,
+            modified:     entry.modified,
+        }
+    }
+}
+
+impl Record {
+    /// Returns the entry of this record in its canonical form, or
+    /// `ParseError::BlankWord` if the word or all of its translations are
+    /// blank
+    fn into_entry(self) -> std::result::Result<Entry, ParseError> {
+        let mut entry      = Entry::new(&self.word, "");
+        let schedule       = self.schedule;
+        entry.tr_word      = self.translations;
+        entry.cur_iter     = schedule.iteration;
+        entry.timeout      = schedule.timeout;
+        entry.timed_out    = entry.timeout > now();
+        entry.ease         = schedule.ease;
+        entry.interval     = schedule.interval;
+        entry.reps         = schedule.reps;
+        entry.stability    = schedule.stability;
+        entry.difficulty   = schedule.difficulty;
+        entry.reverse      = self.reverse.into();
+        entry.deck         = self.deck;
+        entry.notes        = self.notes;
+        entry.audio        = self.audio;
+        entry.image        = self.image;
+        entry.tags         = self.tags;
+        entry.card         = self.card.parse().unwrap_or_default();
+        entry.lapses       = self.lapses;
+        entry.suspended    = self.suspended;
+        entry.starred      = self.starred;
+        entry.buried_until = self.buried;
+        entry.introduced   = self.introduced;
+        entry.added        = self.added;
+        entry.reviewed     = self.reviewed;
+        entry.modified     = self.modified;
+        entry.id           = self.id;
+
+        // The same entries as in a text database, whatever wrote the file
+        entry.normalize();
+        if entry.word.is_empty() || entry.tr_word.is_empty() {
+            return Err(ParseError::BlankWord);
+        }
+        Ok(entry)
+    }
+
+    /// Returns the record as a single line of JSON
+    fn to_json(&self) -> std::io::Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+
+/// A database stored in a JSON file
+pub struct JsonBackend {
+    /// The path to the database file
+    path: PathBuf,
+
+    /// Whether the entries are JSON lines rather than a single array
+    lines: bool,
+
+    /// The number of entries appended to the JSON lines since they were last
+    /// rewritten
+    journal_len: usize,
+}
+
+impl JsonBackend {
+    /// Opens the database file at `path`; the entries are JSON lines if
+    /// `lines` is true, an array otherwise
+    pub fn open(path: &Path, lines: bool) -> std::io::Result<Self> {
+        // Like the text files, a missing file isn't created
+        File::open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            lines,
+            journal_len: 0,
+        })
+    }
+
+    /// Reads the entries of the JSON lines in `contents`.
+    /// A later line with the same words replaces the earlier one, and broken
+    /// lines are quarantined like in the text files.
+    fn load_lines(&mut self, contents: &str)
+            -> (Vec<Entry>, Vec<Quarantined>) {
+        let mut entries     = Vec::new();
+        let mut positions   = HashMap::new();
+        let mut quarantined = Vec::new();
+        let mut replaced    = 0;
+
+        for (idx, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry = serde_json::from_str::<Record>(line)
+                .map_err(|err| ParseError::Json(err.to_string()))
+                .and_then(Record::into_entry);
+            let entry = match entry {
+                Ok(entry)  => entry,
+                Err(error) => {
+                    quarantined.push(Quarantined {
+                        line: idx + 1,
+                        text: line.to_string(),
+                        error,
+                    });
+                    continue;
+                },
+            };
+
+            let key = (entry.word.clone(), entry.tr_word.clone());
+            match positions.get(&key) {
+                Some(&pos) => {
+                    entries[pos] = entry;
+                    replaced += 1;
+                },
+                None => {
+                    positions.insert(key, entries.len());
+                    entries.push(entry);
+                },
+            }
+        }
+
+        self.journal_len = replaced;
+        (entries, quarantined)
+    }
+
+    /// Reads the entries of the JSON array in `contents`.
+    /// The array is parsed as a whole, so a syntax error anywhere makes the
+    /// whole file unreadable; entries without a word or a translation are
+    /// left out.
+    fn load_array(contents: &str) -> Result<Vec<Entry>> {
+        let records: Vec<Record> = serde_json::from_str(contents)
+            .map_err(|err| Error::Parse {
+                line:  err.line(),
+                error: ParseError::Json(err.to_string()),
+            })?;
+        Ok(records.into_iter()
+            .filter_map(|record| record.into_entry().ok())
+            .collect())
+    }
+}
+
+impl Backend for JsonBackend {
+    fn load_entries(&mut self) -> Result<(Vec<Entry>, Vec<Quarantined>)> {
+        let contents = fs::read_to_string(&self.path)?;
+        let contents = contents.trim_start_matches('\u{feff}');
+
+        // An empty file is an empty database, like a new text file
+        if contents.trim().is_empty() {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        if !self.lines {
+            return Ok((Self::load_array(contents)?, Vec::new()));
+        }
+
+        let (entries, mut quarantined) = self.load_lines(contents);
+
+        // A file without a single valid line is rather broken than empty
+        if entries.is_empty() && !quarantined.is_empty() {
+            return Err(quarantined.remove(0).into());
+        }
+        Ok((entries, quarantined))
+    }
+
+    fn persist_entry(&mut self, entry: &Entry) -> std::io::Result<()> {
+        // The array can't be appended to; see `wants_rewrite`
+        let line     = format!("{}\n", Record::from(entry).to_json()?);
+        let mut file = OpenOptions::new().append(true).open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        self.journal_len += 1;
+        Ok(())
+    }
+
+    fn persist_all(&mut self, entries: &[&Entry], quarantined: &[Quarantined])
+            -> std::io::Result<()> {
+        let mut lines = Vec::with_capacity(entries.len());
+        for entry in entries {
+            lines.push(Record::from(*entry).to_json()?);
+        }
+
+        // An array is never quarantined; it's read whole or not at all
+        let contents = match self.lines {
+            true  => lines.iter()
+                .map(String::as_str)
+                .chain(quarantined.iter().map(|line| line.text.as_str()))
+                .map(|line| format!("{}\n", line))
+                .collect(),
+            false => format!("[\n{}\n]\n", lines.join(",\n")),
+        };
+        write_atomic(&self.path, contents.as_bytes())?;
+        self.journal_len = 0;
+        Ok(())
+    }
+
+    fn wants_rewrite(&self) -> bool {
+        !self.lines || self.journal_len >= JOURNAL_MAX_LEN
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        OpenOptions::new().append(true).open(&self.path)?.sync_all()
+    }
+}
@@ -0,0 +1,221 @@
+//! The SQLite backend, for databases too large to be rewritten as a whole.
+//! Every entry is a row of the `entries` table, so a changed entry only
+//! touches its own row.
+
+use std::io;
+use std::path::Path;
+use rusqlite::{params, Connection, Row, NO_PARAMS};
+use crate::db::{Entry, Quarantined, join_tags, join_translations, now,
+                split_tags};
+use crate::error::Result;
+use super::Backend;
+
+/// The schema of the database; the columns mirror the fields of `Entry`
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS entries (
+        word text not null, tr_word text not null,
+        cur_iter integer not null, timeout integer not null,
+        deck text not null, ease real not null, interval integer not null,
+        reps integer not null, rev_iter integer not null,
+        rev_timeout integer not null, rev_ease real not null,
+        rev_interval integer not null, rev_reps integer not null,
+        lapses integer not null default 0,
+        suspended integer not null default 0,
+        notes text not null default '',
+        audio text not null default '',
+        card text not null default 'basic',
+        image text not null default '',
+        buried integer not null default 0,
+        introduced integer not null default 0,
+        stability real not null default 0,
+        difficulty real not null default 0,
+        rev_stability real not null default 0,
+        rev_difficulty real not null default 0,
+        added integer not null default 0,
+        reviewed integer not null default 0,
+        tags text not null default '',
+        id text not null default '',
+        modified integer not null default 0,
+        starred integer not null default 0,
+        PRIMARY KEY (word, tr_word));
+";
+
+/// The number of columns of the first version of the schema
+const BASE_COLUMNS: usize = 13;
+
+/// The columns that were added to the schema since its first version, in
+/// order; they're added to the tables of older databases when opened
+const ADDED_COLUMNS: [&str; 18] = [
+    "ALTER TABLE entries ADD COLUMN lapses integer not null default 0",
+    "ALTER TABLE entries ADD COLUMN suspended integer not null default 0",
+    "ALTER TABLE entries ADD COLUMN notes text not null default ''",
+    "ALTER TABLE entries ADD COLUMN audio text not null default ''",
+    "ALTER TABLE entries ADD COLUMN card text not null default 'basic'",
+    "ALTER TABLE entries ADD COLUMN image text not null default ''",
+    "ALTER TABLE entries ADD COLUMN buried integer not null default 0",
+    "ALTER TABLE entries ADD COLUMN introduced integer not null default 0",
+    "ALTER TABLE entries ADD COLUMN stability real not null default 0",
+    "ALTER TABLE entries ADD COLUMN difficulty real not null default 0",
+    "ALTER TABLE entries ADD COLUMN rev_stability real not null default 0",
+    "ALTER TABLE entries ADD COLUMN rev_difficulty real not null default 0",
+    "ALTER TABLE entries ADD COLUMN added integer not null default 0",
+    "ALTER TABLE entries ADD COLUMN reviewed integer not null default 0",
+    "ALTER TABLE entries ADD COLUMN tags text not null default ''",
+    "ALTER TABLE entries ADD COLUMN id text not null default ''",
+    "ALTER TABLE entries ADD COLUMN modified integer not null default 0",
+    "ALTER TABLE entries ADD COLUMN starred integer not null default 0",
+];
+
+/// Inserts an entry, replacing the one with the same words
+const INSERT: &str = "
+    INSERT OR REPLACE INTO entries VALUES
+        (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15,
+         ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28,
+         ?29, ?30, ?31)
+";
+
+
+/// Converts any error into an `io::Error` so that the backends have a single
+/// error type like the rest of the database code
+fn io_error<E: std::error::Error + Send + Sync + 'static>(err: E) -> io::Error {
+    io::Error::other(err)
+}
+
+/// Adds the columns that are missing in the table of an older database
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    let columns = conn.prepare("SELECT * FROM entries")?.column_count();
+    for (idx, alter) in ADDED_COLUMNS.iter().enumerate() {
+        if columns <= BASE_COLUMNS + idx {
+            conn.execute_batch(alter)?;
+        }
+    }
+    Ok(())
+}
+
+/// Inserts `entry` with the statement of `conn`
+fn insert(conn: &Connection, entry: &Entry) -> rusqlite::Result<()> {
+    // The fixed fields always hold the forward schedule
+    let entry   = entry.stored();
+    let reverse = &entry.reverse;
+    conn.prepare_cached(INSERT)?.execute(params![
+        entry.word, join_translations(&entry.tr_word),
+        entry.cur_iter as i64, entry.timeout as i64,
+        entry.deck, entry.ease, entry.interval as i64, entry.reps,
+        reverse.cur_iter as i64, reverse.timeout as i64, reverse.ease,
+        reverse.interval as i64, reverse.reps,
+        entry.lapses, entry.suspended, entry.notes, entry.audio,
+        entry.card.to_string(), entry.image, entry.buried_until as i64,
+        entry.introduced as i64, entry.stability, entry.difficulty,
+        reverse.stability, reverse.difficulty, entry.added as i64,
+        entry.reviewed as i64, join_tags(&entry.tags), entry.id,
+        entry.modified as i64, entry.starred,
+    ])?;
+    Ok(())
+}
+
+/// Reads an entry from a row of the `entries` table
+fn entry_from_row(row: &Row) -> rusqlite::Result<Entry> {
+    let word: String    = row.get(0)?;
+    let tr_word: String = row.get(1)?;
+    let mut entry = Entry::new(&word, &tr_word);
+
+    entry.cur_iter  = row.get::<_, i64>(2)? as usize;
+    entry.timeout   = row.get::<_, i64>(3)? as u64;
+    entry.timed_out = entry.timeout > now();
+    entry.deck      = row.get(4)?;
+    entry.ease      = row.get(5)?;
+    entry.interval  = row.get::<_, i64>(6)? as u64;
+    entry.reps      = row.get(7)?;
+
+    let reverse      = &mut entry.reverse;
+    reverse.cur_iter = row.get::<_, i64>(8)? as usize;
+    reverse.timeout  = row.get::<_, i64>(9)? as u64;
+    reverse.ease     = row.get(10)?;
+    reverse.interval = row.get::<_, i64>(11)? as u64;
+    reverse.reps     = row.get(12)?;
+
+    entry.lapses    = row.get(13)?;
+    entry.suspended = row.get(14)?;
+    entry.notes     = row.get(15)?;
+    entry.audio     = row.get(16)?;
+
+    // Unknown card types (of newer versions) are shown as basic cards
+    entry.card         = row.get::<_, String>(17)?.parse().unwrap_or_default();
+    entry.image        = row.get(18)?;
+    entry.buried_until = row.get::<_, i64>(19)? as u64;
+    entry.introduced   = row.get::<_, i64>(20)? as u64;
+    entry.stability    = row.get(21)?;
+    entry.difficulty   = row.get(22)?;
+
+    let reverse        = &mut entry.reverse;
+    reverse.stability  = row.get(23)?;
+    reverse.difficulty = row.get(24)?;
+
+    entry.added    = row.get::<_, i64>(25)? as u64;
+    entry.reviewed = row.get::<_, i64>(26)? as u64;
+    entry.tags     = split_tags(&row.get::<_, String>(27)?);
+    entry.id       = row.get(28)?;
+    entry.modified = row.get::<_, i64>(29)? as u64;
+    entry.starred  = row.get(30)?;
+
+    // The same entries as in a text database, whatever wrote the table
+    entry.normalize();
+    Ok(entry)
+}
+
+
+/// A database stored in an SQLite file
+pub struct SqliteBackend {
+    conn: Connection,
+}
+
+impl SqliteBackend {
+    /// Opens the database file at `path`, creating the table of the entries
+    /// if there isn't one yet
+    pub fn open(path: &Path) -> io::Result<Self> {
+        // Unlike the text files, SQLite would create a missing file
+        if !path.exists() {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+
+        let conn = Connection::open(path).map_err(io_error)?;
+        conn.execute_batch(SCHEMA).map_err(io_error)?;
+        migrate(&conn).map_err(io_error)?;
+        Ok(Self { conn })
+    }
+}
+
+impl Backend for SqliteBackend {
+    fn load_entries(&mut self) -> Result<(Vec<Entry>, Vec<Quarantined>)> {
+        let mut stmt = self.conn.prepare("SELECT * FROM entries")
+            .map_err(io_error)?;
+        let rows = stmt.query_map(NO_PARAMS, entry_from_row)
+            .map_err(io_error)?;
+
+        // Blank words would show up as blank buttons in the ui, just like
+        // in the text files
+        let mut entries = Vec::new();
+        for entry in rows {
+            let entry = entry.map_err(io_error)?;
+            if !entry.word.trim().is_empty() && !entry.tr_word.is_empty() {
+                entries.push(entry);
+            }
+        }
+        Ok((entries, Vec::new()))
+    }
+
+    fn persist_entry(&mut self, entry: &Entry) -> io::Result<()> {
+        insert(&self.conn, entry).map_err(io_error)
+    }
+
+    // The rows are read whole, so there's nothing quarantined to keep
+    fn persist_all(&mut self, entries: &[&Entry], _: &[Quarantined])
+            -> io::Result<()> {
+        let tx = self.conn.transaction().map_err(io_error)?;
+        tx.execute("DELETE FROM entries", NO_PARAMS).map_err(io_error)?;
+        for entry in entries {
+            insert(&tx, entry).map_err(io_error)?;
+        }
+        tx.commit().map_err(io_error)
+    }
+}
@@ -0,0 +1,214 @@
+//! The text backend; lines of `DELIMITER` separated columns.
+//! Changed entries are appended to a journal at the end of the file, so that
+//! an answer doesn't rewrite the whole database.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::prelude::*;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use crate::db::{Entry, Format, Quarantined, write_atomic};
+use crate::error::{Error, Result};
+use crate::db::{JOURNAL_MARKER, JOURNAL_MAX_LEN};
+use crate::encoding;
+use super::Backend;
+
+
+/// A database stored in a text file
+pub struct TextBackend {
+    /// The path to the database file
+    path: PathBuf,
+
+    /// The handle to the database file
+    file: File,
+
+    /// The number of entries in the journal of the database file
+    journal_len: usize,
+
+    /// Whether the file isn't plain UTF-8 with `\n` line endings and has to
+    /// be rewritten before anything is appended to it
+    reencode: bool,
+}
+
+impl TextBackend {
+    /// Opens the database file at `path`
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(false)
+            .open(path)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            journal_len: 0,
+            reencode:    false,
+        })
+    }
+}
+
+impl Backend for TextBackend {
+    fn load_entries(&mut self) -> Result<(Vec<Entry>, Vec<Quarantined>)> {
+        // Read the contents of the file
+        let mut contents = Vec::new();
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.read_to_end(&mut contents)?;
+
+        // Make sure the file is text at all before parsing it; the broken
+        // lines of a text file are reported by the parser
+        if Format::detect(&contents) != Some(Format::Text) {
+            return Err(Error::UnknownFormat);
+        }
+        self.reencode = !encoding::is_canonical(&contents);
+        let (contents, _) = encoding::decode(&contents);
+
+        // Parse the entries from the lines of the file.
+        // Entries in the journal replace the earlier entries with the same
+        // words, or are new entries if there are no such entries.
+        // Broken lines are quarantined; blank lines are allowed.
+        let numlines        = contents.lines().count();
+        let mut entries     = Vec::with_capacity(numlines);
+        let mut positions   = HashMap::with_capacity(numlines);
+        let mut in_journal  = false;
+        let mut journal_len = 0;
+        let mut quarantined = Vec::new();
+
+        for (idx, line) in contents.lines().enumerate() {
+            if line == JOURNAL_MARKER {
+                in_journal = true;
+                continue;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry = match Entry::parse(line) {
+                Ok(entry)  => entry,
+                Err(error) => {
+                    quarantined.push(Quarantined {
+                        line: idx + 1,
+                        text: line.to_string(),
+                        error,
+                    });
+                    continue;
+                },
+            };
+
+            let key = (entry.word.clone(), entry.tr_word.clone());
+            if in_journal {
+                journal_len += 1;
+                if let Some(&pos) = positions.get(&key) {
+                    entries[pos] = entry;
+                    continue;
+                }
+            }
+            positions.insert(key, entries.len());
+            entries.push(entry);
+        }
+
+        // A file without a single valid line is rather broken than empty;
+        // its first broken line is reported instead of opening it without
+        // any words
+        if entries.is_empty() && !quarantined.is_empty() {
+            return Err(quarantined.remove(0).into());
+        }
+
+        self.journal_len = journal_len;
+        Ok((entries, quarantined))
+    }
+
+    fn persist_entry(&mut self, entry: &Entry) -> std::io::Result<()> {
+        let mut record = format!("{}\n", entry.db_repr());
+        if self.journal_len == 0 {
+            record.insert_str(0, &format!("{}\n", JOURNAL_MARKER));
+        }
+
+        // The last line of a file edited by hand may lack its line break;
+        // the record would be glued onto it otherwise
+        if self.file.seek(SeekFrom::End(0))? > 0 {
+            let mut last = [0];
+            self.file.seek(SeekFrom::End(-1))?;
+            self.file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                record.insert(0, '\n');
+            }
+        }
+        self.file.write_all(record.as_bytes())?;
+        self.journal_len += 1;
+        Ok(())
+    }
+
+    fn persist_all(&mut self, entries: &[&Entry], quarantined: &[Quarantined])
+            -> std::io::Result<()> {
+        let mut contents = String::new();
+        for entry in entries {
+            contents.push_str(&format!("{}\n", entry.db_repr()));
+        }
+        for line in quarantined {
+            contents.push_str(&format!("{}\n", line.text));
+        }
+
+        // The old handle still points to the replaced file
+        write_atomic(&self.path, contents.as_bytes())?;
+        self.file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.path)?;
+        self.journal_len = 0;
+        self.reencode    = false;
+        Ok(())
+    }
+
+    fn wants_rewrite(&self) -> bool {
+        self.journal_len >= JOURNAL_MAX_LEN || self.reencode
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.sync_all()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::db::Database;
+    use crate::error::{Error, ParseError};
+    use crate::scheduler::Grade;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn appends_to_a_file_without_a_trailing_line_break() {
+        // The entries have their ids, so the answer is appended to the file
+        // instead of rewriting it
+        let dir    = TempDir::new("no-trailing-newline");
+        let lines  = "Katze;; cat;; 0;; 0;; id=1\nHund;; dog;; 0;; 0;; id=2";
+        let path   = dir.write("words.txt", lines.as_bytes());
+        let mut db = Database::open(path.clone()).unwrap();
+        let idx    = db.usable.iter()
+            .position(|entry| entry.word == "Hund")
+            .unwrap();
+        db.update_timeout(idx, Grade::Good, None);
+        db.flush_and_close().unwrap();
+
+        let db = Database::open(path).unwrap();
+        assert!(db.quarantined.is_empty());
+        assert_eq!(db.usable.len(), 1);
+        assert_eq!(db.unusable[0].word, "Hund");
+        assert_eq!(db.unusable[0].cur_iter, 1);
+    }
+
+    #[test]
+    fn reports_the_first_broken_line_of_a_text_file() {
+        let dir  = TempDir::new("broken-lines");
+        let path = dir.write("words.txt", b"\nHund;; dog;; x;; 0\nKatze\n");
+        match Database::open(path) {
+            Err(Error::Parse { line, error }) => {
+                assert_eq!(line, 2);
+                assert_eq!(error, ParseError::Iteration("x".to_string()));
+            },
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(_)    => panic!("a file without a valid line opened"),
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Daily snapshots of the databases, for restoring them after they got
+//! broken (e.g. overwritten by a careless edit by hand).
+//!
+//! Before a database file is written for the first time on a day, the file
+//! as it is is compressed (gzip) into the backups directory next to it (see
+//! `paths::backups_dir`), named after the database and the time:
+//!
+//! ```text
+//! words.csv.backups/words.csv.2024-05-01_08-30-00.gz
+//! ```
+//!
+//! Only the `SNAPSHOT_COUNT` newest snapshots are kept. Just like the backups
+//! made when a database is opened (see `Database::backup`), a snapshot is the
+//! database file alone; the schedules of the profiles, the metadata and the
+//! history stay as they are when it's restored.
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use super::{DAY, backend, now, write_atomic};
+use crate::error::Result;
+use crate::paths;
+use crate::stats;
+
+/// The number of snapshots of a database that are kept
+pub const SNAPSHOT_COUNT: usize = 30;
+
+/// The number of the backups of a database that are kept; a new one is made
+/// whenever it's opened (see `Database::backup`)
+pub const BACKUP_COUNT: usize = 5;
+
+/// The extension of the snapshots
+const SNAPSHOT_EXTENSION: &str = "gz";
+
+
+/// A snapshot of a database
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    /// The path to the snapshot file
+    pub path: PathBuf,
+
+    /// When (in seconds since the epoch) the snapshot was taken
+    pub time: u64,
+
+    /// The number of entries in the snapshot, or `None` if it can't be read
+    pub entries: Option<usize>,
+}
+
+
+/// Formats `time` (in seconds since the epoch, UTC) for the names of the
+/// snapshots, e.g. `2024-05-01_08-30-00`; they're sorted by their names then
+fn timestamp(time: u64) -> String {
+    let (year, month, day) = stats::date(time / DAY);
+    let seconds            = time % DAY;
+    format!("{:04}-{:02}-{:02}_{:02}-{:02}-{:02}", year, month, day,
+            seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// Returns the time (in seconds since the epoch) the file at `path` was last
+/// modified
+fn modified(path: &Path) -> std::io::Result<u64> {
+    let modified = fs::metadata(path)?.modified()?;
+    Ok(modified.duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs()))
+}
+
+/// Returns the paths of the snapshots of the database at `db`, the newest one
+/// first
+fn snapshot_paths(db: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let dir = paths::backups_dir(db);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+    for file in fs::read_dir(&dir)? {
+        let path = file?.path();
+        if path.extension().is_some_and(|ext| ext == SNAPSHOT_EXTENSION) {
+            snapshots.push(path);
+        }
+    }
+    snapshots.sort();
+    snapshots.reverse();
+    Ok(snapshots)
+}
+
+/// Compresses the database file at `db` into a new snapshot and deletes the
+/// oldest ones over `SNAPSHOT_COUNT`.
+/// Returns the path of the snapshot, or `None` if there's nothing to take a
+/// snapshot of; the file doesn't exist yet or it's empty.
+pub fn snapshot(db: &Path) -> Result<Option<PathBuf>> {
+    let contents = match fs::read(db) {
+        Ok(contents) if !contents.is_empty() => contents,
+        Ok(_)                                => return Ok(None),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(None);
+        },
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&contents)?;
+    let compressed = encoder.finish()?;
+
+    // The snapshots taken within the same second (e.g. the one of a file
+    // that's being restored from the newest one) are numbered
+    let dir      = paths::backups_dir(db);
+    let filename = db.file_name().unwrap_or_default().to_string_lossy();
+    let name     = format!("{}.{}", filename, timestamp(now()));
+    let mut path = dir.join(format!("{}.{}", name, SNAPSHOT_EXTENSION));
+    for n in 1.. {
+        if !path.exists() {
+            break;
+        }
+        path = dir.join(format!("{}_{}.{}", name, n, SNAPSHOT_EXTENSION));
+    }
+    fs::create_dir_all(&dir)?;
+    write_atomic(&path, &compressed)?;
+
+    for old in snapshot_paths(db)?.iter().skip(SNAPSHOT_COUNT) {
+        fs::remove_file(old)?;
+    }
+    Ok(Some(path))
+}
+
+/// Takes a snapshot of the database file at `db` unless there's one taken
+/// since `day_start` (seconds since the epoch) already.
+/// Returns whether a snapshot was taken.
+pub fn snapshot_daily(db: &Path, day_start: u64) -> Result<bool> {
+    // The snapshots are never changed, so they were modified when taken
+    if let Some(newest) = snapshot_paths(db)?.first() {
+        if modified(newest)? >= day_start {
+            return Ok(false);
+        }
+    }
+    Ok(snapshot(db)?.is_some())
+}
+
+/// Returns the decompressed contents of the snapshot at `path`
+fn decompress(path: &Path) -> Result<Vec<u8>> {
+    let mut contents = Vec::new();
+    GzDecoder::new(File::open(path)?).read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+/// Returns the number of entries in the snapshot at `path` of the database
+/// at `db`, or `None` if it can't be read
+fn count_entries(db: &Path, path: &Path) -> Option<usize> {
+    let contents = decompress(path).ok()?;
+
+    // The backend is picked by the extension, so the snapshot is read from a
+    // temporary file with the name of the database
+    let tmp   = paths::backups_dir(db).join(db.file_name()?);
+    write_atomic(&tmp, &contents).ok()?;
+    let count = backend::open(&tmp)
+        .and_then(|mut backend| backend.load_entries())
+        .map(|(entries, _)| entries.len());
+    fs::remove_file(&tmp).ok()?;
+    count.ok()
+}
+
+/// Returns the snapshots of the database at `db`, the newest one first
+pub fn list(db: &Path) -> Result<Vec<Snapshot>> {
+    let mut snapshots = Vec::new();
+    for path in snapshot_paths(db)? {
+        snapshots.push(Snapshot {
+            time:    modified(&path)?,
+            entries: count_entries(db, &path),
+            path,
+        });
+    }
+    Ok(snapshots)
+}
+
+/// Replaces the database file at `db` by the snapshot at `path`.
+/// The database has to be closed first; it's reopened afterwards. The file
+/// as it was is kept in a snapshot of its own, so that the restore can be
+/// undone.
+pub fn restore(db: &Path, path: &Path) -> Result<()> {
+    let contents = decompress(path)?;
+    snapshot(db)?;
+    Ok(write_atomic(db, &contents)?)
+}
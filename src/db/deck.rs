@@ -0,0 +1,349 @@
+//! Sharing decks with other users as plain text files (`.wpdeck`).
+//!
+//! A shared deck is a text database (see the text format in `db`) of the
+//! entries of a single deck after a header of `#key=value` lines:
+//!
+//! ```text
+//! #wordpal-deck
+//! #version=2
+//! #deck=German
+//! #ladder=0, 1, 7, 14, 30
+//! #locale=cs
+//!
+//! Hund;; dog|hound;; 0;; 0;; notes=der Hund
+//! ```
+//!
+//! The header ends with a blank line, so that the words may start with `#`.
+//! The version and the name of the deck are required; the timeout ladder
+//! (see `scheduler::parse_ladder`) and the language of the author are
+//! optional. Unknown keys are ignored, like the unknown keys of the entries.
+//!
+//! The entries are shared with their words, notes, media, tags and card
+//! types, but without the schedules of the author; everyone learns them from
+//! the start.
+//!
+//! The format is versioned by `DECK_VERSION`. Decks of newer versions are
+//! refused, the ones of older versions are migrated one version after
+//! another (see `MIGRATIONS`) when they're read:
+//!
+//! - Version 1 is a plain text database without a header, so any database
+//!   can be imported as a deck. Its entries carry the schedules of their
+//!   author and the deck is named after the deck of the entries (if they
+//!   share one) or after the file.
+//! - Version 2 adds the header and drops the schedules.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use super::{Database, Entry, Format, JOURNAL_MARKER, write_atomic};
+use super::import::ImportSummary;
+use crate::encoding;
+use crate::error::{Error, ParseError, Result};
+use crate::locale::{self, Language};
+use crate::scheduler::{ladder_repr, parse_ladder};
+
+/// The version of the shared decks written by `export`
+pub const DECK_VERSION: u32 = 2;
+
+/// The extension of the shared decks
+pub const DECK_EXTENSION: &str = "wpdeck";
+
+/// The first line of the header of a shared deck
+const MAGIC: &str = "#wordpal-deck";
+
+/// What the lines of the header start with
+const HEADER_PREFIX: char = '#';
+
+/// Separates the key of a header line from its value
+const HEADER_DELIMITER: char = '=';
+
+/// Migrates a shared deck read from a file to the version after its own
+type Migration = fn(&mut SharedDeck, &Path);
+
+/// The migrations of the older versions; the `n`th one migrates a deck of
+/// version `n + 1` to the next version
+const MIGRATIONS: [Migration; DECK_VERSION as usize - 1] = [migrate_v1];
+
+
+/// The header of a shared deck
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeckHeader {
+    /// The version of the format of the deck
+    pub version: u32,
+
+    /// The name of the deck
+    pub deck: String,
+
+    /// The timeout ladder (in days) the deck was learned with, if it's
+    /// shared with one
+    pub ladder: Option<Vec<u64>>,
+
+    /// The language of the user interface of the author, if it's known; the
+    /// translations are most likely in it
+    pub locale: Option<Language>,
+}
+
+impl DeckHeader {
+    /// Parses the header `lines` (without the magic line), each with its
+    /// number (counted from 1).
+    /// Returns an error if the version or the name of the deck is missing or
+    /// anything can't be parsed, or if the version is newer than
+    /// `DECK_VERSION`.
+    fn parse(lines: &[(usize, &str)]) -> Result<Self> {
+        let mut version = None;
+        let mut deck    = None;
+        let mut header  = Self {
+            version: DECK_VERSION,
+            deck:    String::new(),
+            ladder:  None,
+            locale:  None,
+        };
+
+        for &(line, text) in lines {
+            let text      = text.trim_start_matches(HEADER_PREFIX);
+            let mut split = text.splitn(2, HEADER_DELIMITER);
+            let key       = split.next().unwrap_or("").trim();
+            let value     = split.next().unwrap_or("").trim();
+            let invalid   = || Error::Parse {
+                line,
+                error: ParseError::Header(key.to_string()),
+            };
+
+            match key {
+                "version" => {
+                    version = Some(value.parse().ok()
+                        .filter(|&version| version != 0)
+                        .ok_or_else(invalid)?);
+                },
+                "deck" if !value.is_empty() => deck = Some(value.to_string()),
+                "deck" => return Err(invalid()),
+                "ladder" => {
+                    header.ladder = Some(parse_ladder(value)
+                        .ok_or_else(invalid)?);
+                },
+                // A language of a newer version is just unknown
+                "locale" => {
+                    header.locale = Language::ALL.iter()
+                        .copied()
+                        .find(|language| language.code() == value);
+                },
+                _ => {},
+            }
+        }
+
+        // The missing keys are reported at the start of the header
+        let first   = lines.first().map_or(1, |&(line, _)| line);
+        let missing = |key: &str| Error::Parse {
+            line:  first,
+            error: ParseError::Header(key.to_string()),
+        };
+        header.version = version.ok_or_else(|| missing("version"))?;
+        header.deck    = deck.ok_or_else(|| missing("deck"))?;
+        if header.version > DECK_VERSION {
+            return Err(Error::UnsupportedVersion(header.version));
+        }
+        Ok(header)
+    }
+
+    /// Returns the header as the lines of a shared deck, with the magic line
+    fn repr(&self) -> String {
+        let mut repr = format!("{}\n", MAGIC);
+        repr.push_str(&format!("#version={}\n", self.version));
+        repr.push_str(&format!("#deck={}\n", self.deck));
+        if let Some(ladder) = &self.ladder {
+            repr.push_str(&format!("#ladder={}\n", ladder_repr(ladder)));
+        }
+        if let Some(locale) = self.locale {
+            repr.push_str(&format!("#locale={}\n", locale.code()));
+        }
+        repr.push('\n');
+        repr
+    }
+}
+
+
+/// A shared deck as it's read from a file
+#[derive(Clone, Debug)]
+pub struct SharedDeck {
+    /// The header of the deck
+    pub header: DeckHeader,
+
+    /// The entries of the deck, in the order of the file
+    pub entries: Vec<Entry>,
+
+    /// The number of lines that couldn't be parsed
+    pub invalid: usize,
+}
+
+impl SharedDeck {
+    /// Reads the shared deck at `path`, migrating it to `DECK_VERSION` if
+    /// it's older.
+    /// Broken entry lines are skipped and counted; a broken header makes the
+    /// whole deck unreadable.
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes         = fs::read(path)?;
+        let (contents, _) = encoding::decode(&bytes);
+        let lines: Vec<(usize, &str)> = contents.lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line))
+            .collect();
+
+        // A file without the magic line is a deck of the first version, as
+        // long as it's a text database at all
+        let (header, body) = match lines.first() {
+            Some((_, line)) if line.trim() == MAGIC => {
+                let end = lines.iter()
+                    .skip(1)
+                    .position(|(_, line)| !line.starts_with(HEADER_PREFIX))
+                    .map_or(lines.len(), |end| end + 1);
+                (DeckHeader::parse(&lines[1..end])?, &lines[end..])
+            },
+            _ if Format::detect(&bytes) == Some(Format::Text) => {
+                let header = DeckHeader {
+                    version: 1,
+                    deck:    String::new(),
+                    ladder:  None,
+                    locale:  None,
+                };
+                (header, &lines[..])
+            },
+            _ => return Err(Error::UnknownFormat),
+        };
+
+        let mut deck = Self {
+            header,
+            entries: Vec::new(),
+            invalid: 0,
+        };
+        deck.parse_entries(body);
+        deck.migrate(path);
+        Ok(deck)
+    }
+
+    /// Parses the entry `lines`, each with its number.
+    /// Like in a text database, an entry in the journal replaces the earlier
+    /// one with the same words.
+    fn parse_entries(&mut self, lines: &[(usize, &str)]) {
+        let mut positions = HashMap::new();
+        for &(_, line) in lines {
+            if line == JOURNAL_MARKER || line.trim().is_empty() {
+                continue;
+            }
+            let entry = match Entry::parse(line) {
+                Ok(entry) => entry,
+                Err(_)    => {
+                    self.invalid += 1;
+                    continue;
+                },
+            };
+
+            let key = (entry.word.clone(), entry.tr_word.clone());
+            match positions.get(&key) {
+                Some(&pos) => self.entries[pos] = entry,
+                None       => {
+                    positions.insert(key, self.entries.len());
+                    self.entries.push(entry);
+                },
+            }
+        }
+    }
+
+    /// Migrates the deck read from `path` to `DECK_VERSION`, one version
+    /// after another
+    fn migrate(&mut self, path: &Path) {
+        while self.header.version < DECK_VERSION {
+            MIGRATIONS[self.header.version as usize - 1](self, path);
+            self.header.version += 1;
+        }
+    }
+}
+
+/// Migrates a deck of version 1 (a plain text database) to version 2; names
+/// the deck and drops the schedules of the entries
+fn migrate_v1(deck: &mut SharedDeck, path: &Path) {
+    let first  = deck.entries.first().map(|entry| entry.deck.clone());
+    let shared = first.filter(|first| {
+        !first.is_empty() && deck.entries.iter().all(|e| &e.deck == first)
+    });
+    deck.header.deck = shared.unwrap_or_else(|| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    });
+    deck.entries = deck.entries.iter().map(shared_entry).collect();
+}
+
+
+/// Returns `entry` as it's shared; its words, notes, media, tags and card
+/// type without the schedules. The deck is named in the header.
+fn shared_entry(entry: &Entry) -> Entry {
+    Entry {
+        tr_word: entry.tr_word.clone(),
+        notes:   entry.notes.clone(),
+        audio:   entry.audio.clone(),
+        image:   entry.image.clone(),
+        tags:    entry.tags.clone(),
+        card:    entry.card,
+        ..Entry::new(&entry.word, "")
+    }
+}
+
+/// Writes the entries of `deck` of `db` (all of them if `None`) to a shared
+/// deck at `path`, with the ladder of the deck and the current language.
+/// The deck is named after the file if it's all the entries.
+/// Returns the number of the shared entries.
+pub fn export(db: &Database, deck: Option<&str>, path: &Path)
+        -> Result<usize> {
+    let entries: Vec<Entry> = db.usable.iter()
+        .chain(db.unusable.iter())
+        .filter(|entry| deck.is_none_or(|deck| entry.deck == deck))
+        .map(shared_entry)
+        .collect();
+    if entries.is_empty() {
+        return Err(Error::EmptyDatabase);
+    }
+
+    let ladder = db.ladder(deck.unwrap_or("")).to_vec();
+    let name   = match deck {
+        Some(deck) => deck.to_string(),
+        None       => path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+    let header = DeckHeader {
+        version: DECK_VERSION,
+        ladder:  Some(ladder),
+        deck:    name,
+        locale:  Some(locale::language()),
+    };
+
+    let mut contents = header.repr();
+    for entry in entries.iter() {
+        contents.push_str(&format!("{}\n", entry.db_repr()));
+    }
+    write_atomic(path, contents.as_bytes())?;
+    Ok(entries.len())
+}
+
+/// Imports the shared deck at `path` into `db`; its entries are added into
+/// the deck named in the header, except the words that are in the database
+/// already.
+/// Returns the header of the deck and what happened.
+/// The changes are persisted by the next `write_db`.
+pub fn import(db: &mut Database, path: &Path)
+        -> Result<(DeckHeader, ImportSummary)> {
+    let deck        = SharedDeck::load(path)?;
+    let mut summary = ImportSummary {
+        invalid: deck.invalid,
+        ..ImportSummary::default()
+    };
+
+    for mut entry in deck.entries {
+        entry.deck = deck.header.deck.clone();
+        match db.insert_entry(entry) {
+            true  => summary.added += 1,
+            false => summary.duplicates += 1,
+        }
+    }
+    Ok((deck.header, summary))
+}
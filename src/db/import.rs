@@ -0,0 +1,181 @@
+//! Importing words from CSV/TSV exports of other tools
+//! (Quizlet, Google Sheets, ...).
+
+use std::fs;
+use std::path::Path;
+use std::collections::HashSet;
+use super::Database;
+use crate::encoding::{self, Encoding};
+use crate::grading;
+
+/// The quote character of quoted fields
+const QUOTE: char = '"';
+
+
+/// How the imported file is laid out
+#[derive(Clone, Copy, Debug)]
+pub struct ImportOptions {
+    /// The character separating the columns
+    pub delimiter: char,
+
+    /// The column (counted from 0) holding the original words
+    pub word_column: usize,
+
+    /// The column (counted from 0) holding the translations
+    pub tr_word_column: usize,
+
+    /// Whether the first row is a header that shouldn't be imported
+    pub has_header: bool,
+
+    /// The encoding of the file; guessed from its contents if `None`
+    pub encoding: Option<Encoding>,
+}
+
+impl ImportOptions {
+    /// Comma separated values with the word and translation in the first two
+    /// columns
+    pub fn csv() -> Self {
+        Self {
+            delimiter:      ',',
+            word_column:    0,
+            tr_word_column: 1,
+            has_header:     false,
+            encoding:       None,
+        }
+    }
+
+    /// Tab separated values with the word and translation in the first two
+    /// columns
+    pub fn tsv() -> Self {
+        Self {
+            delimiter: '\t',
+            ..Self::csv()
+        }
+    }
+
+    /// Guesses the options from the extension of the file.
+    /// Anything that isn't `.csv` is treated as tab separated, because that's
+    /// what the export tools usually produce.
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Self::csv(),
+            _ => Self::tsv(),
+        }
+    }
+}
+
+
+/// What happened during an import
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// The number of words added to the database
+    pub added: usize,
+
+    /// The number of words skipped because they already are in the database
+    pub duplicates: usize,
+
+    /// The number of rows skipped because they were missing a column or
+    /// either of the words was blank
+    pub invalid: usize,
+}
+
+
+/// Splits `contents` into rows of fields.
+/// Fields may be quoted (`"a, b"`), with the quote itself escaped by doubling
+/// it (`""`). Quoted fields may span multiple lines; such line breaks are
+/// replaced by spaces because the database can't hold them.
+pub fn parse_rows(contents: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows   = Vec::new();
+    let mut row    = Vec::new();
+    let mut field  = String::new();
+    let mut quoted = false;
+    let mut chars  = contents.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            QUOTE if quoted && chars.peek() == Some(&QUOTE) => {
+                field.push(QUOTE);
+                chars.next();
+            },
+            QUOTE if quoted => quoted = false,
+            QUOTE if field.is_empty() => quoted = true,
+            '\r' | '\n' if quoted => {
+                if ch == '\n' {
+                    field.push(' ');
+                }
+            },
+            '\r' => {},
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            },
+            ch if ch == delimiter && !quoted => {
+                row.push(std::mem::take(&mut field));
+            },
+            ch => field.push(ch),
+        }
+    }
+
+    // The last row doesn't have to end with a newline
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows
+}
+
+/// Imports the words of the file at `path` into `db`.
+/// Words that are already in the database are skipped.
+/// The changes are persisted by the next `write_db`.
+pub fn import(db: &mut Database, path: &Path, options: &ImportOptions)
+        -> std::io::Result<ImportSummary> {
+    let bytes    = fs::read(path)?;
+    let contents = match options.encoding {
+        Some(encoding) => encoding.decode(&bytes),
+        None           => encoding::decode(&bytes).0,
+    };
+    let rows     = parse_rows(&contents, options.delimiter);
+    let skip     = options.has_header as usize;
+
+    let mut summary = ImportSummary::default();
+    for row in rows.iter().skip(skip) {
+        // Blank lines aren't worth reporting
+        if row.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+
+        let word    = row.get(options.word_column).map(|w| w.trim());
+        let tr_word = row.get(options.tr_word_column).map(|w| w.trim());
+        let (word, tr_word) = match (word, tr_word) {
+            (Some(word), Some(tr_word)) => (word, tr_word),
+            _ => {
+                summary.invalid += 1;
+                continue;
+            },
+        };
+
+        if db.contains_word(word) {
+            summary.duplicates += 1;
+        } else if db.add_entry(word, tr_word, "", "") {
+            summary.added += 1;
+        } else {
+            summary.invalid += 1;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Reads the wordlist at `path`, a word on every line, e.g. one of the
+/// language of the original words to tell whether the columns of a database
+/// are swapped (see `Database::detect_swapped`).
+/// The words are folded (see `grading::fold`), so they have to be folded to
+/// be looked up.
+pub fn read_wordlist(path: &Path) -> std::io::Result<HashSet<String>> {
+    let (contents, _) = encoding::decode(&fs::read(path)?);
+    Ok(contents.lines()
+        .map(grading::fold)
+        .filter(|word| !word.is_empty())
+        .collect())
+}
@@ -0,0 +1,199 @@
+//! Advisory locking of the databases, so that two instances of the program
+//! don't overwrite each other's changes.
+//! The lock is a file next to the database that is created when the database
+//! is opened and deleted when it's closed. It holds the id of the process
+//! that created it and the name of its computer, so that a lock left behind
+//! by a crash can be told apart from a database that's really open; see
+//! `is_stale`. Linux looks the process up in `/proc`, the other platforms ask
+//! their tools (`ps`, `tasklist`).
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+#[cfg(not(target_os = "linux"))]
+use std::process::{Command, Stdio};
+
+
+/// The lock of a database; released when dropped
+#[derive(Debug)]
+pub struct Lock {
+    /// The lock file
+    path: PathBuf,
+}
+
+impl Lock {
+    /// Locks a database by creating the lock file at `path`.
+    /// A stale lock (see `is_stale`) is broken and replaced.
+    /// Fails with `io::ErrorKind::AlreadyExists` if it's locked already.
+    pub fn acquire(path: PathBuf) -> io::Result<Self> {
+        match Self::create(path.clone()) {
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists
+                    && is_stale(&path) => {
+                fs::remove_file(&path)?;
+                Self::create(path)
+            },
+            result => result,
+        }
+    }
+
+    /// Creates the lock file at `path`, unless there is one already
+    fn create(path: PathBuf) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        // The lock file is deleted again if the id can't be written
+        let lock = Self { path };
+        writeln!(file, "{}", std::process::id())?;
+        if let Some(host) = hostname() {
+            writeln!(file, "{}", host)?;
+        }
+        Ok(lock)
+    }
+
+    /// Returns the path of the lock file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Returns whether the lock file at `path` was left behind by a process of
+/// this computer that isn't running anymore, e.g. after a crash.
+/// The processes of the other computers (e.g. sharing the folder) can't be
+/// seen, so their locks are never stale. Neither is a lock without a process
+/// id (e.g. one that's just being created), nor any lock on the platforms
+/// that can't tell whether a process is running.
+fn is_stale(path: &Path) -> bool {
+    let contents  = fs::read_to_string(path).unwrap_or_default();
+    let mut lines = contents.lines().map(str::trim);
+    let pid       = lines.next().and_then(|pid| pid.parse::<u32>().ok());
+    let host      = lines.next();
+    match (pid, host, hostname()) {
+        (Some(pid), Some(host), Some(ours)) if host == ours => {
+            !is_running(pid)
+        },
+        _ => false,
+    }
+}
+
+/// Returns the name of this computer, or `None` if it can't be told
+#[cfg(target_os = "linux")]
+fn hostname() -> Option<String> {
+    fs::read_to_string("/proc/sys/kernel/hostname").ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Returns the name of this computer, or `None` if it can't be told
+#[cfg(not(target_os = "linux"))]
+fn hostname() -> Option<String> {
+    output("hostname", &[])
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Returns whether the process with the id `pid` is running
+#[cfg(target_os = "linux")]
+fn is_running(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// Returns whether the process with the id `pid` is running; `ps` fails for
+/// the processes that don't exist. If it can't be run, it's assumed it is.
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_running(pid: u32) -> bool {
+    Command::new("ps")
+        .args(&["-p", &pid.to_string()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_or(true, |status| status.success())
+}
+
+/// Returns whether the process with the id `pid` is running; `tasklist` lists
+/// it with its id in quotes. If it can't be run, it's assumed it is.
+#[cfg(windows)]
+fn is_running(pid: u32) -> bool {
+    let filter = format!("PID eq {}", pid);
+    output("tasklist", &["/FI", &filter, "/FO", "CSV", "/NH"])
+        .map_or(true, |tasks| tasks.contains(&format!("\"{}\"", pid)))
+}
+
+/// Returns whether the process with the id `pid` is running; it can't be
+/// told, so it's assumed it is
+#[cfg(not(any(unix, windows)))]
+fn is_running(_pid: u32) -> bool {
+    true
+}
+
+/// Returns the standard output of `program` run with `args`, or `None` if it
+/// can't be run or fails
+#[cfg(not(target_os = "linux"))]
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        // Nothing can be done about it here; at worst the lock file has to be
+        // deleted by hand
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn a_lock_of_a_running_process_holds() {
+        let dir  = TempDir::new("running-lock");
+        let path = dir.path("words.txt.lock");
+        let lock = Lock::acquire(path.clone()).unwrap();
+        let err  = Lock::acquire(path.clone()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+
+        // A lock without a process id may be just being created
+        drop(lock);
+        fs::write(&path, b"").unwrap();
+        let err = Lock::acquire(path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn a_lock_of_a_dead_process_is_broken() {
+        // Process ids never get this high on Linux
+        let dir      = TempDir::new("stale-lock");
+        let host     = hostname().unwrap();
+        let contents = format!("4000000000\n{}\n", host);
+        let path     = dir.write("words.txt.lock", contents.as_bytes());
+        let lock     = Lock::acquire(path.clone()).unwrap();
+        let contents = fs::read_to_string(lock.path()).unwrap();
+        assert_eq!(contents, format!("{}\n{}\n", std::process::id(), host));
+
+        drop(lock);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn a_lock_of_another_computer_holds() {
+        let dir = TempDir::new("foreign-lock");
+        for contents in ["4000000000\n", "4000000000\nelsewhere\n"].iter() {
+            let path = dir.write("words.txt.lock", contents.as_bytes());
+            let err  = Lock::acquire(path).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        }
+    }
+}
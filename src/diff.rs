@@ -0,0 +1,94 @@
+//! Character-level diffing of two words.
+//! Used to show the user where exactly their answer went wrong.
+
+/// A single difference between two words.
+/// Positions are character (not byte) indices into the first word.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffOp {
+    /// The character at `pos` should be `to` instead of `from`
+    Substitute { pos: usize, from: char, to: char },
+
+    /// `ch` should be inserted before the character at `pos`
+    Insert { pos: usize, ch: char },
+
+    /// The character `ch` at `pos` should be deleted
+    Delete { pos: usize, ch: char },
+}
+
+/// Returns the smallest list of operations that turn `a` into `b`.
+/// If the words are equal, the list is empty.
+pub fn diff(a: &str, b: &str) -> Vec<DiffOp> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // The classic Levenshtein table;
+    // `dist[i][j]` is the distance between `a[..i]` and `b[..j]`
+    let mut dist = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in dist.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in dist[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i-1] == b[j-1] { 0 } else { 1 };
+            dist[i][j] = (dist[i-1][j-1] + cost)
+                .min(dist[i-1][j] + 1)
+                .min(dist[i][j-1] + 1);
+        }
+    }
+
+    // Walk the table back from the end and collect the operations
+    let mut ops  = Vec::new();
+    let (mut i, mut j) = (a.len(), b.len());
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && a[i-1] == b[j-1] && dist[i][j] == dist[i-1][j-1] {
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && j > 0 && dist[i][j] == dist[i-1][j-1] + 1 {
+            ops.push(DiffOp::Substitute { pos: i-1, from: a[i-1], to: b[j-1] });
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && dist[i][j] == dist[i-1][j] + 1 {
+            ops.push(DiffOp::Delete { pos: i-1, ch: a[i-1] });
+            i -= 1;
+        } else {
+            ops.push(DiffOp::Insert { pos: i, ch: b[j-1] });
+            j -= 1;
+        }
+    }
+
+    ops.reverse();
+    ops
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_single_typo_is_a_single_substitution() {
+        assert_eq!(diff("dog", "dig"), vec![
+            DiffOp::Substitute { pos: 1, from: 'o', to: 'i' },
+        ]);
+        assert_eq!(diff("čaj", "caj"), vec![
+            DiffOp::Substitute { pos: 0, from: 'č', to: 'c' },
+        ]);
+    }
+
+    #[test]
+    fn equal_words_have_no_differences() {
+        assert!(diff("hound", "hound").is_empty());
+        assert!(diff("", "").is_empty());
+    }
+
+    #[test]
+    fn missing_and_extra_characters_are_inserted_and_deleted() {
+        assert_eq!(diff("dg", "dog"), vec![DiffOp::Insert { pos: 1, ch: 'o' }]);
+        assert_eq!(diff("doog", "dog").len(), 1);
+        assert!(matches!(diff("dogs", "dog")[..],
+                         [DiffOp::Delete { pos: 3, ch: 's' }]));
+    }
+}
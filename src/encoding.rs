@@ -0,0 +1,127 @@
+//! Decoding of the text files the words are read from.
+//! The files are mostly UTF-8, but the older ones (and whatever Excel saves
+//! on Czech systems) are in Windows-1250. Both are told apart by whether the
+//! file is valid UTF-8; a Windows-1250 file with any accented letters almost
+//! never is. Byte order marks are dropped and the line endings are unified,
+//! so that the rest of the program only ever sees `\n`. Whatever is written
+//! back is always UTF-8.
+
+use std::borrow::Cow;
+
+/// The byte order mark some editors put at the start of UTF-8 files
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// The characters of the bytes 0x80 to 0xff in Windows-1250; the undefined
+/// ones are replacement characters
+const WINDOWS_1250: [char; 128] = [
+    // 0x80
+    '\u{20AC}', '\u{FFFD}', '\u{201A}', '\u{FFFD}',
+    '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{FFFD}', '\u{2030}', '\u{0160}', '\u{2039}',
+    '\u{015A}', '\u{0164}', '\u{017D}', '\u{0179}',
+
+    // 0x90
+    '\u{FFFD}', '\u{2018}', '\u{2019}', '\u{201C}',
+    '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{FFFD}', '\u{2122}', '\u{0161}', '\u{203A}',
+    '\u{015B}', '\u{0165}', '\u{017E}', '\u{017A}',
+
+    // 0xA0
+    '\u{00A0}', '\u{02C7}', '\u{02D8}', '\u{0141}',
+    '\u{00A4}', '\u{0104}', '\u{00A6}', '\u{00A7}',
+    '\u{00A8}', '\u{00A9}', '\u{015E}', '\u{00AB}',
+    '\u{00AC}', '\u{00AD}', '\u{00AE}', '\u{017B}',
+
+    // 0xB0
+    '\u{00B0}', '\u{00B1}', '\u{02DB}', '\u{0142}',
+    '\u{00B4}', '\u{00B5}', '\u{00B6}', '\u{00B7}',
+    '\u{00B8}', '\u{0105}', '\u{015F}', '\u{00BB}',
+    '\u{013D}', '\u{02DD}', '\u{013E}', '\u{017C}',
+
+    // 0xC0
+    '\u{0154}', '\u{00C1}', '\u{00C2}', '\u{0102}',
+    '\u{00C4}', '\u{0139}', '\u{0106}', '\u{00C7}',
+    '\u{010C}', '\u{00C9}', '\u{0118}', '\u{00CB}',
+    '\u{011A}', '\u{00CD}', '\u{00CE}', '\u{010E}',
+
+    // 0xD0
+    '\u{0110}', '\u{0143}', '\u{0147}', '\u{00D3}',
+    '\u{00D4}', '\u{0150}', '\u{00D6}', '\u{00D7}',
+    '\u{0158}', '\u{016E}', '\u{00DA}', '\u{0170}',
+    '\u{00DC}', '\u{00DD}', '\u{0162}', '\u{00DF}',
+
+    // 0xE0
+    '\u{0155}', '\u{00E1}', '\u{00E2}', '\u{0103}',
+    '\u{00E4}', '\u{013A}', '\u{0107}', '\u{00E7}',
+    '\u{010D}', '\u{00E9}', '\u{0119}', '\u{00EB}',
+    '\u{011B}', '\u{00ED}', '\u{00EE}', '\u{010F}',
+
+    // 0xF0
+    '\u{0111}', '\u{0144}', '\u{0148}', '\u{00F3}',
+    '\u{00F4}', '\u{0151}', '\u{00F6}', '\u{00F7}',
+    '\u{0159}', '\u{016F}', '\u{00FA}', '\u{0171}',
+    '\u{00FC}', '\u{00FD}', '\u{0163}', '\u{02D9}',
+];
+
+
+/// An encoding of a text file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Windows1250,
+}
+
+impl Encoding {
+    /// Guesses the encoding of `bytes`; UTF-8 if they're valid UTF-8,
+    /// Windows-1250 otherwise
+    pub fn detect(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(_)  => Encoding::Utf8,
+            Err(_) => Encoding::Windows1250,
+        }
+    }
+
+    /// Decodes `bytes` in this encoding, without the byte order mark and with
+    /// the line endings unified.
+    /// Invalid UTF-8 sequences are replaced by replacement characters.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        // Some editors put the mark even in front of what isn't UTF-8 at all
+        let bytes = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+        let text  = match self {
+            Encoding::Utf8        => String::from_utf8_lossy(bytes),
+            Encoding::Windows1250 => Cow::Owned(bytes.iter()
+                .map(|&byte| match byte {
+                    0x00..=0x7f => byte as char,
+                    _           => WINDOWS_1250[byte as usize - 0x80],
+                })
+                .collect()),
+        };
+        normalize_line_endings(&text)
+    }
+}
+
+
+/// Decodes `bytes` in whatever encoding they seem to be in.
+/// Returns the text and the encoding it was in.
+pub fn decode(bytes: &[u8]) -> (String, Encoding) {
+    let encoding = Encoding::detect(bytes.strip_prefix(UTF8_BOM)
+        .unwrap_or(bytes));
+    (encoding.decode(bytes), encoding)
+}
+
+/// Returns whether `bytes` are exactly what writing `decode(bytes)` back
+/// would produce; UTF-8 without a byte order mark and with `\n` line endings
+pub fn is_canonical(bytes: &[u8]) -> bool {
+    std::str::from_utf8(bytes).is_ok()
+        && !bytes.starts_with(UTF8_BOM)
+        && !bytes.contains(&b'\r')
+}
+
+/// Replaces the Windows (`\r\n`) and old Mac (`\r`) line endings by `\n`
+fn normalize_line_endings(text: &str) -> String {
+    if !text.contains('\r') {
+        return text.to_string();
+    }
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
@@ -0,0 +1,119 @@
+//! The errors of the database and everything built on it.
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// A result with the error of this crate
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+/// Why a line of a database couldn't be parsed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The line has this many columns; it needs either 2 (the words of a new
+    /// entry) or at least 4
+    ColumnCount(usize),
+
+    /// One of the words is blank
+    BlankWord,
+
+    /// The timeout delay iteration isn't a number
+    Iteration(String),
+
+    /// The timeout isn't a number
+    Timeout(String),
+
+    /// The value of an optional `key=value` column can't be parsed
+    Field(String),
+
+    /// The line isn't a valid entry of a JSON database; the complaint of the
+    /// JSON parser
+    Json(String),
+
+    /// The key of the header of a shared deck is missing or its value can't
+    /// be parsed
+    Header(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::ColumnCount(count) => {
+                write!(f, "expected 2 or at least 4 columns, found {}", count)
+            },
+            ParseError::BlankWord => write!(f, "a word is blank"),
+            ParseError::Iteration(value) => {
+                write!(f, "the iteration '{}' isn't a number", value)
+            },
+            ParseError::Timeout(value) => {
+                write!(f, "the timeout '{}' isn't a number", value)
+            },
+            ParseError::Field(field) => {
+                write!(f, "the column '{}' can't be parsed", field)
+            },
+            ParseError::Json(error) => write!(f, "invalid JSON: {}", error),
+            ParseError::Header(key) => {
+                write!(f, "the header '{}' is missing or invalid", key)
+            },
+        }
+    }
+}
+
+
+/// Errors that can occur while opening, writing or exporting the database
+#[derive(Debug)]
+pub enum Error {
+    /// The underlying file operation failed
+    Io(std::io::Error),
+
+    /// The file isn't in any of the recognized database formats
+    UnknownFormat,
+
+    /// A line of the database (counted from 1) couldn't be parsed
+    Parse { line: usize, error: ParseError },
+
+    /// There are no entries in the database
+    EmptyDatabase,
+
+    /// The database at this path is open somewhere else already
+    Locked(PathBuf),
+
+    /// The file was written by a newer version of wordpal, in this version
+    /// of its format
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err)       => write!(f, "{}", err),
+            Error::UnknownFormat => write!(f, "unknown database format"),
+            Error::Parse { line, error } => {
+                write!(f, "line {}: {}", line, error)
+            },
+            Error::EmptyDatabase => write!(f, "the database is empty"),
+            Error::Locked(path)  => {
+                write!(f, "the database {} is already open", path.display())
+            },
+            Error::UnsupportedVersion(version) => {
+                write!(f, "the file is in a newer format (version {})",
+                       version)
+            },
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _              => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
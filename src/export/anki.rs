@@ -0,0 +1,238 @@
+//! Export into an Anki package (`.apkg`).
+//!
+//! An Anki package is a zip archive holding an SQLite database of the
+//! collection (`collection.anki2`) and a JSON map of the media files (`media`).
+//! Every entry becomes a "Basic" note with a single card whose scheduling
+//! state is mapped to Anki's intervals, so the progress carries over.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use rusqlite::{params, Connection};
+use zip::write::{FileOptions, ZipWriter};
+use crate::db::{Database, Entry, DAY, now};
+use crate::error::{Error, Result};
+use super::json_string;
+
+/// The name of the deck holding the entries that don't belong to any deck
+const DEFAULT_DECK: &str = "Wordpal";
+
+/// The id of the note type of the exported notes
+const MODEL_ID: i64 = 1_342_697_561_419;
+
+/// Anki separates the fields of a note by this character
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+/// The schema of an Anki 2.1 (legacy, `ver` 11) collection
+const SCHEMA: &str = "
+    CREATE TABLE col (
+        id integer primary key, crt integer not null, mod integer not null,
+        scm integer not null, ver integer not null, dty integer not null,
+        usn integer not null, ls integer not null, conf text not null,
+        models text not null, decks text not null, dconf text not null,
+        tags text not null);
+    CREATE TABLE notes (
+        id integer primary key, guid text not null, mid integer not null,
+        mod integer not null, usn integer not null, tags text not null,
+        flds text not null, sfld integer not null, csum integer not null,
+        flags integer not null, data text not null);
+    CREATE TABLE cards (
+        id integer primary key, nid integer not null, did integer not null,
+        ord integer not null, mod integer not null, usn integer not null,
+        type integer not null, queue integer not null, due integer not null,
+        ivl integer not null, factor integer not null, reps integer not null,
+        lapses integer not null, left integer not null, odue integer not null,
+        odid integer not null, flags integer not null, data text not null);
+    CREATE TABLE revlog (
+        id integer primary key, cid integer not null, usn integer not null,
+        ease integer not null, ivl integer not null, lastIvl integer not null,
+        factor integer not null, time integer not null, type integer not null);
+    CREATE TABLE graves (
+        usn integer not null, oid integer not null, type integer not null);
+";
+
+
+/// Converts any error into an `io::Error` so that the export has a single
+/// error type like the rest of the database code
+fn io_error<E: std::error::Error + Send + Sync + 'static>(err: E) -> io::Error {
+    io::Error::other(err)
+}
+
+/// Returns the checksum Anki uses to find duplicate notes; the first 8 hex
+/// digits of the SHA-1 of the sort field
+fn checksum(field: &str) -> i64 {
+    let digest = sha1_smol::Sha1::from(field).digest().bytes();
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) as i64
+}
+
+/// Returns the interval (in days) an Anki review card of `entry` of `db`
+/// should have
+fn interval(db: &Database, entry: &Entry) -> u64 {
+    // Anki review cards always have an interval of at least a day
+    db.interval(entry).max(1)
+}
+
+/// The JSON of a deck named `name` with the id `id`
+fn deck_json(id: i64, name: &str, modified: u64) -> String {
+    format!(concat!(
+        "{{\"id\":{id},\"name\":{name},\"mod\":{modified},\"usn\":-1,",
+        "\"lrnToday\":[0,0],\"revToday\":[0,0],\"newToday\":[0,0],",
+        "\"timeToday\":[0,0],\"collapsed\":false,\"browserCollapsed\":false,",
+        "\"desc\":\"\",\"dyn\":0,\"conf\":1,\"extendNew\":10,",
+        "\"extendRev\":50}}"),
+        id = id, name = json_string(name), modified = modified)
+}
+
+/// The JSON of the "Basic" note type the entries are exported as
+fn models_json(deck_id: i64, modified: u64) -> String {
+    format!(concat!(
+        "{{\"{id}\":{{\"id\":{id},\"name\":\"Wordpal\",\"type\":0,",
+        "\"mod\":{modified},\"usn\":-1,\"sortf\":0,\"did\":{deck},",
+        "\"tmpls\":[{{\"name\":\"Card 1\",\"ord\":0,",
+        "\"qfmt\":\"{{{{Front}}}}\",",
+        "\"afmt\":\"{{{{FrontSide}}}}<hr id=answer>{{{{Back}}}}\",",
+        "\"bqfmt\":\"\",\"bafmt\":\"\",\"did\":null}}],",
+        "\"flds\":[",
+        "{{\"name\":\"Front\",\"ord\":0,\"sticky\":false,\"rtl\":false,",
+        "\"font\":\"Arial\",\"size\":20,\"media\":[]}},",
+        "{{\"name\":\"Back\",\"ord\":1,\"sticky\":false,\"rtl\":false,",
+        "\"font\":\"Arial\",\"size\":20,\"media\":[]}}],",
+        "\"css\":\".card {{ font-family: arial; font-size: 20px; ",
+        "text-align: center; }}\",",
+        "\"latexPre\":\"\\\\documentclass[12pt]{{article}}\\n",
+        "\\\\begin{{document}}\\n\",",
+        "\"latexPost\":\"\\\\end{{document}}\",",
+        "\"tags\":[],\"vers\":[],\"req\":[[0,\"all\",[0]]]}}}}"),
+        id = MODEL_ID, modified = modified, deck = deck_id)
+}
+
+/// The JSON of the default deck options
+fn dconf_json(modified: u64) -> String {
+    format!(concat!(
+        "{{\"1\":{{\"id\":1,\"name\":\"Default\",\"mod\":{modified},",
+        "\"usn\":-1,\"maxTaken\":60,\"autoplay\":true,\"timer\":0,",
+        "\"replayq\":true,\"dyn\":false,",
+        "\"new\":{{\"delays\":[1,10],\"ints\":[1,4,7],\"initialFactor\":2500,",
+        "\"order\":1,\"perDay\":20,\"bury\":true,\"separate\":true}},",
+        "\"rev\":{{\"perDay\":200,\"ease4\":1.3,\"fuzz\":0.05,",
+        "\"minSpace\":1,\"ivlFct\":1,\"maxIvl\":36500,\"bury\":true,",
+        "\"hardFactor\":1.2}},",
+        "\"lapse\":{{\"delays\":[10],\"mult\":0,\"minInt\":1,",
+        "\"leechFails\":8,\"leechAction\":0}}}}}}"),
+        modified = modified)
+}
+
+/// Exports all the entries of `db` into an Anki package at `path`.
+/// An empty database isn't exported.
+pub fn export(db: &Database, path: &Path) -> Result<()> {
+    let now_secs = now();
+    let now_ms   = now_secs as i64 * 1000;
+    let entries: Vec<&Entry> = db.usable.iter()
+        .chain(db.unusable.iter())
+        .collect();
+    if entries.is_empty() {
+        return Err(Error::EmptyDatabase);
+    }
+
+    // Anki counts the due days of review cards from the creation of the
+    // collection, so it is created on the day of the earliest due review
+    let today = now_secs / DAY;
+    let crt   = entries.iter()
+        .filter(|entry| !entry.is_new())
+        .map(|entry| entry.timeout / DAY)
+        .min()
+        .unwrap_or(today)
+        .min(today) * DAY;
+
+    // Every deck of the database becomes an Anki deck
+    let mut deck_names = db.decks();
+    deck_names.insert(0, DEFAULT_DECK.to_string());
+    let deck_id = |deck: &str| {
+        let pos = deck_names.iter().position(|name| name == deck).unwrap_or(0);
+        now_ms + pos as i64
+    };
+    let default   = deck_json(1, "Default", now_secs);
+    let mut decks = vec![format!("\"1\":{}", default)];
+    for name in deck_names.iter() {
+        let id = deck_id(name);
+        decks.push(format!("\"{}\":{}", id, deck_json(id, name, now_secs)));
+    }
+    let decks = format!("{{{}}}", decks.join(","));
+
+    // Build the collection in a temporary file next to the package
+    let collection = path.with_extension("anki2.tmp");
+    let _ = fs::remove_file(&collection);
+    {
+        let conn = Connection::open(&collection).map_err(io_error)?;
+        conn.execute_batch(SCHEMA).map_err(io_error)?;
+
+        let conf = format!(concat!(
+            "{{\"nextPos\":{next},\"estTimes\":true,\"activeDecks\":[1],",
+            "\"sortType\":\"noteFld\",\"timeLim\":0,\"sortBackwards\":false,",
+            "\"addToCur\":true,\"curDeck\":1,\"newBury\":true,",
+            "\"newSpread\":0,\"dueCounts\":true,\"curModel\":\"{model}\",",
+            "\"collapseTime\":1200}}"),
+            next = entries.len() + 1, model = MODEL_ID);
+        conn.execute(
+            "INSERT INTO col
+                VALUES (1, ?, ?, ?, 11, 0, 0, 0, ?, ?, ?, ?, '{}')",
+            params![crt as i64, now_ms, now_ms, conf,
+                    models_json(deck_id(DEFAULT_DECK), now_secs),
+                    decks, dconf_json(now_secs)])
+            .map_err(io_error)?;
+
+        for (idx, entry) in entries.iter().enumerate() {
+            let id     = now_ms + idx as i64;
+            let fields = format!("{}{}{}", entry.word, FIELD_SEPARATOR,
+                                 entry.translations());
+            conn.execute(
+                "INSERT INTO notes VALUES (?, ?, ?, ?, -1, '', ?, ?, ?, 0, '')",
+                params![id, format!("wordpal{:x}", id), MODEL_ID,
+                        now_secs as i64, fields, entry.word,
+                        checksum(&entry.word)])
+                .map_err(io_error)?;
+
+            // New cards are due by their position, review cards by the day
+            let deck = if entry.deck.is_empty() {
+                DEFAULT_DECK
+            } else {
+                &entry.deck
+            };
+            let (kind, due, ivl) = if entry.is_new() {
+                (0, idx as i64 + 1, 0)
+            } else {
+                (2, (entry.timeout / DAY) as i64 - (crt / DAY) as i64,
+                 interval(db, entry) as i64)
+            };
+            let factor = if entry.is_new() {
+                0
+            } else {
+                (entry.ease * 1000.) as i64
+            };
+
+            // Leeches stay suspended in Anki
+            let queue = if entry.suspended { -1 } else { kind };
+            conn.execute(
+                "INSERT INTO cards VALUES
+                    (?, ?, ?, 0, ?, -1, ?, ?, ?, ?, ?, ?, ?, 0, 0, 0, 0, '')",
+                params![id, id, deck_id(deck), now_secs as i64, kind, queue,
+                        due, ivl, factor, entry.reps as i64,
+                        entry.lapses as i64])
+                .map_err(io_error)?;
+        }
+    }
+
+    // And pack it together with an (empty) media map
+    let contents = fs::read(&collection)?;
+    fs::remove_file(&collection)?;
+
+    let mut zip = ZipWriter::new(File::create(path)?);
+    zip.start_file("collection.anki2", FileOptions::default())
+        .map_err(io_error)?;
+    zip.write_all(&contents)?;
+    zip.start_file("media", FileOptions::default()).map_err(io_error)?;
+    zip.write_all(b"{}")?;
+    zip.finish().map_err(io_error)?;
+
+    Ok(())
+}
@@ -0,0 +1,225 @@
+//! Export of the review history and the state of the entries, for analyzing
+//! the learning in a spreadsheet or a script.
+//!
+//! Every answer of the history becomes a review record and every entry an
+//! entry record, with its schedule as it is now. The records are written
+//! either as CSV (a file of reviews and another one of entries, see
+//! `entries_path`) or as a single JSON object of both:
+//!
+//! ```text
+//! { "reviews": [ { "time": ..., ... }, ... ],
+//!   "entries": [ { "word": ..., ... }, ... ] }
+//! ```
+//!
+//! The times are in seconds since the epoch, with the (UTC) dates next to
+//! them for the spreadsheets.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::db::{Database, Entry, DAY, join_translations};
+use crate::error::Result;
+use crate::stats::{self, Review};
+use super::json_string;
+
+/// The suffix of the file name of the entries exported as CSV
+const ENTRIES_SUFFIX: &str = "-entries";
+
+/// The columns of the exported reviews
+const REVIEW_COLUMNS: [&str; 7] = [
+    "time", "date", "word", "translation", "correct", "interval", "response",
+];
+
+/// The columns of the exported entries
+const ENTRY_COLUMNS: [&str; 17] = [
+    "word", "translations", "deck", "new", "suspended", "due", "due_date",
+    "interval", "iteration", "ease", "reps", "lapses", "stability",
+    "difficulty", "introduced", "added", "reviewed",
+];
+
+
+/// The formats the history can be exported in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryFormat {
+    /// Comma separated values; a file of reviews and a file of entries
+    Csv,
+
+    /// A single JSON object
+    Json,
+}
+
+impl HistoryFormat {
+    /// Picks the format by the extension of `path`; anything that isn't
+    /// `.json` is exported as CSV
+    pub fn for_path(path: &Path) -> Self {
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        match extension {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => {
+                HistoryFormat::Json
+            },
+            _ => HistoryFormat::Csv,
+        }
+    }
+}
+
+
+/// A value of a record
+enum Value {
+    Text(String),
+    Number(String),
+    Bool(bool),
+    /// A value that isn't known, e.g. the response time of an old answer
+    Missing,
+}
+
+impl Value {
+    fn number<T: ToString>(number: T) -> Self {
+        Value::Number(number.to_string())
+    }
+
+    /// Returns the value as a field of a CSV line, quoted if needed
+    fn csv(&self) -> String {
+        match self {
+            Value::Text(text) if text.contains(&[',', '"', '\n', '\r'][..]) => {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            },
+            Value::Text(text) | Value::Number(text) => text.clone(),
+            Value::Bool(value) => (*value as u8).to_string(),
+            Value::Missing     => String::new(),
+        }
+    }
+
+    /// Returns the value as a JSON value
+    fn json(&self) -> String {
+        match self {
+            Value::Text(text)   => json_string(text),
+            Value::Number(text) => text.clone(),
+            Value::Bool(value)  => value.to_string(),
+            Value::Missing      => "null".to_string(),
+        }
+    }
+}
+
+
+/// Returns the UTC date of `time` (seconds since the epoch) as `YYYY-MM-DD`
+fn date(time: u64) -> String {
+    let (year, month, day) = stats::date(time / DAY);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Returns the values of `review`, in the order of `REVIEW_COLUMNS`
+fn review_values(review: &Review) -> Vec<Value> {
+    vec![
+        Value::number(review.time),
+        Value::Text(date(review.time)),
+        Value::Text(review.word.clone()),
+        Value::Text(review.tr_word.clone()),
+        Value::Bool(review.correct),
+        Value::number(review.interval),
+        review.response.map_or(Value::Missing, Value::number),
+    ]
+}
+
+/// Returns the values of `entry` of `db`, in the order of `ENTRY_COLUMNS`
+fn entry_values(db: &Database, entry: &Entry) -> Vec<Value> {
+    let new = entry.is_new();
+    let due = |value: Value| if new { Value::Missing } else { value };
+    let known = |time: u64| match time {
+        0    => Value::Missing,
+        time => Value::number(time),
+    };
+    vec![
+        Value::Text(entry.word.clone()),
+        Value::Text(join_translations(&entry.tr_word)),
+        Value::Text(entry.deck.clone()),
+        Value::Bool(new),
+        Value::Bool(entry.suspended),
+        due(Value::number(entry.timeout)),
+        due(Value::Text(date(entry.timeout))),
+        due(Value::number(db.interval(entry))),
+        Value::number(entry.cur_iter),
+        Value::number(entry.ease),
+        Value::number(entry.reps),
+        Value::number(entry.lapses),
+        Value::number(entry.stability),
+        Value::number(entry.difficulty),
+        known(entry.introduced),
+        known(entry.added),
+        known(entry.reviewed),
+    ]
+}
+
+/// Returns the CSV table with the `columns` and a line of each of `records`
+fn csv_table(columns: &[&str], records: &[Vec<Value>]) -> String {
+    let mut table = columns.join(",");
+    table.push('\n');
+    for record in records {
+        let fields: Vec<String> = record.iter().map(Value::csv).collect();
+        table.push_str(&fields.join(","));
+        table.push('\n');
+    }
+    table
+}
+
+/// Returns the JSON array of objects with the `columns` as keys, an object
+/// for each of `records`
+fn json_array(columns: &[&str], records: &[Vec<Value>]) -> String {
+    let objects: Vec<String> = records.iter()
+        .map(|record| {
+            let members: Vec<String> = columns.iter()
+                .zip(record.iter())
+                .map(|(key, value)| {
+                    format!("{}:{}", json_string(key), value.json())
+                })
+                .collect();
+            format!("{{{}}}", members.join(","))
+        })
+        .collect();
+    format!("[\n{}\n]", objects.join(",\n"))
+}
+
+/// Returns the path the entries are exported to as CSV, when the reviews are
+/// exported to `path`; e.g. `history-entries.csv` for `history.csv`
+pub fn entries_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}{}.{}", stem, ENTRIES_SUFFIX,
+                             ext.to_string_lossy()),
+        None      => format!("{}{}", stem, ENTRIES_SUFFIX),
+    };
+    path.with_file_name(name)
+}
+
+/// Exports the review history and the entries of `db` to `path` in
+/// `format`.
+/// Returns the paths of the written files; the CSV entries go to
+/// `entries_path`.
+pub fn export(db: &Database, path: &Path, format: HistoryFormat)
+        -> Result<Vec<PathBuf>> {
+    let reviews: Vec<Vec<Value>> = db.history.load()?
+        .iter()
+        .map(review_values)
+        .collect();
+    let mut entries: Vec<&Entry> = db.usable.iter()
+        .chain(db.unusable.iter())
+        .collect();
+    entries.sort_by(|a, b| (&a.word, &a.tr_word).cmp(&(&b.word, &b.tr_word)));
+    let entries: Vec<Vec<Value>> = entries.into_iter()
+        .map(|entry| entry_values(db, entry))
+        .collect();
+
+    match format {
+        HistoryFormat::Csv => {
+            let entries_path = entries_path(path);
+            fs::write(path, csv_table(&REVIEW_COLUMNS, &reviews))?;
+            fs::write(&entries_path, csv_table(&ENTRY_COLUMNS, &entries))?;
+            Ok(vec![path.to_path_buf(), entries_path])
+        },
+        HistoryFormat::Json => {
+            let json = format!("{{\"reviews\":{},\n\"entries\":{}}}\n",
+                               json_array(&REVIEW_COLUMNS, &reviews),
+                               json_array(&ENTRY_COLUMNS, &entries));
+            fs::write(path, json)?;
+            Ok(vec![path.to_path_buf()])
+        },
+    }
+}
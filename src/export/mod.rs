@@ -0,0 +1,25 @@
+//! Exporting the database and its review history into the formats of other
+//! programs.
+
+#[cfg(feature = "anki")]
+pub mod anki;
+pub mod history;
+
+
+/// Escapes `s` as a JSON string literal
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for ch in s.chars() {
+        match ch {
+            '"'  => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            ch if (ch as u32) < 0x20 => {
+                json.push_str(&format!("\\u{:04x}", ch as u32))
+            },
+            ch => json.push(ch),
+        }
+    }
+    json.push('"');
+    json
+}
@@ -0,0 +1,353 @@
+//! The FSRS scheduler (Free Spaced Repetition Scheduler, version 4.5).
+//! Every entry has a memory state; its stability (the number of days after
+//! which the word is recalled with a 90% chance) and its difficulty (from 1
+//! to 10). The chance of a recall, the retrievability, decays with the time
+//! since the last review, and the next review is scheduled for when it drops
+//! to the desired retention.
+//! How the memory state changes with every answer is given by the weights of
+//! the model. The default ones fit an average user; `optimize` fits them to
+//! the review history of a database.
+
+use std::collections::HashMap;
+use crate::db::{Entry, DAY};
+use crate::scheduler::{Grade, Scheduler};
+use crate::stats::{self, Review};
+
+/// The number of weights of the model
+pub const WEIGHT_COUNT: usize = 17;
+
+/// The weights fitting an average user
+pub const DEFAULT_WEIGHTS: [f64; WEIGHT_COUNT] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474,
+    0.1367, 1.0461, 2.1072, 0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+
+/// The chance of a recall the reviews are scheduled for, unless configured
+/// otherwise
+pub const DEFAULT_RETENTION: f64 = 0.9;
+
+/// The lowest and the highest retention the reviews can be scheduled for
+const RETENTION_BOUNDS: (f64, f64) = (0.7, 0.99);
+
+/// The lowest and the highest value of each weight; the optimized weights
+/// are kept within them so that the model stays sane
+const WEIGHT_BOUNDS: [(f64, f64); WEIGHT_COUNT] = [
+    (0.1, 100.), (0.1, 100.), (0.1, 100.), (0.1, 100.), (1., 10.),
+    (0.1, 5.), (0.1, 5.), (0., 0.75), (0., 4.), (0., 0.8), (0.01, 3.),
+    (0.5, 5.), (0.01, 0.2), (0.01, 0.9), (0.01, 2.), (0., 1.), (1., 6.),
+];
+
+/// The shape of the forgetting curve
+const DECAY: f64 = -0.5;
+
+/// Makes the retrievability 90% after `stability` days
+const FACTOR: f64 = 19. / 81.;
+
+/// The stability (in days) never drops below this
+const MIN_STABILITY: f64 = 0.01;
+
+/// The longest interval (in days) a review is scheduled after
+const MAX_INTERVAL: u64 = 36500;
+
+/// The number of reviews the weights are fitted to at the very least; fewer
+/// of them don't tell much about the user
+pub const MIN_REVIEWS: usize = 100;
+
+/// The number of steps of the optimization
+const OPTIMIZE_STEPS: usize = 100;
+
+/// How far (relative to its value) a weight moves in a step of the
+/// optimization
+const LEARNING_RATE: f64 = 0.05;
+
+
+/// Returns whether the reviews can be scheduled for `retention`
+pub fn is_valid_retention(retention: f64) -> bool {
+    (RETENTION_BOUNDS.0..=RETENTION_BOUNDS.1).contains(&retention)
+}
+
+/// Returns the chance of a recall `elapsed` days after the last review of a
+/// word with `stability`
+pub fn retrievability(elapsed: f64, stability: f64) -> f64 {
+    (1. + FACTOR * elapsed / stability).powf(DECAY)
+}
+
+
+/// How well a word is remembered
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MemoryState {
+    /// The number of days after which the word is recalled with a 90% chance
+    pub stability: f64,
+
+    /// How hard the word is to remember, from 1 to 10
+    pub difficulty: f64,
+}
+
+
+/// The FSRS scheduler.
+/// The timeout ladders aren't used, except to guess the memory state of the
+/// entries that were scheduled by another scheduler before.
+#[derive(Clone, Copy, Debug)]
+pub struct FsrsScheduler {
+    /// The weights of the model
+    pub weights: [f64; WEIGHT_COUNT],
+
+    /// The chance of a recall the reviews are scheduled for, between 0 and 1
+    pub retention: f64,
+}
+
+impl Default for FsrsScheduler {
+    fn default() -> Self {
+        Self::new(DEFAULT_WEIGHTS, DEFAULT_RETENTION)
+    }
+}
+
+impl FsrsScheduler {
+    pub fn new(weights: [f64; WEIGHT_COUNT], retention: f64) -> Self {
+        Self { weights, retention }
+    }
+
+    /// The FSRS rating (1-4) of a grade
+    fn rating(grade: Grade) -> f64 {
+        match grade {
+            Grade::Again => 1.,
+            Grade::Hard  => 2.,
+            Grade::Good  => 3.,
+            Grade::Easy  => 4.,
+        }
+    }
+
+    /// Returns the difficulty of a new word first answered with `grade`
+    fn initial_difficulty(&self, grade: Grade) -> f64 {
+        let w = &self.weights;
+        (w[4] - (Self::rating(grade) - 3.) * w[5]).clamp(1., 10.)
+    }
+
+    /// Returns the memory state of a new word first answered with `grade`
+    pub fn initial_state(&self, grade: Grade) -> MemoryState {
+        MemoryState {
+            stability:  self.weights[Self::rating(grade) as usize - 1],
+            difficulty: self.initial_difficulty(grade),
+        }
+    }
+
+    /// Returns the memory state after a word in `state` was answered with
+    /// `grade` `elapsed` days after its last review
+    pub fn next_state(&self, state: MemoryState, grade: Grade, elapsed: f64)
+            -> MemoryState {
+        let w = &self.weights;
+        let MemoryState { stability, difficulty } = state;
+        let recall = retrievability(elapsed.max(0.), stability);
+
+        let stability = if grade.is_correct() {
+            let hard = if grade == Grade::Hard { w[15] } else { 1. };
+            let easy = if grade == Grade::Easy { w[16] } else { 1. };
+            stability * (1. + w[8].exp() * (11. - difficulty)
+                * stability.powf(-w[9])
+                * ((w[10] * (1. - recall)).exp() - 1.)
+                * hard * easy)
+        } else {
+            let forgotten = w[11] * difficulty.powf(-w[12])
+                * ((stability + 1.).powf(w[13]) - 1.)
+                * (w[14] * (1. - recall)).exp();
+            forgotten.min(stability)
+        };
+
+        // The difficulty moves with the grade, but reverts towards the one
+        // of a new word answered with `Good`
+        let moved      = difficulty - w[6] * (Self::rating(grade) - 3.);
+        let difficulty = w[7] * self.initial_difficulty(Grade::Good)
+            + (1. - w[7]) * moved;
+
+        MemoryState {
+            stability:  stability.max(MIN_STABILITY),
+            difficulty: difficulty.clamp(1., 10.),
+        }
+    }
+
+    /// Returns the interval (in days) after which a word with `stability` is
+    /// recalled with the desired retention
+    pub fn interval(&self, stability: f64) -> u64 {
+        let days = stability / FACTOR
+            * (self.retention.powf(1. / DECAY) - 1.);
+        (days.round() as u64).clamp(1, MAX_INTERVAL)
+    }
+
+    /// Returns the memory state of `entry`, or `None` if it's new.
+    /// An entry scheduled by another scheduler before has none yet; its
+    /// stability is guessed from its current interval.
+    fn memory_state(&self, entry: &Entry, ladder: &[u64])
+            -> Option<MemoryState> {
+        if entry.is_new() {
+            return None;
+        }
+        if entry.stability > 0. {
+            return Some(MemoryState {
+                stability:  entry.stability,
+                difficulty: entry.difficulty,
+            });
+        }
+        Some(MemoryState {
+            stability:  stats::interval(entry, ladder).max(1) as f64,
+            difficulty: self.initial_difficulty(Grade::Good),
+        })
+    }
+}
+
+impl Scheduler for FsrsScheduler {
+    fn schedule(&self, entry: &mut Entry, grade: Grade, ladder: &[u64],
+                now: u64) {
+        let state = match self.memory_state(entry, ladder) {
+            Some(state) => {
+                // The entry was last reviewed an interval before its timeout
+                let interval = stats::interval(entry, ladder);
+                let elapsed  = (now + interval * DAY)
+                    .saturating_sub(entry.timeout);
+                self.next_state(state, grade, elapsed as f64 / DAY as f64)
+            },
+            None => self.initial_state(grade),
+        };
+
+        entry.stability  = state.stability;
+        entry.difficulty = state.difficulty;
+        entry.interval   = self.interval(state.stability);
+        entry.reps       = match grade.is_correct() {
+            true  => entry.reps + 1,
+            false => 0,
+        };
+        entry.timeout = now + entry.interval * DAY;
+    }
+}
+
+
+/// The weights fitted to a review history by `optimize`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fit {
+    /// The fitted weights
+    pub weights: [f64; WEIGHT_COUNT],
+
+    /// The number of reviews the weights were fitted to
+    pub reviews: usize,
+
+    /// How badly the weights predict the reviews; the mean log loss
+    pub loss: f64,
+}
+
+
+/// The reviews of a single entry; the days (whole ones) elapsed since the
+/// review before and whether the word was recalled
+type Sequence = Vec<(u64, bool)>;
+
+/// Splits `reviews` into the reviews of the single entries, in the order
+/// they were done.
+/// Only the first review of a day counts; the ones after it say more about
+/// the short-term memory than about the long-term one. The entries that
+/// were only reviewed once tell nothing about forgetting and are left out.
+fn sequences(reviews: &[Review]) -> Vec<Sequence> {
+    let mut entries: HashMap<(&str, &str), Vec<&Review>> = HashMap::new();
+    for review in reviews {
+        entries.entry((&review.word, &review.tr_word))
+            .or_default()
+            .push(review);
+    }
+
+    entries.into_values()
+        .filter_map(|mut reviews| {
+            reviews.sort_by_key(|review| review.time);
+            let mut sequence = Sequence::new();
+            let mut last_day = None;
+            for review in reviews {
+                let day = review.time / DAY;
+                if last_day == Some(day) {
+                    continue;
+                }
+                let elapsed = last_day.map_or(0, |last_day| day - last_day);
+                sequence.push((elapsed, review.correct));
+                last_day = Some(day);
+            }
+            Some(sequence).filter(|sequence| sequence.len() > 1)
+        })
+        .collect()
+}
+
+/// Returns the mean log loss of the predictions of `scheduler` for the
+/// reviews in `sequences` and the number of the predicted reviews
+fn loss(scheduler: &FsrsScheduler, sequences: &[Sequence]) -> (f64, usize) {
+    let (mut loss, mut count) = (0., 0);
+    for sequence in sequences {
+        let mut state = scheduler.initial_state(Grade::from(sequence[0].1));
+        for &(elapsed, correct) in &sequence[1..] {
+            let recall = retrievability(elapsed as f64, state.stability)
+                .clamp(1e-4, 1. - 1e-4);
+            loss  -= if correct { recall.ln() } else { (1. - recall).ln() };
+            count += 1;
+            state  = scheduler.next_state(state, Grade::from(correct),
+                                          elapsed as f64);
+        }
+    }
+
+    match count {
+        0 => (0., 0),
+        _ => (loss / count as f64, count),
+    }
+}
+
+/// Fits the weights of the model to `reviews` (the review history of a
+/// database), starting with `DEFAULT_WEIGHTS`.
+/// The history only knows whether the words were recalled, so the weights
+/// of the `Hard` and `Easy` answers stay as they are.
+/// Returns `None` if there are fewer than `MIN_REVIEWS` reviews to fit the
+/// weights to.
+pub fn optimize(reviews: &[Review]) -> Option<Fit> {
+    let sequences = sequences(reviews);
+    let mut scheduler = FsrsScheduler::default();
+    let (mut best_loss, count) = loss(&scheduler, &sequences);
+    if count < MIN_REVIEWS {
+        return None;
+    }
+    let mut best = scheduler.weights;
+
+    // Adam, with the gradient estimated by central differences
+    let (beta1, beta2, epsilon) = (0.9, 0.999, 1e-8);
+    let mut mean     = [0.; WEIGHT_COUNT];
+    let mut variance = [0.; WEIGHT_COUNT];
+    for step in 1..=OPTIMIZE_STEPS {
+        let weights = scheduler.weights;
+        let mut gradient = [0.; WEIGHT_COUNT];
+        for (idx, gradient) in gradient.iter_mut().enumerate() {
+            let delta = 1e-4 * weights[idx].abs().max(0.1);
+            let mut probe = scheduler;
+            probe.weights[idx] = weights[idx] + delta;
+            let (above, _) = loss(&probe, &sequences);
+            probe.weights[idx] = weights[idx] - delta;
+            let (below, _) = loss(&probe, &sequences);
+            *gradient = (above - below) / (2. * delta);
+        }
+
+        for (idx, &gradient) in gradient.iter().enumerate() {
+            mean[idx]     = beta1 * mean[idx] + (1. - beta1) * gradient;
+            variance[idx] = beta2 * variance[idx]
+                + (1. - beta2) * gradient * gradient;
+            let mean     = mean[idx] / (1. - beta1.powi(step as i32));
+            let variance = variance[idx] / (1. - beta2.powi(step as i32));
+
+            let rate = LEARNING_RATE * weights[idx].abs().max(0.1);
+            let (low, high) = WEIGHT_BOUNDS[idx];
+            scheduler.weights[idx] = (weights[idx]
+                - rate * mean / (variance.sqrt() + epsilon))
+                .clamp(low, high);
+        }
+
+        let (loss, _) = loss(&scheduler, &sequences);
+        if loss < best_loss {
+            best_loss = loss;
+            best      = scheduler.weights;
+        }
+    }
+
+    Some(Fit {
+        weights: best,
+        reviews: count,
+        loss:    best_loss,
+    })
+}
@@ -0,0 +1,173 @@
+//! Grading of typed answers.
+//! The typed answer is compared to the expected word, tolerating the
+//! differences the user doesn't care about (see `normalize`).
+
+use std::collections::BTreeMap;
+use crate::diff::{diff, DiffOp};
+use crate::normalize::{normalize, Normalization};
+
+/// How strictly typed answers are compared to the expected words
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GradingOptions {
+    /// The differences between the answer and the word that don't matter
+    pub normalization: Normalization,
+
+    /// The number of typos (single character edits) that are tolerated
+    pub max_typos: usize,
+}
+
+impl GradingOptions {
+    /// Returns the options for the answers of the words of `deck`; with the
+    /// normalization of the deck in `normalizations`, if it has its own
+    pub fn for_deck(&self, normalizations: &BTreeMap<String, Normalization>,
+                    deck: &str) -> Self {
+        Self {
+            normalization: normalizations.get(deck)
+                .copied()
+                .unwrap_or(self.normalization),
+            ..*self
+        }
+    }
+}
+
+
+/// The outcome of grading a typed answer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grading {
+    /// Whether the answer is counted as correct
+    pub correct: bool,
+
+    /// The number of typos in the answer, not counting the differences that
+    /// don't matter
+    pub typos: usize,
+
+    /// The differences between the typed answer and the expected word,
+    /// as typed (before the normalization), without the ones that don't
+    /// matter.
+    /// The positions are character indices into the typed answer.
+    pub diff: Vec<DiffOp>,
+}
+
+impl Grading {
+    /// Returns whether the answer is correct only thanks to the tolerated
+    /// typos; the user decides whether such an answer counts
+    pub fn is_near_miss(&self) -> bool {
+        self.correct && self.typos > 0
+    }
+}
+
+
+/// Grades the typed `answer` against the `expected` word
+pub fn grade(answer: &str, expected: &str, options: &GradingOptions)
+        -> Grading {
+    let (answer, expected) = (answer.trim(), expected.trim());
+    let typos              = typos(answer, expected, options);
+
+    // The positions of the normalized words don't match those of the typed
+    // ones, so the forgiven differences are left out of the diff instead
+    let normalization = &options.normalization;
+    let forgiven      = |op: &DiffOp| match *op {
+        DiffOp::Substitute { from, to, .. } => {
+            normalize(&from.to_string(), normalization)
+                == normalize(&to.to_string(), normalization)
+        },
+        DiffOp::Insert { ch, .. } | DiffOp::Delete { ch, .. } => {
+            normalize(&ch.to_string(), normalization).is_empty()
+        },
+    };
+    let diff = match typos {
+        0 => Vec::new(),
+        _ => diff(answer, expected).into_iter()
+            .filter(|op| !forgiven(op))
+            .collect(),
+    };
+
+    Grading {
+        correct: typos <= options.max_typos,
+        typos,
+        diff,
+    }
+}
+
+/// Grades the typed `answer` against the closest of the `expected` words,
+/// e.g. against all the translations of a word
+pub fn grade_any(answer: &str, expected: &[String], options: &GradingOptions)
+        -> Grading {
+    let closest = expected.iter()
+        .min_by_key(|expected| typos(answer, expected, options))
+        .map_or("", |expected| expected.as_str());
+    grade(answer, closest, options)
+}
+
+/// Returns `word` without the differences in case, diacritics and whitespace,
+/// so that e.g. `Čaj ` and `caj` are recognized as the same word
+pub fn fold(word: &str) -> String {
+    let normalization = Normalization {
+        case:       true,
+        diacritics: true,
+        ..Normalization::default()
+    };
+    normalize(word, &normalization)
+}
+
+/// Returns the number of typos in `answer`, not counting the differences
+/// ignored by `options`
+fn typos(answer: &str, expected: &str, options: &GradingOptions) -> usize {
+    diff(&normalize(answer, &options.normalization),
+         &normalize(expected, &options.normalization)).len()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(max_typos: usize) -> GradingOptions {
+        GradingOptions {
+            normalization: Normalization {
+                case:       true,
+                diacritics: true,
+                ..Normalization::default()
+            },
+            max_typos,
+        }
+    }
+
+    #[test]
+    fn a_deck_may_have_a_normalization_of_its_own() {
+        let mut normalizations = BTreeMap::new();
+        normalizations.insert("Czech".to_string(), Normalization::default());
+
+        let czech = options(1).for_deck(&normalizations, "Czech");
+        assert_eq!(czech.normalization, Normalization::default());
+        assert_eq!(czech.max_typos, 1);
+        assert_eq!(options(1).for_deck(&normalizations, "German"),
+                   options(1));
+    }
+
+    #[test]
+    fn an_exact_answer_is_no_near_miss() {
+        let grading = grade("Čaj", "caj", &options(1));
+        assert!(grading.correct);
+        assert!(!grading.is_near_miss());
+        assert!(grading.diff.is_empty());
+    }
+
+    #[test]
+    fn a_tolerated_typo_is_a_near_miss() {
+        let grading = grade("dgo", "dog", &options(2));
+        assert!(grading.correct);
+        assert!(grading.is_near_miss());
+
+        let grading = grade("dgo", "dog", &options(0));
+        assert!(!grading.correct);
+        assert!(!grading.is_near_miss());
+    }
+
+    #[test]
+    fn the_forgiven_differences_are_left_out_of_the_diff() {
+        let grading = grade("Čaju", "caj", &options(1));
+        assert_eq!(grading.typos, 1);
+        assert_eq!(grading.diff, vec![DiffOp::Delete { pos: 3, ch: 'u' }]);
+    }
+}
@@ -0,0 +1,85 @@
+//! Protection against accidental grading.
+//! Mashing a grade button shouldn't be able to push a word up the timeout
+//! ladder several times within a second.
+
+/// The minimal time (in milliseconds) between two grades of the same word
+pub const MIN_REGRADE_DELAY: u64 = 2000;
+
+
+/// Remembers the last grade and rejects implausibly fast repeated grades
+/// of the same word. Fast grades of different words are fine.
+#[derive(Clone, Debug)]
+pub struct GradeGuard {
+    /// The minimal time (in milliseconds) between two grades of one word
+    pub min_delay: u64,
+
+    /// The last graded word and when (in milliseconds) it was graded
+    last: Option<(String, u64)>,
+}
+
+impl Default for GradeGuard {
+    fn default() -> Self {
+        Self::new(MIN_REGRADE_DELAY)
+    }
+}
+
+impl GradeGuard {
+    /// Creates a new guard with a given minimal delay (in milliseconds)
+    pub fn new(min_delay: u64) -> Self {
+        Self {
+            min_delay,
+            last: None,
+        }
+    }
+
+    /// Returns whether `word` may be graded at `now` (in milliseconds).
+    /// Allowed grades are remembered, rejected ones are not.
+    pub fn allow(&mut self, word: &str, now: u64) -> bool {
+        if let Some((last_word, last_time)) = &self.last {
+            if last_word == word
+                    && now.saturating_sub(*last_time) < self.min_delay {
+                return false;
+            }
+        }
+
+        self.last = Some((word.to_string(), now));
+        true
+    }
+
+    /// Forgets the last grade, e.g. because it was undone
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_the_same_word_within_the_delay() {
+        let mut guard = GradeGuard::default();
+        assert!(guard.allow("Hund", 10_000));
+        assert!(!guard.allow("Hund", 10_000 + MIN_REGRADE_DELAY - 1));
+
+        // The rejected grade doesn't restart the delay
+        assert!(guard.allow("Hund", 10_000 + MIN_REGRADE_DELAY));
+    }
+
+    #[test]
+    fn allows_a_different_word_immediately() {
+        let mut guard = GradeGuard::default();
+        assert!(guard.allow("Hund", 10_000));
+        assert!(guard.allow("Katze", 10_000));
+        assert!(guard.allow("Hund", 10_001));
+    }
+
+    #[test]
+    fn reset_forgets_the_last_grade() {
+        let mut guard = GradeGuard::default();
+        assert!(guard.allow("Hund", 10_000));
+        guard.reset();
+        assert!(guard.allow("Hund", 10_001));
+    }
+}
@@ -0,0 +1,54 @@
+//! Hints that reveal the hidden translation bit by bit.
+//! The first hint reveals its first letter, the second one the lengths of its
+//! words (every other letter is shown as an underscore) and the last one the
+//! whole translation:
+//!
+//! ```text
+//! 1: d…
+//! 2: d__ ____, _____
+//! 3: der Hund, Köter
+//! ```
+
+use crate::scheduler::Grade;
+
+/// The number of hints that reveal the whole translation
+pub const MAX_HINTS: usize = 3;
+
+/// What a letter that isn't revealed yet is shown as
+const HIDDEN_LETTER: char = '_';
+
+/// What the rest of the translation is shown as after the first letter
+const ELLIPSIS: char = '…';
+
+
+/// Returns `text` as revealed by `hints` hints.
+/// Only letters (and digits) are hidden; the spaces and punctuation show
+/// where the words start and end.
+pub fn mask(text: &str, hints: usize) -> String {
+    let first = text.char_indices().find(|(_, c)| c.is_alphanumeric());
+    let (start, first) = match first {
+        Some(first) => first,
+        None        => return text.to_string(),
+    };
+
+    match hints {
+        0 => String::new(),
+        1 => format!("{}{}{}", &text[..start], first, ELLIPSIS),
+        2 => text.char_indices()
+            .map(|(idx, c)| match c.is_alphanumeric() && idx != start {
+                true  => HIDDEN_LETTER,
+                false => c,
+            })
+            .collect(),
+        _ => text.to_string(),
+    }
+}
+
+/// Returns `grade` as counted when a hint was used; a recalled word is
+/// counted as recalled with difficulty at best
+pub fn penalize(grade: Grade) -> Grade {
+    match grade {
+        Grade::Good | Grade::Easy => Grade::Hard,
+        _                         => grade,
+    }
+}
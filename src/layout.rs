@@ -0,0 +1,229 @@
+//! Helpers for laying out the words in the window.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Bounds of the font size of a displayed word.
+/// Longer words get smaller fonts so that they fit into the window.
+#[derive(Clone, Copy, Debug)]
+pub struct FontSizes {
+    /// The font size of short words
+    pub max: f32,
+
+    /// The font size is never smaller than this
+    pub min: f32,
+
+    /// Text wider than this many columns (see `text_width`) wraps and
+    /// starts shrinking
+    pub shrink_after: f32,
+}
+
+impl FontSizes {
+    /// The default font sizes of the original word
+    pub const WORD: FontSizes = FontSizes {
+        max:          80.,
+        min:          10.,
+        shrink_after: 40.,
+    };
+
+    /// The default font sizes of the translated word
+    pub const TR_WORD: FontSizes = FontSizes {
+        max:          50.,
+        min:          10.,
+        shrink_after: 50.,
+    };
+}
+
+/// Returns whether `ch` is a wide character that takes two columns; the
+/// CJK characters, Hangul, the fullwidth forms and most emoji
+fn is_wide(ch: char) -> bool {
+    matches!(ch as u32,
+             0x1100..=0x115f | 0x2e80..=0x303e | 0x3041..=0xa4cf
+             | 0xac00..=0xd7a3 | 0xf900..=0xfaff | 0xfe30..=0xfe4f
+             | 0xff00..=0xff60 | 0xffe0..=0xffe6 | 0x1f300..=0x1f64f
+             | 0x1f900..=0x1f9ff | 0x20000..=0x3fffd)
+}
+
+/// Returns the width of `text` in columns; the number of characters the user
+/// sees (so a letter with its accents is one, however it's encoded), with
+/// the wide ones counting twice
+pub fn text_width(text: &str) -> f32 {
+    text.graphemes(true)
+        .map(|grapheme| match grapheme.chars().any(is_wide) {
+            true  => 2.,
+            false => 1.,
+        })
+        .sum()
+}
+
+/// Returns the font size that `word` should be displayed with.
+/// Text wider than `sizes.shrink_after` wraps into more lines; the area it
+/// takes grows with the square of the font size, so the font shrinks with
+/// the square root of the width. A single word can't wrap, so the font of
+/// a word wider than a line shrinks with its width.
+pub fn word_font_size(word: &str, sizes: &FontSizes) -> f32 {
+    let longest = word.split_whitespace()
+        .map(text_width)
+        .fold(0., f32::max);
+    let wrapped = (text_width(word) / sizes.shrink_after).sqrt();
+    let shrink  = (longest / sizes.shrink_after).max(wrapped).max(1.);
+    (sizes.max / shrink).max(sizes.min).min(sizes.max)
+}
+
+/// Horizontal alignment of the displayed words
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    /// Aligned to the start of the text direction
+    Start,
+
+    /// Centered
+    Center,
+
+    /// Aligned to the end of the text direction
+    End,
+}
+
+/// The resolved horizontal alignment of a word in the window
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Center,
+    Right,
+}
+
+/// All the knobs that affect how the words are rendered
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayConfig {
+    /// Font size bounds of the original word
+    pub word_font: FontSizes,
+
+    /// Font size bounds of the translated word
+    pub tr_word_font: FontSizes,
+
+    /// Alignment of the words
+    pub alignment: Alignment,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            word_font:    FontSizes::WORD,
+            tr_word_font: FontSizes::TR_WORD,
+            alignment:    Alignment::Center,
+        }
+    }
+}
+
+impl DisplayConfig {
+    /// Returns the display with the font sizes of the words between `min`
+    /// and `max` (the size of the short original words); the translations
+    /// keep their size relative to the original words
+    pub fn with_font_sizes(min: f32, max: f32) -> Self {
+        let ratio = FontSizes::TR_WORD.max / FontSizes::WORD.max;
+        Self {
+            word_font:    FontSizes { max, min, ..FontSizes::WORD },
+            tr_word_font: FontSizes {
+                max: (max * ratio).max(min),
+                min,
+                ..FontSizes::TR_WORD
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Returns the font size of the original `word`
+    pub fn word_size(&self, word: &str) -> f32 {
+        word_font_size(word, &self.word_font)
+    }
+
+    /// Returns the font size of the translated `tr_word`
+    pub fn tr_word_size(&self, tr_word: &str) -> f32 {
+        word_font_size(tr_word, &self.tr_word_font)
+    }
+
+    /// Returns which side of the window `text` should be aligned to,
+    /// taking its direction into account
+    pub fn side(&self, text: &str) -> Side {
+        match (self.alignment, is_rtl(text)) {
+            (Alignment::Center, _)    => Side::Center,
+            (Alignment::Start, false) => Side::Left,
+            (Alignment::Start, true)  => Side::Right,
+            (Alignment::End, false)   => Side::Right,
+            (Alignment::End, true)    => Side::Left,
+        }
+    }
+}
+
+/// Returns whether `text` is written right-to-left.
+/// The direction is decided by the first character with a strong direction
+/// (Hebrew, Arabic and friends are RTL, letters of other scripts are LTR).
+pub fn is_rtl(text: &str) -> bool {
+    for ch in text.chars() {
+        match ch as u32 {
+            0x0590..=0x08ff | 0xfb1d..=0xfdff | 0xfe70..=0xfeff => return true,
+            _ if ch.is_alphabetic() => return false,
+            _ => {},
+        }
+    }
+    false
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_words_clamp_to_the_max_and_long_ones_to_the_min() {
+        let display = DisplayConfig::with_font_sizes(20., 60.);
+        let long    = "a".repeat(1000);
+        assert_eq!(display.word_size("a"), 60.);
+        assert_eq!(display.word_size(&long), 20.);
+        assert_eq!(display.tr_word_size(&long), 20.);
+
+        let sizes = FontSizes { max: 30., min: 12., shrink_after: 10. };
+        assert_eq!(word_font_size("x", &sizes), 30.);
+        assert_eq!(word_font_size(&long, &sizes), 12.);
+    }
+
+    #[test]
+    fn the_font_shrinks_with_the_width_of_the_word() {
+        let sizes  = FontSizes::WORD;
+        let medium = word_font_size(&"a".repeat(80), &sizes);
+        assert!(medium < sizes.max && medium > sizes.min);
+        assert_eq!(word_font_size(&"a".repeat(40), &sizes), sizes.max);
+        assert_eq!(word_font_size("日本", &sizes),
+                   word_font_size("abcd", &sizes));
+    }
+
+    #[test]
+    fn the_alignment_follows_the_direction_of_the_text() {
+        let display = |alignment| DisplayConfig {
+            alignment,
+            ..DisplayConfig::default()
+        };
+        let (ltr, rtl) = ("dog", "שלום");
+        assert!(is_rtl(rtl) && !is_rtl(ltr));
+        assert!(is_rtl("123 مرحبا") && !is_rtl("123"));
+
+        assert_eq!(display(Alignment::Start).side(ltr), Side::Left);
+        assert_eq!(display(Alignment::Start).side(rtl), Side::Right);
+        assert_eq!(display(Alignment::End).side(ltr), Side::Right);
+        assert_eq!(display(Alignment::End).side(rtl), Side::Left);
+        assert_eq!(display(Alignment::Center).side(rtl), Side::Center);
+        assert_eq!(DisplayConfig::default().side(ltr), Side::Center);
+    }
+
+    #[test]
+    fn the_default_sizes_match_the_default_fonts() {
+        let display = DisplayConfig::default();
+        let long    = "a".repeat(1000);
+        assert_eq!(display.word_size("Hund"), FontSizes::WORD.max);
+        assert_eq!(display.tr_word_size("dog"), FontSizes::TR_WORD.max);
+        assert_eq!(display.word_size(&long), FontSizes::WORD.min);
+
+        // The translations stay smaller than the words they translate
+        let display = DisplayConfig::with_font_sizes(10., 40.);
+        assert_eq!(display.word_size("Hund"), 40.);
+        assert_eq!(display.tr_word_size("dog"), 25.);
+    }
+}
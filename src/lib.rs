@@ -1,3 +1,55 @@
+//! The study logic of wordpal, without any user interface.
+//! The window (`src/main.rs`) and the terminal review (`cli`) are thin
+//! frontends over this library; other frontends can be built the same way.
+//! Build it with `default-features = false` to leave the GUI stack out.
+//!
+//! The usual flow of a frontend:
+//!
+//! 1. `db::Database::open` opens (and locks) a database, in any of the
+//!    `db::Format`s.
+//! 2. `session::prepare` sets it up with the `config::Config` of the user and
+//!    returns a `session::Session`.
+//! 3. `session::Session::next_entry` serves the words one by one, until it
+//!    returns `None`. `grading::grade` compares typed answers with the
+//!    translations.
+//! 4. `session::Session::answer` reschedules the served word with the
+//!    `scheduler::Grade` of the answer (see `db::Database::update_timeout`)
+//!    and records it in the session and the history, which `stats`
+//!    summarizes.
+//! 5. `db::Database::write_db` writes the changes and
+//!    `db::Database::flush_and_close` unlocks the database again.
+//!
+//! `cli::review` is a complete (if small) frontend to look at.
+
 pub mod db;
+pub mod error;
 pub mod rng;
+pub mod queue;
 pub mod locale;
+pub mod config;
+pub mod theme;
+pub mod diff;
+pub mod meta;
+pub mod stats;
+pub mod session;
+pub mod achievements;
+pub mod layout;
+pub mod guard;
+pub mod autosave;
+pub mod grading;
+pub mod normalize;
+pub mod hint;
+pub mod choice;
+pub mod scheduler;
+pub mod fsrs;
+pub mod card;
+pub mod encoding;
+pub mod export;
+pub mod media;
+pub mod notify;
+pub mod paths;
+pub mod cli;
+#[cfg(feature = "sync")]
+pub mod sync;
+#[cfg(test)]
+mod testing;
@@ -1,13 +1,368 @@
+//! The texts shown to the user, in every supported language.
+//! The texts of each language are kept in a TOML file in `locales/` that is
+//! embedded into the binary. The constants below are the keys of the texts;
+//! `tr` returns the text of a key in the current language, which can be
+//! switched at runtime.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use serde::{Deserialize, Serialize};
+
+/// The texts of the languages, in the order of `Language::ALL`
+static SOURCES: [&str; 2] = [
+    include_str!("../locales/cs.toml"),
+    include_str!("../locales/en.toml"),
+];
+
+/// The parsed texts of the languages, in the order of `Language::ALL`
+static TEXTS: OnceLock<Vec<HashMap<String, String>>> = OnceLock::new();
+
+/// The index of the current language in `Language::ALL`
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+
+/// A language the user interface can be shown in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[derive(Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "cs")]
+    Czech,
+
+    #[serde(rename = "en")]
+    English,
+}
+
+impl Language {
+    /// All the supported languages; the first one is the default
+    pub const ALL: [Language; 2] = [Language::Czech, Language::English];
+
+    /// Returns the name of the language, in the language itself
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Czech   => "Čeština",
+            Language::English => "English",
+        }
+    }
+
+    /// Returns the code of the language, as it's written in the config
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::Czech   => "cs",
+            Language::English => "en",
+        }
+    }
+
+    /// Returns the index of the language in `ALL`
+    fn index(&self) -> usize {
+        Self::ALL.iter().position(|language| language == self).unwrap_or(0)
+    }
+}
+
+/// Returns the language the texts are currently shown in
+pub fn language() -> Language {
+    Language::ALL[CURRENT.load(Ordering::Relaxed)]
+}
+
+/// Switches the texts to `language`
+pub fn set_language(language: Language) {
+    CURRENT.store(language.index(), Ordering::Relaxed);
+}
+
+/// Returns the text of `key` in the current language.
+/// Texts missing in the current language are taken from the default one; if
+/// that fails too, the key itself is returned, so that it's easy to spot.
+pub fn tr(key: &'static str) -> &'static str {
+    let texts = TEXTS.get_or_init(|| {
+        SOURCES.iter()
+            .map(|source| toml::from_str(source).unwrap_or_default())
+            .collect()
+    });
+
+    texts[language().index()].get(key)
+        .or_else(|| texts[0].get(key))
+        .map_or(key, |text| text.as_str())
+}
+
 // The root window
-pub static ROOT_WINDOW_TITLE: &str = "Wordpal";
+pub const ROOT_WINDOW_TITLE: &str = "root_window_title";
+pub const READ_ONLY_TITLE: &str = "read_only_title";
+
+// Start screen
+pub const START_RECENT_TITLE: &str = "start_recent_title";
+pub const START_OPEN_LABEL: &str = "start_open_label";
+pub const START_OPEN_READ_ONLY_LABEL: &str = "start_open_read_only_label";
+pub const START_CREATE_LABEL: &str = "start_create_label";
+pub const START_QUIT_LABEL: &str = "start_quit_label";
+
+// Deck picker
+pub const DECKS_TITLE: &str = "decks_title";
+pub const DECKS_ALL_LABEL: &str = "decks_all_label";
+pub const DECKS_STARRED_LABEL: &str = "decks_starred_label";
+pub const DECKS_TAGS: &str = "decks_tags";
+pub const DECK_OPTIONS_LABEL: &str = "deck_options_label";
+pub const DECK_OPTIONS_CONFIG: &str = "deck_options_config";
+pub const DECK_OPTIONS_RETENTION: &str = "deck_options_retention";
+pub const DECK_OPTIONS_CLOSE_LABEL: &str = "deck_options_close_label";
+
+// Review screen
+pub const FORWARD_LABEL: &str = "forward_label";
+pub const REVERSE_LABEL: &str = "reverse_label";
+pub const TYPING_LABEL: &str = "typing_label";
+pub const BUTTONS_LABEL: &str = "buttons_label";
+pub const CHOICE_LABEL: &str = "choice_label";
+pub const LISTEN_LABEL: &str = "listen_label";
+pub const READ_LABEL: &str = "read_label";
+pub const CRAM_LABEL: &str = "cram_label";
+pub const STOP_CRAM_LABEL: &str = "stop_cram_label";
+pub const ANSWER_PLACEHOLDER: &str = "answer_placeholder";
+pub const ADD_LABEL: &str = "add_label";
+pub const EDIT_LABEL: &str = "edit_label";
+pub const BURY_LABEL: &str = "bury_label";
+pub const SUSPEND_LABEL: &str = "suspend_label";
+pub const STAR_LABEL: &str = "star_label";
+pub const UNSTAR_LABEL: &str = "unstar_label";
+pub const IMPORT_LABEL: &str = "import_label";
+pub const MERGE_LABEL: &str = "merge_label";
+pub const SAVE_AS_LABEL: &str = "save_as_label";
+pub const SHARE_LABEL: &str = "share_label";
+pub const EXPORT_LABEL: &str = "export_label";
+pub const STATS_LABEL: &str = "stats_label";
+pub const FORECAST_LABEL: &str = "forecast_label";
+pub const LEECHES_LABEL: &str = "leeches_label";
+pub const DUPLICATES_LABEL: &str = "duplicates_label";
+pub const SWAP_LABEL: &str = "swap_label";
+pub const BACKUPS_LABEL: &str = "backups_label";
+pub const SYNC_LABEL: &str = "sync_label";
+pub const BROWSE_LABEL: &str = "browse_label";
+pub const UNDO_LABEL: &str = "undo_label";
+pub const SETTINGS_LABEL: &str = "settings_label";
+pub const PLAY_LABEL: &str = "play_label";
+pub const REPLAY_LABEL: &str = "replay_label";
+pub const HINT_LABEL: &str = "hint_label";
+pub const NEAR_MISS_MESSAGE: &str = "near_miss_message";
+pub const COUNT_LABEL: &str = "count_label";
+pub const DONT_COUNT_LABEL: &str = "dont_count_label";
+pub const PROGRESS_DUE: &str = "progress_due";
+pub const PROGRESS_STREAK: &str = "progress_streak";
+pub const PROGRESS_GOAL: &str = "progress_goal";
+pub const REMINDER_MESSAGE: &str = "reminder_message";
+pub const TRAY_SHOW_LABEL: &str = "tray_show_label";
+pub const AGAIN_LABEL: &str = "again_label";
+pub const HARD_LABEL: &str = "hard_label";
+pub const GOOD_LABEL: &str = "good_label";
+pub const EASY_LABEL: &str = "easy_label";
+pub const RECALL_AGAIN_LABEL: &str = "recall_again_label";
+pub const RECALL_HARD_LABEL: &str = "recall_hard_label";
+pub const RECALL_GOOD_LABEL: &str = "recall_good_label";
+pub const RECALL_EASY_LABEL: &str = "recall_easy_label";
+pub const CONFIRM_LONGEST_WINDOW_TITLE: &str = "confirm_longest_window_title";
+pub const CONFIRM_LONGEST_MESSAGE: &str = "confirm_longest_message";
+
+// Session summary
+pub const SUMMARY_TITLE: &str = "summary_title";
+pub const SUMMARY_REVIEWED: &str = "summary_reviewed";
+pub const SUMMARY_ACCURACY: &str = "summary_accuracy";
+pub const SUMMARY_DURATION: &str = "summary_duration";
+pub const SUMMARY_CONTINUE_LABEL: &str = "summary_continue_label";
+pub const SUMMARY_AHEAD: &str = "summary_ahead";
+pub const SUMMARY_AHEAD_LABEL: &str = "summary_ahead_label";
+
+// Terminal
+pub const CLI_REVEAL_PROMPT: &str = "cli_reveal_prompt";
+pub const CLI_CONTINUE_PROMPT: &str = "cli_continue_prompt";
+pub const CLI_GRADE_PROMPT: &str = "cli_grade_prompt";
+pub const CLI_USAGE: &str = "cli_usage";
+pub const CLI_LOCKED_MESSAGE: &str = "cli_locked_message";
+pub const CLI_DUE: &str = "cli_due";
+pub const CLI_ADD_FAILED_MESSAGE: &str = "cli_add_failed_message";
+
+// Statistics
+pub const STATS_TITLE: &str = "stats_title";
+pub const STATS_DAILY: &str = "stats_daily";
+pub const STATS_ACCURACY: &str = "stats_accuracy";
+pub const STATS_NEW: &str = "stats_new";
+pub const STATS_YOUNG: &str = "stats_young";
+pub const STATS_MATURE: &str = "stats_mature";
+pub const STATS_RESPONSE: &str = "stats_response";
+pub const STATS_SLOW: &str = "stats_slow";
+pub const STATS_RETENTION: &str = "stats_retention";
+pub const STATS_EXPORT_LABEL: &str = "stats_export_label";
+pub const STATS_CLOSE_LABEL: &str = "stats_close_label";
+
+// Settings
+pub const SETTINGS_TITLE: &str = "settings_title";
+pub const SETTINGS_LANGUAGE: &str = "settings_language";
+pub const SETTINGS_THEME: &str = "settings_theme";
+pub const SETTINGS_SCALE: &str = "settings_scale";
+pub const SETTINGS_FONT_SIZES: &str = "settings_font_sizes";
+pub const SETTINGS_SCHEDULER: &str = "settings_scheduler";
+pub const SETTINGS_OPTIMIZE_LABEL: &str = "settings_optimize_label";
+pub const SETTINGS_LADDERS: &str = "settings_ladders";
+pub const SETTINGS_TWO_WAY: &str = "settings_two_way";
+pub const SETTINGS_GOAL: &str = "settings_goal";
+pub const SETTINGS_AHEAD: &str = "settings_ahead";
+pub const SETTINGS_HINT_PENALTY: &str = "settings_hint_penalty";
+pub const SETTINGS_NORMALIZATION: &str = "settings_normalization";
+pub const SETTINGS_REMINDERS: &str = "settings_reminders";
+pub const SETTINGS_TRAY: &str = "settings_tray";
+pub const SETTINGS_LABELS: &str = "settings_labels";
+pub const SETTINGS_ICONS: &str = "settings_icons";
+pub const SETTINGS_CONFIRM_LONGEST: &str = "settings_confirm_longest";
+pub const THEME_LIGHT: &str = "theme_light";
+pub const THEME_DARK: &str = "theme_dark";
+pub const THEME_COLORBLIND: &str = "theme_colorblind";
+pub const SCHEDULER_FIXED: &str = "scheduler_fixed";
+pub const SCHEDULER_SM2: &str = "scheduler_sm2";
+pub const SCHEDULER_FSRS: &str = "scheduler_fsrs";
+pub const LABELS_GRADES: &str = "labels_grades";
+pub const LABELS_RECALL: &str = "labels_recall";
+pub const NORMALIZE_CASE: &str = "normalize_case";
+pub const NORMALIZE_DIACRITICS: &str = "normalize_diacritics";
+pub const NORMALIZE_PUNCTUATION: &str = "normalize_punctuation";
+pub const NORMALIZE_TRANSLITERATION: &str = "normalize_transliteration";
+pub const SETTINGS_CLOSE_LABEL: &str = "settings_close_label";
+
+// Forecast
+pub const FORECAST_TITLE: &str = "forecast_title";
+pub const FORECAST_TODAY: &str = "forecast_today";
+pub const FORECAST_TOMORROW: &str = "forecast_tomorrow";
+pub const FORECAST_TOTAL: &str = "forecast_total";
+pub const FORECAST_CLOSE_LABEL: &str = "forecast_close_label";
+
+// Leeches
+pub const LEECHES_TITLE: &str = "leeches_title";
+pub const LEECHES_EMPTY: &str = "leeches_empty";
+pub const LEECHES_LAPSES: &str = "leeches_lapses";
+pub const LEECH_EDIT_LABEL: &str = "leech_edit_label";
+pub const LEECH_UNSUSPEND_LABEL: &str = "leech_unsuspend_label";
+pub const LEECHES_CLOSE_LABEL: &str = "leeches_close_label";
+pub const LEECH_WINDOW_TITLE: &str = "leech_window_title";
+pub const LEECH_SUSPENDED_MESSAGE: &str = "leech_suspended_message";
+
+// Duplicates
+pub const DUPLICATES_TITLE: &str = "duplicates_title";
+pub const DUPLICATES_EMPTY: &str = "duplicates_empty";
+pub const DUPLICATES_HINT: &str = "duplicates_hint";
+pub const DUPLICATE_MERGE_LABEL: &str = "duplicate_merge_label";
+pub const DUPLICATES_CLOSE_LABEL: &str = "duplicates_close_label";
+
+// Backups
+pub const BACKUPS_TITLE: &str = "backups_title";
+pub const BACKUPS_EMPTY: &str = "backups_empty";
+pub const BACKUPS_ENTRIES: &str = "backups_entries";
+pub const BACKUP_RESTORE_LABEL: &str = "backup_restore_label";
+pub const BACKUPS_CLOSE_LABEL: &str = "backups_close_label";
+pub const RESTORE_WINDOW_TITLE: &str = "restore_window_title";
+pub const RESTORE_MESSAGE: &str = "restore_message";
+
+// Browser
+pub const BROWSER_TITLE: &str = "browser_title";
+pub const BROWSER_SEARCH_PLACEHOLDER: &str = "browser_search_placeholder";
+pub const BROWSER_TAGS: &str = "browser_tags";
+pub const BROWSER_ALL_TAGS: &str = "browser_all_tags";
+pub const BROWSER_EMPTY: &str = "browser_empty";
+pub const BROWSER_INTERVAL: &str = "browser_interval";
+pub const BROWSER_DUE: &str = "browser_due";
+pub const BROWSER_NEW: &str = "browser_new";
+pub const BROWSER_ADDED: &str = "browser_added";
+pub const BROWSER_REVIEWED: &str = "browser_reviewed";
+pub const BROWSER_DUE_NOW: &str = "browser_due_now";
+pub const BROWSER_EDIT_LABEL: &str = "browser_edit_label";
+pub const BROWSER_DELETE_LABEL: &str = "browser_delete_label";
+pub const BROWSER_SUSPEND_LABEL: &str = "browser_suspend_label";
+pub const BROWSER_UNSUSPEND_LABEL: &str = "browser_unsuspend_label";
+pub const BROWSER_RESET_LABEL: &str = "browser_reset_label";
+pub const BROWSER_SELECT_ALL_LABEL: &str = "browser_select_all_label";
+pub const BROWSER_SELECTED: &str = "browser_selected";
+pub const BROWSER_DAYS_PLACEHOLDER: &str = "browser_days_placeholder";
+pub const BROWSER_POSTPONE_LABEL: &str = "browser_postpone_label";
+pub const BROWSER_DECK_PLACEHOLDER: &str = "browser_deck_placeholder";
+pub const BROWSER_MOVE_LABEL: &str = "browser_move_label";
+pub const BROWSER_CLOSE_LABEL: &str = "browser_close_label";
+
+// Card info
+pub const CARD_INFO_DECK: &str = "card_info_deck";
+pub const CARD_INFO_INTRODUCED: &str = "card_info_introduced";
+pub const CARD_INFO_LAPSES: &str = "card_info_lapses";
+
+// Export
+pub const EXPORT_FILTER_NAME: &str = "export_filter_name";
+pub const EXPORT_CSV_FILTER_NAME: &str = "export_csv_filter_name";
+pub const EXPORT_JSON_FILTER_NAME: &str = "export_json_filter_name";
+
+// Import
+pub const IMPORT_WINDOW_TITLE: &str = "import_window_title";
+pub const IMPORT_FILTER_NAME: &str = "import_filter_name";
+pub const IMPORT_ADDED: &str = "import_added";
+pub const IMPORT_DUPLICATES: &str = "import_duplicates";
+pub const IMPORT_INVALID: &str = "import_invalid";
+pub const IMPORT_DECK: &str = "import_deck";
+pub const IMPORT_LANGUAGE: &str = "import_language";
+pub const DECK_FILTER_NAME: &str = "deck_filter_name";
+
+// Merge
+pub const MERGE_WINDOW_TITLE: &str = "merge_window_title";
+pub const MERGE_ADDED: &str = "merge_added";
+pub const MERGE_UPDATED: &str = "merge_updated";
+pub const MERGE_UNCHANGED: &str = "merge_unchanged";
+pub const SYNC_WINDOW_TITLE: &str = "sync_window_title";
+
+// Swapped columns
+pub const SWAP_WINDOW_TITLE: &str = "swap_window_title";
+pub const WORDLIST_FILTER_NAME: &str = "wordlist_filter_name";
+pub const SWAPPED_MESSAGE: &str = "swapped_message";
+pub const NOT_SWAPPED_MESSAGE: &str = "not_swapped_message";
+
+// Optimization of the scheduler
+pub const OPTIMIZE_WINDOW_TITLE: &str = "optimize_window_title";
+pub const OPTIMIZE_REVIEWS: &str = "optimize_reviews";
+pub const OPTIMIZE_TOO_FEW: &str = "optimize_too_few";
+
+// Word editor
+pub const EDITOR_WORD_PLACEHOLDER: &str = "editor_word_placeholder";
+pub const EDITOR_TR_WORD_PLACEHOLDER: &str = "editor_tr_word_placeholder";
+pub const EDITOR_NOTES_PLACEHOLDER: &str = "editor_notes_placeholder";
+pub const EDITOR_TAGS_PLACEHOLDER: &str = "editor_tags_placeholder";
+pub const EDITOR_SAVE_LABEL: &str = "editor_save_label";
+pub const EDITOR_DELETE_LABEL: &str = "editor_delete_label";
+pub const EDITOR_CANCEL_LABEL: &str = "editor_cancel_label";
+
+// Locked database
+pub const DB_LOCKED_WINDOW_TITLE: &str = "db_locked_window_title";
+pub const DB_LOCKED_MESSAGE: &str = "db_locked_message";
 
 // Error windows
-pub static ERROR_WINDOW_TITLE: &str = "Chyba";
-pub static FAILED_UI_INIT_MESSAGE: &str =
-    "Nastala chyba při inicializaci UI. Nešlo nic.";
-pub static FAILED_DB_INIT_MESSAGE: &str =
-    "Nastala chyba při inicializaci databáze. Nešlo nic.";
-pub static FAILED_DB_WRITE_MESSAGE: &str =
-    "Nastala chyba při zapisování databáze. Nešlo nic.";
-pub static GENERIC_RUNTIME_ERR_MESSAGE: &str =
-    "Nastala chyba. Nešlo nic.";
+pub const ERROR_WINDOW_TITLE: &str = "error_window_title";
+pub const FAILED_UI_INIT_MESSAGE: &str = "failed_ui_init_message";
+pub const FAILED_DB_INIT_MESSAGE: &str = "failed_db_init_message";
+pub const FAILED_DB_FORMAT_MESSAGE: &str = "failed_db_format_message";
+pub const FAILED_DB_WRITE_MESSAGE: &str = "failed_db_write_message";
+pub const FAILED_UNDO_MESSAGE: &str = "failed_undo_message";
+pub const FAILED_STATS_MESSAGE: &str = "failed_stats_message";
+pub const FAILED_BACKUP_MESSAGE: &str = "failed_backup_message";
+pub const FAILED_BACKUPS_MESSAGE: &str = "failed_backups_message";
+pub const FAILED_RESTORE_MESSAGE: &str = "failed_restore_message";
+pub const FAILED_CONFIG_READ_MESSAGE: &str = "failed_config_read_message";
+pub const FAILED_CONFIG_WRITE_MESSAGE: &str = "failed_config_write_message";
+pub const FAILED_IMPORT_MESSAGE: &str = "failed_import_message";
+pub const FAILED_MERGE_MESSAGE: &str = "failed_merge_message";
+pub const FAILED_WORDLIST_MESSAGE: &str = "failed_wordlist_message";
+pub const FAILED_SYNC_MESSAGE: &str = "failed_sync_message";
+pub const FAILED_SAVE_AS_MESSAGE: &str = "failed_save_as_message";
+pub const FAILED_OPTIMIZE_MESSAGE: &str = "failed_optimize_message";
+pub const FAILED_EXPORT_MESSAGE: &str = "failed_export_message";
+pub const FAILED_AUDIO_MESSAGE: &str = "failed_audio_message";
+pub const GENERIC_RUNTIME_ERR_MESSAGE: &str = "generic_runtime_err_message";
+
+// Warning windows
+pub const WARNING_WINDOW_TITLE: &str = "warning_window_title";
+pub const SKIPPED_LINES_MESSAGE: &str = "skipped_lines_message";
+pub const SKIPPED_LINES_MORE: &str = "skipped_lines_more";
+
+// Achievements
+pub const ACHIEVEMENT_WINDOW_TITLE: &str = "achievement_window_title";
+pub const ACHIEVEMENT_HUNDRED_REVIEWS: &str = "achievement_hundred_reviews";
+pub const ACHIEVEMENT_WEEK_STREAK: &str = "achievement_week_streak";
+pub const ACHIEVEMENT_DECK_MASTERED: &str = "achievement_deck_mastered";
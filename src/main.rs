@@ -1,217 +1,4846 @@
 #![windows_subsystem = "windows"]
 
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
 use std::process::exit;
-use rfd::{MessageDialog, MessageLevel, FileDialog};
+use std::thread;
+use std::time::Duration;
+use rfd::{MessageDialog, MessageLevel, MessageButtons, FileDialog};
 use iced::{
     button,
+    canvas,
+    scrollable,
+    text_input,
     Button,
+    Canvas,
+    Checkbox,
+    Image,
+    ProgressBar,
+    Scrollable,
+    TextInput,
+    Radio,
+    Container,
+    Color,
     Element,
     Column,
     Row,
     Text,
-    Sandbox,
+    Application,
+    Command,
+    Clipboard,
+    Subscription,
     Settings,
+    executor,
     HorizontalAlignment,
     VerticalAlignment,
     Length,
     Align,
+    Point,
+    Rectangle,
+    Size,
 };
+use iced::futures::channel::mpsc;
+use iced::futures::stream::{BoxStream, StreamExt};
+use iced_native::{event, keyboard, window, Event};
+use iced_native::subscription::Recipe;
+use iced_native::keyboard::KeyCode;
 use wordpal::db::*;
+use wordpal::db::import::{self, ImportOptions, ImportSummary};
+use wordpal::db::deck::{self, DeckHeader, DECK_EXTENSION};
+use wordpal::db::backup::{self, Snapshot, BACKUP_COUNT};
+#[cfg(feature = "anki")]
+use wordpal::export::anki;
+#[cfg(feature = "sync")]
+use wordpal::sync::{self, Remote};
+use wordpal::export::history::{self, HistoryFormat};
+use wordpal::error::Error;
 use wordpal::locale::*;
+use wordpal::config::{Config, are_valid_font_sizes};
+use wordpal::theme::{palette, set_theme, Theme};
+use wordpal::layout::*;
+use wordpal::guard::GradeGuard;
+use wordpal::autosave::AutoSave;
+use wordpal::grading::{self, Grading, GradingOptions};
+use wordpal::normalize::{self, Normalization};
+use wordpal::hint::{self, MAX_HINTS};
+use wordpal::choice::{self, Question, CHOICE_COUNT};
+use wordpal::diff::DiffOp;
+use wordpal::stats::{self, Stats, date_label, due_label, seconds_label};
+use wordpal::session::{self, Session, Summary};
+use wordpal::scheduler::{Algorithm, Grade, GradeLabels, Preset, ladder_repr,
+                         parse_ladder};
+use wordpal::fsrs::is_valid_retention;
+use wordpal::card::Cloze;
+use wordpal::media::{self, Player};
+use wordpal::notify;
+use wordpal::paths;
+use wordpal::cli::{self, Invocation, quarantined_lines};
+
+/// The number of days shown in the daily review counts of the statistics
+const STATS_DAYS: u64 = 14;
+
+/// The number of the slowest answered words listed in the statistics
+const SLOW_WORDS_SHOWN: usize = 10;
+
+/// The scales (in percent) of the user interface offered in the settings
+const UI_SCALES: [u16; 5] = [75, 100, 125, 150, 200];
+
+/// The font size of the notes of the entries
+const NOTES_SIZE: u16 = 20;
+
+/// The height the notes of an entry take at most; longer notes are scrolled
+const NOTES_MAX_HEIGHT: u32 = 150;
+
+/// The number of days shown in the forecast of the reviews; today and the
+/// next 30 days
+const FORECAST_DAYS: u64 = 31;
+
+/// Whether the pronunciation of the original word is played as soon as it's
+/// shown; otherwise it's only played with the play button
+const AUTOPLAY_AUDIO: bool = true;
+
+/// The height of the images of the entries; wider images are scaled down
+const IMAGE_HEIGHT: u16 = 200;
+
+/// How often (in seconds) it's checked whether it's time for a reminder of
+/// the due words
+const REMINDER_CHECK_SECONDS: u64 = 60;
 
 /// A wrapper around MessageDialog with MessageLevel::Error
 fn error(message: &str) {
         MessageDialog::new()
-            .set_level(MessageLevel::Error)
-            .set_title(ERROR_WINDOW_TITLE)
-            .set_description(message)
+            .set_level(MessageLevel::Error)
+            .set_title(tr(ERROR_WINDOW_TITLE))
+            .set_description(message)
+            .show();
+}
+
+/// A wrapper around MessageDialog with MessageLevel::Warning
+fn warning(message: &str) {
+        MessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_title(tr(WARNING_WINDOW_TITLE))
+            .set_description(message)
+            .show();
+}
+
+/// Converts the side of the window to iced's alignment
+fn alignment(side: Side) -> HorizontalAlignment {
+    match side {
+        Side::Left   => HorizontalAlignment::Left,
+        Side::Center => HorizontalAlignment::Center,
+        Side::Right  => HorizontalAlignment::Right,
+    }
+}
+
+fn main() {
+    // Everything but opening the window is done in the terminal (see
+    // `cli::Invocation`)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str>   = args.iter().map(String::as_str).collect();
+    let read_only = match Invocation::parse(&args) {
+        Some(Invocation::Window(read_only)) => read_only,
+        Some(invocation)                    => exit(invocation.run()),
+        None                                => exit(cli::usage()),
+    };
+
+    // The window is closed by the app, so that the database is unlocked
+    let settings = Settings {
+        flags:                 read_only,
+        exit_on_close_request: false,
+        ..Settings::default()
+    };
+    if App::run(settings).is_err() {
+        error(tr(GENERIC_RUNTIME_ERR_MESSAGE));
+        exit(1);
+    };
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    OpenPressed,
+    OpenReadOnlyPressed,
+    RecentPressed(usize),
+    CreatePressed,
+    QuitPressed,
+    DeckPicked(Option<usize>),
+    TagPicked(usize),
+    StarredPicked,
+    DeckOptionsPressed(usize),
+    DeckAlgorithmPicked(Option<Algorithm>),
+    DeckLadderChanged(String),
+    DeckRetentionChanged(String),
+    DeckOptionsClosePressed,
+    GradePressed(Grade),
+    WordPressed,
+    DirectionPressed,
+    AnswerModePressed,
+    ListeningPressed,
+    InfoPressed,
+    CramPressed,
+    PlayPressed,
+    HintPressed,
+    AnswerChanged(String),
+    AnswerSubmitted,
+    NearMissJudged(bool),
+    ChoicePicked(usize),
+    AddPressed,
+    EditPressed,
+    BuryPressed,
+    SuspendPressed,
+    StarPressed,
+    ImportPressed,
+    MergePressed,
+    SaveAsPressed,
+    SharePressed,
+    StatsPressed,
+    UndoPressed,
+    ContinuePressed,
+    StudyAheadPressed(u64),
+    StatsExportPressed,
+    StatsClosePressed,
+    ForecastPressed,
+    ForecastClosePressed,
+    LeechesPressed,
+    LeechEditPressed(usize),
+    LeechUnsuspendPressed(usize),
+    LeechesClosePressed,
+    DuplicatesPressed,
+    DuplicateMergePressed(usize),
+    DuplicatesClosePressed,
+    SwapColumnsPressed,
+    BackupsPressed,
+    BackupRestorePressed(usize),
+    BackupsClosePressed,
+    BrowsePressed,
+    BrowserQueryChanged(String),
+    BrowserTagPicked(Option<usize>),
+    BrowserEditPressed(usize),
+    BrowserDeletePressed(usize),
+    BrowserSuspendPressed(usize),
+    BrowserResetPressed(usize),
+    BrowserSelected(usize, bool),
+    BrowserAllSelected(bool),
+    BrowserDaysChanged(String),
+    BrowserDeckChanged(String),
+    BrowserBulkResetPressed,
+    BrowserBulkDeletePressed,
+    BrowserBulkPostponePressed,
+    BrowserBulkMovePressed,
+    BrowserClosePressed,
+    SettingsPressed,
+    SettingsClosePressed,
+    AlgorithmPicked(Algorithm),
+    OptimizePressed,
+    LadderChanged(usize, String),
+    TwoWayToggled(String, bool),
+    NormalizationToggled(Option<String>, normalize::Step, bool),
+    GoalChanged(String),
+    AheadChanged(String),
+    ScalePicked(u16),
+    MinFontChanged(String),
+    MaxFontChanged(String),
+    HintPenaltyToggled(bool),
+    GradeLabelsPicked(GradeLabels),
+    GradeIconsToggled(bool),
+    ConfirmLongestToggled(bool),
+    RemindersToggled(bool),
+    #[cfg(feature = "tray")]
+    TrayToggled(bool),
+    #[cfg(feature = "tray")]
+    Tray(tray::Action),
+    LanguagePicked(Language),
+    ThemePicked(Theme),
+    #[cfg(feature = "anki")]
+    ExportPressed,
+    #[cfg(feature = "sync")]
+    SyncPressed,
+    EditorWordChanged(String),
+    EditorTrWordChanged(String),
+    EditorNotesChanged(String),
+    EditorTagsChanged(String),
+    EditorSavePressed,
+    EditorDeletePressed,
+    EditorCancelPressed,
+    KeyPressed(KeyCode),
+    FocusMoved(bool),
+    FocusChanged(bool),
+    ReminderTick,
+    CloseRequested,
+}
+
+/// The screen that is currently shown
+enum Screen {
+    /// No database is open yet
+    Start(Start),
+
+    /// A database with decks is open and the user picks one to review
+    Decks(Box<Decks>),
+
+    /// A database is open and the words are being reviewed
+    Study(Box<Study>),
+}
+
+struct App {
+    screen:    Screen,
+    config:    Config,
+    /// The settings, shown over any screen while they're open
+    settings:  Option<SettingsView>,
+    /// Whether the window should close
+    exiting:   bool,
+    /// Which control of the screen the keys press
+    focus:     Focus,
+    /// Whether the window is focused; the reminders are only shown while it
+    /// isn't
+    focused:   bool,
+    /// When (in seconds since the epoch) the user is reminded of the due
+    /// words; only set while the window is in the background
+    remind_at: Option<u64>,
+    /// The icon in the system tray, if the window hides to it
+    #[cfg(feature = "tray")]
+    tray:      Option<tray::Tray>,
+    /// Whether the window is hidden to the tray
+    #[cfg(feature = "tray")]
+    hidden:    bool,
+}
+
+/// The keyboard focus; which control of the screen Enter and Space press.
+/// Tab moves it to the next control and Shift+Tab to the previous one, in the
+/// order they're shown. Past the last (or before the first) control, nothing
+/// is focused and the keys are the shortcuts of the screen again:
+///
+/// ```text
+/// shortcuts -Tab-> first control -Tab-> ... -Tab-> last control -Tab-+
+///     ^                                                              |
+///     +--------------------------------------------------------------+
+/// ```
+///
+/// The controls are told apart by their messages, so the focus stays on
+/// a control that's shown again, e.g. on the same grade for the next word.
+#[derive(Default)]
+struct Focus {
+    /// The messages of the controls of the shown screen, in the order of
+    /// Tab; recorded by the view of the screen
+    controls: Vec<Message>,
+    /// The message of the focused control, or `None` if the keys are the
+    /// shortcuts
+    focused:  Option<Message>,
+}
+
+impl Focus {
+    /// Returns the control after the focused one, or the one before it if
+    /// `back`; `None` for the shortcuts.
+    /// A focused control that isn't shown anymore counts as no focus.
+    fn next(&self, back: bool) -> Option<Message> {
+        let count   = self.controls.len();
+        let current = self.focused.as_ref()
+            .and_then(|focused| {
+                self.controls.iter().position(|control| control == focused)
+            })
+            .unwrap_or(count);
+
+        // The shortcuts are the position after the last control
+        let next = match back {
+            true  => (current + count) % (count + 1),
+            false => (current + 1) % (count + 1),
+        };
+        self.controls.get(next).cloned()
+    }
+
+    /// Returns the message of the focused control if it's shown
+    fn pressed(&self) -> Option<Message> {
+        self.focused.clone()
+            .filter(|focused| self.controls.contains(focused))
+    }
+
+    /// Records `button` as a control pressed by `message` with `style`; the
+    /// focused one is marked with `style::Focused`
+    fn control<'a>(&mut self, button: Button<'a, Message>, message: Message,
+                   style: style::Button) -> Button<'a, Message> {
+        let style: Box<dyn button::StyleSheet> =
+            match self.focused.as_ref() == Some(&message) {
+                true  => Box::new(style::Focused(style)),
+                false => Box::new(style),
+            };
+        self.controls.push(message.clone());
+        button.style(style).on_press(message)
+    }
+
+    /// Records a plain button labeled `label` pressed by `message`
+    fn button<'a>(&mut self, state: &'a mut button::State, label: &str,
+                  message: Message) -> Button<'a, Message> {
+        self.control(Button::new(state, Text::new(label)), message,
+                     style::Button::Normal)
+    }
+
+    /// Records a text input submitting `message`; it shows its focus by
+    /// itself
+    fn input(&mut self, message: Message) {
+        self.controls.push(message);
+    }
+}
+
+
+/// The start screen, offering to open one of the databases opened last or
+/// another one, create a new one, change the settings or quit
+#[derive(Default)]
+struct Start {
+    /// The databases opened last, the latest one first
+    recent:           Vec<(PathBuf, button::State)>,
+    open_button:      button::State,
+    /// Opens a database without ever writing to it, e.g. a shared one
+    read_only_button: button::State,
+    create_button:    button::State,
+    settings_button:  button::State,
+    quit_button:      button::State,
+}
+
+impl Start {
+    fn new(config: &Config) -> Self {
+        Self {
+            recent: config.recent_databases.iter()
+                .map(|path| (path.clone(), button::State::default()))
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    fn view(&mut self, focus: &mut Focus) -> Element<'_, Message> {
+        // This is how we want the window to look:
+        // +---------------+
+        // |   ---------   | -> self.recent (if any)
+        // |    -------    | -> self.open_button
+        // |    -------    | -> self.read_only_button
+        // |    -------    | -> self.create_button
+        // |    -------    | -> self.settings_button
+        // |    -------    | -> self.quit_button
+        // +---------------+
+        let buttons = [
+            (&mut self.open_button,   START_OPEN_LABEL, Message::OpenPressed),
+            (&mut self.read_only_button, START_OPEN_READ_ONLY_LABEL,
+             Message::OpenReadOnlyPressed),
+            (&mut self.create_button, START_CREATE_LABEL,
+             Message::CreatePressed),
+            (&mut self.settings_button, SETTINGS_LABEL,
+             Message::SettingsPressed),
+            (&mut self.quit_button,   START_QUIT_LABEL, Message::QuitPressed),
+        ];
+
+        let mut col = Column::new()
+            .align_items(Align::Center)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(30)
+            .push(Text::new(tr(ROOT_WINDOW_TITLE)).size(80));
+
+        if !self.recent.is_empty() {
+            let mut recent = Column::new()
+                .align_items(Align::Center)
+                .spacing(10)
+                .push(Text::new(tr(START_RECENT_TITLE)).size(30));
+            for (idx, (path, state)) in self.recent.iter_mut().enumerate() {
+                let label = Text::new(path.display().to_string())
+                    .size(20)
+                    .horizontal_alignment(HorizontalAlignment::Center);
+                let button = Button::new(state, label)
+                    .min_width(300)
+                    .padding(5);
+                recent = recent.push(focus.control(button,
+                                                   Message::RecentPressed(idx),
+                                                   style::Button::Normal));
+            }
+            col = col.push(recent);
+        }
+
+        for (state, label, message) in buttons {
+            let label = Text::new(tr(label))
+                .size(30)
+                .horizontal_alignment(HorizontalAlignment::Center);
+            let button = Button::new(state, label)
+                .min_width(300)
+                .padding(10);
+            col = col.push(focus.control(button, message,
+                                         style::Button::Normal));
+        }
+
+        col.into()
+    }
+}
+
+/// The deck picker, shown before reviewing a database with multiple decks
+struct Decks {
+    database:    Database,
+    decks:       Vec<String>,
+    /// The tags of the entries, for studying the tagged ones of all the decks
+    tags:        Vec<String>,
+    /// Whether any entries are starred, for studying them on their own
+    starred:        bool,
+    /// The scheduling options of a deck, shown instead of the decks
+    options:        Option<DeckOptionsView>,
+    all_button:     button::State,
+    buttons:        Vec<button::State>,
+    option_buttons: Vec<button::State>,
+    star_button:    button::State,
+    tag_buttons:    Vec<button::State>,
+    scroll:         scrollable::State,
+}
+
+impl Decks {
+    fn new(db: Database) -> Self {
+        let decks = db.decks();
+        let tags: Vec<String> = db.tags().into_iter()
+            .map(|(tag, _)| tag)
+            .collect();
+        Self {
+            starred:        db.starred_count() != 0,
+            database:       db,
+            options:        None,
+            buttons:        vec![button::State::default(); decks.len()],
+            option_buttons: vec![button::State::default(); decks.len()],
+            tag_buttons:    vec![button::State::default(); tags.len()],
+            all_button:     button::State::default(),
+            star_button:    button::State::default(),
+            scroll:         scrollable::State::default(),
+            decks,
+            tags,
+        }
+    }
+
+    /// Shows the scheduling options of the `deck`th deck
+    fn show_options(&mut self, deck: usize) {
+        if let Some(deck) = self.decks.get(deck) {
+            let preset   = self.database.meta.deck_presets.get(deck);
+            self.options = Some(DeckOptionsView::new(deck.clone(), preset));
+        }
+    }
+
+    /// Handles the messages of the deck options.
+    /// The preset of the deck changes as soon as a typed parameter is valid
+    /// or cleared; the deck is scheduled with it once it's picked (see
+    /// `session::prepare`).
+    fn update_options(&mut self, message: Message) {
+        let options = match &mut self.options {
+            Some(options) => options,
+            None          => return,
+        };
+        match message {
+            Message::DeckAlgorithmPicked(algorithm) => {
+                options.algorithm = algorithm;
+            },
+            Message::DeckLadderChanged(text)    => options.ladder    = text,
+            Message::DeckRetentionChanged(text) => options.retention = text,
+            // The preset is written with the metadata
+            Message::DeckOptionsClosePressed => {
+                self.options = None;
+                if let Err(err) = self.database.write_db() {
+                    error(&format!("{}\n\n({})", tr(FAILED_DB_WRITE_MESSAGE),
+                                   err));
+                }
+                return;
+            },
+            _ => return,
+        }
+
+        let presets = &mut self.database.meta.deck_presets;
+        match options.preset() {
+            Some(preset) => presets.insert(options.deck.clone(), preset),
+            None         => presets.remove(&options.deck),
+        };
+    }
+
+    /// Starts reviewing the `deck`th deck, or all of them if it's `None`
+    fn pick(mut self, deck: Option<usize>, config: &Config) -> Study {
+        self.database.active_deck = deck.and_then(|deck| {
+            self.decks.get(deck).cloned()
+        });
+        Study::new(self.database, config)
+    }
+
+    /// Starts reviewing the entries tagged with the `tag`th tag, whatever
+    /// deck they're in
+    fn pick_tag(mut self, tag: usize, config: &Config) -> Study {
+        self.database.active_tag = self.tags.get(tag).cloned();
+        Study::new(self.database, config)
+    }
+
+    /// Starts reviewing the starred entries of all the decks
+    fn pick_starred(mut self, config: &Config) -> Study {
+        self.database.starred_only = true;
+        Study::new(self.database, config)
+    }
+
+    fn view(&mut self, config: &Config, focus: &mut Focus)
+            -> Element<'_, Message> {
+        if let Some(options) = &mut self.options {
+            return options.view(config);
+        }
+
+        // This is how we want the window to look:
+        // +---------------+
+        // |    -------    | -> self.all_button
+        // |   ----- --    | -> self.buttons, each with its button of
+        // |   ----- --    |    self.option_buttons (unless the database is
+        // |               |    read-only)
+        // |    -------    | -> self.star_button (if any entries are starred)
+        // |   ---------   | -> the tags title (if there are any tags)
+        // |    -------    | -> self.tag_buttons
+        // +---------------+
+        let label = |text: &str| {
+            Text::new(text)
+                .size(30)
+                .horizontal_alignment(HorizontalAlignment::Center)
+        };
+
+        let all_button = Button::new(&mut self.all_button,
+                                     label(tr(DECKS_ALL_LABEL)))
+            .min_width(300)
+            .padding(10);
+        let mut col = Column::new()
+            .align_items(Align::Center)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(20)
+            .push(Text::new(tr(DECKS_TITLE)).size(50))
+            .push(focus.control(all_button, Message::DeckPicked(None),
+                                style::Button::Normal));
+
+        // A read-only database can't remember the options
+        let read_only = self.database.is_read_only();
+        let decks     = self.decks.iter()
+            .zip(self.buttons.iter_mut())
+            .zip(self.option_buttons.iter_mut());
+        for (idx, ((deck, state), options)) in decks.enumerate() {
+            let button = Button::new(state, label(deck))
+                .min_width(300)
+                .padding(10);
+            let mut row = Row::new()
+                .align_items(Align::Center)
+                .spacing(10)
+                .push(focus.control(button, Message::DeckPicked(Some(idx)),
+                                    style::Button::Normal));
+            if !read_only {
+                row = row.push(focus.button(options, tr(DECK_OPTIONS_LABEL),
+                                            Message::DeckOptionsPressed(idx))
+                    .padding(10));
+            }
+            col = col.push(row);
+        }
+
+        if self.starred {
+            let button = Button::new(&mut self.star_button,
+                                     label(tr(DECKS_STARRED_LABEL)))
+                .min_width(300)
+                .padding(10);
+            col = col.push(focus.control(button, Message::StarredPicked,
+                                         style::Button::Normal));
+        }
+
+        if !self.tags.is_empty() {
+            col = col.push(Text::new(tr(DECKS_TAGS)).size(40));
+        }
+        let tags = self.tags.iter().zip(self.tag_buttons.iter_mut());
+        for (idx, (tag, state)) in tags.enumerate() {
+            let button = Button::new(state, label(tag))
+                .min_width(300)
+                .padding(10);
+            col = col.push(focus.control(button, Message::TagPicked(idx),
+                                         style::Button::Normal));
+        }
+
+        Scrollable::new(&mut self.scroll).push(col).into()
+    }
+}
+
+/// The scheduling options of a deck; its preset, kept in the metadata of
+/// the database (see `Meta::deck_presets`)
+struct DeckOptionsView {
+    deck:            String,
+    /// The algorithm of the preset, or `None` if the deck follows the config
+    algorithm:       Option<Algorithm>,
+    /// The ladder of the preset as typed; empty for the one of the config
+    ladder:          String,
+    ladder_input:    text_input::State,
+    /// The retention of the preset as typed; empty for the one of the config
+    retention:       String,
+    retention_input: text_input::State,
+    close_button:    button::State,
+}
+
+impl DeckOptionsView {
+    /// Creates the options of `deck` with its `preset`, if it has one
+    fn new(deck: String, preset: Option<&Preset>) -> Self {
+        let ladder    = preset.and_then(|preset| preset.ladder.as_deref());
+        let retention = preset.and_then(|preset| preset.retention);
+        Self {
+            deck,
+            algorithm:       preset.map(|preset| preset.algorithm),
+            ladder:          ladder.map_or_else(String::new, ladder_repr),
+            ladder_input:    text_input::State::default(),
+            retention:       retention.map_or_else(String::new,
+                                                   |r| r.to_string()),
+            retention_input: text_input::State::default(),
+            close_button:    button::State::default(),
+        }
+    }
+
+    /// Returns the preset as it's picked and typed, or `None` if the deck
+    /// follows the config.
+    /// Only the parameters of the picked algorithm are kept, and only if
+    /// they're valid.
+    fn preset(&self) -> Option<Preset> {
+        let algorithm = self.algorithm?;
+        let mut preset = Preset::new(algorithm);
+        match algorithm {
+            Algorithm::Fixed => preset.ladder = parse_ladder(&self.ladder),
+            Algorithm::Sm2   => {},
+            Algorithm::Fsrs  => {
+                preset.retention = self.retention.trim().parse().ok()
+                    .filter(|&retention| is_valid_retention(retention));
+            },
+        }
+        Some(preset)
+    }
+
+    fn view(&mut self, config: &Config) -> Element<'_, Message> {
+        // This is how we want the window to look:
+        // +---------------+
+        // |   ---------   | -> the name of the deck
+        // |   ---------   | -> the schedulers; the one of the config or
+        // |               |    the one of the deck
+        // |   ---------   | -> self.ladder_input (if the fixed scheduler is
+        // |               |    picked)
+        // |   ---------   | -> self.retention_input (if FSRS is picked)
+        // |    -------    | -> self.close_button
+        // +---------------+
+        let mut schedulers = Column::new()
+            .spacing(10)
+            .push(Text::new(tr(SETTINGS_SCHEDULER)).size(30))
+            .push(Radio::new(None, tr(DECK_OPTIONS_CONFIG),
+                             Some(self.algorithm),
+                             Message::DeckAlgorithmPicked)
+                .style(style::Radio));
+        for &algorithm in Algorithm::ALL.iter() {
+            let radio = Radio::new(Some(algorithm), tr(algorithm.name()),
+                                   Some(self.algorithm),
+                                   Message::DeckAlgorithmPicked)
+                .style(style::Radio);
+            schedulers = schedulers.push(radio);
+        }
+
+        let mut col = Column::new()
+            .align_items(Align::Center)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(20)
+            .push(Text::new(&self.deck).size(50))
+            .push(schedulers);
+
+        // The parameters left empty are the ones of the config
+        match self.algorithm {
+            Some(Algorithm::Fixed) => {
+                let default = config.deck_ladders.get(&self.deck)
+                    .unwrap_or(&config.ladder);
+                let input   = TextInput::new(&mut self.ladder_input,
+                                             &ladder_repr(default),
+                                             &self.ladder,
+                                             Message::DeckLadderChanged)
+                    .padding(5)
+                    .width(Length::Units(250))
+                    .style(style::TextInput);
+                col = col.push(Row::new()
+                    .align_items(Align::Center)
+                    .spacing(10)
+                    .push(Text::new(tr(SETTINGS_LADDERS)))
+                    .push(input));
+            },
+            Some(Algorithm::Fsrs) => {
+                let input = TextInput::new(&mut self.retention_input,
+                                           &config.retention.to_string(),
+                                           &self.retention,
+                                           Message::DeckRetentionChanged)
+                    .padding(5)
+                    .width(Length::Units(100))
+                    .style(style::TextInput);
+                col = col.push(Row::new()
+                    .align_items(Align::Center)
+                    .spacing(10)
+                    .push(Text::new(tr(DECK_OPTIONS_RETENTION)))
+                    .push(input));
+            },
+            _ => {},
+        }
+
+        col.push(Button::new(&mut self.close_button,
+                             Text::new(tr(DECK_OPTIONS_CLOSE_LABEL)))
+            .style(style::Button::Normal)
+            .on_press(Message::DeckOptionsClosePressed)
+            .padding(10))
+            .into()
+    }
+}
+
+/// The form for adding a new word or editing an existing one
+#[derive(Default)]
+struct Editor {
+    /// The index of the edited usable entry, `None` if a word is being added
+    editing:        Option<usize>,
+    word:           String,
+    tr_word:        String,
+    notes:          String,
+    /// The tags separated by `TAG_DELIMITER`
+    tags:           String,
+    word_input:     text_input::State,
+    tr_word_input:  text_input::State,
+    notes_input:    text_input::State,
+    tags_input:     text_input::State,
+    save_button:    button::State,
+    delete_button:  button::State,
+    cancel_button:  button::State,
+}
+
+impl Editor {
+    fn new(editing: Option<usize>, word: &str, tr_word: &str, notes: &str,
+           tags: &str) -> Self {
+        Self {
+            editing,
+            word:       word.to_string(),
+            tr_word:    tr_word.to_string(),
+            notes:      notes.to_string(),
+            tags:       tags.to_string(),
+            word_input: text_input::State::focused(),
+            ..Self::default()
+        }
+    }
+
+    fn view(&mut self) -> Element<'_, Message> {
+        // This is how we want the window to look:
+        // +---------------+
+        // |  -----------  | -> self.word_input
+        // |  -----------  | -> self.tr_word_input
+        // |  -----------  | -> self.notes_input
+        // |  -----------  | -> self.tags_input
+        // |  --- --- ---  | -> save | delete (if editing) | cancel
+        // +---------------+
+        let word = TextInput::new(&mut self.word_input,
+                                  tr(EDITOR_WORD_PLACEHOLDER),
+                                  &self.word, Message::EditorWordChanged)
+            .padding(10)
+            .size(30)
+            .style(style::TextInput);
+
+        let tr_word = TextInput::new(&mut self.tr_word_input,
+                                     tr(EDITOR_TR_WORD_PLACEHOLDER),
+                                     &self.tr_word,
+                                     Message::EditorTrWordChanged)
+            .on_submit(Message::EditorSavePressed)
+            .padding(10)
+            .size(30)
+            .style(style::TextInput);
+
+        let notes = TextInput::new(&mut self.notes_input,
+                                   tr(EDITOR_NOTES_PLACEHOLDER),
+                                   &self.notes, Message::EditorNotesChanged)
+            .on_submit(Message::EditorSavePressed)
+            .padding(10)
+            .size(20)
+            .style(style::TextInput);
+
+        let tags = TextInput::new(&mut self.tags_input,
+                                  tr(EDITOR_TAGS_PLACEHOLDER),
+                                  &self.tags, Message::EditorTagsChanged)
+            .on_submit(Message::EditorSavePressed)
+            .padding(10)
+            .size(20)
+            .style(style::TextInput);
+
+        let mut buttons = Row::new()
+            .spacing(20)
+            .push(Button::new(&mut self.save_button,
+                              Text::new(tr(EDITOR_SAVE_LABEL)))
+                .style(style::Button::Normal)
+                .on_press(Message::EditorSavePressed)
+                .padding(10));
+        if self.editing.is_some() {
+            let label = Text::new(tr(EDITOR_DELETE_LABEL));
+            buttons = buttons.push(Button::new(&mut self.delete_button, label)
+                .style(style::Button::Normal)
+                .on_press(Message::EditorDeletePressed)
+                .padding(10));
+        }
+        buttons = buttons.push(Button::new(&mut self.cancel_button,
+                                           Text::new(tr(EDITOR_CANCEL_LABEL)))
+            .style(style::Button::Normal)
+            .on_press(Message::EditorCancelPressed)
+            .padding(10));
+
+        Column::new()
+            .align_items(Align::Center)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(30)
+            .push(word)
+            .push(tr_word)
+            .push(notes)
+            .push(tags)
+            .push(buttons)
+            .into()
+    }
+}
+
+/// The statistics of the open database
+struct StatsView {
+    stats:         Stats,
+    export_button: button::State,
+    close_button:  button::State,
+}
+
+impl StatsView {
+    fn new(stats: Stats) -> Self {
+        Self {
+            stats,
+            export_button: button::State::default(),
+            close_button:  button::State::default(),
+        }
+    }
+
+    fn view(&mut self) -> Element<'_, Message> {
+        // This is how we want the window to look:
+        // +---------------+
+        // |   ---------   | -> the title
+        // |   ---------   | -> the daily review counts
+        // |   ---------   | -> accuracy, new, young and mature entries
+        // |   ---------   | -> the average response time
+        // |   ---------   | -> the slowest words (if there are any)
+        // |   ---------   | -> the retention by the intervals of the words
+        // |    -------    | -> self.export_button
+        // |    -------    | -> self.close_button
+        // +---------------+
+        let mut daily = Column::new()
+            .align_items(Align::Center)
+            .spacing(5)
+            .push(Text::new(tr(STATS_DAILY)).size(30));
+        for (day, count) in self.stats.daily.iter() {
+            let (_, month, day) = stats::date(*day);
+            daily = daily.push(Text::new(format!("{}. {}.: {}",
+                                                 day, month, count)));
+        }
+
+        let accuracy = match self.stats.accuracy() {
+            Some(accuracy) => format!("{:.0} %", accuracy * 100.),
+            None           => "-".to_string(),
+        };
+        let response = match self.stats.average_response() {
+            Some(response) => seconds_label(response),
+            None           => "-".to_string(),
+        };
+        let totals = [
+            (tr(STATS_ACCURACY), accuracy),
+            (tr(STATS_NEW),      self.stats.new.to_string()),
+            (tr(STATS_YOUNG),    self.stats.young.to_string()),
+            (tr(STATS_MATURE),   self.stats.mature.to_string()),
+            (tr(STATS_RESPONSE), response),
+        ];
+
+        let mut col = Column::new()
+            .align_items(Align::Center)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(20)
+            .push(Text::new(tr(STATS_TITLE)).size(50))
+            .push(daily);
+        for (label, value) in totals.iter() {
+            col = col.push(Text::new(format!("{}: {}", label, value))
+                .size(25));
+        }
+
+        if !self.stats.slow.is_empty() {
+            let slow: Vec<String> = self.stats.slow.iter()
+                .take(SLOW_WORDS_SHOWN)
+                .map(|(word, response)| {
+                    format!("{} ({})", word, seconds_label(*response))
+                })
+                .collect();
+            col = col.push(Text::new(format!("{}: {}", tr(STATS_SLOW),
+                                             slow.join(", ")))
+                .size(20));
+        }
+
+        // The buckets are labeled with the ranges of their intervals
+        let mut retention = Column::new()
+            .align_items(Align::Center)
+            .spacing(5)
+            .push(Text::new(tr(STATS_RETENTION)).size(30));
+        let mut min = 0;
+        for bucket in self.stats.retention.iter() {
+            let range = match bucket.max_interval {
+                Some(max) if min == 0 => format!("<= {}", max),
+                Some(max)             => format!("{} - {}", min, max),
+                None                  => format!("> {}", min - 1),
+            };
+            let value = match bucket.retention() {
+                Some(retention) => format!("{:.0} %", retention * 100.),
+                None            => "-".to_string(),
+            };
+            retention = retention.push(Text::new(format!("{}: {} ({})",
+                                                         range, value,
+                                                         bucket.reviews)));
+            min = bucket.max_interval.map_or(min, |max| max + 1);
+        }
+        col = col.push(retention);
+
+        col.push(Button::new(&mut self.export_button,
+                             Text::new(tr(STATS_EXPORT_LABEL)))
+                .style(style::Button::Normal)
+                .on_press(Message::StatsExportPressed)
+                .padding(10))
+            .push(Button::new(&mut self.close_button,
+                              Text::new(tr(STATS_CLOSE_LABEL)))
+                .style(style::Button::Normal)
+                .on_press(Message::StatsClosePressed)
+                .padding(10))
+            .into()
+    }
+}
+
+/// The forecast of the reviews of the open database
+struct ForecastView {
+    chart:        ForecastChart,
+    close_button: button::State,
+}
+
+impl ForecastView {
+    fn new(db: &Database) -> Self {
+        let now = now();
+        Self {
+            chart: ForecastChart {
+                today:  now / DAY,
+                counts: db.due_histogram(now, FORECAST_DAYS),
+            },
+            close_button: button::State::default(),
+        }
+    }
+
+    fn view(&mut self) -> Element<'_, Message> {
+        // This is how we want the window to look:
+        // +---------------+
+        // |   ---------   | -> the title
+        // |   ---------   | -> today, tomorrow and all of the days
+        // |  |_|_|||_|_|  | -> the daily counts
+        // |    -------    | -> self.close_button
+        // +---------------+
+        let counts = &self.chart.counts;
+        let totals = [
+            (tr(FORECAST_TODAY),    counts.first().copied().unwrap_or(0)),
+            (tr(FORECAST_TOMORROW), counts.get(1).copied().unwrap_or(0)),
+            (tr(FORECAST_TOTAL),    counts.iter().sum()),
+        ];
+
+        let mut col = Column::new()
+            .align_items(Align::Center)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(20)
+            .push(Text::new(tr(FORECAST_TITLE)).size(50));
+        for (label, count) in totals.iter() {
+            col = col.push(Text::new(format!("{}: {}", label, count)).size(25));
+        }
+
+        col.push(Canvas::new(&mut self.chart)
+                .width(Length::Fill)
+                .height(Length::Fill))
+            .push(Button::new(&mut self.close_button,
+                              Text::new(tr(FORECAST_CLOSE_LABEL)))
+                .style(style::Button::Normal)
+                .on_press(Message::ForecastClosePressed)
+                .padding(10))
+            .into()
+    }
+}
+
+/// The bar chart of the reviews due on each of the upcoming days
+struct ForecastChart {
+    /// The first day of the chart, in days since the epoch
+    today:  u64,
+    /// The number of reviews due on each day, starting today
+    counts: Vec<usize>,
+}
+
+impl canvas::Program<Message> for ForecastChart {
+    fn draw(&self, bounds: Rectangle, _cursor: canvas::Cursor)
+            -> Vec<canvas::Geometry> {
+        // The counts are written above the bars and every week is marked
+        // with its date below them
+        const LABEL_SIZE: f32 = 16.;
+        const WEEK: usize     = 7;
+
+        let palette   = palette();
+        let mut frame = canvas::Frame::new(bounds.size());
+        let max       = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        let bar_width = frame.width() / self.counts.len().max(1) as f32;
+        let bottom    = frame.height() - LABEL_SIZE;
+        let height    = bottom - LABEL_SIZE;
+
+        for (day, &count) in self.counts.iter().enumerate() {
+            let x      = day as f32 * bar_width;
+            let center = x + bar_width / 2.;
+            let bar    = height * count as f32 / max as f32;
+            frame.fill_rectangle(Point::new(x + 1., bottom - bar),
+                                 Size::new((bar_width - 2.).max(1.), bar),
+                                 Color::from(palette.border));
+
+            if count != 0 {
+                frame.fill_text(canvas::Text {
+                    content: count.to_string(),
+                    position: Point::new(center, bottom - bar - LABEL_SIZE),
+                    color: palette.text.into(),
+                    size: LABEL_SIZE,
+                    horizontal_alignment: HorizontalAlignment::Center,
+                    ..canvas::Text::default()
+                });
+            }
+            if day % WEEK == 0 {
+                let (_, month, date) = stats::date(self.today + day as u64);
+                frame.fill_text(canvas::Text {
+                    content: format!("{}. {}.", date, month),
+                    position: Point::new(center, bottom),
+                    color: palette.text.into(),
+                    size: LABEL_SIZE,
+                    horizontal_alignment: HorizontalAlignment::Center,
+                    ..canvas::Text::default()
+                });
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+/// The leeches of the open database; the words that were forgotten so many
+/// times that they aren't reviewed anymore
+struct LeechesView {
+    leeches:      Vec<Entry>,
+    /// The edit and unsuspend buttons of the leeches
+    buttons:      Vec<(button::State, button::State)>,
+    close_button: button::State,
+    scroll:       scrollable::State,
+}
+
+impl LeechesView {
+    fn new(db: &Database) -> Self {
+        let leeches: Vec<Entry> = db.leeches().into_iter()
+            .cloned()
+            .collect();
+        Self {
+            buttons:      vec![Default::default(); leeches.len()],
+            close_button: button::State::default(),
+            scroll:       scrollable::State::default(),
+            leeches,
+        }
+    }
+
+    fn view(&mut self) -> Element<'_, Message> {
+        // This is how we want the window to look:
+        // +---------------+
+        // |   ---------   | -> the title
+        // | ----- --- --- | -> the leeches; the words | edit | unsuspend
+        // | ----- --- --- |
+        // |    -------    | -> self.close_button
+        // +---------------+
+        let mut col = Column::new()
+            .align_items(Align::Center)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(20)
+            .push(Text::new(tr(LEECHES_TITLE)).size(50));
+        if self.leeches.is_empty() {
+            col = col.push(Text::new(tr(LEECHES_EMPTY)).size(25));
+        }
+
+        let leeches = self.leeches.iter().zip(self.buttons.iter_mut());
+        for (idx, (leech, (edit, unsuspend))) in leeches.enumerate() {
+            let words = format!("{} - {} ({}: {})",
+                                leech.word, leech.translations(),
+                                tr(LEECHES_LAPSES), leech.lapses);
+            col = col.push(Row::new()
+                .align_items(Align::Center)
+                .spacing(10)
+                .push(Text::new(words).size(25))
+                .push(Button::new(edit, Text::new(tr(LEECH_EDIT_LABEL)))
+                    .style(style::Button::Normal)
+                    .on_press(Message::LeechEditPressed(idx)))
+                .push(Button::new(unsuspend,
+                                  Text::new(tr(LEECH_UNSUSPEND_LABEL)))
+                    .style(style::Button::Normal)
+                    .on_press(Message::LeechUnsuspendPressed(idx))));
+        }
+
+        col = col.push(Button::new(&mut self.close_button,
+                                   Text::new(tr(LEECHES_CLOSE_LABEL)))
+            .style(style::Button::Normal)
+            .on_press(Message::LeechesClosePressed)
+            .padding(10));
+        Scrollable::new(&mut self.scroll).push(col).into()
+    }
+}
+
+/// The groups of entries with the same words that only differ in case,
+/// diacritics or whitespace, which can be merged into one
+struct DuplicatesView {
+    /// The entries of the groups, with their current intervals (in days)
+    groups:       Vec<Vec<(Entry, u64)>>,
+    /// The merge buttons of the groups
+    buttons:      Vec<button::State>,
+    close_button: button::State,
+    scroll:       scrollable::State,
+}
+
+impl DuplicatesView {
+    fn new(db: &Database) -> Self {
+        let groups: Vec<Vec<(Entry, u64)>> = db.find_duplicates().into_iter()
+            .map(|group| {
+                group.into_iter()
+                    .map(|entry| (entry.clone(), db.interval(entry)))
+                    .collect()
+            })
+            .collect();
+        Self {
+            buttons:      vec![Default::default(); groups.len()],
+            close_button: button::State::default(),
+            scroll:       scrollable::State::default(),
+            groups,
+        }
+    }
+
+    /// Returns the words and translations of the entries of the `idx`th
+    /// group, see `Database::merge_duplicates`
+    fn keys(&self, idx: usize) -> Vec<(String, Vec<String>)> {
+        self.groups.get(idx)
+            .into_iter()
+            .flatten()
+            .map(|(entry, _)| (entry.word.clone(), entry.tr_word.clone()))
+            .collect()
+    }
+
+    fn view(&mut self) -> Element<'_, Message> {
+        // This is how we want the window to look:
+        // +---------------+
+        // |   ---------   | -> the title
+        // |  -----------  | -> what merging does
+        // | -----         | -> the groups; the words and interval of each
+        // | -----         |    entry
+        // |   ---         |    merge
+        // |    -------    | -> self.close_button
+        // +---------------+
+        let mut col = Column::new()
+            .align_items(Align::Center)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(20)
+            .push(Text::new(tr(DUPLICATES_TITLE)).size(50));
+        if self.groups.is_empty() {
+            col = col.push(Text::new(tr(DUPLICATES_EMPTY)).size(25));
+        } else {
+            col = col.push(Text::new(tr(DUPLICATES_HINT)));
+        }
+
+        let groups = self.groups.iter().zip(self.buttons.iter_mut());
+        for (idx, (group, merge)) in groups.enumerate() {
+            let mut entries = Column::new().spacing(5);
+            for (entry, interval) in group {
+                let words = format!("{} - {} ({}: {})",
+                                    entry.word, entry.translations(),
+                                    tr(BROWSER_INTERVAL), interval);
+                entries = entries.push(Text::new(words).size(25));
+            }
+            col = col.push(Column::new()
+                .spacing(10)
+                .push(entries)
+                .push(Button::new(merge, Text::new(tr(DUPLICATE_MERGE_LABEL)))
+                    .style(style::Button::Normal)
+                    .on_press(Message::DuplicateMergePressed(idx))));
+        }
+
+        col = col.push(Button::new(&mut self.close_button,
+                                   Text::new(tr(DUPLICATES_CLOSE_LABEL)))
+            .style(style::Button::Normal)
+            .on_press(Message::DuplicatesClosePressed)
+            .padding(10));
+        Scrollable::new(&mut self.scroll).push(col).into()
+    }
+}
+
+/// The daily snapshots of the open database, any of which it can be
+/// restored to
+struct BackupsView {
+    snapshots:    Vec<Snapshot>,
+    /// The restore buttons of the snapshots
+    buttons:      Vec<button::State>,
+    close_button: button::State,
+    scroll:       scrollable::State,
+}
+
+impl BackupsView {
+    fn new(snapshots: Vec<Snapshot>) -> Self {
+        Self {
+            buttons:      vec![Default::default(); snapshots.len()],
+            close_button: button::State::default(),
+            scroll:       scrollable::State::default(),
+            snapshots,
+        }
+    }
+
+    fn view(&mut self) -> Element<'_, Message> {
+        // This is how we want the window to look:
+        // +---------------+
+        // |   ---------   | -> the title
+        // |   ----- ---   | -> the snapshots, the newest one first; when
+        // |   ----- ---   |    they were taken and their words | restore
+        // |    -------    | -> self.close_button
+        // +---------------+
+        let mut col = Column::new()
+            .align_items(Align::Center)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(20)
+            .push(Text::new(tr(BACKUPS_TITLE)).size(50));
+        if self.snapshots.is_empty() {
+            col = col.push(Text::new(tr(BACKUPS_EMPTY)).size(25));
+        }
+
+        let snapshots = self.snapshots.iter().zip(self.buttons.iter_mut());
+        for (idx, (snapshot, restore)) in snapshots.enumerate() {
+            let seconds = snapshot.time % DAY;
+            let entries = snapshot.entries
+                .map_or_else(|| "-".to_string(), |count| count.to_string());
+            let label   = format!("{} {:02}:{:02} ({}: {})",
+                                  date_label(snapshot.time), seconds / 3600,
+                                  seconds / 60 % 60, tr(BACKUPS_ENTRIES),
+                                  entries);
+            col = col.push(Row::new()
+                .align_items(Align::Center)
+                .spacing(10)
+                .push(Text::new(label).size(25))
+                .push(Button::new(restore,
+                                  Text::new(tr(BACKUP_RESTORE_LABEL)))
+                    .style(style::Button::Normal)
+                    .on_press(Message::BackupRestorePressed(idx))));
+        }
+
+        col = col.push(Button::new(&mut self.close_button,
+                                   Text::new(tr(BACKUPS_CLOSE_LABEL)))
+            .style(style::Button::Normal)
+            .on_press(Message::BackupsClosePressed)
+            .padding(10));
+        Scrollable::new(&mut self.scroll).push(col).into()
+    }
+}
+
+/// The list of all the entries of the database, filtered by a search
+#[derive(Default)]
+struct BrowserView {
+    query:        String,
+    /// The tag the entries are filtered by, if any
+    tag:          Option<String>,
+    /// The tags of all the entries, with the numbers of their entries
+    tags:         Vec<(String, usize)>,
+    /// The button showing the entries of any tag, then the ones of the tags
+    tag_buttons:  (button::State, Vec<button::State>),
+    /// The entries matching the query and the tag
+    entries:      Vec<Entry>,
+    /// The current intervals (in days) of the entries
+    intervals:    Vec<u64>,
+    /// The edit, delete, suspend and reset buttons of the entries
+    buttons:      Vec<(button::State, button::State,
+                       button::State, button::State)>,
+    /// Whether the entries are selected for the bulk actions
+    selected:     Vec<bool>,
+    bulk:         BulkActions,
+    search_input: text_input::State,
+    close_button: button::State,
+    scroll:       scrollable::State,
+}
+
+impl BrowserView {
+    fn new(db: &Database) -> Self {
+        let mut browser = Self {
+            search_input: text_input::State::focused(),
+            ..Self::default()
+        };
+        browser.refresh(db);
+        browser
+    }
+
+    /// Lists the entries of `db` matching the query and the tag again.
+    /// The tag isn't filtered by anymore once no entry carries it.
+    fn refresh(&mut self, db: &Database) {
+        self.tags = db.tags();
+        if let Some(tag) = &self.tag {
+            if !self.tags.iter().any(|(other, _)| other == tag) {
+                self.tag = None;
+            }
+        }
+        self.tag_buttons.1 = vec![Default::default(); self.tags.len()];
+
+        let tag      = self.tag.as_deref();
+        self.entries = db.search(&self.query).into_iter()
+            .filter(|entry| tag.is_none_or(|tag| entry.has_tag(tag)))
+            .cloned()
+            .collect();
+        self.intervals = self.entries.iter()
+            .map(|entry| db.interval(entry))
+            .collect();
+        self.buttons  = vec![Default::default(); self.entries.len()];
+        self.selected = vec![false; self.entries.len()];
+    }
+
+    /// Returns the words and translations of the selected entries, see
+    /// `Database::change_entries`
+    fn selected_keys(&self) -> Vec<(String, Vec<String>)> {
+        self.entries.iter()
+            .zip(self.selected.iter())
+            .filter(|(_, &selected)| selected)
+            .map(|(entry, _)| (entry.word.clone(), entry.tr_word.clone()))
+            .collect()
+    }
+
+    fn view(&mut self) -> Element<'_, Message> {
+        // This is how we want the window to look:
+        // +---------------------+
+        // |       ---------     | -> the title
+        // | ---  -----------    | -> the tags title (if there are any tags) |
+        // | ---  -------------  |    self.search_input
+        // | ---  - ------------ | -> self.tag_buttons (all, then the tags) |
+        // |      - ------------ |    select all | the bulk actions (if any
+        // |                     |    entries are selected); reset | delete |
+        // |                     |    postpone by self.days | move into
+        // |                     |    self.deck
+        // |                     | -> the entries; selected | the words,
+        // |                     |    interval, due date, added and last
+        // |                     |    reviewed | edit | delete | suspend |
+        // |                     |    reset
+        // |       -------       | -> self.close_button
+        // +---------------------+
+        let search = TextInput::new(&mut self.search_input,
+                                    tr(BROWSER_SEARCH_PLACEHOLDER),
+                                    &self.query, Message::BrowserQueryChanged)
+            .padding(10)
+            .size(25)
+            .style(style::TextInput);
+
+        let mut col = Column::new()
+            .align_items(Align::Center)
+            .width(Length::Fill)
+            .spacing(20)
+            .push(search);
+        if self.entries.is_empty() {
+            col = col.push(Text::new(tr(BROWSER_EMPTY)).size(25));
+        } else {
+            col = col.push(self.bulk.view(&self.selected));
+        }
+
+        let now     = now();
+        let entries = self.entries.iter()
+            .zip(self.intervals.iter())
+            .zip(self.buttons.iter_mut())
+            .zip(self.selected.iter());
+        for (idx, (((entry, interval), buttons), &selected)) in
+                entries.enumerate() {
+            let words = format!("{} - {} ({}: {}, {}: {}, {}: {}, {}: {})",
+                                entry.word, entry.translations(),
+                                tr(BROWSER_INTERVAL), interval,
+                                tr(BROWSER_DUE), due_label(entry, now),
+                                tr(BROWSER_ADDED), date_label(entry.added),
+                                tr(BROWSER_REVIEWED),
+                                date_label(entry.reviewed));
+            let suspend_label = match entry.suspended {
+                true  => tr(BROWSER_UNSUSPEND_LABEL),
+                false => tr(BROWSER_SUSPEND_LABEL),
+            };
+
+            let (edit, delete, suspend, reset) = buttons;
+            col = col.push(Row::new()
+                .align_items(Align::Center)
+                .spacing(10)
+                .push(Checkbox::new(selected, "", move |selected| {
+                        Message::BrowserSelected(idx, selected)
+                    })
+                    .style(style::Checkbox))
+                .push(Text::new(words).size(25))
+                .push(Button::new(edit, Text::new(tr(BROWSER_EDIT_LABEL)))
+                    .style(style::Button::Normal)
+                    .on_press(Message::BrowserEditPressed(idx)))
+                .push(Button::new(delete, Text::new(tr(BROWSER_DELETE_LABEL)))
+                    .style(style::Button::Normal)
+                    .on_press(Message::BrowserDeletePressed(idx)))
+                .push(Button::new(suspend, Text::new(suspend_label))
+                    .style(style::Button::Normal)
+                    .on_press(Message::BrowserSuspendPressed(idx)))
+                .push(Button::new(reset, Text::new(tr(BROWSER_RESET_LABEL)))
+                    .style(style::Button::Normal)
+                    .on_press(Message::BrowserResetPressed(idx))));
+        }
+
+        // The picked tag (or all of them) can't be picked again
+        let mut row = Row::new().spacing(20);
+        if !self.tags.is_empty() {
+            let (all, buttons) = &mut self.tag_buttons;
+            let picked         = self.tag.clone();
+            let mut all        = Button::new(all,
+                                             Text::new(tr(BROWSER_ALL_TAGS)))
+                .style(style::Button::Normal)
+                .width(Length::Fill);
+            if picked.is_some() {
+                all = all.on_press(Message::BrowserTagPicked(None));
+            }
+
+            let mut sidebar = Column::new()
+                .width(Length::Units(200))
+                .spacing(10)
+                .push(Text::new(tr(BROWSER_TAGS)).size(30))
+                .push(all);
+            let tags = self.tags.iter().zip(buttons.iter_mut());
+            for (idx, ((tag, count), state)) in tags.enumerate() {
+                let label      = format!("{} ({})", tag, count);
+                let mut button = Button::new(state, Text::new(label))
+                    .style(style::Button::Normal)
+                    .width(Length::Fill);
+                if picked.as_ref() != Some(tag) {
+                    button = button
+                        .on_press(Message::BrowserTagPicked(Some(idx)));
+                }
+                sidebar = sidebar.push(button);
+            }
+            row = row.push(sidebar);
+        }
+
+        let col = Column::new()
+            .align_items(Align::Center)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(20)
+            .push(Text::new(tr(BROWSER_TITLE)).size(50))
+            .push(row.push(col))
+            .push(Button::new(&mut self.close_button,
+                              Text::new(tr(BROWSER_CLOSE_LABEL)))
+                .style(style::Button::Normal)
+                .on_press(Message::BrowserClosePressed)
+                .padding(10));
+        Scrollable::new(&mut self.scroll).push(col).into()
+    }
+}
+
+/// The actions changing all the entries selected in the browser at once
+#[derive(Default)]
+struct BulkActions {
+    /// The number of days to postpone the selected entries by, as typed
+    days:       String,
+    /// The deck to move the selected entries into, as typed
+    deck:       String,
+    /// The reset, delete, postpone and move buttons
+    buttons:    (button::State, button::State,
+                 button::State, button::State),
+    days_input: text_input::State,
+    deck_input: text_input::State,
+}
+
+impl BulkActions {
+    /// Returns the row selecting all the entries, with the actions if any of
+    /// them are `selected`
+    fn view(&mut self, selected: &[bool]) -> Row<'_, Message> {
+        let all   = selected.iter().all(|&selected| selected);
+        let count = selected.iter().filter(|&&selected| selected).count();
+        let row   = Row::new()
+            .align_items(Align::Center)
+            .spacing(10)
+            .push(Checkbox::new(all, tr(BROWSER_SELECT_ALL_LABEL),
+                                Message::BrowserAllSelected)
+                .style(style::Checkbox));
+        if count == 0 {
+            return row;
+        }
+
+        let (reset, delete, postpone, move_) = &mut self.buttons;
+        row.push(Text::new(format!("{}: {}", tr(BROWSER_SELECTED), count)))
+            .push(Button::new(reset, Text::new(tr(BROWSER_RESET_LABEL)))
+                .style(style::Button::Normal)
+                .on_press(Message::BrowserBulkResetPressed))
+            .push(Button::new(delete, Text::new(tr(BROWSER_DELETE_LABEL)))
+                .style(style::Button::Normal)
+                .on_press(Message::BrowserBulkDeletePressed))
+            .push(TextInput::new(&mut self.days_input,
+                                 tr(BROWSER_DAYS_PLACEHOLDER), &self.days,
+                                 Message::BrowserDaysChanged)
+                .padding(5)
+                .width(Length::Units(80))
+                .style(style::TextInput))
+            .push(Button::new(postpone, Text::new(tr(BROWSER_POSTPONE_LABEL)))
+                .style(style::Button::Normal)
+                .on_press(Message::BrowserBulkPostponePressed))
+            .push(TextInput::new(&mut self.deck_input,
+                                 tr(BROWSER_DECK_PLACEHOLDER), &self.deck,
+                                 Message::BrowserDeckChanged)
+                .padding(5)
+                .width(Length::Units(150))
+                .style(style::TextInput))
+            .push(Button::new(move_, Text::new(tr(BROWSER_MOVE_LABEL)))
+                .style(style::Button::Normal)
+                .on_press(Message::BrowserBulkMovePressed))
+    }
+}
+
+/// The input of a timeout ladder in the settings
+struct LadderInput {
+    /// The deck of the ladder, `None` for the default ladder
+    deck:  Option<String>,
+    /// The steps of the ladder as typed
+    text:  String,
+    input: text_input::State,
+}
+
+/// The settings of the application
+struct SettingsView {
+    /// Whether there's an open database the scheduler can be fitted to
+    can_optimize:    bool,
+    optimize_button: button::State,
+    /// The default ladder and the ladders of the decks of the open database
+    ladders:         Vec<LadderInput>,
+    /// The daily goal as typed
+    goal:            String,
+    goal_input:      text_input::State,
+    /// The hours of studying ahead as typed
+    ahead:           String,
+    ahead_input:     text_input::State,
+    /// The font sizes of the words as typed
+    min_font:        String,
+    min_font_input:  text_input::State,
+    max_font:        String,
+    max_font_input:  text_input::State,
+    close_button:    button::State,
+    scroll:          scrollable::State,
+}
+
+impl SettingsView {
+    /// Creates the settings with the ladders of `config` for all the decks of
+    /// `database`, if one is open; the decks without a ladder of their own
+    /// are left empty
+    fn new(config: &Config, database: Option<&Database>) -> Self {
+        let default = LadderInput {
+            deck:  None,
+            text:  ladder_repr(&config.ladder),
+            input: text_input::State::default(),
+        };
+        let decks = database.map_or_else(Vec::new, Database::decks);
+        let decks = decks.into_iter().map(|deck| LadderInput {
+            text:  config.deck_ladders.get(&deck)
+                .map_or_else(String::new, |ladder| ladder_repr(ladder)),
+            deck:  Some(deck),
+            input: text_input::State::default(),
+        });
+
+        Self {
+            can_optimize:    database.is_some(),
+            optimize_button: button::State::default(),
+            ladders:         std::iter::once(default).chain(decks).collect(),
+            goal:            config.daily_goal.to_string(),
+            goal_input:      text_input::State::default(),
+            ahead:           config.ahead_hours.to_string(),
+            ahead_input:     text_input::State::default(),
+            min_font:        config.min_font_size.to_string(),
+            min_font_input:  text_input::State::default(),
+            max_font:        config.max_font_size.to_string(),
+            max_font_input:  text_input::State::default(),
+            close_button:    button::State::default(),
+            scroll:          scrollable::State::default(),
+        }
+    }
+
+    fn view(&mut self, config: &Config) -> Element<'_, Message> {
+        // This is how we want the window to look:
+        // +---------------+
+        // |   ---------   | -> the title
+        // |   ---------   | -> the languages
+        // |   ---------   | -> the themes
+        // |   ---------   | -> the scale of the window contents and the
+        // |               |    font sizes of the words
+        // |   ---------   | -> the schedulers and self.optimize_button (if
+        // |               |    FSRS is picked and a database is open)
+        // |   ---------   | -> the ladders; the default one and the ones of
+        // |               |    the decks
+        // |   ---------   | -> the two-way decks (if there are any decks)
+        // |   ---------   | -> the differences the typed answers ignore;
+        // |               |    by default and in the decks
+        // |   ---------   | -> the labels of the grade buttons, their icons
+        // |               |    and the confirmation of the longest interval
+        // |   ---------   | -> the daily goal
+        // |   ---------   | -> the hours of studying ahead
+        // |   ---------   | -> the hint penalty
+        // |   ---------   | -> the reminders
+        // |   ---------   | -> the tray (with the tray feature)
+        // |    -------    | -> self.close_button
+        // +---------------+
+        let mut languages = Column::new()
+            .spacing(10)
+            .push(Text::new(tr(SETTINGS_LANGUAGE)).size(30));
+        for language in Language::ALL.iter() {
+            languages = languages.push(Radio::new(*language, language.name(),
+                                                  Some(config.language),
+                                                  Message::LanguagePicked)
+                .style(style::Radio));
+        }
+
+        let mut themes = Column::new()
+            .spacing(10)
+            .push(Text::new(tr(SETTINGS_THEME)).size(30));
+        for theme in Theme::ALL.iter() {
+            themes = themes.push(Radio::new(*theme, tr(theme.name()),
+                                            Some(config.theme),
+                                            Message::ThemePicked)
+                .style(style::Radio));
+        }
+
+        let mut scales = Row::new().spacing(10);
+        for &scale in UI_SCALES.iter() {
+            scales = scales.push(Radio::new(scale, format!("{} %", scale),
+                                            Some(config.ui_scale),
+                                            Message::ScalePicked)
+                .style(style::Radio));
+        }
+        let fonts = Row::new()
+            .spacing(10)
+            .align_items(Align::Center)
+            .push(Text::new(tr(SETTINGS_FONT_SIZES)))
+            .push(TextInput::new(&mut self.min_font_input, "", &self.min_font,
+                                 Message::MinFontChanged)
+                .padding(5)
+                .width(Length::Units(60))
+                .style(style::TextInput))
+            .push(Text::new("-"))
+            .push(TextInput::new(&mut self.max_font_input, "", &self.max_font,
+                                 Message::MaxFontChanged)
+                .padding(5)
+                .width(Length::Units(60))
+                .style(style::TextInput));
+        let size = Column::new()
+            .spacing(10)
+            .push(Text::new(tr(SETTINGS_SCALE)).size(30))
+            .push(scales)
+            .push(fonts);
+
+        let mut schedulers = Column::new()
+            .spacing(10)
+            .push(Text::new(tr(SETTINGS_SCHEDULER)).size(30));
+        for algorithm in Algorithm::ALL.iter() {
+            schedulers = schedulers.push(Radio::new(*algorithm,
+                                                    tr(algorithm.name()),
+                                                    Some(config.scheduler),
+                                                    Message::AlgorithmPicked)
+                .style(style::Radio));
+        }
+        if config.scheduler == Algorithm::Fsrs && self.can_optimize {
+            schedulers = schedulers.push(Button::new(&mut self.optimize_button,
+                    Text::new(tr(SETTINGS_OPTIMIZE_LABEL)))
+                .style(style::Button::Normal)
+                .on_press(Message::OptimizePressed));
+        }
+
+        // Every deck of the open database can be reviewed in both directions
+        let decks: Vec<String> = self.ladders.iter()
+            .filter_map(|ladder| ladder.deck.clone())
+            .collect();
+        let any_decks   = !decks.is_empty();
+        let mut two_way = Column::new()
+            .spacing(10)
+            .push(Text::new(tr(SETTINGS_TWO_WAY)).size(30));
+        for deck in decks {
+            let checked = config.two_way_decks.contains(&deck);
+            two_way = two_way.push(Checkbox::new(checked, deck.clone(),
+                                                 move |two_way| {
+                    Message::TwoWayToggled(deck.clone(), two_way)
+                })
+                .style(style::Checkbox));
+        }
+
+        // The decks without a normalization of their own show the default
+        let mut normalizations = Column::new()
+            .spacing(10)
+            .push(Text::new(tr(SETTINGS_NORMALIZATION)).size(30));
+        let scopes = self.ladders.iter().map(|ladder| ladder.deck.clone());
+        for deck in scopes {
+            let (label, normalization) = match &deck {
+                Some(deck) => (deck.as_str(), config.normalization(deck)),
+                None       => (tr(DECKS_ALL_LABEL), config.normalization),
+            };
+            let mut row = Row::new()
+                .align_items(Align::Center)
+                .spacing(10)
+                .push(Text::new(label).width(Length::Units(150)));
+            for &step in normalize::Step::ALL.iter() {
+                let deck = deck.clone();
+                row = row.push(Checkbox::new(normalization.is_on(step),
+                                             tr(step.name()),
+                                             move |on| {
+                        Message::NormalizationToggled(deck.clone(), step, on)
+                    })
+                    .style(style::Checkbox));
+            }
+            normalizations = normalizations.push(row);
+        }
+
+        // The decks without a ladder of their own use the default one
+        let mut ladders = Column::new()
+            .spacing(10)
+            .push(Text::new(tr(SETTINGS_LADDERS)).size(30));
+        let default = ladder_repr(&config.ladder);
+        for (idx, ladder) in self.ladders.iter_mut().enumerate() {
+            let label = match &ladder.deck {
+                Some(deck) => deck.as_str(),
+                None       => tr(DECKS_ALL_LABEL),
+            };
+            let input = TextInput::new(&mut ladder.input, &default,
+                                       &ladder.text,
+                                       move |text| {
+                                           Message::LadderChanged(idx, text)
+                                       })
+                .padding(5)
+                .width(Length::Units(250))
+                .style(style::TextInput);
+            ladders = ladders.push(Row::new()
+                .align_items(Align::Center)
+                .spacing(10)
+                .push(Text::new(label).width(Length::Units(150)))
+                .push(input));
+        }
+
+        let mut buttons = Column::new()
+            .spacing(10)
+            .push(Text::new(tr(SETTINGS_LABELS)).size(30));
+        for labels in GradeLabels::ALL.iter() {
+            buttons = buttons.push(Radio::new(*labels, tr(labels.name()),
+                                              Some(config.grade_labels),
+                                              Message::GradeLabelsPicked)
+                .style(style::Radio));
+        }
+        buttons = buttons
+            .push(Checkbox::new(config.grade_icons, tr(SETTINGS_ICONS),
+                                Message::GradeIconsToggled)
+                .style(style::Checkbox))
+            .push(Checkbox::new(config.confirm_longest,
+                                tr(SETTINGS_CONFIRM_LONGEST),
+                                Message::ConfirmLongestToggled)
+                .style(style::Checkbox));
+
+        let goal = Column::new()
+            .spacing(10)
+            .push(Text::new(tr(SETTINGS_GOAL)).size(30))
+            .push(TextInput::new(&mut self.goal_input, "0", &self.goal,
+                                 Message::GoalChanged)
+                .padding(5)
+                .width(Length::Units(100))
+                .style(style::TextInput));
+
+        let ahead = Column::new()
+            .spacing(10)
+            .push(Text::new(tr(SETTINGS_AHEAD)).size(30))
+            .push(TextInput::new(&mut self.ahead_input, "0", &self.ahead,
+                                 Message::AheadChanged)
+                .padding(5)
+                .width(Length::Units(100))
+                .style(style::TextInput));
+
+        let mut col = Column::new()
+            .align_items(Align::Center)
+            .width(Length::Fill)
+            .padding(10)
+            .spacing(20)
+            .push(Text::new(tr(SETTINGS_TITLE)).size(50))
+            .push(languages)
+            .push(themes)
+            .push(size)
+            .push(schedulers)
+            .push(ladders);
+        if any_decks {
+            col = col.push(two_way);
+        }
+        col = col.push(normalizations)
+            .push(buttons)
+            .push(goal)
+            .push(ahead)
+            .push(Checkbox::new(config.hint_penalty, tr(SETTINGS_HINT_PENALTY),
+                                Message::HintPenaltyToggled)
+                .style(style::Checkbox))
+            .push(Checkbox::new(config.reminders, tr(SETTINGS_REMINDERS),
+                                Message::RemindersToggled)
+                .style(style::Checkbox));
+        #[cfg(feature = "tray")]
+        {
+            col = col.push(Checkbox::new(config.tray, tr(SETTINGS_TRAY),
+                                         Message::TrayToggled)
+                .style(style::Checkbox));
+        }
+        col = col.push(Button::new(&mut self.close_button,
+                                   Text::new(tr(SETTINGS_CLOSE_LABEL)))
+            .style(style::Button::Normal)
+            .on_press(Message::SettingsClosePressed)
+            .padding(10));
+        Scrollable::new(&mut self.scroll).push(col).into()
+    }
+}
+
+/// How the words are answered on the review screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AnswerMode {
+    /// The user recalls the translation and grades it with the buttons
+    Buttons,
+
+    /// The user types the translation and it's graded
+    Typing,
+
+    /// The user picks the translation among a few candidates; see `choice`
+    Choice,
+}
+
+impl AnswerMode {
+    /// Returns the mode the toolbar button switches to
+    fn next(self) -> Self {
+        match self {
+            AnswerMode::Buttons => AnswerMode::Typing,
+            AnswerMode::Typing  => AnswerMode::Choice,
+            AnswerMode::Choice  => AnswerMode::Buttons,
+        }
+    }
+
+    /// Returns the localization key of the label of the button switching to
+    /// this mode
+    fn label(self) -> &'static str {
+        match self {
+            AnswerMode::Buttons => BUTTONS_LABEL,
+            AnswerMode::Typing  => TYPING_LABEL,
+            AnswerMode::Choice  => CHOICE_LABEL,
+        }
+    }
+}
+
+
+/// The review screen of an open database
+struct Study {
+    database:         Database,
+    current_entry:    Option<(Entry, usize)>,
+    /// The card the current entry is reviewed as; which of its words is
+    /// shown first
+    card:             Option<CardId>,
+    word:             String,
+    tr_word:          String,
+    /// The accepted answers of the current entry; the translations, or the
+    /// original word in reverse
+    answers:          Vec<String>,
+    /// The sentence of the current entry if it's a cloze card; `word` is the
+    /// sentence with the blanks then
+    cloze:            Option<Cloze>,
+    /// The image of the current entry, if it has one that exists
+    image:            Option<PathBuf>,
+    /// When (in milliseconds since the epoch) the current entry was shown
+    shown_at:         u64,
+    /// The notes of the current entry, shown with the translation
+    notes:            String,
+    /// Plays the pronunciations of the current entries
+    player:           Player,
+    /// Whether the words are prompted by their pronunciation alone (the
+    /// listening mode)
+    listening:        bool,
+    /// Whether the current entry is prompted by its pronunciation alone; its
+    /// word and image are hidden until the translation is revealed
+    heard:            bool,
+    tr_word_hidden:   bool,
+    /// The number of hints used on the current entry; see `hint::mask`
+    hints:            usize,
+    /// Whether the entries recalled with a hint are graded as hard at best
+    hint_penalty:     bool,
+    /// What the buttons of the grades are labeled with
+    grade_labels:     GradeLabels,
+    /// Whether the buttons of the grades are marked with icons as well
+    grade_icons:      bool,
+    /// Whether the user is asked before an answer moves a word to the
+    /// longest interval
+    confirm_longest:  bool,
+    /// Whether the card info (the timestamps and the schedule) of the current
+    /// entry is shown
+    info:             bool,
+    display:          DisplayConfig,
+    guard:            GradeGuard,
+    /// Decides when the changes of the database are written
+    autosave:         AutoSave,
+    grading:          GradingOptions,
+    /// The normalizations of the typed answers of the decks that have their
+    /// own; the others use the one in `grading`
+    normalizations:   BTreeMap<String, Normalization>,
+    /// How the words are answered
+    mode:             AnswerMode,
+    answer:           String,
+    /// The grading of the typed answer, shown until the next word is drawn
+    graded:           Option<Grading>,
+    /// The grading of a typed answer within the tolerated typos; the entry
+    /// is graded once the user decides whether it counts
+    near_miss:        Option<Grading>,
+    count_buttons:    [button::State; 2],
+    answer_input:     text_input::State,
+    /// The multiple-choice question of the current entry; `None` unless the
+    /// candidates are picked and there are enough of them
+    question:         Option<Question>,
+    /// The candidate picked in the question, shown until the next word is
+    /// drawn
+    picked:           Option<usize>,
+    choice_buttons:   [button::State; CHOICE_COUNT],
+    mode_button:      button::State,
+    listen_button:    button::State,
+    cram_button:      button::State,
+    word_button:      button::State,
+    play_button:      button::State,
+    hint_button:      button::State,
+    notes_scroll:     scrollable::State,
+    grade_buttons:    [button::State; 4],
+    direction_button: button::State,
+    add_button:       button::State,
+    edit_button:      button::State,
+    bury_button:      button::State,
+    suspend_button:   button::State,
+    star_button:      button::State,
+    import_button:    button::State,
+    merge_button:     button::State,
+    save_as_button:   button::State,
+    share_button:     button::State,
+    #[cfg(feature = "anki")]
+    export_button:    button::State,
+    #[cfg(feature = "sync")]
+    sync_button:      button::State,
+    stats_button:     button::State,
+    forecast_button:  button::State,
+    leeches_button:   button::State,
+    duplicate_button: button::State,
+    swap_button:      button::State,
+    backups_button:   button::State,
+    browse_button:    button::State,
+    undo_button:      button::State,
+    settings_button:  button::State,
+    continue_button:  button::State,
+    ahead_button:     button::State,
+    editor:           Option<Editor>,
+    stats:            Option<StatsView>,
+    forecast:         Option<ForecastView>,
+    leeches:          Option<LeechesView>,
+    duplicates:       Option<DuplicatesView>,
+    backups:          Option<BackupsView>,
+    browser:          Option<BrowserView>,
+    session:          Session,
+    /// The summary of the finished session, shown instead of the words
+    summary:          Option<Summary>,
+    /// The deck and the ladder of the shared deck imported last, for the app
+    /// to remember in the config
+    shared_ladder:    Option<(String, Vec<u64>)>,
+    /// The copy of the database on the server, if it's synced
+    #[cfg(feature = "sync")]
+    remote:           Option<Remote>,
+}
+
+impl Study {
+    fn new(mut db: Database, config: &Config) -> Self {
+        let session = session::prepare(&mut db, config);
+        #[cfg(feature = "sync")]
+        let remote  = Remote::from_config(config, &db.path);
+        let mut study = Self {
+            word:             String::new(),
+            tr_word:          String::new(),
+            answers:          Vec::new(),
+            cloze:            None,
+            image:            None,
+            shown_at:         0,
+            notes:            String::new(),
+            player:           Player::new(),
+            listening:        false,
+            heard:            false,
+            tr_word_hidden:   true,
+            hints:            0,
+            info:             false,
+            hint_penalty:     config.hint_penalty,
+            grade_labels:     config.grade_labels,
+            grade_icons:      config.grade_icons,
+            confirm_longest:  config.confirm_longest,
+            display:          config.display(),
+            guard:            GradeGuard::default(),
+            autosave:         AutoSave::default(),
+            grading:          GradingOptions {
+                normalization: config.normalization,
+                ..GradingOptions::default()
+            },
+            normalizations:   config.deck_normalizations.clone(),
+            mode:             AnswerMode::Buttons,
+            answer:           String::new(),
+            graded:           None,
+            near_miss:        None,
+            count_buttons:    Default::default(),
+            answer_input:     text_input::State::default(),
+            question:         None,
+            picked:           None,
+            choice_buttons:   Default::default(),
+            mode_button:      button::State::default(),
+            listen_button:    button::State::default(),
+            cram_button:      button::State::default(),
+            database:         db,
+            current_entry:    None,
+            card:             None,
+            grade_buttons:    Default::default(),
+            word_button:      button::State::default(),
+            play_button:      button::State::default(),
+            hint_button:      button::State::default(),
+            notes_scroll:     scrollable::State::default(),
+            direction_button: button::State::default(),
+            add_button:       button::State::default(),
+            edit_button:      button::State::default(),
+            bury_button:      button::State::default(),
+            suspend_button:   button::State::default(),
+            star_button:      button::State::default(),
+            import_button:    button::State::default(),
+            merge_button:     button::State::default(),
+            save_as_button:   button::State::default(),
+            share_button:     button::State::default(),
+            #[cfg(feature = "anki")]
+            export_button:    button::State::default(),
+            #[cfg(feature = "sync")]
+            sync_button:      button::State::default(),
+            stats_button:     button::State::default(),
+            forecast_button:  button::State::default(),
+            leeches_button:   button::State::default(),
+            duplicate_button: button::State::default(),
+            swap_button:      button::State::default(),
+            backups_button:   button::State::default(),
+            browse_button:    button::State::default(),
+            undo_button:      button::State::default(),
+            settings_button:  button::State::default(),
+            continue_button:  button::State::default(),
+            ahead_button:     button::State::default(),
+            editor:           None,
+            stats:            None,
+            forecast:         None,
+            leeches:          None,
+            duplicates:       None,
+            backups:          None,
+            browser:          None,
+            session,
+            summary:          None,
+            shared_ladder:    None,
+            #[cfg(feature = "sync")]
+            remote,
+        };
+
+        // Draw the first word so that the ui can show it immediately
+        // without any further action
+        if config.preload_first_entry {
+            study.next_entry();
+        }
+
+        study
+    }
+
+    /// Returns the direction the current entry is shown in
+    fn direction(&self) -> Direction {
+        self.card.as_ref()
+            .map_or_else(|| self.database.direction(), |card| card.direction)
+    }
+
+    /// Draws the next entry from the database and shows it.
+    /// Both words are set to "" and the summary of the session is shown if
+    /// the session is over.
+    fn next_entry(&mut self) {
+        let entry = self.session.next_entry(&mut self.database, now());
+        self.show_entry(entry);
+    }
+
+    /// Shows `entry` (an entry and its index in the database).
+    /// Both words are set to "" and the summary of the session is shown if
+    /// there's no entry.
+    fn show_entry(&mut self, entry: Option<(Entry, usize)>) {
+        // New words that are being learned are shown with their translation
+        self.current_entry  = entry;
+        self.tr_word_hidden = !self.database.is_learning();
+        self.hints          = 0;
+        self.summary        = match self.current_entry {
+            Some(_) => None,
+            None    => Some(self.session.summary(now())),
+        };
+        self.answer.clear();
+        self.graded         = None;
+        self.near_miss      = None;
+        self.picked         = None;
+        self.answer_input   = text_input::State::focused();
+        self.shown_at       = now_millis();
+
+        // The end of the session is a good moment to write the rest
+        if self.summary.is_some() && self.autosave.is_pending() {
+            self.save();
+        }
+        self.card = self.current_entry.as_ref()
+            .map(|(entry, _)| self.database.card(entry));
+        if let Some((entry, _)) = &self.current_entry {
+            // In reverse, the translations are shown first.
+            // A cloze sentence is shown with the blanks in both directions and
+            // the hidden parts are the answer.
+            let cloze     = entry.cloze();
+            let direction = self.direction();
+            let (word, tr_word, answers) = match (&cloze, direction) {
+                (Some(cloze), _) => (cloze.blanked(), entry.translations(),
+                                     vec![cloze.hidden()]),
+                (None, Direction::Forward) => {
+                    (entry.word.clone(), entry.translations(),
+                     entry.tr_word.clone())
+                },
+                (None, Direction::Reverse) => {
+                    (entry.translations(), entry.word.clone(),
+                     vec![entry.word.clone()])
+                },
+            };
+            self.word    = word;
+            self.tr_word = tr_word;
+            self.answers = answers;
+            self.notes   = entry.notes.clone();
+            self.cloze   = cloze;
+            self.image   = media::image_path(&self.database.path, entry);
+        } else {
+            self.word    = "".to_string();
+            self.tr_word = "".to_string();
+            self.answers.clear();
+            self.notes.clear();
+            self.cloze   = None;
+            self.image   = None;
+        }
+        self.notes_scroll = scrollable::State::default();
+        self.deal_question();
+
+        // The original word is only shown first in the forward direction,
+        // and a cloze sentence would give its hidden parts away.
+        // A missing pronunciation isn't worth interrupting the review for;
+        // in the listening mode, the word is just shown as usual then.
+        self.player.stop();
+        self.heard = false;
+        if (AUTOPLAY_AUDIO || self.listening)
+                && self.direction() == Direction::Forward
+                && self.cloze.is_none() {
+            let played = self.pronounce().unwrap_or(false);
+            self.heard = self.listening && played;
+        }
+    }
+
+    /// Deals the multiple-choice question of the current entry if the
+    /// candidates are picked; the other entries of the database are the
+    /// distractors
+    fn deal_question(&mut self) {
+        let direction = self.direction();
+        let db        = &mut self.database;
+        self.question = match (&self.current_entry, self.mode) {
+            (Some((entry, _)), AnswerMode::Choice) => {
+                let entries = db.usable.iter().chain(db.unusable.iter());
+                choice::deal(entry, direction, entries, &mut db.rng)
+            },
+            _ => None,
+        };
+    }
+
+    /// Returns whether the current entry was answered already; typed or
+    /// picked
+    fn is_answered(&self) -> bool {
+        self.graded.is_some() || self.picked.is_some()
+    }
+
+    /// Pronounces the original word of the current entry.
+    /// Returns false if there's no entry or it can't be pronounced.
+    fn pronounce(&mut self) -> std::io::Result<bool> {
+        match &self.current_entry {
+            Some((entry, _)) => self.player.pronounce(&self.database.path,
+                                                      entry),
+            None             => Ok(false),
+        }
+    }
+
+    /// Grades the current entry.
+    /// New words that are only being presented in a batch aren't graded.
+    /// While cramming, the answer only counts towards the session.
+    /// A word recalled with a hint is graded as hard at best, if the hint
+    /// penalty is on.
+    /// If asked to, the user has to confirm an answer that moves the word to
+    /// the longest interval.
+    /// Returns false if the grade was rejected and nothing should happen.
+    fn grade(&mut self, grade: Grade) -> bool {
+        let grade = match self.hints > 0 && self.hint_penalty {
+            true  => hint::penalize(grade),
+            false => grade,
+        };
+        if self.database.is_learning() {
+            return true;
+        }
+        let (entry, index) = match &self.current_entry {
+            Some((entry, index)) => (entry, *index),
+            None                 => return true,
+        };
+
+        // Ignore buttons mashed faster than anyone can actually answer.
+        // Cramming doesn't touch the schedules.
+        let now = now_millis();
+        if !self.database.is_cramming() {
+            if !self.guard.allow(&entry.word, now) {
+                return false;
+            }
+            let confirm = self.confirm_longest
+                && self.database.reaches_longest_interval(index, grade);
+            if confirm && !confirm_longest() {
+                return false;
+            }
+        }
+        let response = now.saturating_sub(self.shown_at);
+        let answered = self.session.answer(&mut self.database, index, grade,
+                                           Some(response));
+        if answered.leech {
+            MessageDialog::new()
+                .set_level(MessageLevel::Info)
+                .set_title(tr(LEECH_WINDOW_TITLE))
+                .set_description(tr(LEECH_SUSPENDED_MESSAGE))
+                .show();
+        }
+        for achievement in answered.achievements {
+            MessageDialog::new()
+                .set_level(MessageLevel::Info)
+                .set_title(tr(ACHIEVEMENT_WINDOW_TITLE))
+                .set_description(achievement.description())
+                .show();
+        }
+
+        true
+    }
+
+    fn update(&mut self, message: Message) {
+        // If the user clicks on the untranslated word, the translated word
+        // is shown/hidden.
+        // If they click on any of the grade buttons, the entry is timed out.
+        // Without a preloaded word, the first answer draws it instead of
+        // grading nothing.
+        let answer = matches!(message, Message::WordPressed
+                                       | Message::GradePressed(_)
+                                       | Message::AnswerSubmitted
+                                       | Message::ChoicePicked(_));
+        if answer && self.current_entry.is_none() && self.summary.is_none() {
+            self.next_entry();
+            return;
+        }
+        match message {
+            Message::WordPressed => {
+                self.tr_word_hidden = !self.tr_word_hidden;
+                return;
+            }
+            Message::DirectionPressed => {
+                let direction = self.database.direction().flip();
+                let separate  = self.database.separates_directions();
+                self.database.set_direction(direction, separate);
+            },
+            Message::AnswerModePressed => {
+                self.mode = self.mode.next();
+            },
+            Message::ListeningPressed => {
+                self.listening = !self.listening;
+            },
+            Message::InfoPressed => {
+                self.info = !self.info;
+                return;
+            },
+            Message::PlayPressed => {
+                if let Err(err) = self.pronounce() {
+                    error(&format!("{}\n\n({})", tr(FAILED_AUDIO_MESSAGE),
+                                   err));
+                }
+                return;
+            },
+            // The last hint reveals the whole translation
+            Message::HintPressed => {
+                self.hints += 1;
+                if self.hints >= MAX_HINTS {
+                    self.tr_word_hidden = false;
+                }
+                return;
+            },
+            Message::CramPressed => {
+                let cram = !self.database.is_cramming();
+                self.database.set_cram(cram);
+                self.session.restart(now());
+            },
+            Message::AnswerChanged(answer) => {
+                // The graded answer stays as it was typed
+                if self.graded.is_none() && self.near_miss.is_none() {
+                    self.answer = answer;
+                }
+                return;
+            },
+            // The answers were saved when they were graded
+            Message::AnswerSubmitted => {
+                if self.submit_answer() {
+                    self.next_entry();
+                }
+                return;
+            },
+            Message::NearMissJudged(counts) => {
+                self.judge_near_miss(counts);
+                return;
+            },
+            // The answers are saved when they're picked
+            Message::ChoicePicked(choice) => {
+                if self.pick(choice) {
+                    self.next_entry();
+                }
+                return;
+            },
+            Message::GradePressed(grade) => {
+                if self.grade(grade) {
+                    self.save_answer();
+                    self.next_entry();
+                }
+                return;
+            },
+            Message::AddPressed => {
+                // The new words are tagged like the ones being studied
+                let tag     = self.database.active_tag.as_deref();
+                self.editor = Some(Editor::new(None, "", "", "",
+                                               tag.unwrap_or("")));
+                return;
+            },
+            Message::EditPressed => {
+                if let Some((entry, index)) = &self.current_entry {
+                    let tr_word = join_translations(&entry.tr_word);
+                    self.editor = Some(Editor::new(Some(*index), &entry.word,
+                                                   &tr_word, &entry.notes,
+                                                   &join_tags(&entry.tags)));
+                }
+                return;
+            },
+            // Burying and suspending skip the entry without grading it
+            Message::BuryPressed => {
+                if !self.change_current(Database::bury) {
+                    return;
+                }
+            },
+            Message::SuspendPressed => {
+                if !self.change_current(Database::suspend) {
+                    return;
+                }
+            },
+            // Starring keeps the entry on the screen
+            Message::StarPressed => {
+                if self.change_current(Database::toggle_star) {
+                    if let Some((entry, _)) = &mut self.current_entry {
+                        entry.starred = !entry.starred;
+                    }
+                    self.save();
+                }
+                return;
+            },
+            Message::ImportPressed => {
+                if !self.import() {
+                    return;
+                }
+            },
+            Message::MergePressed => {
+                if !self.merge() {
+                    return;
+                }
+            },
+            Message::SaveAsPressed => {
+                self.save_as();
+                return;
+            },
+            Message::SharePressed => {
+                self.share();
+                return;
+            },
+            #[cfg(feature = "sync")]
+            Message::SyncPressed => {
+                if !self.sync() {
+                    return;
+                }
+            },
+            #[cfg(feature = "anki")]
+            Message::ExportPressed => {
+                self.export_anki();
+                return;
+            },
+            Message::StatsPressed => {
+                self.show_stats();
+                return;
+            },
+            Message::StatsExportPressed => {
+                self.export_history();
+                return;
+            },
+            Message::StatsClosePressed => {
+                self.stats = None;
+                return;
+            },
+            Message::ForecastPressed => {
+                self.forecast = Some(ForecastView::new(&self.database));
+                return;
+            },
+            Message::ForecastClosePressed => {
+                self.forecast = None;
+                return;
+            },
+            Message::LeechesPressed => {
+                self.leeches = Some(LeechesView::new(&self.database));
+                return;
+            },
+            Message::LeechesClosePressed => {
+                self.leeches = None;
+                return;
+            },
+            Message::LeechEditPressed(idx) => {
+                if let Some(index) = self.leech_index(idx) {
+                    let entry    = &self.database.usable[index];
+                    let tr_word  = join_translations(&entry.tr_word);
+                    self.editor  = Some(Editor::new(Some(index), &entry.word,
+                                                    &tr_word, &entry.notes,
+                                                    &join_tags(&entry.tags)));
+                    self.leeches = None;
+                }
+                return;
+            },
+            Message::LeechUnsuspendPressed(idx) => {
+                if let Some(index) = self.leech_index(idx) {
+                    self.database.unsuspend(index);
+                    self.save();
+                    self.leeches = Some(LeechesView::new(&self.database));
+                }
+                return;
+            },
+            Message::DuplicatesPressed => {
+                self.duplicates = Some(DuplicatesView::new(&self.database));
+                return;
+            },
+            Message::DuplicatesClosePressed => {
+                self.duplicates = None;
+                return;
+            },
+            Message::SwapColumnsPressed => {
+                if !self.swap_columns() {
+                    return;
+                }
+            },
+            Message::DuplicateMergePressed(idx) => {
+                let keys = match &self.duplicates {
+                    Some(duplicates) => duplicates.keys(idx),
+                    None             => return,
+                };
+                if self.database.merge_duplicates(&keys) {
+                    self.save();
+                    self.next_entry();
+                    self.duplicates = Some(DuplicatesView::new(&self.database));
+                }
+                return;
+            },
+            Message::BackupsPressed => {
+                match backup::list(&self.database.path) {
+                    Ok(snapshots) => {
+                        self.backups = Some(BackupsView::new(snapshots));
+                    },
+                    Err(err) => error(&format!("{}\n\n({})",
+                                               tr(FAILED_BACKUPS_MESSAGE),
+                                               err)),
+                }
+                return;
+            },
+            Message::BackupsClosePressed => {
+                self.backups = None;
+                return;
+            },
+            Message::BrowsePressed => {
+                self.browser = Some(BrowserView::new(&self.database));
+                return;
+            },
+            Message::BrowserQueryChanged(query) => {
+                if let Some(browser) = &mut self.browser {
+                    browser.query = query;
+                    browser.refresh(&self.database);
+                }
+                return;
+            },
+            Message::BrowserTagPicked(tag) => {
+                if let Some(browser) = &mut self.browser {
+                    browser.tag = tag.and_then(|tag| {
+                        browser.tags.get(tag).map(|(tag, _)| tag.clone())
+                    });
+                    browser.refresh(&self.database);
+                }
+                return;
+            },
+            Message::BrowserClosePressed => {
+                self.browser = None;
+                return;
+            },
+            Message::BrowserEditPressed(idx) => {
+                if let Some(index) = self.browsed_index(idx) {
+                    let entry    = &self.database.usable[index];
+                    let tr_word  = join_translations(&entry.tr_word);
+                    self.editor  = Some(Editor::new(Some(index), &entry.word,
+                                                    &tr_word, &entry.notes,
+                                                    &join_tags(&entry.tags)));
+                    self.browser = None;
+                }
+                return;
+            },
+            Message::BrowserDeletePressed(idx) => {
+                self.change_browsed(idx, |db, index| {
+                    db.remove_entry(index).is_some()
+                });
+                return;
+            },
+            Message::BrowserSuspendPressed(idx) => {
+                self.change_browsed(idx, |db, index| {
+                    match db.usable[index].suspended {
+                        true  => db.unsuspend(index),
+                        false => db.suspend(index),
+                    }
+                });
+                return;
+            },
+            Message::BrowserResetPressed(idx) => {
+                self.change_browsed(idx, Database::reset_entry);
+                return;
+            },
+            Message::BrowserSelected(idx, selected) => {
+                let flag = self.browser.as_mut()
+                    .and_then(|browser| browser.selected.get_mut(idx));
+                if let Some(flag) = flag {
+                    *flag = selected;
+                }
+                return;
+            },
+            Message::BrowserAllSelected(selected) => {
+                if let Some(browser) = &mut self.browser {
+                    browser.selected.iter_mut()
+                        .for_each(|flag| *flag = selected);
+                }
+                return;
+            },
+            Message::BrowserDaysChanged(days) => {
+                if let Some(browser) = &mut self.browser {
+                    browser.bulk.days = days;
+                }
+                return;
+            },
+            Message::BrowserDeckChanged(deck) => {
+                if let Some(browser) = &mut self.browser {
+                    browser.bulk.deck = deck;
+                }
+                return;
+            },
+            Message::BrowserBulkResetPressed => {
+                self.change_selected(Database::reset_entry);
+                return;
+            },
+            Message::BrowserBulkDeletePressed => {
+                self.change_selected(|db, index| {
+                    db.remove_entry(index).is_some()
+                });
+                return;
+            },
+            Message::BrowserBulkPostponePressed => {
+                let days = self.browser.as_ref()
+                    .and_then(|browser| browser.bulk.days.trim().parse().ok());
+                if let Some(days) = days {
+                    self.change_selected(|db, index| db.postpone(index, days));
+                }
+                return;
+            },
+            Message::BrowserBulkMovePressed => {
+                let deck = self.browser.as_ref()
+                    .map(|browser| browser.bulk.deck.clone());
+                if let Some(deck) = deck {
+                    self.change_selected(|db, index| db.set_deck(index, &deck));
+                }
+                return;
+            },
+            Message::UndoPressed => {
+                self.undo();
+                return;
+            },
+            Message::ContinuePressed => {
+                self.session.restart(now());
+            },
+            Message::StudyAheadPressed(hours) => {
+                self.database.study_ahead(ahead_until(hours));
+                self.session.restart(now());
+            },
+            Message::KeyPressed(key_code) => {
+                if let Some(message) = self.shortcut(key_code) {
+                    self.update(message);
+                }
+                return;
+            },
+            message => {
+                if !self.update_editor(message) {
+                    return;
+                }
+            },
+        }
+
+        self.save();
+        self.next_entry();
+    }
+
+    /// Returns the message of the keyboard shortcut `key_code`, if any.
+    /// The shortcuts only work while the words are being reviewed with the
+    /// grading buttons or the candidates of the questions, except for Escape
+    /// closing what's shown over the words.
+    fn shortcut(&self, key_code: KeyCode) -> Option<Message> {
+        if key_code == KeyCode::Escape {
+            return self.close_message();
+        }
+        if self.editor.is_some() || self.stats.is_some()
+                || self.forecast.is_some() || self.leeches.is_some()
+                || self.duplicates.is_some() || self.backups.is_some()
+                || self.browser.is_some()
+                || self.mode == AnswerMode::Typing {
+            return None;
+        }
+        if key_code == KeyCode::U {
+            return Some(Message::UndoPressed);
+        }
+        if key_code == KeyCode::B && self.summary.is_none() {
+            return Some(Message::BuryPressed);
+        }
+        if key_code == KeyCode::P && self.summary.is_none() {
+            return Some(Message::PlayPressed);
+        }
+        if key_code == KeyCode::S && self.summary.is_none()
+                && !self.is_answered() {
+            return Some(Message::StarPressed);
+        }
+        if self.summary.is_some() {
+            return match key_code {
+                KeyCode::Space | KeyCode::Enter => {
+                    Some(Message::ContinuePressed)
+                },
+                _ => None,
+            };
+        }
+
+        // The candidates are picked by their numbers and the next word is
+        // drawn by picking again
+        if self.question.is_some() {
+            return match key_code {
+                KeyCode::Key1 | KeyCode::Numpad1 => {
+                    Some(Message::ChoicePicked(0))
+                },
+                KeyCode::Key2 | KeyCode::Numpad2 => {
+                    Some(Message::ChoicePicked(1))
+                },
+                KeyCode::Key3 | KeyCode::Numpad3 => {
+                    Some(Message::ChoicePicked(2))
+                },
+                KeyCode::Key4 | KeyCode::Numpad4 => {
+                    Some(Message::ChoicePicked(3))
+                },
+                KeyCode::Space | KeyCode::Enter if self.picked.is_some() => {
+                    Some(Message::ChoicePicked(0))
+                },
+                KeyCode::I => Some(Message::InfoPressed),
+                _ => None,
+            };
+        }
+
+        match key_code {
+            KeyCode::Space | KeyCode::Enter => Some(Message::WordPressed),
+            KeyCode::H if self.tr_word_hidden => Some(Message::HintPressed),
+            KeyCode::I => Some(Message::InfoPressed),
+            KeyCode::Key1 | KeyCode::Numpad1 | KeyCode::Left => {
+                Some(Message::GradePressed(Grade::Again))
+            },
+            KeyCode::Key2 | KeyCode::Numpad2 => {
+                Some(Message::GradePressed(Grade::Hard))
+            },
+            KeyCode::Key3 | KeyCode::Numpad3 | KeyCode::Right => {
+                Some(Message::GradePressed(Grade::Good))
+            },
+            KeyCode::Key4 | KeyCode::Numpad4 => {
+                Some(Message::GradePressed(Grade::Easy))
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the message closing what's shown over the words (e.g. the
+    /// editor or the stats), if anything
+    fn close_message(&self) -> Option<Message> {
+        let shown = [
+            (self.editor.is_some(),     Message::EditorCancelPressed),
+            (self.stats.is_some(),      Message::StatsClosePressed),
+            (self.forecast.is_some(),   Message::ForecastClosePressed),
+            (self.leeches.is_some(),    Message::LeechesClosePressed),
+            (self.duplicates.is_some(), Message::DuplicatesClosePressed),
+            (self.backups.is_some(),    Message::BackupsClosePressed),
+            (self.browser.is_some(),    Message::BrowserClosePressed),
+        ];
+        shown.iter()
+            .find(|(shown, _)| *shown)
+            .map(|(_, message)| message.clone())
+    }
+
+    /// Returns whether the answer is being typed into its focused input
+    fn is_answer_focused(&self) -> bool {
+        self.mode == AnswerMode::Typing && self.summary.is_none()
+            && self.answer_input.is_focused()
+    }
+
+    /// Focuses the input of the typed answer, or unfocuses it
+    fn focus_answer(&mut self, focused: bool) {
+        match focused {
+            true  => self.answer_input.focus(),
+            false => self.answer_input.unfocus(),
+        }
+    }
+
+    /// Writes the database to the file system, including the answers that
+    /// haven't been written yet.
+    /// Only the changed entries are appended, so this is cheap even for
+    /// larger databases.
+    fn save(&mut self) {
+        // Cramming leaves the schedules alone, so only edits are written
+        if self.database.is_cramming() && !self.database.is_dirty() {
+            return;
+        }
+        if let Err(err) = self.database.write_db() {
+            error(&format!("{}\n\n({})", tr(FAILED_DB_WRITE_MESSAGE), err));
+        }
+        self.autosave.flushed(now());
+    }
+
+    /// Counts an answer and writes the database once enough answers piled up
+    /// or enough time passed since the last write. The rest is written at the
+    /// end of the session or when the database is closed.
+    fn save_answer(&mut self) {
+        // Cramming leaves the schedules alone
+        if self.database.is_cramming() {
+            return;
+        }
+        if self.autosave.record(now()) {
+            self.save();
+        }
+    }
+
+    /// Grades the typed answer and shows the result.
+    /// Returns true if the answer was already graded and the next word should
+    /// be drawn.
+    fn submit_answer(&mut self) -> bool {
+        if self.graded.is_some() {
+            return true;
+        }
+        if self.current_entry.is_none() || self.near_miss.is_some() {
+            return false;
+        }
+
+        // Each deck may ignore other differences
+        let deck    = self.current_entry.as_ref()
+            .map_or("", |(entry, _)| entry.deck.as_str());
+        let options = self.grading.for_deck(&self.normalizations, deck);
+        let grading = grading::grade_any(&self.answer, &self.answers,
+                                         &options);
+        match grading.is_near_miss() {
+            true  => self.near_miss = Some(grading),
+            false => self.apply_grading(grading),
+        }
+        false
+    }
+
+    /// Grades the current entry by the `grading` of the typed answer and
+    /// shows it
+    fn apply_grading(&mut self, grading: Grading) {
+        if self.grade(grading.correct.into()) {
+            self.graded         = Some(grading);
+            self.tr_word_hidden = false;
+            self.save_answer();
+        }
+    }
+
+    /// Grades the near miss of the typed answer as correct if it `counts`,
+    /// as wrong otherwise
+    fn judge_near_miss(&mut self, counts: bool) {
+        if let Some(grading) = self.near_miss.take() {
+            self.apply_grading(Grading { correct: counts, ..grading });
+        }
+    }
+
+    /// Grades the `choice`th candidate of the question and shows the right
+    /// one.
+    /// Returns true if a candidate was already picked and the next word
+    /// should be drawn.
+    fn pick(&mut self, choice: usize) -> bool {
+        if self.picked.is_some() {
+            return true;
+        }
+        let correct = match &self.question {
+            Some(question) => question.is_correct(choice),
+            None           => return false,
+        };
+        if self.grade(correct.into()) {
+            self.picked         = Some(choice);
+            self.tr_word_hidden = false;
+            self.save_answer();
+        }
+        false
+    }
+
+    /// Asks for a CSV/TSV file or a shared deck and imports its words into
+    /// the database.
+    /// The ladder of a shared deck is kept in `shared_ladder` for the app.
+    /// Returns true if any words were imported.
+    fn import(&mut self) -> bool {
+        let path = FileDialog::new()
+            .add_filter(tr(IMPORT_FILTER_NAME), &["csv", "tsv", "txt"])
+            .add_filter(tr(DECK_FILTER_NAME), &[DECK_EXTENSION])
+            .pick_file();
+        let path = match path {
+            Some(path) => path,
+            None       => return false,
+        };
+
+        let is_deck = path.extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(DECK_EXTENSION));
+        let imported = match is_deck {
+            true  => deck::import(&mut self.database, &path)
+                .map(|(header, summary)| (Some(header), summary)),
+            false => {
+                let options = ImportOptions::for_path(&path);
+                import::import(&mut self.database, &path, &options)
+                    .map(|summary| (None, summary))
+                    .map_err(Error::from)
+            },
+        };
+        match imported {
+            Ok((header, summary)) => {
+                MessageDialog::new()
+                    .set_level(MessageLevel::Info)
+                    .set_title(tr(IMPORT_WINDOW_TITLE))
+                    .set_description(&import_summary(header.as_ref(),
+                                                     &summary))
+                    .show();
+                self.shared_ladder = header.and_then(|header| {
+                    let deck = header.deck;
+                    header.ladder.map(|ladder| (deck, ladder))
+                });
+                summary.added != 0
+            },
+            Err(err) => {
+                error(&format!("{}\n\n({})", tr(FAILED_IMPORT_MESSAGE), err));
+                false
+            },
+        }
+    }
+
+    /// Asks for another copy of the database and merges it into this one.
+    /// Returns true if anything changed.
+    fn merge(&mut self) -> bool {
+        let path = match FileDialog::new().pick_file() {
+            Some(path) => path,
+            None       => return false,
+        };
+
+        // The other copy may be open somewhere else; it's only read anyway
+        let other = match Database::open_read_only(path) {
+            Ok(other) => other,
+            Err(err) => {
+                error(&format!("{}\n\n({})", tr(FAILED_MERGE_MESSAGE), err));
+                return false;
+            },
+        };
+        if !other.quarantined.is_empty() {
+            warning(&quarantined_lines(&other.quarantined));
+        }
+
+        let summary = self.database.merge(&other);
+        MessageDialog::new()
+            .set_level(MessageLevel::Info)
+            .set_title(tr(MERGE_WINDOW_TITLE))
+            .set_description(&format!("{}: {}\n{}: {}\n{}: {}",
+                tr(MERGE_ADDED), summary.added,
+                tr(MERGE_UPDATED), summary.updated,
+                tr(MERGE_UNCHANGED), summary.unchanged))
+            .show();
+        summary.added != 0 || summary.updated != 0
+    }
+
+    /// Asks for a wordlist of the language of the original words, tells
+    /// whether the columns of the database seem to be swapped by it and
+    /// offers to swap them either way.
+    /// Returns true if the columns were swapped.
+    fn swap_columns(&mut self) -> bool {
+        let path = FileDialog::new()
+            .add_filter(tr(WORDLIST_FILTER_NAME), &["txt"])
+            .pick_file();
+        let path = match path {
+            Some(path) => path,
+            None       => return false,
+        };
+        let wordlist = match import::read_wordlist(&path) {
+            Ok(wordlist) => wordlist,
+            Err(err) => {
+                error(&format!("{}\n\n({})", tr(FAILED_WORDLIST_MESSAGE),
+                               err));
+                return false;
+            },
+        };
+
+        let is_source_word = |word: &str| {
+            wordlist.contains(&grading::fold(word))
+        };
+        let message = match self.database.detect_swapped(is_source_word) {
+            true  => tr(SWAPPED_MESSAGE),
+            false => tr(NOT_SWAPPED_MESSAGE),
+        };
+        let swap = MessageDialog::new()
+            .set_level(MessageLevel::Info)
+            .set_title(tr(SWAP_WINDOW_TITLE))
+            .set_description(message)
+            .set_buttons(MessageButtons::YesNo)
+            .show();
+        if swap {
+            self.database.swap_all_columns();
+        }
+        swap
+    }
+
+    /// Syncs the database with its copy on the server and tells what the copy
+    /// changed.
+    /// Returns true if anything changed.
+    #[cfg(feature = "sync")]
+    fn sync(&mut self) -> bool {
+        let remote  = match &self.remote {
+            Some(remote) => remote,
+            None         => return false,
+        };
+        let summary = match sync_database(&mut self.database, remote) {
+            Some(summary) => summary,
+            None          => return false,
+        };
+        MessageDialog::new()
+            .set_level(MessageLevel::Info)
+            .set_title(tr(SYNC_WINDOW_TITLE))
+            .set_description(&format!("{}: {}\n{}: {}\n{}: {}",
+                tr(MERGE_ADDED), summary.added,
+                tr(MERGE_UPDATED), summary.updated,
+                tr(MERGE_UNCHANGED), summary.unchanged))
+            .show();
+        summary.added != 0 || summary.updated != 0
+    }
+
+    /// Asks where to save an Anki package and exports the database into it
+    #[cfg(feature = "anki")]
+    fn export_anki(&self) {
+        let path = FileDialog::new()
+            .add_filter(tr(EXPORT_FILTER_NAME), &["apkg"])
+            .save_file();
+        if let Some(path) = path {
+            if let Err(err) = anki::export(&self.database, &path) {
+                error(&format!("{}\n\n({})", tr(FAILED_EXPORT_MESSAGE), err));
+            }
+        }
+    }
+
+    /// Asks where to save the active deck (or all the words if no deck is
+    /// picked) and shares it there; see `deck`
+    fn share(&self) {
+        let path = FileDialog::new()
+            .add_filter(tr(DECK_FILTER_NAME), &[DECK_EXTENSION])
+            .save_file();
+        if let Some(path) = path {
+            let active = self.database.active_deck.as_deref();
+            if let Err(err) = deck::export(&self.database, active, &path) {
+                error(&format!("{}\n\n({})", tr(FAILED_EXPORT_MESSAGE), err));
+            }
+        }
+    }
+
+    /// Asks where to save the database and continues with the saved copy;
+    /// the format is picked by the extension (e.g. `.json` for JSON)
+    fn save_as(&mut self) {
+        if let Some(path) = FileDialog::new().save_file() {
+            if let Err(err) = self.database.save_as(path) {
+                error(&format!("{}\n\n({})", tr(FAILED_SAVE_AS_MESSAGE),
+                               err));
+            }
+        }
+    }
+
+    /// Reverts the last answer and shows its entry again
+    fn undo(&mut self) {
+        // The answers given while cramming can't be undone; there's nothing
+        // to undo
+        if self.database.is_cramming() {
+            return;
+        }
+        match self.database.undo_last_update() {
+            Ok(Some(entry)) => {
+                // The word may be graded again right away
+                self.session.undo();
+                self.guard.reset();
+                self.save();
+                self.show_entry(Some(entry));
+            },
+            Ok(None) => {},
+            Err(err) => {
+                error(&format!("{}\n\n({})", tr(FAILED_UNDO_MESSAGE), err));
+            },
+        }
+    }
+
+    /// Computes the statistics of the database and shows them
+    fn show_stats(&mut self) {
+        let history = match self.database.history.load() {
+            Ok(history) => history,
+            Err(err) => {
+                error(&format!("{}\n\n({})", tr(FAILED_STATS_MESSAGE), err));
+                return;
+            },
+        };
+
+        let stats = Stats::compute(&self.database, &history, now(),
+                                   STATS_DAYS);
+        self.stats = Some(StatsView::new(stats));
+    }
+
+    /// Asks where to save the review history and the entries and exports
+    /// them there, as CSV or JSON by the extension
+    fn export_history(&self) {
+        let path = FileDialog::new()
+            .add_filter(tr(EXPORT_CSV_FILTER_NAME), &["csv"])
+            .add_filter(tr(EXPORT_JSON_FILTER_NAME), &["json"])
+            .save_file();
+        if let Some(path) = path {
+            let format = HistoryFormat::for_path(&path);
+            if let Err(err) = history::export(&self.database, &path, format) {
+                error(&format!("{}\n\n({})", tr(FAILED_EXPORT_MESSAGE), err));
+            }
+        }
+    }
+
+    /// Returns the index of the `idx`th listed leech among the usable
+    /// entries, see `Database::leech_index`
+    fn leech_index(&mut self, idx: usize) -> Option<usize> {
+        let leech = self.leeches.as_ref()?.leeches.get(idx)?;
+        self.database.leech_index(&leech.word, &leech.tr_word)
+    }
+
+    /// Changes the current entry with `change` (given the database and the
+    /// index of the entry among the usable ones).
+    /// Returns false if there's no current entry or `change` failed.
+    fn change_current<F>(&mut self, change: F) -> bool
+            where F: FnOnce(&mut Database, usize) -> bool {
+        match &self.current_entry {
+            Some((_, index)) => change(&mut self.database, *index),
+            None             => false,
+        }
+    }
+
+    /// Returns the index of the `idx`th entry listed in the browser among the
+    /// usable entries, see `Database::entry_index`
+    fn browsed_index(&mut self, idx: usize) -> Option<usize> {
+        let entry = self.browser.as_ref()?.entries.get(idx)?;
+        self.database.entry_index(&entry.word, &entry.tr_word)
+    }
+
+    /// Changes the `idx`th entry listed in the browser with `change` (given
+    /// the database and the index of the entry among the usable ones).
+    /// If `change` returns true, the database is saved, the entries are
+    /// listed again and the next word is drawn, since the current one may
+    /// have been changed as well.
+    fn change_browsed<F>(&mut self, idx: usize, change: F)
+            where F: FnOnce(&mut Database, usize) -> bool {
+        let index = match self.browsed_index(idx) {
+            Some(index) => index,
+            None        => return,
+        };
+        if !change(&mut self.database, index) {
+            return;
+        }
+
+        self.save();
+        self.next_entry();
+        if let Some(browser) = &mut self.browser {
+            browser.refresh(&self.database);
+        }
+    }
+
+    /// Changes the entries selected in the browser with `change`, like
+    /// `change_browsed`; the database is saved once for all of them
+    fn change_selected<F>(&mut self, change: F)
+            where F: FnMut(&mut Database, usize) -> bool {
+        let keys = match &self.browser {
+            Some(browser) => browser.selected_keys(),
+            None          => return,
+        };
+        if self.database.change_entries(&keys, change) == 0 {
+            return;
+        }
+
+        self.save();
+        self.next_entry();
+        if let Some(browser) = &mut self.browser {
+            browser.refresh(&self.database);
+        }
+    }
+
+    /// Handles the messages of the editor.
+    /// Returns true if the database changed and the next word should be drawn.
+    fn update_editor(&mut self, message: Message) -> bool {
+        let editor = match &mut self.editor {
+            Some(editor) => editor,
+            None         => return false,
+        };
+
+        match message {
+            Message::EditorWordChanged(word)      => editor.word    = word,
+            Message::EditorTrWordChanged(tr_word) => editor.tr_word = tr_word,
+            Message::EditorNotesChanged(notes)    => editor.notes   = notes,
+            Message::EditorTagsChanged(tags)      => editor.tags    = tags,
+            Message::EditorCancelPressed          => self.editor    = None,
+            Message::EditorSavePressed => {
+                let saved = match editor.editing {
+                    Some(index) => self.database.edit_entry(
+                        index, &editor.word, &editor.tr_word, &editor.notes,
+                        &editor.tags),
+                    None => self.database.add_entry(
+                        &editor.word, &editor.tr_word, &editor.notes,
+                        &editor.tags),
+                };
+                if saved {
+                    self.editor = None;
+                    return true;
+                }
+            },
+            Message::EditorDeletePressed => {
+                if let Some(index) = editor.editing {
+                    self.database.remove_entry(index);
+                }
+                self.editor = None;
+                return true;
+            },
+            _ => {},
+        }
+
+        false
+    }
+
+    fn view(&mut self, config: &Config, focus: &mut Focus)
+            -> Element<'_, Message> {
+        if let Some(editor) = &mut self.editor {
+            return editor.view();
+        }
+        if let Some(stats) = &mut self.stats {
+            return stats.view();
+        }
+        if let Some(forecast) = &mut self.forecast {
+            return forecast.view();
+        }
+        if let Some(leeches) = &mut self.leeches {
+            return leeches.view();
+        }
+        if let Some(duplicates) = &mut self.duplicates {
+            return duplicates.view();
+        }
+        if let Some(backups) = &mut self.backups {
+            return backups.view();
+        }
+        if let Some(browser) = &mut self.browser {
+            return browser.view();
+        }
+
+        // Dynamically calculate the font sizes of the words; the words take
+        // the whole width, so that the longer ones wrap into more lines
+        let word_size    = self.display.word_size(&self.word);
+        let tr_word_size = self.display.tr_word_size(&self.tr_word);
+
+        // Create all the widgets and return.
+        // This is how we want the window to look:
+        // +---------------+
+        // |  --- --- ---  | -> the toolbar (direction, answer mode,
+        // |               |    listening, cram (if the database isn't
+        // |               |    read-only), add, import, merge, save as,
+        // |               |    share, stats, forecast, leeches,
+        // |               |    duplicates, backups (if the database isn't
+        // |               |    read-only), browse, settings, undo, edit,
+        // |               |    bury, suspend)
+        // |     -----     | -> the progress; the words left and due today
+        // |               |    and the time spent in the session
+        // |   --- -----   | -> the streak and the daily goal (if any)
+        // |     -----     | -> the card info (if it's toggled on with I)
+        // |    -------    | -> self.image (if the entry has one and isn't
+        // |               |    heard)
+        // |  -----------  | -> self.word_button (the word is hidden until
+        // |               |    revealed if it's heard)
+        // |  -----------  | -> self.tr_word (if it isn't hidden, otherwise
+        // |               |    masked by the used hints)
+        // |     -----     | -> self.hint_button (if the translation is
+        // |               |    hidden and there's no question)
+        // |     -----     | -> self.play_button (if the original word is
+        // |               |    shown and can be pronounced; replays it if
+        // |               |    it's heard)
+        // |  -----------  | -> self.notes (if there are any and the
+        // |               |    translation isn't hidden)
+        // |  -- -- -- --  | -> self.grade_buttons (again, hard, good, easy)
+        // +---------------+
+        // In the typing mode, the grading buttons are replaced by the answer
+        // input and the graded answer. In the multiple-choice mode, they're
+        // replaced by the candidates of the question, one under another:
+        // |  -----------  | -> self.choice_buttons (the right one and the
+        // |  -----------  |    wrong pick are marked once picked)
+        // |  -----------  |
+        // |  -----------  |
+        // The controls are created from the top down, so that Tab moves the
+        // focus through them in the order they're shown.
+        let direction_label = match self.database.direction() {
+            Direction::Forward => tr(FORWARD_LABEL),
+            Direction::Reverse => tr(REVERSE_LABEL),
+        };
+        let listen_label = match self.listening {
+            true  => tr(READ_LABEL),
+            false => tr(LISTEN_LABEL),
+        };
+        let cram_label = match self.database.is_cramming() {
+            true  => tr(STOP_CRAM_LABEL),
+            false => tr(CRAM_LABEL),
+        };
+        let mut toolbar = Row::new()
+            .spacing(10)
+            .push(focus.button(&mut self.direction_button, direction_label,
+                               Message::DirectionPressed))
+            .push(focus.button(&mut self.mode_button,
+                               tr(self.mode.next().label()),
+                               Message::AnswerModePressed))
+            .push(focus.button(&mut self.listen_button, listen_label,
+                               Message::ListeningPressed));
+        // A read-only database is crammed until it's closed
+        if !self.database.is_read_only() {
+            toolbar = toolbar.push(focus.button(&mut self.cram_button,
+                                                cram_label,
+                                                Message::CramPressed));
+        }
+        toolbar = toolbar
+            .push(focus.button(&mut self.add_button, tr(ADD_LABEL),
+                               Message::AddPressed))
+            .push(focus.button(&mut self.import_button, tr(IMPORT_LABEL),
+                               Message::ImportPressed))
+            .push(focus.button(&mut self.merge_button, tr(MERGE_LABEL),
+                               Message::MergePressed))
+            .push(focus.button(&mut self.save_as_button, tr(SAVE_AS_LABEL),
+                               Message::SaveAsPressed))
+            .push(focus.button(&mut self.share_button, tr(SHARE_LABEL),
+                               Message::SharePressed));
+        #[cfg(feature = "anki")]
+        {
+            toolbar = toolbar.push(focus.button(&mut self.export_button,
+                                                tr(EXPORT_LABEL),
+                                                Message::ExportPressed));
+        }
+        #[cfg(feature = "sync")]
+        if self.remote.is_some() {
+            toolbar = toolbar.push(focus.button(&mut self.sync_button,
+                                                tr(SYNC_LABEL),
+                                                Message::SyncPressed));
+        }
+        toolbar = toolbar
+            .push(focus.button(&mut self.stats_button, tr(STATS_LABEL),
+                               Message::StatsPressed))
+            .push(focus.button(&mut self.forecast_button, tr(FORECAST_LABEL),
+                               Message::ForecastPressed))
+            .push(focus.button(&mut self.leeches_button, tr(LEECHES_LABEL),
+                               Message::LeechesPressed))
+            .push(focus.button(&mut self.duplicate_button,
+                               tr(DUPLICATES_LABEL),
+                               Message::DuplicatesPressed))
+            .push(focus.button(&mut self.swap_button, tr(SWAP_LABEL),
+                               Message::SwapColumnsPressed));
+        if !self.database.is_read_only() {
+            toolbar = toolbar.push(focus.button(&mut self.backups_button,
+                                                tr(BACKUPS_LABEL),
+                                                Message::BackupsPressed));
+        }
+        toolbar = toolbar
+            .push(focus.button(&mut self.browse_button, tr(BROWSE_LABEL),
+                               Message::BrowsePressed))
+            .push(focus.button(&mut self.settings_button, tr(SETTINGS_LABEL),
+                               Message::SettingsPressed));
+        if self.database.can_undo() && !self.database.is_cramming() {
+            toolbar = toolbar.push(focus.button(&mut self.undo_button,
+                                                tr(UNDO_LABEL),
+                                                Message::UndoPressed));
+        }
+        // A graded entry has already been moved out of the usable entries
+        if self.current_entry.is_some() && self.graded.is_none()
+                && self.picked.is_none() {
+            let starred    = self.current_entry.as_ref()
+                .is_some_and(|(entry, _)| entry.starred);
+            let star_label = match starred {
+                true  => tr(UNSTAR_LABEL),
+                false => tr(STAR_LABEL),
+            };
+            toolbar = toolbar
+                .push(focus.button(&mut self.edit_button, tr(EDIT_LABEL),
+                                   Message::EditPressed))
+                .push(focus.button(&mut self.bury_button, tr(BURY_LABEL),
+                                   Message::BuryPressed))
+                .push(focus.button(&mut self.suspend_button,
+                                   tr(SUSPEND_LABEL),
+                                   Message::SuspendPressed))
+                .push(focus.button(&mut self.star_button, star_label,
+                                   Message::StarPressed));
+        }
+
+        let mut col = Column::new()
+            .align_items(Align::Center)
+            .height(Length::Fill)
+            .padding(10)
+            .spacing(30)
+            .push(toolbar);
+
+        // The toolbar stays usable after the session, so that words can be
+        // added to an empty database
+        if let Some(summary) = &self.summary {
+            let hours = config.ahead_hours;
+            let ahead = match hours {
+                0 => 0,
+                _ => self.database.due_ahead_count(ahead_until(hours)),
+            };
+            return col.push(summary_view(summary, ahead, hours,
+                                         &mut self.continue_button,
+                                         &mut self.ahead_button, focus))
+                .into();
+        }
+
+        // The answered words count into the words due today, so that the
+        // total doesn't shrink while reviewing. Cramming serves every word,
+        // due or not.
+        if !self.database.is_cramming() {
+            let now     = now();
+            let left    = self.database.usable_count();
+            let total   = self.session.answers.len()
+                + self.database.due_today_count(now);
+            let elapsed = now.saturating_sub(self.session.started) / 60;
+            col = col.push(Text::new(format!("{} / {} {}, {} min", left,
+                                             total, tr(PROGRESS_DUE),
+                                             elapsed))
+                .size(20));
+        }
+
+        // The goal counts every answer of the day, not just this session's
+        let now            = now();
+        let (new, reviews) = self.database.meta.done_today(now);
+        let streak         = self.database.meta.current_streak(now);
+        let mut motivation = Row::new()
+            .align_items(Align::Center)
+            .spacing(20)
+            .push(Text::new(format!("{}: {}", tr(PROGRESS_STREAK), streak))
+                .size(20));
+        if config.daily_goal != 0 {
+            let (done, goal) = (new + reviews, config.daily_goal);
+            motivation = motivation
+                .push(Text::new(format!("{}: {} / {}", tr(PROGRESS_GOAL),
+                                        done, goal))
+                    .size(20))
+                .push(ProgressBar::new(0.0..=goal as f32, done as f32)
+                    .width(Length::Units(200))
+                    .height(Length::Units(10))
+                    .style(style::ProgressBar));
+        }
+        col = col.push(motivation);
+
+        if let (true, Some((entry, _))) = (self.info, &self.current_entry) {
+            let interval = self.database.interval(entry);
+            col = col.push(card_info(entry, interval, now));
+        }
+
+        // The image is a part of the prompt, so it's shown right away, unless
+        // the word is to be recognized by its sound alone
+        let hidden = self.heard && self.tr_word_hidden;
+        if let (Some(image), false) = (&self.image, hidden) {
+            col = col.push(Image::new(image)
+                .height(Length::Units(IMAGE_HEIGHT)));
+        }
+
+        // If a word is empty, don't show its widget.
+        // Revealing a cloze sentence fills in its blanks and a heard word is
+        // only revealed with its translation.
+        let text = Color::from(palette().text);
+        if !self.word.is_empty() {
+            let shown_word = match &self.cloze {
+                Some(cloze) if !self.tr_word_hidden => cloze.revealed(),
+                _                                   => self.word.clone(),
+            };
+            let side = self.display.side(&self.word);
+            let word = Text::new(shown_word)
+                .size(word_size as u16)
+                .width(Length::Fill)
+                .color(if hidden {Color::TRANSPARENT} else {text})
+                .vertical_alignment(VerticalAlignment::Center)
+                .horizontal_alignment(alignment(side));
+            let word_button = Button::new(&mut self.word_button, word)
+                .width(Length::Fill)
+                .height(Length::Fill);
+            col = col.push(focus.control(word_button, Message::WordPressed,
+                                         style::Button::Invisible));
+        }
+        let tr_side = self.display.side(&self.tr_word);
+        if !self.tr_word.is_empty() && !self.tr_word_hidden {
+            col = col.push(Text::new(&self.tr_word)
+                .size(tr_word_size as u16)
+                .width(Length::Fill)
+                .color(text)
+                .vertical_alignment(VerticalAlignment::Center)
+                .horizontal_alignment(alignment(tr_side)));
+        }
+        // The hints reveal the hidden translation bit by bit. A hint would
+        // only tell the right candidate apart.
+        if !self.tr_word.is_empty() && self.tr_word_hidden
+                && self.question.is_none() {
+            if self.hints != 0 {
+                col = col.push(Text::new(hint::mask(&self.tr_word,
+                                                    self.hints))
+                    .size(tr_word_size as u16)
+                    .width(Length::Fill)
+                    .vertical_alignment(VerticalAlignment::Center)
+                    .horizontal_alignment(alignment(tr_side)));
+            }
+            col = col.push(focus.button(&mut self.hint_button, tr(HINT_LABEL),
+                                        Message::HintPressed));
+        }
+
+        // The original word is the translation in reverse and a cloze
+        // sentence is only whole when revealed, so these can only be played
+        // once the translation is revealed
+        let direction      = self.card.as_ref().map(|card| card.direction);
+        let original_shown = match direction {
+            _ if self.cloze.is_some()       => !self.tr_word_hidden,
+            Some(Direction::Forward) | None => true,
+            Some(Direction::Reverse)        => !self.tr_word_hidden,
+        };
+        let playable = self.current_entry.as_ref().is_some_and(|(entry, _)| {
+            original_shown && media::can_pronounce(entry)
+        });
+        if playable {
+            let label = match self.heard {
+                true  => tr(REPLAY_LABEL),
+                false => tr(PLAY_LABEL),
+            };
+            col = col.push(focus.button(&mut self.play_button, label,
+                                        Message::PlayPressed));
+        }
+        if !self.notes.is_empty() && !self.tr_word_hidden {
+            col = col.push(Scrollable::new(&mut self.notes_scroll)
+                .max_height(NOTES_MAX_HEIGHT)
+                .push(Text::new(&self.notes).size(NOTES_SIZE)));
+        }
+
+        // A question that couldn't be dealt is graded with the buttons
+        let answer_box: Element<Message> = match (self.mode, &self.question) {
+            (AnswerMode::Typing, _) => {
+                let input = TextInput::new(&mut self.answer_input,
+                                           tr(ANSWER_PLACEHOLDER),
+                                           &self.answer,
+                                           Message::AnswerChanged)
+                    .on_submit(Message::AnswerSubmitted)
+                    .padding(10)
+                    .size(30)
+                    .style(style::TextInput);
+                focus.input(Message::AnswerSubmitted);
+
+                let mut col = Column::new()
+                    .align_items(Align::Center)
+                    .height(Length::Fill)
+                    .padding(10)
+                    .spacing(20)
+                    .push(input);
+                if let Some(grading) = &self.graded {
+                    col = col.push(graded_answer(&self.answer, grading));
+                }
+                if let Some(grading) = &self.near_miss {
+                    let [count, dont_count] = &mut self.count_buttons;
+                    let buttons = Row::new()
+                        .spacing(20)
+                        .push(focus.button(count, tr(COUNT_LABEL),
+                                           Message::NearMissJudged(true)))
+                        .push(focus.button(dont_count, tr(DONT_COUNT_LABEL),
+                                           Message::NearMissJudged(false)));
+                    col = col.push(graded_answer(&self.answer, grading))
+                        .push(Text::new(tr(NEAR_MISS_MESSAGE)))
+                        .push(buttons);
+                }
+                col.into()
+            },
+            (AnswerMode::Choice, Some(question)) => {
+                let picked  = self.picked;
+                let buttons = question.choices.iter()
+                    .zip(self.choice_buttons.iter_mut())
+                    .enumerate();
+                let col = Column::new()
+                    .align_items(Align::Center)
+                    .height(Length::Fill)
+                    .padding(10)
+                    .spacing(10);
+
+                buttons.fold(col, |col, (idx, (choice, state))| {
+                    let style = match picked {
+                        Some(_) if question.is_correct(idx) => {
+                            style::Button::Correct
+                        },
+                        Some(picked) if picked == idx => {
+                            style::Button::Incorrect
+                        },
+                        _ => style::Button::Normal,
+                    };
+                    let label = Text::new(format!("{}: {}", idx + 1, choice))
+                        .horizontal_alignment(HorizontalAlignment::Center);
+                    let button = Button::new(state, label)
+                        .min_height(30)
+                        .width(Length::Fill);
+                    col.push(focus.control(button, Message::ChoicePicked(idx),
+                                           style))
+                }).into()
+            },
+            _ => {
+                let buttons = Grade::ALL.iter()
+                    .zip(self.grade_buttons.iter_mut());
+                let row = Row::new()
+                    .align_items(Align::Center)
+                    .height(Length::Fill)
+                    .padding(10)
+                    .spacing(20);
+
+                let labels = self.grade_labels;
+                let icons  = self.grade_icons;
+                buttons.fold(row, |row, (&grade, state)| {
+                    let label = Text::new(grade_label(grade, labels, icons))
+                        .horizontal_alignment(HorizontalAlignment::Center);
+                    let style = match grade {
+                        Grade::Again => style::Button::Incorrect,
+                        _            => style::Button::Correct,
+                    };
+                    let button = Button::new(state, label)
+                        .min_width(50)
+                        .min_height(30)
+                        .width(Length::Fill);
+                    row.push(focus.control(button, Message::GradePressed(grade),
+                                           style))
+                }).into()
+            },
+        };
+
+        col.push(answer_box).into()
+    }
+}
+
+/// Returns the message telling what an import did; with the name and the
+/// language of the deck if it's a shared one
+fn import_summary(header: Option<&DeckHeader>, summary: &ImportSummary)
+        -> String {
+    let mut message = String::new();
+    if let Some(header) = header {
+        let language = header.locale.map_or("-", |locale| locale.name());
+        message.push_str(&format!("{}: {}\n{}: {}\n\n",
+                                  tr(IMPORT_DECK), header.deck,
+                                  tr(IMPORT_LANGUAGE), language));
+    }
+    message.push_str(&format!("{}: {}\n{}: {}\n{}: {}",
+                              tr(IMPORT_ADDED), summary.added,
+                              tr(IMPORT_DUPLICATES), summary.duplicates,
+                              tr(IMPORT_INVALID), summary.invalid));
+    message
+}
+
+/// Shows the summary of a finished session with a button to start a new one.
+/// If `ahead` words become due in the next `hours` hours, they can be studied
+/// ahead with another button. The buttons are recorded in `focus`.
+fn summary_view<'a>(summary: &Summary, ahead: usize, hours: u64,
+                    continue_button: &'a mut button::State,
+                    ahead_button: &'a mut button::State, focus: &mut Focus)
+        -> Column<'a, Message> {
+    let accuracy = match summary.accuracy {
+        Some(accuracy) => format!("{:.0} %", accuracy * 100.),
+        None           => "-".to_string(),
+    };
+    let duration = format!("{} min {} s",
+                           summary.duration / 60, summary.duration % 60);
+    let lines = [
+        (tr(SUMMARY_REVIEWED), summary.reviewed.to_string()),
+        (tr(SUMMARY_ACCURACY), accuracy),
+        (tr(SUMMARY_DURATION), duration),
+    ];
+
+    let mut col = Column::new()
+        .align_items(Align::Center)
+        .spacing(20)
+        .push(Text::new(tr(SUMMARY_TITLE)).size(50));
+    for (label, value) in lines.iter() {
+        col = col.push(Text::new(format!("{}: {}", label, value)).size(30));
+    }
+
+    col = col.push(focus.button(continue_button, tr(SUMMARY_CONTINUE_LABEL),
+                                Message::ContinuePressed)
+        .padding(10));
+    if ahead != 0 {
+        col = col
+            .push(Text::new(format!("{} ({} h): {}", tr(SUMMARY_AHEAD),
+                                    hours, ahead))
+                .size(30))
+            .push(focus.button(ahead_button, tr(SUMMARY_AHEAD_LABEL),
+                               Message::StudyAheadPressed(hours))
+                .padding(10));
+    }
+    col
+}
+
+/// Shows when `entry` was added, first answered and last reviewed, with its
+/// current `interval` (in days) and when it's due at `now`
+fn card_info<'a>(entry: &Entry, interval: u64, now: u64)
+        -> Column<'a, Message> {
+    let lines = [
+        (tr(CARD_INFO_DECK), entry.deck.clone()),
+        (tr(BROWSER_ADDED), date_label(entry.added)),
+        (tr(CARD_INFO_INTRODUCED), date_label(entry.introduced)),
+        (tr(BROWSER_REVIEWED), date_label(entry.reviewed)),
+        (tr(BROWSER_INTERVAL), interval.to_string()),
+        (tr(BROWSER_DUE), due_label(entry, now)),
+        (tr(CARD_INFO_LAPSES), entry.lapses.to_string()),
+    ];
+
+    let mut col = Column::new()
+        .align_items(Align::Center)
+        .spacing(5);
+    for (label, value) in lines.iter().filter(|(_, value)| !value.is_empty()) {
+        col = col.push(Text::new(format!("{}: {}", label, value)).size(20));
+    }
+    col
+}
+
+/// Returns the label of the button of `grade`; one of `labels`, after the
+/// icon of the grade if `icons` is true
+fn grade_label(grade: Grade, labels: GradeLabels, icons: bool) -> String {
+    let label = tr(labels.label(grade));
+    match icons {
+        true  => format!("{} {}", grade.icon(), label),
+        false => label.to_string(),
+    }
+}
+
+/// Asks the user whether to replace the open database by a snapshot
+fn confirm_restore() -> bool {
+    MessageDialog::new()
+        .set_level(MessageLevel::Warning)
+        .set_title(tr(RESTORE_WINDOW_TITLE))
+        .set_description(tr(RESTORE_MESSAGE))
+        .set_buttons(MessageButtons::YesNo)
+        .show()
+}
+
+/// Asks the user whether to move a word to the longest interval
+fn confirm_longest() -> bool {
+    MessageDialog::new()
+        .set_level(MessageLevel::Warning)
+        .set_title(tr(CONFIRM_LONGEST_WINDOW_TITLE))
+        .set_description(tr(CONFIRM_LONGEST_MESSAGE))
+        .set_buttons(MessageButtons::YesNo)
+        .show()
+}
+
+/// Shows the typed answer with the mistyped characters in red
+fn graded_answer<'a>(answer: &str, grading: &Grading) -> Row<'a, Message> {
+    let wrong: Vec<usize> = grading.diff.iter()
+        .filter_map(|op| match op {
+            DiffOp::Substitute { pos, .. } => Some(*pos),
+            DiffOp::Delete { pos, .. }     => Some(*pos),
+            DiffOp::Insert { .. }          => None,
+        })
+        .collect();
+
+    let palette = palette();
+    let mut row = Row::new();
+    for (pos, ch) in answer.trim().chars().enumerate() {
+        let color = match wrong.contains(&pos) {
+            true  => palette.incorrect,
+            false => palette.correct,
+        };
+        row = row.push(Text::new(ch.to_string()).size(30).color(color));
+    }
+    row
+}
+
+/// A subscription that produces a message every `interval`.
+/// iced only has timers with an async runtime, so this one is timed by
+/// a thread of its own.
+struct Ticks {
+    interval: Duration,
+}
+
+impl<H: Hasher, I> Recipe<H, I> for Ticks {
+    type Output = ();
+
+    fn hash(&self, state: &mut H) {
+        std::any::TypeId::of::<Self>().hash(state);
+        self.interval.hash(state);
+    }
+
+    fn stream(self: Box<Self>, _input: BoxStream<'static, I>)
+            -> BoxStream<'static, ()> {
+        let (sender, receiver) = mpsc::unbounded();
+        let interval           = self.interval;
+
+        // The thread ends with the first tick after the subscription did
+        thread::spawn(move || {
+            loop {
+                thread::sleep(interval);
+                if sender.unbounded_send(()).is_err() {
+                    break;
+                }
+            }
+        });
+        receiver.boxed()
+    }
+}
+
+
+impl Application for App {
+    type Executor = executor::Default;
+    type Message  = Message;
+    /// The database to open read-only instead of the one opened last
+    type Flags    = Option<PathBuf>;
+
+    fn new(read_only: Option<PathBuf>) -> (Self, Command<Message>) {
+        // A broken config shouldn't keep the user from reviewing
+        let config = Config::load().unwrap_or_else(|err| {
+            error(&format!("{}\n\n({})", tr(FAILED_CONFIG_READ_MESSAGE), err));
+            Config::default()
+        });
+        set_language(config.language);
+        set_theme(config.theme);
+
+        let mut app = Self {
+            screen:    Screen::Start(Start::new(&config)),
+            config,
+            settings:  None,
+            exiting:   false,
+            focus:     Focus::default(),
+            focused:   true,
+            remind_at: None,
+            #[cfg(feature = "tray")]
+            tray:      None,
+            #[cfg(feature = "tray")]
+            hidden:    false,
+        };
+        #[cfg(feature = "tray")]
+        app.toggle_tray(app.config.tray);
+        match read_only {
+            Some(path) => {
+                let db = Database::open_read_only(path);
+                if let Some(db) = open_database(db) {
+                    app.opened(db);
+                }
+            },
+            None => app.reopen(),
+        }
+        (app, Command::none())
+    }
+
+    fn title(&self) -> String {
+        let read_only = self.database().is_some_and(Database::is_read_only);
+        let title = tr(ROOT_WINDOW_TITLE);
+        match read_only {
+            true  => format!("{} {}", title, tr(READ_ONLY_TITLE)),
+            false => String::from(title),
+        }
+    }
+
+    fn update(&mut self, message: Message, _clipboard: &mut Clipboard)
+            -> Command<Message> {
+        // Enter and Space press the focused control rather than being the
+        // shortcuts of the screen
+        let message = match message {
+            Message::KeyPressed(KeyCode::Enter)
+                    | Message::KeyPressed(KeyCode::Space) => {
+                self.focus.pressed().unwrap_or(message)
+            },
+            message => message,
+        };
+
+        let screen = std::mem::discriminant(&self.screen);
+        match (&mut self.screen, message) {
+            (_, Message::SettingsPressed) => {
+                self.settings = Some(SettingsView::new(&self.config,
+                                                       self.database()));
+            },
+            (_, Message::AlgorithmPicked(algorithm)) => {
+                self.pick_algorithm(algorithm);
+            },
+            (_, Message::OptimizePressed) => self.optimize(),
+            (_, Message::LadderChanged(idx, text)) => {
+                self.change_ladder(idx, text);
+            },
+            (_, Message::TwoWayToggled(deck, two_way)) => {
+                self.toggle_two_way(deck, two_way);
+            },
+            (_, Message::NormalizationToggled(deck, step, on)) => {
+                self.toggle_normalization(deck, step, on);
+            },
+            (_, Message::GoalChanged(text)) => self.change_goal(text),
+            (_, Message::AheadChanged(text)) => self.change_ahead(text),
+            (_, Message::ScalePicked(scale)) => {
+                self.config.ui_scale = scale;
+                self.save_config();
+            },
+            (_, Message::MinFontChanged(text)) => {
+                self.change_font_sizes(Some(text), None);
+            },
+            (_, Message::MaxFontChanged(text)) => {
+                self.change_font_sizes(None, Some(text));
+            },
+            (_, Message::HintPenaltyToggled(penalty)) => {
+                self.toggle_hint_penalty(penalty);
+            },
+            (_, Message::GradeLabelsPicked(labels)) => {
+                self.change_buttons(|config| config.grade_labels = labels);
+            },
+            (_, Message::GradeIconsToggled(icons)) => {
+                self.change_buttons(|config| config.grade_icons = icons);
+            },
+            (_, Message::ConfirmLongestToggled(confirm)) => {
+                self.change_buttons(|config| config.confirm_longest = confirm);
+            },
+            (_, Message::RemindersToggled(reminders)) => {
+                self.config.reminders = reminders;
+                self.save_config();
+            },
+            (_, Message::FocusChanged(focused)) => self.change_focus(focused),
+            (_, Message::FocusMoved(back)) => {
+                self.focus_on(self.focus.next(back));
+            },
+            (_, Message::ReminderTick) => self.remind(),
+            (_, Message::SettingsClosePressed) => self.settings = None,
+            // The window only hides if there's a tray to bring it back from
+            #[cfg(feature = "tray")]
+            (_, Message::CloseRequested)
+                    if self.tray.as_ref().is_some_and(tray::Tray::is_shown) => {
+                self.hide();
+            },
+            (_, Message::CloseRequested) => self.close(),
+            #[cfg(feature = "tray")]
+            (_, Message::TrayToggled(tray)) => {
+                self.config.tray = tray;
+                self.save_config();
+                self.toggle_tray(tray);
+            },
+            #[cfg(feature = "tray")]
+            (_, Message::Tray(action)) => self.tray_action(action),
+            (_, Message::LanguagePicked(language)) => {
+                self.pick_language(language);
+            },
+            (_, Message::ThemePicked(theme)) => {
+                set_theme(theme);
+                self.config.theme = theme;
+                self.save_config();
+            },
+            // Escape closes the settings, then leaves the focused control
+            // for the shortcuts and then closes what's shown over the words
+            (_, Message::KeyPressed(KeyCode::Escape))
+                    if self.settings.is_some() => self.settings = None,
+            (_, Message::KeyPressed(KeyCode::Escape))
+                    if self.focus.pressed().is_some() => self.focus_on(None),
+            // The shortcuts of the screen don't work in the settings
+            (_, Message::KeyPressed(_)) if self.settings.is_some() => {},
+            (Screen::Start(_), Message::OpenPressed) => {
+                // Ask for a database file and attempt to open it.
+                // If the user cancels the dialog, stay on the start screen.
+                if let Some(path) = FileDialog::new().pick_file() {
+                    if let Some(db) = open_database(Database::open(path)) {
+                        self.opened(db);
+                    }
+                }
+            },
+            (Screen::Start(_), Message::OpenReadOnlyPressed) => {
+                if let Some(path) = FileDialog::new().pick_file() {
+                    let db = Database::open_read_only(path);
+                    if let Some(db) = open_database(db) {
+                        self.opened(db);
+                    }
+                }
+            },
+            (Screen::Start(_), Message::RecentPressed(idx)) => {
+                self.open_recent(idx);
+            },
+            (Screen::Start(_), Message::CreatePressed) => {
+                if let Some(path) = FileDialog::new().save_file() {
+                    if let Some(db) = open_database(Database::create(path)) {
+                        self.opened(db);
+                    }
+                }
+            },
+            (Screen::Start(_), Message::QuitPressed) => exit(0),
+            (Screen::Decks(_), Message::DeckPicked(deck)) => {
+                self.study_picked(|decks, config| decks.pick(deck, config));
+            },
+            (Screen::Decks(_), Message::TagPicked(tag)) => {
+                self.study_picked(|decks, config| decks.pick_tag(tag, config));
+            },
+            (Screen::Decks(_), Message::StarredPicked) => {
+                self.study_picked(|decks, config| decks.pick_starred(config));
+            },
+            (Screen::Decks(decks), Message::DeckOptionsPressed(deck)) => {
+                decks.show_options(deck);
+            },
+            (Screen::Decks(decks), Message::KeyPressed(KeyCode::Escape)) => {
+                decks.update_options(Message::DeckOptionsClosePressed);
+            },
+            (Screen::Decks(decks), message) => decks.update_options(message),
+            (Screen::Study(study), Message::BackupRestorePressed(idx)) => {
+                let snapshot = study.backups.as_ref()
+                    .and_then(|backups| backups.snapshots.get(idx))
+                    .map(|snapshot| snapshot.path.clone());
+                if let Some(snapshot) = snapshot {
+                    if confirm_restore() {
+                        self.restore(snapshot);
+                    }
+                }
+            },
+            (Screen::Study(study), message) => {
+                study.update(message);
+
+                // The typed answer of a new word is focused right away
+                if study.is_answer_focused() {
+                    self.focus.focused = Some(Message::AnswerSubmitted);
+                }
+
+                // An imported deck keeps its ladder, unless the deck has one
+                // of its own already
+                let config = &mut self.config;
+                let ladder = study.shared_ladder.take()
+                    .filter(|(deck, ladder)| {
+                        !config.deck_ladders.contains_key(deck)
+                            && *ladder != config.ladder
+                    });
+                let laddered = ladder.is_some();
+                if let Some((deck, ladder)) = ladder {
+                    config.deck_ladders.insert(deck, ladder);
+                    study.database.set_ladders(&config.ladder,
+                                               &config.deck_ladders);
+                }
+
+                // A database saved under another name is opened as such the
+                // next time
+                let db      = &study.database;
+                let renamed = !db.is_read_only()
+                    && self.config.recent_databases.first() != Some(&db.path);
+                let renamed = renamed.then(|| db.path.clone());
+
+                // Remember the review direction for the next time
+                let direction = study.database.direction();
+                if direction != self.config.direction {
+                    self.config.direction = direction;
+                    self.save_config();
+                }
+                if let Some(path) = renamed {
+                    self.config.remember_database(&path);
+                    self.save_config();
+                }
+                if laddered {
+                    self.save_config();
+                }
+            },
+            _ => {},
+        }
+
+        // Another screen starts without a focus
+        if std::mem::discriminant(&self.screen) != screen {
+            self.focus = Focus::default();
+        }
+        #[cfg(feature = "tray")]
+        self.refresh_tray();
+        Command::none()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        // Keys handled by a widget (e.g. typed into a text input) aren't
+        // shortcuts, but Tab moves the focus out of a text input as well
+        let events = iced_native::subscription::events_with(|event, status| {
+            match (event, status) {
+                (Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code: KeyCode::Tab, modifiers,
+                }), _) => {
+                    Some(Message::FocusMoved(modifiers.shift))
+                },
+                (Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code, ..
+                }), event::Status::Ignored) => {
+                    Some(Message::KeyPressed(key_code))
+                },
+                (Event::Window(window::Event::CloseRequested), _) => {
+                    Some(Message::CloseRequested)
+                },
+                (Event::Window(window::Event::Focused), _) => {
+                    Some(Message::FocusChanged(true))
+                },
+                (Event::Window(window::Event::Unfocused), _) => {
+                    Some(Message::FocusChanged(false))
+                },
+                _ => None,
+            }
+        });
+
+        // The time is only watched while there's something to remind of
+        let mut subscriptions = vec![events];
+        if self.remind_at.is_some() {
+            let interval = Duration::from_secs(REMINDER_CHECK_SECONDS);
+            subscriptions.push(Subscription::from_recipe(Ticks { interval })
+                .map(|()| Message::ReminderTick));
+        }
+        #[cfg(feature = "tray")]
+        if let Some(tray) = &self.tray {
+            subscriptions.push(tray.actions().map(Message::Tray));
+        }
+        Subscription::batch(subscriptions)
+    }
+
+    #[cfg(feature = "tray")]
+    fn mode(&self) -> iced::window::Mode {
+        match self.hidden {
+            true  => iced::window::Mode::Hidden,
+            false => iced::window::Mode::Windowed,
+        }
+    }
+
+    fn background_color(&self) -> Color {
+        palette().background.into()
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.config.scale_factor()
+    }
+
+    fn should_exit(&self) -> bool {
+        self.exiting
+    }
+
+    fn view(&mut self) -> Element<'_, Message> {
+        // The controls are recorded anew, as they're shown
+        let focus = &mut self.focus;
+        focus.controls.clear();
+        let content = match (&mut self.settings, &mut self.screen) {
+            (Some(settings), _)          => settings.view(&self.config),
+            (None, Screen::Start(start)) => start.view(focus),
+            (None, Screen::Decks(decks)) => decks.view(&self.config, focus),
+            (None, Screen::Study(study)) => {
+                study.view(&self.config, focus)
+            },
+        };
+
+        // The container sets the colors of the theme for everything inside
+        Container::new(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(style::Container)
+            .into()
+    }
+}
+
+impl App {
+    /// Switches the user interface to `language` and remembers it in the
+    /// config
+    fn pick_language(&mut self, language: Language) {
+        set_language(language);
+        self.config.language = language;
+        self.save_config();
+    }
+
+    /// Moves the keyboard focus to the control pressed by `focused`, or to
+    /// the shortcuts if it's `None`; the input of the typed answer is
+    /// focused along with its control
+    fn focus_on(&mut self, focused: Option<Message>) {
+        let typing = focused == Some(Message::AnswerSubmitted);
+        self.focus.focused = focused;
+        if let Screen::Study(study) = &mut self.screen {
+            study.focus_answer(typing);
+        }
+    }
+
+    /// Returns the open database, if any
+    fn database(&self) -> Option<&Database> {
+        match &self.screen {
+            Screen::Start(_)     => None,
+            Screen::Decks(decks) => Some(&decks.database),
+            Screen::Study(study) => Some(&study.database),
+        }
+    }
+
+    /// Schedules the reviews with `algorithm` from now on and remembers it in
+    /// the config
+    fn pick_algorithm(&mut self, algorithm: Algorithm) {
+        self.config.scheduler = algorithm;
+        self.save_config();
+        let db = match &mut self.screen {
+            Screen::Start(_)     => return,
+            Screen::Decks(decks) => &mut decks.database,
+            Screen::Study(study) => &mut study.database,
+        };
+        session::set_scheduler(db, &self.config);
+    }
+
+    /// Fits the FSRS scheduler to the review history of the open database
+    /// and tells the user how it went
+    fn optimize(&mut self) {
+        let db = match &mut self.screen {
+            Screen::Start(_)     => return,
+            Screen::Decks(decks) => &mut decks.database,
+            Screen::Study(study) => &mut study.database,
+        };
+        let fit = match db.optimize_fsrs() {
+            Ok(fit) => fit,
+            Err(err) => {
+                error(&format!("{}\n\n({})", tr(FAILED_OPTIMIZE_MESSAGE),
+                               err));
+                return;
+            },
+        };
+        let description = match fit {
+            Some(fit) => {
+                session::set_scheduler(db, &self.config);
+                if let Err(err) = db.write_db() {
+                    error(&format!("{}\n\n({})", tr(FAILED_DB_WRITE_MESSAGE),
+                                   err));
+                }
+                format!("{}: {}", tr(OPTIMIZE_REVIEWS), fit.reviews)
+            },
+            None => tr(OPTIMIZE_TOO_FEW).to_string(),
+        };
+        MessageDialog::new()
+            .set_level(MessageLevel::Info)
+            .set_title(tr(OPTIMIZE_WINDOW_TITLE))
+            .set_description(&description)
             .show();
-}
+    }
 
-fn main() {
-    if App::run(Settings::default()).is_err() {
-        error(GENERIC_RUNTIME_ERR_MESSAGE);
-        exit(0);
-    };
-}
+    /// Changes the text of the `idx`th ladder in the settings.
+    /// If it's a valid ladder, it's remembered in the config and used by the
+    /// open database right away; a deck whose ladder is cleared uses the
+    /// default one again.
+    fn change_ladder(&mut self, idx: usize, text: String) {
+        let ladder = match self.settings.as_mut()
+                .and_then(|settings| settings.ladders.get_mut(idx)) {
+            Some(ladder) => ladder,
+            None         => return,
+        };
+        ladder.text = text;
 
+        let config = &mut self.config;
+        match (&ladder.deck, parse_ladder(&ladder.text)) {
+            (None, Some(steps))       => config.ladder = steps,
+            (Some(deck), Some(steps)) => {
+                config.deck_ladders.insert(deck.clone(), steps);
+            },
+            (Some(deck), None) if ladder.text.trim().is_empty() => {
+                config.deck_ladders.remove(deck);
+            },
+            _ => return,
+        }
 
-#[derive(Clone, Copy, Debug)]
-pub enum Message {
-    CorrectPressed,
-    IncorrectPressed,
-    WordPressed,
-}
+        self.save_config();
+        let db = match &mut self.screen {
+            Screen::Start(_)     => return,
+            Screen::Decks(decks) => &mut decks.database,
+            Screen::Study(study) => &mut study.database,
+        };
+        db.set_ladders(&self.config.ladder, &self.config.deck_ladders);
+    }
 
-struct App {
-    database:         Database,
-    current_entry:    Option<(Entry, usize)>,
-    word:             String,
-    tr_word:          String,
-    tr_word_hidden:   bool,
-    word_button:      button::State,
-    correct_button:   button::State,
-    incorrect_button: button::State,
-}
+    /// Reviews the entries of `deck` in both directions (if `two_way`) or in
+    /// the direction of the database, and remembers it in the config
+    fn toggle_two_way(&mut self, deck: String, two_way: bool) {
+        let config = &mut self.config;
+        match two_way {
+            true  => config.two_way_decks.insert(deck),
+            false => config.two_way_decks.remove(&deck),
+        };
 
-impl Sandbox for App {
-    type Message = Message;
+        self.save_config();
+        let db = match &mut self.screen {
+            Screen::Start(_)     => return,
+            Screen::Decks(decks) => &mut decks.database,
+            Screen::Study(study) => &mut study.database,
+        };
+        db.set_two_way_decks(&self.config.two_way_decks);
+    }
 
-    fn new() -> Self {
-        // Ask for a database file and attempt to open it
-        let db = FileDialog::new().pick_file().unwrap_or_else(|| {
-            exit(0)
-        });
-        let mut db = Database::open(db).unwrap_or_else(|err| {
-            error(&format!("{}\n\n({})", FAILED_DB_INIT_MESSAGE, err));
-            exit(0);
-        });
+    /// Changes the daily goal as it's typed in the settings.
+    /// The config only changes once the text is a number.
+    fn change_goal(&mut self, text: String) {
+        let settings = match &mut self.settings {
+            Some(settings) => settings,
+            None           => return,
+        };
+        settings.goal = text;
+        if let Ok(goal) = settings.goal.trim().parse() {
+            self.config.daily_goal = goal;
+            self.save_config();
+        }
+    }
 
-        // Initiate the words so that the ui can show them immediately
-        // without any further action
-        let entry       = db.random_entry();
-        let mut word    = String::new();
-        let mut tr_word = String::new();
+    /// Changes the hours of studying ahead as they're typed in the settings.
+    /// The config only changes once the text is a number.
+    fn change_ahead(&mut self, text: String) {
+        let settings = match &mut self.settings {
+            Some(settings) => settings,
+            None           => return,
+        };
+        settings.ahead = text;
+        if let Ok(hours) = settings.ahead.trim().parse() {
+            self.config.ahead_hours = hours;
+            self.save_config();
+        }
+    }
 
-        if let Some((entry, _)) = &entry {
-            word    = entry.word.clone();
-            tr_word = entry.tr_word.clone();
+    /// Changes the smallest and the largest font sizes of the words as they're
+    /// typed in the settings (the ones that are `None` stay as they are).
+    /// The config only changes once both are numbers and the smallest one
+    /// isn't larger than the largest one.
+    fn change_font_sizes(&mut self, min: Option<String>, max: Option<String>) {
+        let settings = match &mut self.settings {
+            Some(settings) => settings,
+            None           => return,
+        };
+        if let Some(min) = min {
+            settings.min_font = min;
+        }
+        if let Some(max) = max {
+            settings.max_font = max;
         }
 
-        Self {
-            word,
-            tr_word,
-            tr_word_hidden:   true,
-            database:         db,
-            current_entry:    entry,
-            correct_button:   button::State::default(),
-            incorrect_button: button::State::default(),
-            word_button:      button::State::default(),
+        let sizes = (settings.min_font.trim().parse(),
+                     settings.max_font.trim().parse());
+        if let (Ok(min), Ok(max)) = sizes {
+            if are_valid_font_sizes(min, max) {
+                self.config.min_font_size = min;
+                self.config.max_font_size = max;
+                self.save_config();
+                if let Screen::Study(study) = &mut self.screen {
+                    study.display = self.config.display();
+                }
+            }
         }
     }
 
-    fn title(&self) -> String {
-        String::from(ROOT_WINDOW_TITLE)
+    /// Turns the normalization `step` of the typed answers on or off in
+    /// `deck` (by default, if `None`) and remembers it in the config.
+    /// A deck normalized just like by default doesn't keep a normalization
+    /// of its own, so that it follows the default again.
+    fn toggle_normalization(&mut self, deck: Option<String>,
+                            step: normalize::Step, on: bool) {
+        let config = &mut self.config;
+        match deck {
+            None       => config.normalization.set(step, on),
+            Some(deck) => {
+                let mut normalization = config.normalization(&deck);
+                normalization.set(step, on);
+                if normalization == config.normalization {
+                    config.deck_normalizations.remove(&deck);
+                } else {
+                    config.deck_normalizations.insert(deck, normalization);
+                }
+            },
+        }
+        self.save_config();
+
+        if let Screen::Study(study) = &mut self.screen {
+            study.grading.normalization = self.config.normalization;
+            study.normalizations        = self.config.deck_normalizations
+                .clone();
+        }
     }
 
-    fn update(&mut self, message: Message) {
-        // If the user clicks on the untranslated word, the translated word
-        // is shown/hidden.
-        // If they click on either of the correct/incorrect buttons,
-        // the entry is timed out.
-        match message {
-            Message::WordPressed => {
-                self.tr_word_hidden = !self.tr_word_hidden;
-                return;
-            }
-            Message::CorrectPressed => {
-                if let Some((_, index)) = self.current_entry {
-                    self.database.update_timeout(index, true);
+    /// Turns the hint penalty on or off and remembers it in the config
+    fn toggle_hint_penalty(&mut self, penalty: bool) {
+        self.config.hint_penalty = penalty;
+        self.save_config();
+        if let Screen::Study(study) = &mut self.screen {
+            study.hint_penalty = penalty;
+        }
+    }
+
+    /// Changes how the buttons of the grades look and behave with `change` to
+    /// the config, and remembers it
+    fn change_buttons(&mut self, change: impl FnOnce(&mut Config)) {
+        change(&mut self.config);
+        self.save_config();
+        if let Screen::Study(study) = &mut self.screen {
+            study.grade_labels    = self.config.grade_labels;
+            study.grade_icons     = self.config.grade_icons;
+            study.confirm_longest = self.config.confirm_longest;
+        }
+    }
+
+    /// Remembers whether the window is focused.
+    /// Once the window goes to the background, the user is to be reminded
+    /// when the next review becomes due (if the reminders are on).
+    fn change_focus(&mut self, focused: bool) {
+        self.focused   = focused;
+        self.remind_at = match self.database() {
+            Some(db) if !focused && self.config.reminders => db.next_due(now()),
+            _ => None,
+        };
+    }
+
+    /// Notifies the user of the due words if it's time for the reminder.
+    /// There's only one reminder until the window is focused again.
+    fn remind(&mut self) {
+        let now = now();
+        if self.focused || self.remind_at.is_none_or(|at| now < at) {
+            return;
+        }
+        self.remind_at = None;
+
+        let due = self.database().map_or(0, |db| db.due_count(now));
+        if due == 0 {
+            return;
+        }
+        // A dialog would pop up in front of whatever the user is doing
+        let body = format!("{}: {}", tr(REMINDER_MESSAGE), due);
+        if let Err(err) = notify::notify(tr(ROOT_WINDOW_TITLE), &body) {
+            eprintln!("{}", err);
+        }
+    }
+
+    /// Puts the icon into the system tray or removes it
+    #[cfg(feature = "tray")]
+    fn toggle_tray(&mut self, tray: bool) {
+        self.tray   = match tray {
+            true  => Some(tray::Tray::new()),
+            false => None,
+        };
+        self.hidden = false;
+        self.refresh_tray();
+    }
+
+    /// Shows the number of the due words in the tooltip of the tray icon
+    #[cfg(feature = "tray")]
+    fn refresh_tray(&mut self) {
+        let due = self.database().map_or(0, |db| db.due_count(now()));
+        if let Some(tray) = &mut self.tray {
+            tray.set_due(due);
+        }
+    }
+
+    /// Hides the window to the tray, writing the answers first in case the
+    /// app is never brought back
+    #[cfg(feature = "tray")]
+    fn hide(&mut self) {
+        if let Screen::Study(study) = &mut self.screen {
+            study.save();
+        }
+        self.hidden = true;
+    }
+
+    /// Does what the user picked in the menu of the tray icon
+    #[cfg(feature = "tray")]
+    fn tray_action(&mut self, action: tray::Action) {
+        match action {
+            tray::Action::Show  => self.hidden = false,
+            tray::Action::Stats => {
+                self.hidden = false;
+                if let Screen::Study(study) = &mut self.screen {
+                    study.update(Message::StatsPressed);
                 }
-                self.tr_word_hidden = true;
             },
-            Message::IncorrectPressed => {
-                if let Some((_, index)) = self.current_entry {
-                    self.database.update_timeout(index, false);
-                }
-                self.tr_word_hidden = true;
+            tray::Action::Quit  => self.close(),
+        }
+    }
+
+    /// Writes the config, showing an error if that fails
+    fn save_config(&self) {
+        if let Err(err) = self.config.save() {
+            error(&format!("{}\n\n({})", tr(FAILED_CONFIG_WRITE_MESSAGE),
+                           err));
+        }
+    }
+
+    /// Reopens the database opened last.
+    /// If it can't be opened anymore, the start screen stays, so that the
+    /// user can pick another one.
+    fn reopen(&mut self) {
+        let path = match &self.config.last_database {
+            Some(path) => path.clone(),
+            None       => return,
+        };
+
+        if let Some(db) = open_database(Database::open(path)) {
+            self.opened(db);
+        }
+    }
+
+    /// Writes and closes the open database (unlocking it) and closes the
+    /// window
+    fn close(&mut self) {
+        let start = Screen::Start(Start::default());
+        let db    = match std::mem::replace(&mut self.screen, start) {
+            Screen::Start(_)     => None,
+            Screen::Decks(decks) => Some(decks.database),
+            Screen::Study(study) => Some(study.database),
+        };
+
+        // The answers of the session go to the server as well
+        #[cfg(feature = "sync")]
+        let db = db.map(|db| self.sync(db));
+        if let Some(Err(err)) = db.map(Database::flush_and_close) {
+            error(&format!("{}\n\n({})", tr(FAILED_DB_WRITE_MESSAGE), err));
+        }
+        self.exiting = true;
+    }
+
+    /// Closes the open database, replaces its file by the `snapshot` and
+    /// reopens it
+    fn restore(&mut self, snapshot: PathBuf) {
+        let start = Screen::Start(Start::default());
+        let db    = match std::mem::replace(&mut self.screen, start) {
+            Screen::Study(study) => study.database,
+            screen               => {
+                self.screen = screen;
+                return;
             },
+        };
+
+        // The unwritten changes go to the file first; they're kept in the
+        // snapshot of it that's taken before it's replaced
+        let path = db.path.clone();
+        if let Err(err) = db.flush_and_close() {
+            error(&format!("{}\n\n({})", tr(FAILED_DB_WRITE_MESSAGE), err));
+        }
+        if let Err(err) = backup::restore(&path, &snapshot) {
+            error(&format!("{}\n\n({})", tr(FAILED_RESTORE_MESSAGE), err));
         }
+        if let Some(db) = open_database(Database::open(path)) {
+            self.opened(db);
+        }
+    }
 
-        // Write the database to the file system.
-        // XXX: Doing this on every update is slow and can get extreme if done
-        //      with larger databases - this should be optimized somehow.
-        if let Err(err) = self.database.write_db() {
-            error(&format!("{}\n\n({})", FAILED_DB_WRITE_MESSAGE, err));
+    /// Syncs `db` with its copy on the server, if the databases are synced,
+    /// and returns it
+    #[cfg(feature = "sync")]
+    fn sync(&self, mut db: Database) -> Database {
+        if let Some(remote) = Remote::from_config(&self.config, &db.path) {
+            sync_database(&mut db, &remote);
         }
+        db
+    }
 
-        // Change the word to the new entry, or set them both to "" if there are
-        // no more usable entries.
-        self.current_entry = self.database.random_entry();
-        if let Some((entry, _)) = &self.current_entry {
-            self.word    = entry.word.clone();
-            self.tr_word = entry.tr_word.clone();
-        } else {
-            self.word    = "".to_string();
-            self.tr_word = "".to_string();
+    /// Switches to the review of a freshly opened database, letting the user
+    /// pick a deck first if there are any
+    fn opened(&mut self, db: Database) {
+        if !db.quarantined.is_empty() {
+            warning(&quarantined_lines(&db.quarantined));
+        }
+
+        // The changes made on the other devices come first
+        #[cfg(feature = "sync")]
+        let db = self.sync(db);
+
+        // A failed backup shouldn't keep the user from reviewing
+        if let Err(err) = db.backup(BACKUP_COUNT) {
+            error(&format!("{}\n\n({})", tr(FAILED_BACKUP_MESSAGE), err));
+        }
+
+        // A read-only database would be reopened writable the next time
+        if !db.is_read_only()
+                && self.config.recent_databases.first() != Some(&db.path) {
+            self.config.remember_database(&db.path);
+            self.save_config();
         }
+
+        self.screen = match db.is_pickable() {
+            false => Screen::Study(Box::new(Study::new(db, &self.config))),
+            true  => Screen::Decks(Box::new(Decks::new(db))),
+        };
     }
 
-    fn view(&mut self) -> Element<Message> {
-        // Dynamically calculate the font sizes of the words
-        let word_size    = 80. / (self.word.len() as f32 / 40.).max(1.);
-        let tr_word_size = 50. / (self.tr_word.len() as f32 / 50.).max(1.);
+    /// Opens the `idx`th of the databases opened last; the start screen stays
+    /// if it can't be opened
+    fn open_recent(&mut self, idx: usize) {
+        let path = self.config.recent_databases.get(idx).cloned();
+        let db   = path.and_then(|path| {
+            open_database(Database::open(path))
+        });
+        if let Some(db) = db {
+            self.opened(db);
+        }
+    }
 
-        // Create all the widgets and return.
-        // This is how we want the window to look:
-        // +---------------+
-        // |  -----------  | -> self.word_button
-        // |  -----------  | -> self.tr_word (if it isn't hidden)
-        // |  ----- -----  | -> self.correct_button | self.incorrect_button
-        // +---------------+
+    /// Leaves the decks screen for reviewing what `pick` picks of them
+    fn study_picked<F>(&mut self, pick: F)
+            where F: FnOnce(Decks, &Config) -> Study {
+        let start  = Screen::Start(Start::default());
+        let screen = std::mem::replace(&mut self.screen, start);
+        if let Screen::Decks(decks) = screen {
+            let study   = pick(*decks, &self.config);
+            self.screen = Screen::Study(Box::new(study));
+        }
+    }
+}
 
-        let correct_button = Button::new(&mut self.correct_button,
-                                         Text::new(""))
-            .on_press(Message::CorrectPressed)
-            .min_width(50)
-            .min_height(30)
-            .width(Length::Fill)
-            .style(style::Button::Correct);
+/// Shows an error dialog if the database failed to open.
+/// A database that is open somewhere else already can be opened read-only.
+fn open_database(db: wordpal::error::Result<Database>) -> Option<Database> {
+    match db {
+        Ok(db) => Some(db),
+        Err(Error::Locked(path)) => {
+            let message = format!("{}\n\n{}", tr(DB_LOCKED_MESSAGE),
+                                  paths::lock_path(&path).display());
+            let read_only = MessageDialog::new()
+                .set_level(MessageLevel::Warning)
+                .set_title(tr(DB_LOCKED_WINDOW_TITLE))
+                .set_description(&message)
+                .set_buttons(MessageButtons::YesNo)
+                .show();
+            match read_only {
+                true  => open_database(Database::open_read_only(path)),
+                false => None,
+            }
+        },
+        Err(Error::UnknownFormat) => {
+            error(tr(FAILED_DB_FORMAT_MESSAGE));
+            None
+        },
+        Err(err) => {
+            error(&format!("{}\n\n({})", tr(FAILED_DB_INIT_MESSAGE), err));
+            None
+        },
+    }
+}
 
-        let incorrect_button = Button::new(&mut self.incorrect_button,
-                                           Text::new(""))
-            .on_press(Message::IncorrectPressed)
-            .min_width(50)
-            .min_height(30)
-            .width(Length::Fill)
-            .style(style::Button::Incorrect);
+/// Syncs `db` with its copy on the server (see `wordpal::sync`). A failed
+/// sync is only reported; the database works without it.
+/// Returns what the copy changed, or `None` if the sync failed.
+#[cfg(feature = "sync")]
+fn sync_database(db: &mut Database, remote: &Remote)
+        -> Option<MergeSummary> {
+    match sync::sync(db, remote) {
+        Ok(summary) => Some(summary),
+        Err(err)    => {
+            error(&format!("{}\n\n({})", tr(FAILED_SYNC_MESSAGE), err));
+            None
+        },
+    }
+}
 
-        let word  = Text::new(&self.word)
-            .size(word_size as u16)
-            .vertical_alignment(VerticalAlignment::Center)
-            .horizontal_alignment(HorizontalAlignment::Center);
+/// The icon in the system tray; it shows the number of the due words and
+/// offers to show the window or the statistics, or to quit
+#[cfg(feature = "tray")]
+mod tray {
+    use std::any::TypeId;
+    use std::hash::{Hash, Hasher};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use iced::Subscription;
+    use iced::futures::channel::mpsc::{self, UnboundedReceiver,
+                                       UnboundedSender};
+    use iced::futures::stream::{self, BoxStream, StreamExt};
+    use iced_native::subscription::Recipe;
+    use wordpal::locale::*;
 
-        let word_button = Button::new(&mut self.word_button, word)
-            .on_press(Message::WordPressed)
-            .height(Length::Fill)
-            .style(style::Button::Invisible);
+    /// The freedesktop name of the icon
+    const ICON_NAME: &str = "accessories-dictionary";
 
-        let tr_word = Text::new(&self.tr_word)
-            .size(tr_word_size as u16)
-            .color(if self.tr_word_hidden {[0.,0.,0.,0.]} else {[0.,0.,0.,1.]})
-            .vertical_alignment(VerticalAlignment::Center)
-            .horizontal_alignment(HorizontalAlignment::Center);
+    /// What the user picked in the menu of the icon
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Action {
+        /// Show the window; also a click on the icon
+        Show,
 
-        let horizontal_box = Row::new()
-            .align_items(Align::Center)
-            .height(Length::Fill)
-            .padding(10)
-            .spacing(50)
-            .push(correct_button)
-            .push(incorrect_button);
+        /// Show the window with the statistics
+        Stats,
 
-        let mut col = Column::new()
-            .align_items(Align::Center)
-            .height(Length::Fill)
-            .padding(10)
-            .spacing(30);
+        /// Close the database and quit
+        Quit,
+    }
+
+    /// The icon as the tray service sees it
+    struct Icon {
+        /// The number of the due words, shown in the tooltip
+        due:    usize,
+        /// Whether there's a tray showing the icon
+        shown:  Arc<AtomicBool>,
+        sender: UnboundedSender<Action>,
+    }
+
+    impl Icon {
+        /// Returns the menu item with `label` that picks `action`
+        fn item(label: &'static str, action: Action) -> ksni::MenuItem<Self> {
+            ksni::menu::StandardItem {
+                label:    tr(label).to_string(),
+                activate: Box::new(move |icon: &mut Self| icon.pick(action)),
+                ..Default::default()
+            }.into()
+        }
+
+        fn pick(&self, action: Action) {
+            // Nobody listens anymore once the app is gone
+            let _ = self.sender.unbounded_send(action);
+        }
+    }
+
+    impl ksni::Tray for Icon {
+        fn id(&self) -> String {
+            "wordpal".to_string()
+        }
+
+        fn title(&self) -> String {
+            tr(ROOT_WINDOW_TITLE).to_string()
+        }
+
+        fn icon_name(&self) -> String {
+            ICON_NAME.to_string()
+        }
+
+        fn tool_tip(&self) -> ksni::ToolTip {
+            ksni::ToolTip {
+                title:       tr(ROOT_WINDOW_TITLE).to_string(),
+                description: format!("{}: {}", tr(REMINDER_MESSAGE), self.due),
+                ..Default::default()
+            }
+        }
+
+        fn activate(&mut self, _x: i32, _y: i32) {
+            self.pick(Action::Show);
+        }
+
+        fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
+            vec![
+                Self::item(TRAY_SHOW_LABEL, Action::Show),
+                Self::item(STATS_LABEL, Action::Stats),
+                ksni::MenuItem::Separator,
+                Self::item(START_QUIT_LABEL, Action::Quit),
+            ]
+        }
+
+        fn watcher_online(&self) {
+            self.shown.store(true, Ordering::Relaxed);
+        }
 
-        // If a word is empty, don't show its widget
-        if self.word.len() != 0 {
-            col = col.push(word_button);
+        fn watcher_offine(&self) -> bool {
+            self.shown.store(false, Ordering::Relaxed);
+            true
         }
-        if self.tr_word.len() != 0 && !self.tr_word_hidden {
-            col = col.push(tr_word);
+    }
+
+    /// The receiver of the picked actions, until it's subscribed to
+    type Actions = Arc<Mutex<Option<UnboundedReceiver<Action>>>>;
+
+    /// The icon in the tray, removed once it's dropped
+    pub struct Tray {
+        handle:  ksni::Handle<Icon>,
+        shown:   Arc<AtomicBool>,
+        actions: Actions,
+        due:     usize,
+    }
+
+    impl Tray {
+        /// Puts the icon into the tray.
+        /// Without a tray (or D-Bus), there's just no icon; see `is_shown`.
+        pub fn new() -> Self {
+            let (sender, receiver) = mpsc::unbounded();
+            let shown   = Arc::new(AtomicBool::new(false));
+            let service = ksni::TrayService::new(Icon {
+                due:    0,
+                shown:  shown.clone(),
+                sender,
+            });
+            let handle  = service.handle();
+            thread::spawn(move || {
+                if let Err(err) = service.run() {
+                    eprintln!("{}", err);
+                }
+            });
+
+            Self {
+                handle,
+                shown,
+                actions: Arc::new(Mutex::new(Some(receiver))),
+                due:     0,
+            }
+        }
+
+        /// Returns whether a tray shows the icon, so that the window can be
+        /// hidden to it
+        pub fn is_shown(&self) -> bool {
+            self.shown.load(Ordering::Relaxed)
+        }
+
+        /// Shows `due` words in the tooltip
+        pub fn set_due(&mut self, due: usize) {
+            if self.due != due {
+                self.due = due;
+                self.handle.update(|icon| icon.due = due);
+            }
+        }
+
+        /// Returns the subscription to the actions picked in the menu
+        pub fn actions(&self) -> Subscription<Action> {
+            Subscription::from_recipe(ActionStream(self.actions.clone()))
+        }
+    }
+
+    impl Drop for Tray {
+        fn drop(&mut self) {
+            self.handle.shutdown();
+        }
+    }
+
+    /// The recipe of the subscription to the picked actions
+    struct ActionStream(Actions);
+
+    impl<H: Hasher, I> Recipe<H, I> for ActionStream {
+        type Output = Action;
+
+        fn hash(&self, state: &mut H) {
+            TypeId::of::<Self>().hash(state);
         }
 
-        col.push(horizontal_box).into()
+        // The actions can only be received by one stream
+        fn stream(self: Box<Self>, _input: BoxStream<'static, I>)
+                -> BoxStream<'static, Action> {
+            match self.0.lock().unwrap().take() {
+                Some(receiver) => receiver.boxed(),
+                None           => stream::empty().boxed(),
+            }
+        }
     }
 }
 
 mod style {
-    use iced::{button, Background, Color};
+    use iced::{button, checkbox, container, progress_bar, radio};
+    use iced::text_input;
+    use iced::{Background, Color};
+    use wordpal::theme::palette;
+
+    /// The whole window
+    pub struct Container;
+
+    impl container::StyleSheet for Container {
+        fn style(&self) -> container::Style {
+            let palette = palette();
+            container::Style {
+                text_color: Some(palette.text.into()),
+                background: Some(Background::Color(palette.background.into())),
+                ..container::Style::default()
+            }
+        }
+    }
 
     pub enum Button {
+        Normal,
         Correct,
         Incorrect,
         Invisible,
@@ -219,20 +4848,36 @@ mod style {
 
     impl button::StyleSheet for Button {
         fn active(&self) -> button::Style {
+            let palette = palette();
             match self {
+                Button::Normal => {
+                    button::Style {
+                        border_color: palette.border.into(),
+                        border_width: 1.,
+                        border_radius: 2.,
+                        background: Some(Background::Color(
+                            palette.surface.into())),
+                        text_color: palette.text.into(),
+                        ..button::Style::default()
+                    }
+                },
                 Button::Correct => {
                     button::Style {
-                        border_color: Color::BLACK,
+                        border_color: palette.border.into(),
                         border_width: 2.,
-                        background: Some(Background::Color([0.,1.,0.].into())),
+                        background: Some(Background::Color(
+                            palette.correct.into())),
+                        text_color: Color::WHITE,
                         ..button::Style::default()
                     }
                 },
                 Button::Incorrect => {
                     button::Style {
-                        border_color: Color::BLACK,
+                        border_color: palette.border.into(),
                         border_width: 2.,
-                        background: Some(Background::Color([1.,0.,0.,].into())),
+                        background: Some(Background::Color(
+                            palette.incorrect.into())),
+                        text_color: Color::WHITE,
                         ..button::Style::default()
                     }
                 },
@@ -240,11 +4885,205 @@ mod style {
                     button::Style {
                         border_color: Color::TRANSPARENT,
                         background: Some(Background::Color(Color::TRANSPARENT)),
-                        text_color: Color::BLACK,
+                        text_color: palette.text.into(),
                         ..button::Style::default()
                     }
                 },
             }
         }
     }
+
+    /// A button with the keyboard focus; marked by a thicker border in the
+    /// color of the text
+    pub struct Focused(pub Button);
+
+    impl button::StyleSheet for Focused {
+        fn active(&self) -> button::Style {
+            button::Style {
+                border_color: palette().text.into(),
+                border_width: 3.,
+                ..self.0.active()
+            }
+        }
+    }
+
+    pub struct TextInput;
+
+    impl text_input::StyleSheet for TextInput {
+        fn active(&self) -> text_input::Style {
+            let palette = palette();
+            text_input::Style {
+                background: Background::Color(palette.surface.into()),
+                border_radius: 2.,
+                border_width: 1.,
+                border_color: palette.border.into(),
+            }
+        }
+
+        fn focused(&self) -> text_input::Style {
+            text_input::Style {
+                border_width: 2.,
+                ..self.active()
+            }
+        }
+
+        fn placeholder_color(&self) -> Color {
+            palette().placeholder.into()
+        }
+
+        fn value_color(&self) -> Color {
+            palette().text.into()
+        }
+
+        fn selection_color(&self) -> Color {
+            palette().border.into()
+        }
+    }
+
+    pub struct ProgressBar;
+
+    impl progress_bar::StyleSheet for ProgressBar {
+        fn style(&self) -> progress_bar::Style {
+            let palette = palette();
+            progress_bar::Style {
+                background: Background::Color(palette.surface.into()),
+                bar: Background::Color(palette.correct.into()),
+                border_radius: 2.,
+            }
+        }
+    }
+
+    pub struct Checkbox;
+
+    impl checkbox::StyleSheet for Checkbox {
+        fn active(&self, _is_checked: bool) -> checkbox::Style {
+            let palette = palette();
+            checkbox::Style {
+                background: Background::Color(palette.surface.into()),
+                checkmark_color: palette.text.into(),
+                border_radius: 2.,
+                border_width: 1.,
+                border_color: palette.border.into(),
+            }
+        }
+
+        fn hovered(&self, is_checked: bool) -> checkbox::Style {
+            checkbox::Style {
+                border_width: 2.,
+                ..self.active(is_checked)
+            }
+        }
+    }
+
+    pub struct Radio;
+
+    impl radio::StyleSheet for Radio {
+        fn active(&self) -> radio::Style {
+            let palette = palette();
+            radio::Style {
+                background: Background::Color(palette.surface.into()),
+                dot_color: palette.text.into(),
+                border_width: 1.,
+                border_color: palette.border.into(),
+            }
+        }
+
+        fn hovered(&self) -> radio::Style {
+            radio::Style {
+                border_width: 2.,
+                ..self.active()
+            }
+        }
+    }
+}
+
+
+// The helpers of the tests of the library aren't a part of it
+#[cfg(test)]
+#[path = "testing.rs"]
+mod testing;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    /// Writes a database of `lines` into `dir` and returns its path
+    fn write(dir: &TempDir, lines: &[&str]) -> PathBuf {
+        let contents = lines.iter()
+            .map(|line| format!("{}\n", line))
+            .collect::<String>();
+        dir.write("words.txt", contents.as_bytes())
+    }
+
+    /// Returns the app on the start screen, with `path` as the database
+    /// opened last so that opening it doesn't write the config file
+    fn app(path: PathBuf) -> App {
+        let config = Config {
+            recent_databases: vec![path],
+            ..Config::default()
+        };
+        App {
+            screen:    Screen::Start(Start::new(&config)),
+            config,
+            settings:  None,
+            exiting:   false,
+            focus:     Focus::default(),
+            focused:   true,
+            remind_at: None,
+            #[cfg(feature = "tray")]
+            tray:      None,
+            #[cfg(feature = "tray")]
+            hidden:    false,
+        }
+    }
+
+    #[test]
+    fn a_database_without_decks_is_studied_right_away() {
+        let dir     = TempDir::new("no-decks");
+        let mut app = app(write(&dir, &["Hund;; dog;; 0;; 0"]));
+        app.open_recent(0);
+        match &app.screen {
+            Screen::Study(study) => assert!(study.current_entry.is_some()),
+            _                    => panic!("the words aren't studied"),
+        }
+    }
+
+    #[test]
+    fn a_database_with_decks_is_studied_once_a_deck_is_picked() {
+        let dir     = TempDir::new("decks");
+        let mut app = app(write(&dir, &[
+            "Hund;; dog;; 0;; 0;; deck=German",
+            "pes;; dog;; 0;; 0;; deck=Czech",
+        ]));
+        app.open_recent(0);
+        let german = match &app.screen {
+            Screen::Decks(decks) => {
+                decks.decks.iter().position(|deck| deck == "German")
+            },
+            _ => panic!("the decks aren't offered"),
+        };
+
+        app.study_picked(|decks, config| decks.pick(german, config));
+        match &app.screen {
+            Screen::Study(study) => {
+                let deck = study.database.active_deck.as_deref();
+                assert_eq!(deck, Some("German"));
+            },
+            _ => panic!("the picked deck isn't studied"),
+        }
+    }
+
+    #[test]
+    fn the_start_screen_stays_without_a_database() {
+        let dir     = TempDir::new("no-database");
+        let mut app = app(write(&dir, &["Hund;; dog;; 0;; 0"]));
+        app.open_recent(1);
+        assert!(matches!(app.screen, Screen::Start(_)));
+        assert!(app.database().is_none());
+
+        // Nothing is picked on the start screen
+        app.study_picked(|decks, config| decks.pick(None, config));
+        assert!(matches!(app.screen, Screen::Start(_)));
+    }
 }
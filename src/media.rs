@@ -0,0 +1,159 @@
+//! Pronunciation and pictures of the words.
+//! An entry can reference an audio file (relative to the database) that is
+//! played when the entry is shown. Entries without one can be read out by the
+//! text-to-speech of the system if the `tts` feature is enabled.
+//! Both are played by the tools of the platform in a child process, so that
+//! the ui doesn't wait for them to finish.
+//! An entry can also reference an image, which the ui shows with the word.
+
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use crate::db::Entry;
+use crate::paths::media_path;
+
+/// The audio players tried in order on the platforms without a player of
+/// their own, with their arguments before the path of the file
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const PLAYERS: [(&str, &[&str]); 3] = [
+    ("paplay", &[]),
+    ("ffplay", &["-nodisp", "-autoexit", "-loglevel", "quiet"]),
+    ("aplay",  &["-q"]),
+];
+
+
+/// Returns the path of the image of `entry` of the database at `db`, or
+/// `None` if it has none or the image doesn't exist
+pub fn image_path(db: &Path, entry: &Entry) -> Option<PathBuf> {
+    if entry.image.is_empty() {
+        return None;
+    }
+    Some(media_path(db, &entry.image)).filter(|path| path.exists())
+}
+
+/// Returns whether `entry` can be pronounced at all
+pub fn can_pronounce(entry: &Entry) -> bool {
+    !entry.audio.is_empty() || cfg!(feature = "tts")
+}
+
+/// Spawns `program` with `args` without any of its output
+pub(crate) fn spawn(program: &str, args: &[&str]) -> io::Result<Child> {
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+}
+
+/// Starts playing the audio file at `path`
+#[cfg(target_os = "macos")]
+fn play_file(path: &str) -> io::Result<Child> {
+    spawn("afplay", &[path])
+}
+
+/// Starts playing the audio file at `path`; only WAV files are supported
+#[cfg(target_os = "windows")]
+fn play_file(path: &str) -> io::Result<Child> {
+    let script = format!("(New-Object Media.SoundPlayer '{}').PlaySync()",
+                         path.replace('\'', "''"));
+    spawn("powershell", &["-NoProfile", "-Command", &script])
+}
+
+/// Starts playing the audio file at `path` with the first of `PLAYERS` that
+/// is installed
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn play_file(path: &str) -> io::Result<Child> {
+    for (player, args) in PLAYERS.iter() {
+        let mut args = args.to_vec();
+        args.push(path);
+        match spawn(player, &args) {
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            result => return result,
+        }
+    }
+    Err(io::Error::new(ErrorKind::NotFound, "no audio player found"))
+}
+
+/// Starts reading `text` out with the speech synthesis of macOS
+#[cfg(all(feature = "tts", target_os = "macos"))]
+fn speak(text: &str) -> io::Result<Child> {
+    spawn("say", &["--", text])
+}
+
+/// Starts reading `text` out with SAPI
+#[cfg(all(feature = "tts", target_os = "windows"))]
+fn speak(text: &str) -> io::Result<Child> {
+    let script = format!(concat!(
+        "Add-Type -AssemblyName System.Speech; ",
+        "(New-Object System.Speech.Synthesis.SpeechSynthesizer)",
+        ".Speak('{}')"),
+        text.replace('\'', "''"));
+    spawn("powershell", &["-NoProfile", "-Command", &script])
+}
+
+/// Starts reading `text` out with speech-dispatcher
+#[cfg(all(feature = "tts", not(any(target_os = "macos",
+                                   target_os = "windows"))))]
+fn speak(text: &str) -> io::Result<Child> {
+    spawn("spd-say", &["--", text])
+}
+
+
+/// Plays the pronunciations of the entries; only one at a time
+#[derive(Debug, Default)]
+pub struct Player {
+    /// The process playing the last pronunciation
+    child: Option<Child>,
+}
+
+impl Player {
+    /// Creates a new player that doesn't play anything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pronounces the original word of `entry` of the database at `db`.
+    /// Its audio file is played, or the word is read out if it has none and
+    /// the `tts` feature is enabled. The last pronunciation is stopped first.
+    /// Returns false if the entry can't be pronounced.
+    pub fn pronounce(&mut self, db: &Path, entry: &Entry) -> io::Result<bool> {
+        self.stop();
+
+        if !entry.audio.is_empty() {
+            let path = media_path(db, &entry.audio);
+            if !path.exists() {
+                return Err(io::Error::new(ErrorKind::NotFound,
+                                          path.display().to_string()));
+            }
+            self.child = Some(play_file(&path.to_string_lossy())?);
+            return Ok(true);
+        }
+
+        // A cloze sentence is read out whole, without the braces
+        #[cfg(feature = "tts")]
+        {
+            let text = entry.cloze()
+                .map_or_else(|| entry.word.clone(), |cloze| cloze.revealed());
+            self.child = Some(speak(&text)?);
+            Ok(true)
+        }
+        #[cfg(not(feature = "tts"))]
+        Ok(false)
+    }
+
+    /// Stops the last pronunciation if it's still playing
+    pub fn stop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            // The process may have finished already; it's reaped either way
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,195 @@
+//! This module handles the database metadata;
+//! everything that isn't tied to a single entry (review counts, streaks,
+//! unlocked achievements, ...). It is stored in a sidecar file next to the
+//! database so that the database itself stays a plain list of words.
+
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use crate::achievements::Achievement;
+use crate::db::{DAY, write_atomic};
+use crate::paths;
+use crate::fsrs::WEIGHT_COUNT;
+use crate::scheduler::Preset;
+
+/// Key/value delimiter in the metadata file
+const META_DELIMITER: char = '=';
+
+
+/// The metadata of a database
+#[derive(Clone, Debug, Default)]
+pub struct Meta {
+    /// Path to the metadata file
+    pub path: PathBuf,
+
+    /// Total number of reviews ever done
+    pub reviews: u64,
+
+    /// Number of consecutive days with at least one review
+    pub streak: u64,
+
+    /// The day (in days since the epoch) of the last review
+    pub last_review_day: u64,
+
+    /// Achievements unlocked so far
+    pub achievements: Vec<Achievement>,
+
+    /// Number of new words first answered on the day of the last review
+    pub new_today: u64,
+
+    /// Number of reviews (of words that weren't new) done on the day of the
+    /// last review
+    pub reviews_today: u64,
+
+    /// If set, at most this many new words are introduced per day
+    pub new_limit: Option<u64>,
+
+    /// If set, at most this many reviews are done per day
+    pub review_limit: Option<u64>,
+
+    /// The weights of the FSRS scheduler fitted to the review history, see
+    /// `fsrs::optimize`; the default ones are used if unset
+    pub fsrs_weights: Option<[f64; WEIGHT_COUNT]>,
+
+    /// The scheduling of the decks that don't follow the config, by the names
+    /// of the decks; one `deck_preset` line each
+    pub deck_presets: BTreeMap<String, Preset>,
+}
+
+impl Meta {
+    /// Loads the metadata of database `db`.
+    /// If there's no metadata file yet, empty metadata is returned.
+    pub fn load(db: &Path) -> std::io::Result<Self> {
+        let path = paths::meta_path(db);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+
+        let mut meta = Self { path, ..Self::default() };
+
+        // Unknown keys and unparseable values are ignored
+        for line in contents.lines() {
+            let mut split = line.splitn(2, META_DELIMITER);
+            let key   = split.next().unwrap_or("").trim();
+            let value = split.next().unwrap_or("").trim();
+
+            match key {
+                "reviews"         => meta.reviews = value.parse().unwrap_or(0),
+                "streak"          => meta.streak  = value.parse().unwrap_or(0),
+                "last_review_day" => {
+                    meta.last_review_day = value.parse().unwrap_or(0);
+                },
+                "achievements"    => {
+                    meta.achievements = value.split(',')
+                        .filter_map(Achievement::from_name)
+                        .collect();
+                },
+                "new_today"       => {
+                    meta.new_today = value.parse().unwrap_or(0);
+                },
+                "reviews_today"   => {
+                    meta.reviews_today = value.parse().unwrap_or(0);
+                },
+                "new_limit"       => meta.new_limit    = value.parse().ok(),
+                "review_limit"    => meta.review_limit = value.parse().ok(),
+                "fsrs_weights"    => {
+                    meta.fsrs_weights = value.split(',')
+                        .map(|weight| weight.trim().parse().ok())
+                        .collect::<Option<Vec<f64>>>()
+                        .and_then(|weights| weights.try_into().ok());
+                },
+                "deck_preset"     => {
+                    if let Some((deck, preset)) = Preset::parse(value) {
+                        meta.deck_presets.insert(deck, preset);
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        Ok(meta)
+    }
+
+    /// Writes the metadata to its file
+    pub fn save(&self) -> std::io::Result<()> {
+        let achievements: Vec<&str> = self.achievements.iter()
+            .map(|achievement| achievement.name())
+            .collect();
+
+        let fsrs_weights = self.fsrs_weights.map(|weights| {
+            let weights: Vec<String> = weights.iter()
+                .map(f64::to_string)
+                .collect();
+            weights.join(",")
+        });
+
+        let values = [
+            ("reviews",         Some(self.reviews.to_string())),
+            ("streak",          Some(self.streak.to_string())),
+            ("last_review_day", Some(self.last_review_day.to_string())),
+            ("achievements",    Some(achievements.join(","))),
+            ("new_today",       Some(self.new_today.to_string())),
+            ("reviews_today",   Some(self.reviews_today.to_string())),
+            ("new_limit",       self.new_limit.map(|limit| limit.to_string())),
+            ("review_limit",    self.review_limit.map(|l| l.to_string())),
+            ("fsrs_weights",    fsrs_weights),
+        ];
+
+        // Unset values are left out
+        let mut contents = String::new();
+        for (key, value) in values.iter() {
+            if let Some(value) = value {
+                contents.push_str(&format!("{}{}{}\n",
+                                           key, META_DELIMITER, value));
+            }
+        }
+        for (deck, preset) in self.deck_presets.iter() {
+            contents.push_str(&format!("deck_preset{}{}\n", META_DELIMITER,
+                                       preset.repr(deck)));
+        }
+        write_atomic(&self.path, contents.as_bytes())
+    }
+
+    /// Returns the number of new words and reviews done on the day of `now`
+    /// (seconds since the epoch)
+    pub fn done_today(&self, now: u64) -> (u64, u64) {
+        if self.last_review_day != now / DAY {
+            return (0, 0);
+        }
+        (self.new_today, self.reviews_today)
+    }
+
+    /// Returns the streak as of `now` (seconds since the epoch); 0 if there
+    /// was no review yesterday or today, so the streak was broken
+    pub fn current_streak(&self, now: u64) -> u64 {
+        match now / DAY > self.last_review_day + 1 {
+            true  => 0,
+            false => self.streak,
+        }
+    }
+
+    /// Records a review done at `now` (seconds since the epoch).
+    /// `new` is whether the reviewed word was new.
+    pub fn record_review(&mut self, now: u64, new: bool) {
+        let today = now / DAY;
+
+        // The daily counts start over every day
+        let (new_today, reviews_today) = self.done_today(now);
+        self.new_today     = new_today + new as u64;
+        self.reviews_today = reviews_today + !new as u64;
+
+        // The streak continues if the last review was yesterday
+        if self.streak == 0 || today > self.last_review_day + 1 {
+            self.streak = 1;
+        } else if today == self.last_review_day + 1 {
+            self.streak += 1;
+        }
+
+        self.reviews        += 1;
+        self.last_review_day = today;
+    }
+}
@@ -0,0 +1,158 @@
+//! Normalization of the words before they're compared, e.g. of the typed
+//! answers and the translations in `grading`.
+//! The word goes through a pipeline of steps, each of which can be turned on
+//! or off in a `Normalization`:
+//!
+//! 1. The whitespace between the words is collapsed into single spaces.
+//! 2. The case is folded (`Pes` is `pes`).
+//! 3. The letters that are commonly spelled out without a keyboard layout of
+//!    the language are transliterated (`über` is `ueber`, `Straße` is
+//!    `Strasse`).
+//! 4. The diacritics are stripped (`čaj` is `caj`).
+//! 5. The punctuation is dropped (`dog!` is `dog`, `don't` is `dont`).
+//!
+//! The transliteration comes before the diacritics, so that `über` still
+//! matches `ueber` with both of them on (but not `uber` anymore).
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+use crate::locale::*;
+
+/// The letters that are transliterated, with their transliterations
+const TRANSLITERATIONS: [(char, &str); 17] = [
+    ('ä', "ae"), ('ö', "oe"), ('ü', "ue"), ('ß', "ss"),
+    ('Ä', "Ae"), ('Ö', "Oe"), ('Ü', "Ue"), ('ẞ', "SS"),
+    ('æ', "ae"), ('œ', "oe"), ('ø', "oe"), ('å', "aa"),
+    ('Æ', "Ae"), ('Œ', "Oe"), ('Ø', "Oe"), ('Å', "Aa"),
+    ('þ', "th"),
+];
+
+
+/// Which differences between the words are ignored when they're compared
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Normalization {
+    /// Whether `Pes` and `pes` are the same word
+    pub case: bool,
+
+    /// Whether `čaj` and `caj` are the same word
+    pub diacritics: bool,
+
+    /// Whether `dog!` and `dog` are the same word
+    pub punctuation: bool,
+
+    /// Whether `über` and `ueber` (or `ß` and `ss`) are the same word
+    pub transliteration: bool,
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Self {
+            case:            true,
+            diacritics:      false,
+            punctuation:     false,
+            transliteration: false,
+        }
+    }
+}
+
+impl Normalization {
+    /// Returns whether `step` is on
+    pub fn is_on(&self, step: Step) -> bool {
+        match step {
+            Step::Case            => self.case,
+            Step::Diacritics      => self.diacritics,
+            Step::Punctuation     => self.punctuation,
+            Step::Transliteration => self.transliteration,
+        }
+    }
+
+    /// Turns `step` on or off
+    pub fn set(&mut self, step: Step, on: bool) {
+        match step {
+            Step::Case            => self.case            = on,
+            Step::Diacritics      => self.diacritics      = on,
+            Step::Punctuation     => self.punctuation     = on,
+            Step::Transliteration => self.transliteration = on,
+        }
+    }
+}
+
+
+/// A step of the normalization that can be turned on or off
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Folding the case
+    Case,
+
+    /// Stripping the diacritics
+    Diacritics,
+
+    /// Dropping the punctuation
+    Punctuation,
+
+    /// Transliterating the umlauts and ligatures
+    Transliteration,
+}
+
+impl Step {
+    /// All the steps
+    pub const ALL: [Step; 4] = [Step::Case, Step::Diacritics,
+                                Step::Punctuation, Step::Transliteration];
+
+    /// Returns the localization key of the name of the step
+    pub fn name(&self) -> &'static str {
+        match self {
+            Step::Case            => NORMALIZE_CASE,
+            Step::Diacritics      => NORMALIZE_DIACRITICS,
+            Step::Punctuation     => NORMALIZE_PUNCTUATION,
+            Step::Transliteration => NORMALIZE_TRANSLITERATION,
+        }
+    }
+}
+
+
+/// Returns `word` without the differences that are ignored by
+/// `normalization`
+pub fn normalize(word: &str, normalization: &Normalization) -> String {
+    // Whitespace between the words never matters. The decomposed letters are
+    // composed, so that they're transliterated just like the composed ones.
+    let mut word: String = word.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .nfc()
+        .collect();
+
+    if normalization.case {
+        word = word.to_lowercase();
+    }
+
+    if normalization.transliteration {
+        word = word.chars()
+            .map(|ch| {
+                TRANSLITERATIONS.iter()
+                    .find(|(letter, _)| *letter == ch)
+                    .map_or_else(|| ch.to_string(), |(_, tr)| tr.to_string())
+            })
+            .collect();
+    }
+
+    // Decompose the characters and drop the accents.
+    // The rest is composed back so that the typos are counted per letter.
+    if normalization.diacritics {
+        word = word.nfd().filter(|&ch| !is_combining_mark(ch)).collect();
+    }
+
+    // The words separated by the punctuation alone are glued together, and
+    // the spaces it leaves around are collapsed again
+    if normalization.punctuation {
+        word = word.chars()
+            .filter(|ch| ch.is_alphanumeric() || ch.is_whitespace())
+            .collect::<String>()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+    word.nfc().collect()
+}
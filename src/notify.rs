@@ -0,0 +1,57 @@
+//! Desktop notifications, e.g. the reminders of the words that became due.
+//! Like the pronunciations in `media`, they're shown by the tools of the
+//! platform in a child process.
+
+use std::io;
+use std::process::Child;
+use std::thread;
+use crate::media::spawn;
+
+/// How long (in seconds) a notification stays on the screen on the platforms
+/// that don't decide it themselves
+#[cfg(target_os = "windows")]
+const NOTIFICATION_SECONDS: u32 = 10;
+
+
+/// Shows a desktop notification with `title` and `body`, without waiting for
+/// it to go away
+pub fn notify(title: &str, body: &str) -> io::Result<()> {
+    let mut child = show(title, body)?;
+
+    // The process is reaped once it's done, so that it doesn't linger
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Shows the notification with the notification center of macOS
+#[cfg(target_os = "macos")]
+fn show(title: &str, body: &str) -> io::Result<Child> {
+    let quote  = |text: &str| {
+        format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+    };
+    let script = format!("display notification {} with title {}",
+                         quote(body), quote(title));
+    spawn("osascript", &["-e", &script])
+}
+
+/// Shows the notification as a balloon of an icon in the notification area
+#[cfg(target_os = "windows")]
+fn show(title: &str, body: &str) -> io::Result<Child> {
+    let script = format!(concat!(
+        "Add-Type -AssemblyName System.Windows.Forms; ",
+        "$icon = New-Object System.Windows.Forms.NotifyIcon; ",
+        "$icon.Icon = [System.Drawing.SystemIcons]::Information; ",
+        "$icon.Visible = $true; ",
+        "$icon.ShowBalloonTip({0}000, '{1}', '{2}', 'Info'); ",
+        "Start-Sleep -Seconds {0}; ",
+        "$icon.Dispose()"),
+        NOTIFICATION_SECONDS, title.replace('\'', "''"),
+        body.replace('\'', "''"));
+    spawn("powershell", &["-NoProfile", "-Command", &script])
+}
+
+/// Shows the notification with the notification daemon of the desktop
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn show(title: &str, body: &str) -> io::Result<Child> {
+    spawn("notify-send", &["--app-name=wordpal", "--", title, body])
+}
@@ -0,0 +1,152 @@
+//! Where wordpal keeps its files.
+//! Everything that belongs to a database lives next to it, named after it;
+//! for `words.csv` these are:
+//!
+//! ```text
+//! words.csv.lock          the lock of the open database
+//! words.csv.meta          the metadata (streaks, achievements, ...)
+//! words.csv.history       the review history the statistics are made of
+//! words.csv.bak.1, ...    the backups, the newest one first
+//! words.csv.backups       the daily snapshots (see `db::backup`)
+//! words.csv.NAME.profile  the schedule of the profile NAME
+//! ```
+//!
+//! and the media files of the entries are relative to its directory. Only the
+//! config lives elsewhere, in the config directory of the platform.
+//!
+//! In portable mode, the config lives in the directory of the program as well,
+//! so that the whole folder (e.g. on a USB stick) can be carried between
+//! computers. Portable mode is turned on by a `PORTABLE_MARKER` file next to
+//! the program. The databases in the portable directory are remembered by
+//! their paths relative to it, since the stick may be mounted elsewhere on
+//! every computer.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use directories::ProjectDirs;
+
+/// The file next to the program that turns the portable mode on
+pub const PORTABLE_MARKER: &str = "wordpal.portable";
+
+/// The name of the config file in the config directory
+const CONFIG_FILENAME: &str = "config.toml";
+
+/// Extension of the lock files of the open databases
+const LOCK_EXTENSION: &str = "lock";
+
+/// Extension appended to the database filename to get the metadata filename
+const META_EXTENSION: &str = "meta";
+
+/// Extension appended to the database filename to get the history filename
+const HISTORY_EXTENSION: &str = "history";
+
+/// Extension of the database backups; the backups are numbered from 1 (the
+/// newest one)
+const BACKUP_EXTENSION: &str = "bak";
+
+/// Extension of the directory of the daily snapshots
+const BACKUPS_EXTENSION: &str = "backups";
+
+/// Extension of the per-profile schedule files
+const PROFILE_EXTENSION: &str = "profile";
+
+/// Extension of the temporary files written before replacing the originals
+const TMP_EXTENSION: &str = "tmp";
+
+
+/// Returns `path` with `.extension` appended to it
+fn with_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(format!(".{}", extension));
+    PathBuf::from(path)
+}
+
+/// Returns the directory of the program if it runs in portable mode, i.e. if
+/// there's a `PORTABLE_MARKER` next to it
+pub fn portable_dir() -> Option<PathBuf> {
+    let exe = env::current_exe().ok()?;
+    let dir = exe.parent()?;
+    Some(dir.to_path_buf()).filter(|dir| dir.join(PORTABLE_MARKER).is_file())
+}
+
+/// Returns whether the program runs in portable mode
+pub fn is_portable() -> bool {
+    portable_dir().is_some()
+}
+
+/// Returns the path of the config file, or `None` if the platform has no
+/// config directory (e.g. there's no home directory).
+/// In portable mode, the config file is next to the program.
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(dir) = portable_dir() {
+        return Some(dir.join(CONFIG_FILENAME));
+    }
+    ProjectDirs::from("", "", "wordpal")
+        .map(|dirs| dirs.config_dir().join(CONFIG_FILENAME))
+}
+
+/// Returns `path` the way it's remembered in the config; relative to the
+/// portable directory if it's in it, as it is otherwise
+pub fn remembered(path: &Path) -> PathBuf {
+    let dir = match portable_dir() {
+        Some(dir) => dir,
+        None      => return path.to_path_buf(),
+    };
+    path.strip_prefix(&dir)
+        .map_or_else(|_| path.to_path_buf(), Path::to_path_buf)
+}
+
+/// Returns the path a `remembered` path stands for; a relative path is
+/// relative to the portable directory in portable mode
+pub fn resolve(path: &Path) -> PathBuf {
+    match portable_dir() {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _                               => path.to_path_buf(),
+    }
+}
+
+/// Returns the path of the lock file of database `db`
+pub fn lock_path(db: &Path) -> PathBuf {
+    with_extension(db, LOCK_EXTENSION)
+}
+
+/// Returns the path of the metadata file of database `db`
+pub fn meta_path(db: &Path) -> PathBuf {
+    with_extension(db, META_EXTENSION)
+}
+
+/// Returns the path of the history file of database `db`
+pub fn history_path(db: &Path) -> PathBuf {
+    with_extension(db, HISTORY_EXTENSION)
+}
+
+/// Returns the path of the `n`th newest backup of database `db`
+pub fn backup_path(db: &Path, n: usize) -> PathBuf {
+    with_extension(db, &format!("{}.{}", BACKUP_EXTENSION, n))
+}
+
+/// Returns the path of the directory of the daily snapshots of database `db`
+pub fn backups_dir(db: &Path) -> PathBuf {
+    with_extension(db, BACKUPS_EXTENSION)
+}
+
+/// Returns the path of the schedule file of `profile` for database `db`
+pub fn profile_path(db: &Path, profile: &str) -> PathBuf {
+    with_extension(db, &format!("{}.{}", profile, PROFILE_EXTENSION))
+}
+
+/// Returns the path of the temporary file `path` is written into before it's
+/// replaced
+pub fn tmp_path(path: &Path) -> PathBuf {
+    with_extension(path, TMP_EXTENSION)
+}
+
+/// Returns the path of a media `file` (an audio file or an image) of an entry
+/// of the database at `db`.
+/// Relative paths are relative to the directory of the database.
+pub fn media_path(db: &Path, file: &str) -> PathBuf {
+    match db.parent() {
+        Some(dir) => dir.join(file),
+        None      => PathBuf::from(file),
+    }
+}
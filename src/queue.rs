@@ -0,0 +1,238 @@
+//! A shuffled queue of the entries to review.
+//! Instead of picking a random entry every time (which can pick the same
+//! entry twice in a row), the entries are shuffled once and dealt one by one
+//! like a deck of cards.
+//!
+//! The shuffle is weighted (see `weight`); the long overdue entries and the
+//! recently forgotten ones tend to be dealt earlier in the round, while every
+//! entry is still dealt exactly once.
+
+use std::collections::HashMap;
+use crate::db::{Entry, DAY, now};
+use crate::rng::Rng;
+
+/// The weight every entry has; a new or a freshly due one has just this
+const BASE_WEIGHT: f64 = 1.;
+
+/// The weight added for every day an entry is overdue
+const OVERDUE_WEIGHT: f64 = 0.1;
+
+/// Entries overdue longer than this many days aren't weighted any more
+const MAX_OVERDUE_DAYS: f64 = 30.;
+
+/// The weight added for every lapse of an entry reviewed just now
+const LAPSE_WEIGHT: f64 = 0.5;
+
+/// The weight of the lapses halves in this many days since the last review,
+/// so that a word forgotten long ago loses its boost
+const LAPSE_HALF_LIFE: f64 = 7.;
+
+/// Shuffles `items` in place (Fisher-Yates)
+pub fn shuffle<T>(items: &mut [T], rng: &mut Rng) {
+    for idx in (1..items.len()).rev() {
+        let swap = rng.range(0, idx as u64) as usize;
+        items.swap(idx, swap);
+    }
+}
+
+/// Returns the weight of `entry` in the shuffle at `now` (seconds since the
+/// epoch); the higher it is, the earlier the entry tends to be dealt.
+/// The weight grows with the days the entry is overdue and with its lapses,
+/// which count less the longer ago the entry was last reviewed. The lapses
+/// of an entry that doesn't know its last review count fully.
+pub fn weight(entry: &Entry, now: u64) -> f64 {
+    if entry.is_new() {
+        return BASE_WEIGHT;
+    }
+
+    let overdue = now.saturating_sub(entry.timeout) as f64 / DAY as f64;
+    let decay   = match entry.reviewed {
+        0        => 1.,
+        reviewed => {
+            let days = now.saturating_sub(reviewed) as f64 / DAY as f64;
+            0.5f64.powf(days / LAPSE_HALF_LIFE)
+        },
+    };
+    BASE_WEIGHT
+        + overdue.min(MAX_OVERDUE_DAYS) * OVERDUE_WEIGHT
+        + entry.lapses as f64 * LAPSE_WEIGHT * decay
+}
+
+/// Shuffles `items` in place so that the ones with the higher `weights` tend
+/// to be the last ones (Efraimidis-Spirakis); each item is as likely to be
+/// the last one as its share of the weights
+pub fn weighted_shuffle<T>(items: &mut Vec<T>, weights: &[f64],
+                           rng: &mut Rng) {
+    // Every item draws an exponential key with the rate of its weight; the
+    // items are ordered by the keys, the smallest one last
+    let mut keyed: Vec<(f64, T)> = items.drain(..)
+        .zip(weights)
+        .map(|(item, &weight)| (-rng.unit().ln() / weight, item))
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    items.extend(keyed.into_iter().map(|(_, item)| item));
+}
+
+
+/// The entries that are yet to be dealt in the current round
+#[derive(Clone, Debug, Default)]
+pub struct ReviewQueue {
+    /// The words (original, translated) of the entries yet to be dealt,
+    /// the next one last.
+    /// The entries are remembered by their words because their indices
+    /// change as the entries are answered.
+    words: Vec<(String, Vec<String>)>,
+
+    /// The words of the last dealt entry
+    last: Option<(String, Vec<String>)>,
+}
+
+impl ReviewQueue {
+    /// Creates a new empty queue; it's filled on the first deal
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deals the next entry; returns its index in `usable`.
+    /// Only the `candidates` (indices into `usable`) are dealt. Entries that
+    /// aren't candidates anymore are skipped. Once every candidate was
+    /// dealt, the candidates are shuffled again.
+    /// Returns `None` if there are no candidates.
+    pub fn deal(&mut self, usable: &[Entry], candidates: &[usize],
+                rng: &mut Rng) -> Option<usize> {
+        let indices: HashMap<(&str, &[String]), usize> = candidates.iter()
+            .map(|&idx| {
+                let entry = &usable[idx];
+                ((entry.word.as_str(), entry.tr_word.as_slice()), idx)
+            })
+            .collect();
+        if indices.is_empty() {
+            return None;
+        }
+
+        loop {
+            while let Some(words) = self.words.pop() {
+                let key = (words.0.as_str(), words.1.as_slice());
+                if let Some(&idx) = indices.get(&key) {
+                    self.last = Some(words);
+                    return Some(idx);
+                }
+            }
+            self.reshuffle(usable, candidates, rng);
+        }
+    }
+
+    /// Starts a new round with the `candidates` (indices into `usable`)
+    /// shuffled by their weights.
+    /// The entry dealt last isn't dealt first again, unless it's the only one.
+    fn reshuffle(&mut self, usable: &[Entry], candidates: &[usize],
+                 rng: &mut Rng) {
+        let now     = now();
+        let weights: Vec<f64> = candidates.iter()
+            .map(|&idx| weight(&usable[idx], now))
+            .collect();
+        self.words = candidates.iter()
+            .map(|&idx| (usable[idx].word.clone(), usable[idx].tr_word.clone()))
+            .collect();
+        weighted_shuffle(&mut self.words, &weights, rng);
+
+        if self.words.len() > 1 && self.words.last() == self.last.as_ref() {
+            let last = self.words.len() - 1;
+            self.words.swap(0, last);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a due entry of `word` with `lapses`, last reviewed `days` ago
+    fn forgotten(word: &str, lapses: u32, days: u64, now: u64) -> Entry {
+        let mut entry  = Entry::new(word, "translation");
+        entry.timeout  = now;
+        entry.lapses   = lapses;
+        entry.reviewed = now - days * DAY;
+        entry
+    }
+
+    #[test]
+    fn the_weighted_shuffle_keeps_every_item() {
+        let mut rng   = Rng::with_seed(813);
+        let mut items = (0..50).collect::<Vec<usize>>();
+        let weights   = (0..50).map(|idx| idx as f64 + 1.).collect::<Vec<_>>();
+        weighted_shuffle(&mut items, &weights, &mut rng);
+
+        let mut sorted = items.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..50).collect::<Vec<_>>());
+        assert_ne!(items, sorted);
+    }
+
+    #[test]
+    fn the_weighted_shuffle_favors_the_heavier_items() {
+        // The items are dealt from the end; each is dealt first as often as
+        // its share of the weights
+        let weights   = [1., 2., 5.];
+        let mut first = [0u32; 3];
+        for seed in 1..=10_000 {
+            let mut rng   = Rng::with_seed(seed);
+            let mut items = vec![0, 1, 2];
+            weighted_shuffle(&mut items, &weights, &mut rng);
+            first[items[2]] += 1;
+        }
+        for (idx, &count) in first.iter().enumerate() {
+            let expected = 10_000. * weights[idx] / 8.;
+            assert!((count as f64 - expected).abs() < 250., "{:?}", first);
+        }
+    }
+
+    #[test]
+    fn overdue_entries_weigh_more_up_to_a_limit() {
+        let now     = now();
+        let overdue = |days: u64| {
+            let mut entry = Entry::new("Hund", "dog");
+            entry.timeout = now - days * DAY;
+            weight(&entry, now)
+        };
+        assert_eq!(weight(&Entry::new("Hund", "dog"), now), BASE_WEIGHT);
+        assert_eq!(overdue(0), BASE_WEIGHT);
+        assert!(overdue(10) > overdue(1));
+        assert_eq!(overdue(100), overdue(MAX_OVERDUE_DAYS as u64));
+    }
+
+    #[test]
+    fn an_old_failure_weighs_less_than_a_recent_one() {
+        let now    = now();
+        let recent = weight(&forgotten("recent", 2, 1, now), now);
+        let old    = weight(&forgotten("old", 2, 60, now), now);
+        let never  = weight(&forgotten("never", 0, 1, now), now);
+        assert!(recent > old && old > never);
+        assert!(old - never < 0.01);
+
+        // An entry whose last review is unknown keeps its lapses
+        let mut unknown = forgotten("unknown", 2, 0, now);
+        unknown.reviewed = 0;
+        assert_eq!(weight(&unknown, now), BASE_WEIGHT + 2. * LAPSE_WEIGHT);
+    }
+
+    #[test]
+    fn a_recent_failure_is_dealt_first_more_often() {
+        let now    = now();
+        let usable = vec![forgotten("old", 2, 60, now),
+                          forgotten("recent", 2, 1, now)];
+
+        let mut recent_first = 0;
+        for seed in 1..=1000 {
+            let mut queue = ReviewQueue::new();
+            let mut rng   = Rng::with_seed(seed);
+            if queue.deal(&usable, &[0, 1], &mut rng) == Some(1) {
+                recent_first += 1;
+            }
+        }
+
+        // The recent failure weighs about 1.9 against about 1.0
+        assert!((600..720).contains(&recent_first), "{}", recent_first);
+    }
+}
@@ -1,23 +1,125 @@
-/// A deterministic PRNG (xorshift)
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The seed used instead of zero, on which xorshift would get stuck
+const FALLBACK_SEED: u64 = 0x1337133713371337;
+
+/// A PRNG (xorshift64*).
+/// It's seeded from system entropy, unless a seed is given explicitly.
 pub struct Rng(u64);
 
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Rng {
-    /// Creates a new RNG
+    /// Creates a new RNG seeded from system entropy, so that every session
+    /// goes differently
     pub fn new() -> Self {
-        Self(0x1337133713371337)
+        // The std hash maps are keyed randomly by the OS for every process;
+        // the time and the address of the hasher are mixed in for good measure
+        let mut hasher = RandomState::new().build_hasher();
+        let time = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos());
+        hasher.write_u128(time);
+        hasher.write_usize(&hasher as *const _ as usize);
+        Self::with_seed(hasher.finish())
     }
 
-    /// Returns a pseudo-random (predetermined) number
+    /// Creates a new RNG from a given seed; the same seed always yields the
+    /// same numbers.
+    /// Xorshift gets stuck on a zero state, so a zero seed is replaced
+    /// with a fixed one.
+    pub fn with_seed(seed: u64) -> Self {
+        if seed == 0 {
+            Self(FALLBACK_SEED)
+        } else {
+            Self(seed)
+        }
+    }
+
+    /// Returns a pseudo-random number
     pub fn rand(&mut self) -> u64 {
-        let ret = self.0;
-        self.0 ^= self.0 << 13;
-        self.0 ^= self.0 >> 17;
-        self.0 ^= self.0 << 43;
-        ret
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    /// Returns a pseudo-random number in (0, 1]
+    pub fn unit(&mut self) -> f64 {
+        // The 53 top bits fill the mantissa of the float exactly
+        ((self.rand() >> 11) + 1) as f64 / (1u64 << 53) as f64
     }
 
-    /// Returns a pseudo-random (predetermined) number within a given range
+    /// Returns a pseudo-random number within a given (inclusive) range.
+    /// Every number in the range is equally likely.
     pub fn range(&mut self, min: u64, max: u64) -> u64 {
-        (self.rand() % (max - min + 1)) + min
+        let span = (max - min).wrapping_add(1);
+        if span == 0 {
+            // The range is the whole of u64
+            return self.rand();
+        }
+
+        // Numbers past the last whole multiple of `span` would make the
+        // lower numbers of the range more likely, so they're rejected
+        let limit = u64::MAX / span * span;
+        loop {
+            let num = self.rand();
+            if num < limit {
+                return min + num % span;
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_yields_the_same_numbers() {
+        let (mut a, mut b) = (Rng::with_seed(42), Rng::with_seed(42));
+        for _ in 0..100 {
+            assert_eq!(a.rand(), b.rand());
+        }
+        assert_eq!(Rng::with_seed(0).rand(),
+                   Rng::with_seed(FALLBACK_SEED).rand());
+    }
+
+    #[test]
+    fn range_stays_in_its_bounds_and_is_roughly_uniform() {
+        let mut rng    = Rng::with_seed(813);
+        let mut counts = [0u32; 10];
+        for _ in 0..100_000 {
+            let num = rng.range(5, 14);
+            assert!((5..=14).contains(&num));
+            counts[(num - 5) as usize] += 1;
+        }
+
+        // Each number is expected 10000 times; a fair RNG strays only by
+        // a few hundred
+        for &count in counts.iter() {
+            assert!((9500..10500).contains(&count), "{:?}", counts);
+        }
+
+        assert_eq!(rng.range(7, 7), 7);
+        let _ = rng.range(0, u64::MAX);
+    }
+
+    #[test]
+    fn unit_is_within_zero_and_one() {
+        let mut rng = Rng::with_seed(813);
+        let mut sum = 0.;
+        for _ in 0..10_000 {
+            let unit = rng.unit();
+            assert!(unit > 0. && unit <= 1.);
+            sum += unit;
+        }
+        assert!((sum / 10_000. - 0.5).abs() < 0.02);
     }
 }
@@ -0,0 +1,481 @@
+//! This module decides when an entry should be reviewed again.
+
+use serde::{Deserialize, Serialize};
+use crate::db::{Entry, DAY};
+use crate::rng::Rng;
+use crate::fsrs::is_valid_retention;
+use crate::locale::{SCHEDULER_FIXED, SCHEDULER_SM2, SCHEDULER_FSRS,
+                     LABELS_GRADES, LABELS_RECALL, AGAIN_LABEL, HARD_LABEL,
+                     GOOD_LABEL, EASY_LABEL, RECALL_AGAIN_LABEL,
+                     RECALL_HARD_LABEL, RECALL_GOOD_LABEL, RECALL_EASY_LABEL};
+
+/// The word timeout values (in days) of the default fixed ladder
+pub const TIMEOUT_DELAYS: [u64; 5] = [0, 1, 7, 14, 30];
+
+/// The most steps a ladder can have
+pub const MAX_LADDER_STEPS: usize = 32;
+
+/// Separates the values of a written `Preset`
+const PRESET_DELIMITER: char = ';';
+
+/// The ease factor every entry starts with
+pub const DEFAULT_EASE: f64 = 2.5;
+
+/// The ease factor never drops below this
+const MIN_EASE: f64 = 1.3;
+
+/// The interval grows only by this factor when the word was hard to recall
+const HARD_FACTOR: f64 = 1.2;
+
+/// The interval of the SM-2 scheduler grows by this factor more when the word
+/// was easy to recall
+const EASY_FACTOR: f64 = 1.3;
+
+/// The hour the days of the reviews start at, unless configured otherwise;
+/// a review late at night still counts into the day before
+pub const DEFAULT_DAY_START_HOUR: u8 = 4;
+
+/// An hour in seconds
+const HOUR: i64 = 3600;
+
+/// The share of the first `FUZZ_SHORT_DAYS` days of an interval it's fuzzed
+/// by either way
+const SHORT_FUZZ: f64 = 0.15;
+
+/// The share of the rest of an interval it's fuzzed by either way; less, so
+/// that a word isn't due weeks early
+const LONG_FUZZ: f64 = 0.05;
+
+/// The number of days of an interval that are fuzzed by `SHORT_FUZZ`
+const FUZZ_SHORT_DAYS: u64 = 30;
+
+/// The intervals (in days) shorter than this aren't fuzzed; they'd be spread
+/// by less than a day
+const MIN_FUZZED_DAYS: u64 = 3;
+
+
+/// How well the user knew the word
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Grade {
+    /// The word was forgotten
+    Again,
+
+    /// The word was recalled, but with difficulty
+    Hard,
+
+    /// The word was recalled
+    Good,
+
+    /// The word was recalled without any effort
+    Easy,
+}
+
+impl Grade {
+    /// All the grades, from the worst one
+    pub const ALL: [Grade; 4] = [Grade::Again, Grade::Hard, Grade::Good,
+                                 Grade::Easy];
+
+    /// Returns whether the word was recalled at all
+    pub fn is_correct(self) -> bool {
+        self != Grade::Again
+    }
+
+    /// Returns the icon the button of the grade can be marked with
+    pub fn icon(self) -> &'static str {
+        match self {
+            Grade::Again => "\u{2717}",
+            Grade::Hard  => "~",
+            Grade::Good  => "\u{2713}",
+            Grade::Easy  => "\u{2713}\u{2713}",
+        }
+    }
+}
+
+/// A binary answer; whether the word was recalled
+impl From<bool> for Grade {
+    fn from(correct: bool) -> Self {
+        match correct {
+            true  => Grade::Good,
+            false => Grade::Again,
+        }
+    }
+}
+
+
+/// What the buttons of the grades are labeled with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GradeLabels {
+    /// The names of the grades; "Again", "Hard", ...
+    #[default]
+    Grades,
+
+    /// How well the user recalled the word; "I didn't know", "I knew", ...
+    Recall,
+}
+
+impl GradeLabels {
+    /// All the kinds of labels; the first one is the default
+    pub const ALL: [GradeLabels; 2] = [GradeLabels::Grades,
+                                       GradeLabels::Recall];
+
+    /// Returns the key of the name of the labels in `locale`
+    pub fn name(&self) -> &'static str {
+        match self {
+            GradeLabels::Grades => LABELS_GRADES,
+            GradeLabels::Recall => LABELS_RECALL,
+        }
+    }
+
+    /// Returns the key of the label of the button of `grade` in `locale`
+    pub fn label(&self, grade: Grade) -> &'static str {
+        match (self, grade) {
+            (GradeLabels::Grades, Grade::Again) => AGAIN_LABEL,
+            (GradeLabels::Grades, Grade::Hard)  => HARD_LABEL,
+            (GradeLabels::Grades, Grade::Good)  => GOOD_LABEL,
+            (GradeLabels::Grades, Grade::Easy)  => EASY_LABEL,
+            (GradeLabels::Recall, Grade::Again) => RECALL_AGAIN_LABEL,
+            (GradeLabels::Recall, Grade::Hard)  => RECALL_HARD_LABEL,
+            (GradeLabels::Recall, Grade::Good)  => RECALL_GOOD_LABEL,
+            (GradeLabels::Recall, Grade::Easy)  => RECALL_EASY_LABEL,
+        }
+    }
+}
+
+
+/// When the days of the reviews start.
+/// The timeouts are rounded down to the start of their day, so that a word
+/// answered late in the evening is due the next day (or the day after a week,
+/// ...) as soon as the day starts rather than at the very same time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DayStart {
+    /// The time of the day (in seconds since the midnight in UTC) the days
+    /// start at
+    offset: u64,
+}
+
+impl Default for DayStart {
+    fn default() -> Self {
+        Self::new(DEFAULT_DAY_START_HOUR, 0)
+    }
+}
+
+impl DayStart {
+    /// The days start at `hour` o'clock in the timezone `utc_offset` hours
+    /// away from UTC
+    pub fn new(hour: u8, utc_offset: i8) -> Self {
+        let offset = (hour as i64 - utc_offset as i64) * HOUR;
+        Self {
+            offset: offset.rem_euclid(DAY as i64) as u64,
+        }
+    }
+
+    /// Returns when (in seconds since the epoch) the day of `time` started
+    pub fn start(&self, time: u64) -> u64 {
+        ((time + DAY - self.offset) / DAY * DAY + self.offset)
+            .saturating_sub(DAY)
+    }
+
+    /// Returns when (in seconds since the epoch) the day after the one of
+    /// `time` starts
+    pub fn next(&self, time: u64) -> u64 {
+        self.start(time) + DAY
+    }
+}
+
+
+/// Returns whether `ladder` can be used to schedule the entries; it has
+/// between 1 and `MAX_LADDER_STEPS` steps and none of them is shorter than
+/// the one before
+pub fn is_valid_ladder(ladder: &[u64]) -> bool {
+    (1..=MAX_LADDER_STEPS).contains(&ladder.len())
+        && ladder.windows(2).all(|steps| steps[0] <= steps[1])
+}
+
+/// Parses a ladder written as days separated by commas or spaces, e.g.
+/// `0, 1, 7, 14, 30`.
+/// Returns `None` if it isn't a valid ladder.
+pub fn parse_ladder(text: &str) -> Option<Vec<u64>> {
+    let ladder = text.split(|ch: char| ch == ',' || ch.is_whitespace())
+        .filter(|step| !step.is_empty())
+        .map(|step| step.parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    Some(ladder).filter(|ladder| is_valid_ladder(ladder))
+}
+
+/// Returns `ladder` written the way `parse_ladder` reads it
+pub fn ladder_repr(ladder: &[u64]) -> String {
+    let steps: Vec<String> = ladder.iter().map(u64::to_string).collect();
+    steps.join(", ")
+}
+
+/// Returns the interval of `days` days made a bit shorter or longer at
+/// random, so that the words learned on the same day don't keep coming due
+/// on the same days; by up to `SHORT_FUZZ` of its first `FUZZ_SHORT_DAYS`
+/// days and `LONG_FUZZ` of the rest, e.g. a week by up to a day and two
+/// months by up to 6 days.
+/// The same `seed` (see `fuzz_seed`) always fuzzes an interval the same way.
+pub fn fuzz(days: u64, seed: u64) -> u64 {
+    if days < MIN_FUZZED_DAYS {
+        return days;
+    }
+    let short  = days.min(FUZZ_SHORT_DAYS);
+    let spread = short as f64 * SHORT_FUZZ + (days - short) as f64 * LONG_FUZZ;
+    let offset = (Rng::with_seed(seed).unit() * 2. - 1.) * spread;
+    ((days as f64 + offset).round() as u64).max(1)
+}
+
+/// Returns the seed of the fuzz of the entry with the id `id` that's
+/// scheduled to `timeout` (seconds since the epoch); every entry is fuzzed
+/// its own way, and differently on every review
+pub fn fuzz_seed(id: &str, timeout: u64) -> u64 {
+    // FNV-1a; unlike the hashers of std, it's the same in every build
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in id.bytes().chain(timeout.to_le_bytes()) {
+        hash ^= byte as u64;
+        hash  = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+
+/// The algorithms the reviews can be scheduled with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    /// `FixedScheduler`
+    #[default]
+    Fixed,
+
+    /// `Sm2Scheduler`
+    Sm2,
+
+    /// `fsrs::FsrsScheduler`
+    Fsrs,
+}
+
+impl Algorithm {
+    /// All the algorithms; the first one is the default
+    pub const ALL: [Algorithm; 3] = [Algorithm::Fixed, Algorithm::Sm2,
+                                     Algorithm::Fsrs];
+
+    /// Returns the key of the name of the algorithm in `locale`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Fixed => SCHEDULER_FIXED,
+            Algorithm::Sm2   => SCHEDULER_SM2,
+            Algorithm::Fsrs  => SCHEDULER_FSRS,
+        }
+    }
+
+    /// Returns the code of the algorithm, as it's written in the config
+    pub fn code(&self) -> &'static str {
+        match self {
+            Algorithm::Fixed => "fixed",
+            Algorithm::Sm2   => "sm2",
+            Algorithm::Fsrs  => "fsrs",
+        }
+    }
+}
+
+
+/// The scheduling of a deck that doesn't follow the one of the config; the
+/// algorithm and its parameters.
+/// The parameters that aren't set are the ones of the config.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preset {
+    /// The algorithm the reviews of the deck are scheduled with
+    pub algorithm: Algorithm,
+
+    /// The timeout ladder (in days) of the fixed scheduler; a valid one
+    pub ladder: Option<Vec<u64>>,
+
+    /// The chance of a recall the FSRS scheduler schedules the reviews for;
+    /// a valid one (see `fsrs::is_valid_retention`)
+    pub retention: Option<f64>,
+}
+
+impl Preset {
+    /// Creates a preset of `algorithm` with the parameters of the config
+    pub fn new(algorithm: Algorithm) -> Self {
+        Self {
+            algorithm,
+            ladder:    None,
+            retention: None,
+        }
+    }
+
+    /// Parses the preset of a deck written by `repr`; the algorithm, the
+    /// ladder, the retention and the name of the deck separated by `;`, e.g.
+    /// `fixed;0, 1, 3, 7;;Kanji` (the name may contain `;` itself).
+    /// Returns the name of the deck with the preset, or `None` if the
+    /// algorithm is unknown or a parameter isn't valid.
+    pub fn parse(text: &str) -> Option<(String, Self)> {
+        let mut split = text.splitn(4, PRESET_DELIMITER);
+        let algorithm = split.next()?.trim();
+        let ladder    = split.next()?.trim();
+        let retention = split.next()?.trim();
+        let deck      = split.next()?;
+
+        let preset = Self {
+            algorithm: Algorithm::ALL.iter()
+                .copied()
+                .find(|other| other.code() == algorithm)?,
+            ladder:    match ladder.is_empty() {
+                true  => None,
+                false => Some(parse_ladder(ladder)?),
+            },
+            retention: match retention.is_empty() {
+                true  => None,
+                false => Some(retention.parse().ok()
+                    .filter(|&retention| is_valid_retention(retention))?),
+            },
+        };
+        Some((deck.to_string(), preset))
+    }
+
+    /// Returns the preset of `deck` written the way `parse` reads it
+    pub fn repr(&self, deck: &str) -> String {
+        let ladder    = self.ladder.as_deref().map_or_else(String::new,
+                                                           ladder_repr);
+        let retention = self.retention.map_or_else(String::new,
+                                                   |r| r.to_string());
+        let values    = [self.algorithm.code(), &ladder, &retention, deck];
+        values.join(&PRESET_DELIMITER.to_string())
+    }
+}
+
+
+/// Something that schedules the next review of an entry
+pub trait Scheduler {
+    /// Updates the scheduling state and the timeout of `entry` after it was
+    /// graded with `grade` at `now` (seconds since the epoch).
+    /// `ladder` is the timeout ladder (in days) of the deck of the entry; a
+    /// valid one (see `is_valid_ladder`).
+    fn schedule(&self, entry: &mut Entry, grade: Grade, ladder: &[u64],
+                now: u64);
+}
+
+
+/// The original scheduler; a fixed ladder of timeouts.
+/// Every correct answer moves the entry one rung up the ladder (an easy one
+/// two rungs), every incorrect answer one rung down. A hard answer keeps the
+/// entry on its rung, only a bit longer.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FixedScheduler;
+
+impl Scheduler for FixedScheduler {
+    fn schedule(&self, entry: &mut Entry, grade: Grade, ladder: &[u64],
+                now: u64) {
+        // Update `cur_iter` and also don't overflow; the entry may be past
+        // the end of a ladder that was shortened since
+        let top = ladder.len() - 1;
+        entry.cur_iter = entry.cur_iter.min(top);
+        match grade {
+            Grade::Again => entry.cur_iter = entry.cur_iter.saturating_sub(1),
+            Grade::Hard  => {
+                // At least a day, so that the word isn't due right away
+                let delay = ladder[entry.cur_iter] * DAY;
+                let delay = (delay as f64 * HARD_FACTOR) as u64;
+                entry.timeout = now + delay.max(DAY);
+                return;
+            },
+            Grade::Good  => entry.cur_iter = (entry.cur_iter + 1).min(top),
+            Grade::Easy  => entry.cur_iter = (entry.cur_iter + 2).min(top),
+        }
+
+        entry.timeout = now + ladder[entry.cur_iter] * DAY;
+    }
+}
+
+
+/// The SM-2 algorithm (as used by SuperMemo 2).
+/// Every entry has its own ease factor which grows when the entry is easy to
+/// remember and shrinks when it isn't, and the interval grows by this factor
+/// with every correct answer. The timeout ladders aren't used.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sm2Scheduler;
+
+impl Sm2Scheduler {
+    /// The SM-2 quality (0-5) of a grade
+    fn quality(grade: Grade) -> f64 {
+        match grade {
+            Grade::Again => 1.,
+            Grade::Hard  => 3.,
+            Grade::Good  => 4.,
+            Grade::Easy  => 5.,
+        }
+    }
+}
+
+impl Scheduler for Sm2Scheduler {
+    fn schedule(&self, entry: &mut Entry, grade: Grade, _ladder: &[u64],
+                now: u64) {
+        let quality = Self::quality(grade);
+
+        if grade.is_correct() {
+            // A word studied ahead was only remembered for the time since its
+            // last review, not for its whole interval
+            let interval = match now < entry.timeout {
+                true  => ((now + entry.interval * DAY)
+                    .saturating_sub(entry.timeout) / DAY).max(1),
+                false => entry.interval,
+            };
+            let good = match entry.reps {
+                0 => 1,
+                1 => 6,
+                _ => (interval as f64 * entry.ease).round() as u64,
+            };
+            entry.interval = match grade {
+                Grade::Hard => {
+                    ((interval as f64 * HARD_FACTOR).round() as u64).max(1)
+                },
+                Grade::Easy => (good as f64 * EASY_FACTOR).round() as u64,
+                _           => good,
+            };
+            entry.reps += 1;
+        } else {
+            entry.reps     = 0;
+            entry.interval = 1;
+        }
+
+        let penalty = 5. - quality;
+        entry.ease  = (entry.ease + 0.1 - penalty * (0.08 + penalty * 0.02))
+            .max(MIN_EASE);
+        entry.timeout = now + entry.interval * DAY;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzz_keeps_short_intervals_and_repeats_for_a_seed() {
+        for days in 0..MIN_FUZZED_DAYS {
+            assert_eq!(fuzz(days, 825), days);
+        }
+
+        let seed = fuzz_seed("3f2a", 1_600_000_000);
+        assert_eq!(seed, fuzz_seed("3f2a", 1_600_000_000));
+        assert_ne!(seed, fuzz_seed("3f2a", 1_600_000_000 + DAY));
+        assert_ne!(seed, fuzz_seed("3f2b", 1_600_000_000));
+        assert_eq!(fuzz(40, seed), fuzz(40, seed));
+    }
+
+    #[test]
+    fn fuzz_spreads_intervals_within_their_bounds() {
+        // A week by up to a day, two months by up to 6 days
+        for &(days, spread) in [(7, 1), (60, 6)].iter() {
+            let mut fuzzed = std::collections::HashSet::new();
+            for seed in 0..1000 {
+                let interval = fuzz(days, fuzz_seed("", seed));
+                assert!(interval + spread >= days && interval <= days + spread,
+                        "{} fuzzed to {}", days, interval);
+                fuzzed.insert(interval);
+            }
+            assert_eq!(fuzzed.len() as u64, spread * 2 + 1);
+        }
+    }
+}
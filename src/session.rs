@@ -0,0 +1,340 @@
+//! A review session; the words reviewed since the session started.
+//! The session also enforces the daily limits of new words and reviews
+//! stored in the metadata of the database.
+
+use crate::db::{Batches, Database, Entry, now};
+use crate::config::Config;
+use crate::achievements::Achievement;
+use crate::scheduler::{Algorithm, DayStart, FixedScheduler, Grade,
+                       Scheduler, Sm2Scheduler};
+use crate::fsrs::{FsrsScheduler, DEFAULT_WEIGHTS, WEIGHT_COUNT};
+
+
+/// The state of a review session
+#[derive(Clone, Debug)]
+pub struct Session {
+    /// If set, the session is over after this many answers
+    pub size: Option<usize>,
+
+    /// When (in seconds since the epoch) the session started
+    pub started: u64,
+
+    /// The answers given in this session (whether they were correct), the
+    /// latest one last
+    pub answers: Vec<bool>,
+
+    /// The daily limit of new words used if the database doesn't set its own
+    pub new_limit: Option<u64>,
+
+    /// The daily limit of reviews used if the database doesn't set its own
+    pub review_limit: Option<u64>,
+}
+
+/// What happened in a finished session
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    /// The number of answers
+    pub reviewed: usize,
+
+    /// The share of correct answers (0-1), or `None` if there weren't any
+    pub accuracy: Option<f64>,
+
+    /// How long (in seconds) the session took
+    pub duration: u64,
+}
+
+/// What an answer led to (see `Session::answer`), for the frontend to tell
+/// the user
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Answered {
+    /// Whether the entry was suspended as a leech
+    pub leech: bool,
+
+    /// The achievements unlocked by the answer
+    pub achievements: Vec<Achievement>,
+}
+
+impl Session {
+    /// Starts a new session at `now` (seconds since the epoch) that ends
+    /// after `size` answers, if set
+    pub fn new(size: Option<usize>, now: u64) -> Self {
+        Self {
+            size,
+            started:      now,
+            answers:      Vec::new(),
+            new_limit:    None,
+            review_limit: None,
+        }
+    }
+
+    /// Returns the next entry of `db` to review at `now` (seconds since the
+    /// epoch) and its index, just like `Database::random_entry`.
+    /// Returns `None` once the session is over; either because it reached its
+    /// size, because both of the daily limits were reached or because there's
+    /// nothing usable left.
+    pub fn next_entry(&mut self, db: &mut Database, now: u64)
+            -> Option<(Entry, usize)> {
+        if self.size.is_some_and(|size| self.answers.len() >= size) {
+            return None;
+        }
+
+        // Cramming isn't limited; it doesn't count as reviewing
+        if db.is_cramming() {
+            db.serve_new     = true;
+            db.serve_reviews = true;
+            return db.random_entry();
+        }
+
+        // Only serve the kinds of words whose daily limit wasn't reached
+        let (new, reviews) = db.meta.done_today(now);
+        let new_limit      = db.meta.new_limit.or(self.new_limit);
+        let review_limit   = db.meta.review_limit.or(self.review_limit);
+        db.serve_new     = new_limit.is_none_or(|limit| new < limit);
+        db.serve_reviews = review_limit.is_none_or(|limit| reviews < limit);
+        db.random_entry()
+    }
+
+    /// Starts the session over at `now` (seconds since the epoch), keeping
+    /// its size and limits
+    pub fn restart(&mut self, now: u64) {
+        self.started = now;
+        self.answers.clear();
+    }
+
+    /// Records an answer given in this session
+    pub fn record(&mut self, correct: bool) {
+        self.answers.push(correct);
+    }
+
+    /// Grades the `index`th usable entry of `db` with `grade`, answered in
+    /// `response` milliseconds, and records the answer.
+    /// While cramming, the answer only counts towards the session.
+    pub fn answer(&mut self, db: &mut Database, index: usize, grade: Grade,
+                  response: Option<u64>) -> Answered {
+        let leech = !db.is_cramming()
+            && db.update_timeout(index, grade, response);
+        self.record(grade.is_correct());
+        Answered {
+            leech,
+            achievements: db.check_achievements(),
+        }
+    }
+
+    /// Forgets the last answer given in this session, e.g. because it was
+    /// undone
+    pub fn undo(&mut self) {
+        self.answers.pop();
+    }
+
+    /// Returns the summary of the session at `now` (seconds since the epoch)
+    pub fn summary(&self, now: u64) -> Summary {
+        let reviewed = self.answers.len();
+        let correct  = self.answers.iter().filter(|&&correct| correct).count();
+        let accuracy = match reviewed {
+            0 => None,
+            _ => Some(correct as f64 / reviewed as f64),
+        };
+
+        Summary {
+            reviewed,
+            accuracy,
+            duration: now.saturating_sub(self.started),
+        }
+    }
+}
+
+
+/// Sets `db` up for reviewing with `config` and returns the review session.
+/// This is what every frontend does before serving the first word.
+pub fn prepare(db: &mut Database, config: &Config) -> Session {
+    db.batches = config.new_word_batch_size.map(Batches::new);
+    set_scheduler(db, config);
+    db.set_direction(config.direction, config.separate_directions);
+    db.both_directions = config.both_directions;
+    db.leech_threshold = Some(config.leech_threshold)
+        .filter(|&threshold| threshold != 0);
+    db.relearn_steps   = config.relearn_steps.clone();
+    db.relearn_cap     = config.relearn_cap;
+    db.set_ladders(&config.ladder, &config.deck_ladders);
+    db.set_deck_new_limits(&config.deck_new_limits);
+    db.set_two_way_decks(&config.two_way_decks);
+    db.day_start = DayStart::new(config.day_start_hour, config.utc_offset);
+
+    let mut session      = Session::new(config.session_size, now());
+    session.new_limit    = config.new_limit;
+    session.review_limit = config.review_limit;
+    session
+}
+
+/// Gives `db` the scheduler of the algorithm picked in `config`, and the
+/// decks with a preset in the metadata of `db` the schedulers of theirs.
+/// The FSRS scheduler uses the weights fitted to the history of `db` (see
+/// `Database::optimize_fsrs`), or the default ones if there are none yet.
+/// The ladders of the presets are set up by `Database::set_ladders`.
+pub fn set_scheduler(db: &mut Database, config: &Config) {
+    let weights  = db.meta.fsrs_weights.unwrap_or(DEFAULT_WEIGHTS);
+    db.scheduler = scheduler(config.scheduler, weights, config.retention);
+    db.deck_schedulers = db.meta.deck_presets.iter()
+        .map(|(deck, preset)| {
+            let retention = preset.retention.unwrap_or(config.retention);
+            (deck.clone(), scheduler(preset.algorithm, weights, retention))
+        })
+        .collect();
+}
+
+/// Returns the scheduler of `algorithm`; FSRS with `weights` and
+/// `retention`
+fn scheduler(algorithm: Algorithm, weights: [f64; WEIGHT_COUNT],
+             retention: f64) -> Box<dyn Scheduler> {
+    match algorithm {
+        Algorithm::Fixed => Box::new(FixedScheduler),
+        Algorithm::Sm2   => Box::new(Sm2Scheduler),
+        Algorithm::Fsrs  => Box::new(FsrsScheduler::new(weights, retention)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Direction;
+    use crate::testing::TempDir;
+
+    /// Opens a database of `lines` in `dir`
+    fn open(dir: &TempDir, lines: &[&str]) -> Database {
+        let contents = lines.iter()
+            .map(|line| format!("{}\n", line))
+            .collect::<String>();
+        Database::open(dir.write("words.txt", contents.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn the_first_word_passes_the_filters_like_the_others() {
+        let dir    = TempDir::new("first-word-filters");
+        let mut db = open(&dir, &[
+            "Hund;; dog;; 0;; 0;; deck=German",
+            "pes;; dog;; 0;; 0;; deck=Czech",
+            "Katze;; cat;; 0;; 0;; deck=German",
+            "kočka;; cat;; 0;; 0;; deck=Czech",
+        ]);
+        db.active_deck = Some("German".to_string());
+        let mut session = prepare(&mut db, &Config::default());
+
+        let mut served = Vec::new();
+        while let Some((entry, idx)) = session.next_entry(&mut db, now()) {
+            served.push(entry.word.clone());
+            db.update_timeout(idx, Grade::Easy, None);
+            session.record(true);
+        }
+        served.sort();
+        assert_eq!(served, vec!["Hund", "Katze"]);
+    }
+
+    #[test]
+    fn the_directions_share_a_schedule_unless_configured_otherwise() {
+        let dir = TempDir::new("separate-directions");
+        for &separate in [true, false].iter() {
+            let mut db = open(&dir, &["Hund;; dog;; 2;; 1"]);
+            let config = Config {
+                direction:           Direction::Reverse,
+                separate_directions: separate,
+                ..Config::default()
+            };
+            let mut session = prepare(&mut db, &config);
+            assert_eq!(db.separates_directions(), separate);
+
+            // The reverse direction of a word reviewed only forward is new
+            let (entry, _) = session.next_entry(&mut db, now()).unwrap();
+            let cur_iter   = match separate {
+                true  => 0,
+                false => 2,
+            };
+            assert_eq!(entry.is_new(), separate);
+            assert_eq!(entry.cur_iter, cur_iter);
+            db.flush_and_close().unwrap();
+        }
+    }
+
+    #[test]
+    fn the_session_ends_after_the_configured_size() {
+        let dir    = TempDir::new("session-size");
+        let mut db = open(&dir, &[
+            "Hund;; dog;; 0;; 0",
+            "Katze;; cat;; 0;; 0",
+            "Maus;; mouse;; 0;; 0",
+        ]);
+        let config = Config {
+            session_size: Some(2),
+            ..Config::default()
+        };
+        let mut session = prepare(&mut db, &config);
+        assert_eq!(session.size, Some(2));
+
+        for correct in [true, false].iter() {
+            let (_, idx) = session.next_entry(&mut db, now()).unwrap();
+            db.update_timeout(idx, Grade::Good, None);
+            session.record(*correct);
+        }
+        assert!(session.next_entry(&mut db, now()).is_none());
+        assert_eq!(db.usable.len(), 1);
+
+        let summary = session.summary(now());
+        assert_eq!(summary.reviewed, 2);
+        assert_eq!(summary.accuracy, Some(0.5));
+    }
+
+    #[test]
+    fn a_batch_of_new_words_is_learned_and_then_quizzed() {
+        let dir    = TempDir::new("new-word-batches");
+        let mut db = open(&dir, &[
+            "Hund;; dog;; 0;; 0",
+            "Katze;; cat;; 0;; 0",
+            "Maus;; mouse;; 0;; 0",
+        ]);
+        let config = Config {
+            new_word_batch_size: Some(2),
+            ..Config::default()
+        };
+        let mut session = prepare(&mut db, &config);
+
+        // The words being learned aren't graded, just like in the GUI
+        let mut served = Vec::new();
+        for _ in 0..4 {
+            let (entry, idx) = session.next_entry(&mut db, now()).unwrap();
+            let learning     = db.is_learning();
+            if !learning {
+                db.update_timeout(idx, Grade::Easy, None);
+            }
+            served.push((entry.word, learning));
+        }
+
+        let (learned, quizzed) = served.split_at(2);
+        assert!(learned.iter().all(|(_, learning)| *learning));
+        assert!(quizzed.iter().all(|(_, learning)| !*learning));
+        assert_ne!(learned[0].0, learned[1].0);
+        assert_eq!(learned.iter().map(|(word, _)| word).collect::<Vec<_>>(),
+                   quizzed.iter().map(|(word, _)| word).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn an_answer_while_cramming_only_counts_towards_the_session() {
+        let dir         = TempDir::new("answer-cram");
+        let mut db      = open(&dir, &["Hund;; dog;; 0;; 0"]);
+        let mut session = prepare(&mut db, &Config::default());
+
+        db.set_cram(true);
+        let (_, idx) = session.next_entry(&mut db, now()).unwrap();
+        let answered = session.answer(&mut db, idx, Grade::Good, None);
+        assert!(!answered.leech);
+        assert!(db.usable[0].is_new());
+        assert_eq!(session.answers, vec![true]);
+
+        db.set_cram(false);
+        let (_, idx) = session.next_entry(&mut db, now()).unwrap();
+        session.answer(&mut db, idx, Grade::Again, None);
+        assert_eq!(session.answers, vec![true, false]);
+        assert!(db.usable.iter()
+            .chain(db.unusable.iter())
+            .all(|entry| !entry.is_new()));
+    }
+}
@@ -0,0 +1,419 @@
+//! This module handles the review history and the statistics computed from
+//! it. Every answer is appended to a history file next to the database, so
+//! the history survives any changes to the schedule of the entries.
+
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use crate::db::{Database, Entry, DAY, DELIMITER, escape, split_line};
+use crate::db::write_atomic;
+use crate::locale::*;
+use crate::paths;
+
+/// Entries with an interval of at least this many days are mature
+pub const MATURE_INTERVAL: u64 = 21;
+
+/// Longer response times (in milliseconds) count as this long, so that a card
+/// left on the screen while away doesn't skew the averages
+pub const MAX_RESPONSE: u64 = 60_000;
+
+/// Words answered on average at least this many times slower than all the
+/// words are slow
+pub const SLOW_FACTOR: u64 = 2;
+
+/// The longest intervals (in days) of the buckets the retention is counted
+/// in; the intervals longer than the last one make a bucket of their own
+pub const RETENTION_BUCKETS: [u64; 4] = [1, 7, 14, 30];
+
+
+/// A single answer in the review history
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Review {
+    /// When (in seconds since the epoch) the answer was given
+    pub time: u64,
+
+    /// Whether the user knew the word
+    pub correct: bool,
+
+    /// The number of days until the entry was due again after the answer
+    pub interval: u64,
+
+    /// The original word of the entry
+    pub word: String,
+
+    /// The translated word of the entry
+    pub tr_word: String,
+
+    /// How long (in milliseconds) the card was shown before it was answered,
+    /// if known
+    pub response: Option<u64>,
+}
+
+impl Review {
+    /// Parses a line of the history file.
+    /// The response time is optional; the older answers don't have it.
+    /// Returns `None` if the line is broken.
+    pub fn parse_from_line(line: &str) -> Option<Self> {
+        let columns = split_line(line);
+        if columns.len() != 5 && columns.len() != 6 {
+            return None;
+        }
+        let response = match columns.get(5) {
+            Some(response) => Some(response.trim().parse().ok()?),
+            None           => None,
+        };
+
+        Some(Self {
+            time:     columns[0].trim().parse().ok()?,
+            correct:  columns[1].trim() == "1",
+            interval: columns[2].trim().parse().ok()?,
+            word:     columns[3].clone(),
+            tr_word:  columns[4].clone(),
+            response,
+        })
+    }
+
+    /// Returns the in-history representation of this review
+    pub fn history_repr(&self) -> String {
+        let mut repr = format!("{}{}{}{}{}{}{}{}{}",
+                               self.time,              DELIMITER,
+                               self.correct as u8,     DELIMITER,
+                               self.interval,          DELIMITER,
+                               escape(&self.word),     DELIMITER,
+                               escape(&self.tr_word));
+        if let Some(response) = self.response {
+            repr.push_str(&format!("{}{}", DELIMITER, response));
+        }
+        repr
+    }
+}
+
+
+/// The review history of a database.
+/// The answers are collected in memory and appended to the history file when
+/// the database is written.
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    /// Path to the history file
+    pub path: PathBuf,
+
+    /// The answers that haven't been written to the file yet
+    pending: Vec<Review>,
+}
+
+impl History {
+    /// Creates the history of database `db`.
+    /// Nothing is read; the history file is only ever appended to.
+    pub fn new(db: &Path) -> Self {
+        Self {
+            path:    paths::history_path(db),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Remembers an answer until the next `save`
+    pub fn record(&mut self, review: Review) {
+        self.pending.push(review);
+    }
+
+    /// Appends the remembered answers to the history file
+    pub fn save(&mut self) -> std::io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut records = String::new();
+        for review in self.pending.iter() {
+            records.push_str(&format!("{}\n", review.history_repr()));
+        }
+
+        OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&self.path)?
+            .write_all(records.as_bytes())?;
+        self.pending.clear();
+        Ok(())
+    }
+
+    /// Removes the last recorded answer, e.g. because it was undone.
+    /// If it was already written, it is cut off the end of the history file.
+    pub fn remove_last(&mut self) -> std::io::Result<()> {
+        if self.pending.pop().is_some() {
+            return Ok(());
+        }
+
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        let mut lines: Vec<&str> = contents.lines().collect();
+        lines.pop();
+
+        let mut contents = lines.join("\n");
+        if !contents.is_empty() {
+            contents.push('\n');
+        }
+        write_atomic(&self.path, contents.as_bytes())
+    }
+
+    /// Reads all the answers in the history file, including the ones that
+    /// haven't been written yet. Broken lines are skipped.
+    pub fn load(&self) -> std::io::Result<Vec<Review>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+
+        let mut reviews: Vec<Review> = contents.lines()
+            .filter_map(Review::parse_from_line)
+            .collect();
+        reviews.extend(self.pending.iter().cloned());
+        Ok(reviews)
+    }
+}
+
+
+/// Statistics of a database and its review history
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of reviews on each of the last few days, the oldest first,
+    /// as pairs of (days since the epoch, reviews)
+    pub daily: Vec<(u64, usize)>,
+
+    /// The total number of answers
+    pub reviews: usize,
+
+    /// The number of correct answers
+    pub correct: usize,
+
+    /// The number of entries that were never reviewed
+    pub new: usize,
+
+    /// The number of reviewed entries that aren't mature yet
+    pub young: usize,
+
+    /// The number of entries with an interval of at least `MATURE_INTERVAL`
+    pub mature: usize,
+
+    /// The number of answers with a known response time
+    pub timed: usize,
+
+    /// The total response time (in milliseconds) of the timed answers, each
+    /// one at most `MAX_RESPONSE`
+    pub response_time: u64,
+
+    /// The words answered abnormally slowly (see `SLOW_FACTOR`), as pairs of
+    /// (word, average response time in milliseconds), the slowest first
+    pub slow: Vec<(String, u64)>,
+
+    /// The answers by the intervals the words had; see
+    /// `retention_by_interval`
+    pub retention: Vec<RetentionBucket>,
+}
+
+impl Stats {
+    /// Computes the statistics of `db` with its review `history` at `now`
+    /// (seconds since the epoch); the daily counts go `days` days back.
+    pub fn compute(db: &Database, history: &[Review], now: u64, days: u64)
+            -> Self {
+        let today     = now / DAY;
+        let first_day = (today + 1).saturating_sub(days);
+        let mut stats = Self {
+            daily: (first_day..=today).map(|day| (day, 0)).collect(),
+            ..Self::default()
+        };
+
+        // The total response time and the number of timed answers of every
+        // word
+        let mut responses: HashMap<&str, (u64, u64)> = HashMap::new();
+        for review in history {
+            stats.reviews += 1;
+            stats.correct += review.correct as usize;
+
+            let day = review.time / DAY;
+            if (first_day..=today).contains(&day) {
+                stats.daily[(day - first_day) as usize].1 += 1;
+            }
+
+            if let Some(response) = review.response {
+                let response = response.min(MAX_RESPONSE);
+                stats.timed         += 1;
+                stats.response_time += response;
+
+                let word = responses.entry(&review.word).or_insert((0, 0));
+                word.0 += response;
+                word.1 += 1;
+            }
+        }
+
+        if let Some(average) = stats.average_response() {
+            stats.slow = responses.into_iter()
+                .map(|(word, (total, count))| (word.to_string(), total / count))
+                .filter(|(_, response)| *response >= average * SLOW_FACTOR)
+                .collect();
+            stats.slow.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        }
+
+        stats.retention = retention_by_interval(history);
+        for entry in db.usable.iter().chain(db.unusable.iter()) {
+            if entry.is_new() {
+                stats.new += 1;
+            } else if db.interval(entry) >= MATURE_INTERVAL {
+                stats.mature += 1;
+            } else {
+                stats.young += 1;
+            }
+        }
+
+        stats
+    }
+
+    /// Returns the share of correct answers (0-1), or `None` if there were
+    /// no answers yet
+    pub fn accuracy(&self) -> Option<f64> {
+        if self.reviews == 0 {
+            return None;
+        }
+        Some(self.correct as f64 / self.reviews as f64)
+    }
+
+    /// Returns the average response time (in milliseconds), or `None` if no
+    /// answer was timed yet
+    pub fn average_response(&self) -> Option<u64> {
+        if self.timed == 0 {
+            return None;
+        }
+        Some(self.response_time / self.timed as u64)
+    }
+}
+
+/// The answers of the words with an interval in a range, for telling how well
+/// the words are retained over that long
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RetentionBucket {
+    /// The longest interval (in days) in the bucket, or `None` for the
+    /// intervals longer than all of `RETENTION_BUCKETS`
+    pub max_interval: Option<u64>,
+
+    /// The number of answers
+    pub reviews: usize,
+
+    /// The number of correct answers
+    pub correct: usize,
+}
+
+impl RetentionBucket {
+    /// Returns the share of correct answers (0-1), or `None` if there were
+    /// no answers
+    pub fn retention(&self) -> Option<f64> {
+        if self.reviews == 0 {
+            return None;
+        }
+        Some(self.correct as f64 / self.reviews as f64)
+    }
+}
+
+/// Returns the answers of the review `history` (the oldest first) in the
+/// buckets of the intervals the words had when they were answered, one
+/// bucket for each of `RETENTION_BUCKETS` and one for the longer intervals.
+/// The interval a word had is the one its previous answer gave it, so the
+/// first answers of the words aren't counted; nothing was retained yet.
+/// A low retention in the buckets of the long intervals means that the
+/// schedule stretches them too far.
+pub fn retention_by_interval(history: &[Review]) -> Vec<RetentionBucket> {
+    let mut buckets: Vec<RetentionBucket> = RETENTION_BUCKETS.iter()
+        .map(|&max| Some(max))
+        .chain(std::iter::once(None))
+        .map(|max_interval| RetentionBucket {
+            max_interval,
+            ..RetentionBucket::default()
+        })
+        .collect();
+
+    // The interval each word got from its latest answer so far
+    let mut intervals: HashMap<(&str, &str), u64> = HashMap::new();
+    for review in history {
+        let key = (review.word.as_str(), review.tr_word.as_str());
+        if let Some(interval) = intervals.insert(key, review.interval) {
+            let bucket = RETENTION_BUCKETS.iter()
+                .position(|&max| interval <= max)
+                .unwrap_or(RETENTION_BUCKETS.len());
+            buckets[bucket].reviews += 1;
+            buckets[bucket].correct += review.correct as usize;
+        }
+    }
+    buckets
+}
+
+/// Returns the current interval (in days) of `entry` scheduled with the
+/// timeout `ladder`.
+/// The SM-2 and FSRS schedulers keep their own interval; the fixed ladder
+/// only knows the rung the entry is on.
+pub fn interval(entry: &Entry, ladder: &[u64]) -> u64 {
+    if entry.interval != 0 {
+        return entry.interval;
+    }
+    ladder[entry.cur_iter.min(ladder.len() - 1)]
+}
+
+/// Converts a day (in days since the epoch) to a (year, month, day) date
+pub fn date(day: u64) -> (u64, u64, u64) {
+    // Howard Hinnant's `civil_from_days`, restricted to dates after the epoch
+    let z   = day + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp  = (5 * doy + 2) / 153;
+    let d   = doy - (153 * mp + 2) / 5 + 1;
+    let m   = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y   = yoe + era * 400 + (m <= 2) as u64;
+    (y, m, d)
+}
+
+/// Formats a time in seconds since the epoch as its date, e.g. `1. 5. 2024`,
+/// or as `-` if it's 0 (unknown)
+pub fn date_label(time: u64) -> String {
+    if time == 0 {
+        return "-".to_string();
+    }
+    let (year, month, day) = date(time / DAY);
+    format!("{}. {}. {}", day, month, year)
+}
+
+/// Formats a time in milliseconds as seconds, e.g. `4.2 s`
+pub fn seconds_label(millis: u64) -> String {
+    format!("{:.1} s", millis as f64 / 1000.)
+}
+
+/// Returns when `entry` is due at `now` (seconds since the epoch); its date,
+/// "now" or "new"
+pub fn due_label(entry: &Entry, now: u64) -> String {
+    if entry.is_new() {
+        tr(BROWSER_NEW).to_string()
+    } else if entry.time_until_due(now) == 0 {
+        tr(BROWSER_DUE_NOW).to_string()
+    } else {
+        date_label(entry.timeout)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_the_dates_and_the_times() {
+        assert_eq!(date(0), (1970, 1, 1));
+        assert_eq!(date(19_844), (2024, 5, 1));
+        assert_eq!(date_label(0), "-");
+        assert_eq!(date_label(19_844 * DAY + 3600), "1. 5. 2024");
+        assert_eq!(seconds_label(4240), "4.2 s");
+    }
+}
@@ -0,0 +1,237 @@
+//! Syncing a database with its copy on a WebDAV server (e.g. a Nextcloud
+//! folder the phone syncs too), which is the copy all the devices share
+//! (only with the `sync` feature).
+//! The copies live in a folder on the server (`Config::sync_url`), each
+//! named after its database.
+//!
+//! A sync pulls the copy from the server (if there's one yet), merges it into
+//! the database (see `Database::merge`), writes the database and pushes it
+//! back in place of the copy:
+//!
+//! ```text
+//! GET <url>                         -> merge -> write_db
+//! PUT <url>, If-Match: <the ETag>
+//! ```
+//!
+//! The push only replaces the very copy that was pulled. If another device
+//! pushed in between, the sync starts over with the newer copy, at most
+//! `ATTEMPTS` times.
+//!
+//! The copy is the database file alone, in the format of the database; the
+//! metadata, the history and the schedules of the profiles aren't synced.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::Duration;
+use ureq::{Agent, AgentBuilder, Request};
+use crate::config::Config;
+use crate::db::{Database, MergeSummary};
+use crate::error::Result;
+
+/// The number of times a sync is tried before it gives up on the other
+/// devices pushing in between
+pub const ATTEMPTS: usize = 3;
+
+/// How long a request may take before the server is given up on
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The status of a push whose precondition failed; the copy changed
+const PRECONDITION_FAILED: u16 = 412;
+
+/// The status of a pull of a copy that isn't there yet
+const NOT_FOUND: u16 = 404;
+
+/// The characters of a file name that are put into a URL as they are
+const URL_SAFE: &str = "-._~";
+
+/// The characters of base64, by their values
+const BASE64: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+
+/// The copy of a database on a WebDAV server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Remote {
+    /// The URL of the copy of the database file on the server
+    pub url: String,
+
+    /// The user signing in to the server, or "" if it doesn't need one
+    pub user: String,
+
+    /// The password of the user; preferably an app password that can be
+    /// revoked on its own
+    pub password: String,
+}
+
+impl Remote {
+    /// Returns the copy of the database at `db` in the folder of `config`,
+    /// or `None` if syncing isn't set up
+    pub fn from_config(config: &Config, db: &Path) -> Option<Self> {
+        let folder = config.sync_url.trim().trim_end_matches('/');
+        let name   = db.file_name()?.to_string_lossy();
+        if folder.is_empty() {
+            return None;
+        }
+        Some(Self {
+            url:      format!("{}/{}", folder, encode_url(&name)),
+            user:     config.sync_user.clone(),
+            password: config.sync_password.clone(),
+        })
+    }
+
+    /// Returns a request of `method` to the copy, signed in if there's a user
+    fn request(&self, agent: &Agent, method: &str) -> Request {
+        let request = agent.request(method, &self.url);
+        if self.user.is_empty() {
+            return request;
+        }
+        let credentials = format!("{}:{}", self.user, self.password);
+        request.set("Authorization",
+                    &format!("Basic {}", base64(credentials.as_bytes())))
+    }
+}
+
+
+/// What a push may only replace
+enum Precondition {
+    /// The copy with this ETag
+    Match(String),
+
+    /// No copy at all; the database is pushed for the first time
+    Missing,
+
+    /// Whatever is there; the server doesn't tell the copies apart
+    Any,
+}
+
+
+/// Percent-encodes `name` (a file name) for a URL
+fn encode_url(name: &str) -> String {
+    let mut encoded = String::new();
+    for byte in name.bytes() {
+        match byte.is_ascii_alphanumeric() || URL_SAFE.contains(byte as char) {
+            true  => encoded.push(byte as char),
+            false => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Encodes `bytes` in base64 (with padding), for the basic authentication
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let triple = chunk.iter()
+            .enumerate()
+            .fold(0u32, |triple, (idx, &byte)| {
+                triple | ((byte as u32) << (16 - 8 * idx))
+            });
+        for idx in 0..4 {
+            match idx <= chunk.len() {
+                true  => {
+                    let value = (triple >> (18 - 6 * idx)) & 0x3f;
+                    encoded.push(BASE64[value as usize] as char);
+                },
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
+
+/// Converts the error of a request into an `io::Error` so that the sync has
+/// the error type of the rest of the database code
+fn io_error(err: ureq::Error) -> io::Error {
+    match err {
+        ureq::Error::Status(status, response) => {
+            io::Error::other(format!("{} {}", status, response.status_text()))
+        },
+        ureq::Error::Transport(transport) => io::Error::other(transport),
+    }
+}
+
+/// Downloads the copy; returns its contents with its ETag (if the server
+/// has one), or `None` if there's no copy yet
+fn pull(remote: &Remote, agent: &Agent)
+        -> io::Result<Option<(Vec<u8>, Option<String>)>> {
+    let response = match remote.request(agent, "GET").call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(NOT_FOUND, _)) => return Ok(None),
+        Err(err) => return Err(io_error(err)),
+    };
+
+    let etag         = response.header("ETag").map(str::to_string);
+    let mut contents = Vec::new();
+    response.into_reader().read_to_end(&mut contents)?;
+    Ok(Some((contents, etag)))
+}
+
+/// Uploads `contents` in place of the copy, unless the copy doesn't meet
+/// `precondition` anymore.
+/// Returns false if it doesn't; someone else pushed in between.
+fn push(remote: &Remote, agent: &Agent, contents: &[u8],
+        precondition: &Precondition) -> io::Result<bool> {
+    let request = remote.request(agent, "PUT");
+    let request = match precondition {
+        Precondition::Match(etag) => request.set("If-Match", etag),
+        Precondition::Missing     => request.set("If-None-Match", "*"),
+        Precondition::Any         => request,
+    };
+    match request.send_bytes(contents) {
+        Ok(_) => Ok(true),
+        Err(ureq::Error::Status(PRECONDITION_FAILED, _)) => Ok(false),
+        Err(err) => Err(io_error(err)),
+    }
+}
+
+/// Merges the pulled `contents` of the copy into `db`.
+/// The backend is picked by the extension, so the copy is read from
+/// a temporary file with the name of the database.
+fn merge(db: &mut Database, contents: &[u8]) -> Result<MergeSummary> {
+    let dir = std::env::temp_dir()
+        .join(format!("wordpal-sync-{}", std::process::id()));
+    let tmp = dir.join(db.path.file_name().unwrap_or_default());
+    fs::create_dir_all(&dir)?;
+    fs::write(&tmp, contents)?;
+
+    let copy = Database::open_read_only(tmp);
+    fs::remove_dir_all(&dir)?;
+    Ok(db.merge(&copy?))
+}
+
+/// Syncs `db` with its copy on the server; pulls the copy, merges it into the
+/// database, writes the database and pushes it back.
+/// A read-only database is only merged with the copy, without writing or
+/// pushing anything.
+/// Returns what the copy changed in the database; the summaries of all the
+/// attempts if the copy was pushed to in between.
+pub fn sync(db: &mut Database, remote: &Remote) -> Result<MergeSummary> {
+    let agent       = AgentBuilder::new().timeout(TIMEOUT).build();
+    let mut summary = MergeSummary::default();
+
+    for _ in 0..ATTEMPTS {
+        // Whatever wasn't written yet is pushed as well
+        db.write_db()?;
+
+        let precondition = match pull(remote, &agent)? {
+            Some((contents, etag)) => {
+                let merged = merge(db, &contents)?;
+                summary.added    += merged.added;
+                summary.updated  += merged.updated;
+                summary.unchanged = merged.unchanged;
+                etag.map_or(Precondition::Any, Precondition::Match)
+            },
+            None => Precondition::Missing,
+        };
+        if db.is_read_only() {
+            return Ok(summary);
+        }
+
+        db.write_db()?;
+        if push(remote, &agent, &fs::read(&db.path)?, &precondition)? {
+            return Ok(summary);
+        }
+    }
+    Err(io::Error::other("the copy on the server keeps changing").into())
+}
@@ -0,0 +1,45 @@
+//! Helpers of the tests; scratch databases in the temporary directory.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Tells apart the directories of the tests running at once
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+
+/// A directory of its own for a test, removed with everything in it when
+/// it's dropped
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates an empty directory named after the test
+    pub fn new(name: &str) -> Self {
+        let count = COUNTER.fetch_add(1, Ordering::SeqCst);
+        let path  = std::env::temp_dir().join(format!(
+            "wordpal-test-{}-{}-{}", std::process::id(), count, name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    /// Returns the path of `file` in the directory
+    pub fn path(&self, file: &str) -> PathBuf {
+        self.path.join(file)
+    }
+
+    /// Writes `contents` into `file` in the directory and returns its path
+    pub fn write(&self, file: &str, contents: &[u8]) -> PathBuf {
+        let path = self.path(file);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,120 @@
+//! The color schemes of the user interface.
+//! Every theme has a palette of all the colors the user interface uses,
+//! including the ones of correct and incorrect answers, so that there can be
+//! schemes that don't rely on telling red from green.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use serde::{Deserialize, Serialize};
+use crate::locale::{THEME_LIGHT, THEME_DARK, THEME_COLORBLIND};
+
+/// The index of the current theme in `Theme::ALL`
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+/// A color as (red, green, blue), each in 0-1
+pub type Rgb = [f32; 3];
+
+
+/// The colors of a theme
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    /// The background of the window
+    pub background: Rgb,
+
+    /// The text on the background
+    pub text: Rgb,
+
+    /// The background of the buttons and the inputs
+    pub surface: Rgb,
+
+    /// The borders of the buttons and the inputs
+    pub border: Rgb,
+
+    /// The placeholders of empty inputs
+    pub placeholder: Rgb,
+
+    /// The button and the characters of correct answers
+    pub correct: Rgb,
+
+    /// The button and the characters of incorrect answers
+    pub incorrect: Rgb,
+}
+
+
+/// A color scheme of the user interface
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Dark text on a light background
+    #[default]
+    Light,
+
+    /// Light text on a dark background
+    Dark,
+
+    /// Light, with blue and orange instead of green and red
+    Colorblind,
+}
+
+impl Theme {
+    /// All the themes; the first one is the default
+    pub const ALL: [Theme; 3] = [Theme::Light, Theme::Dark, Theme::Colorblind];
+
+    /// Returns the key of the name of the theme in `locale`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Light      => THEME_LIGHT,
+            Theme::Dark       => THEME_DARK,
+            Theme::Colorblind => THEME_COLORBLIND,
+        }
+    }
+
+    /// Returns the colors of the theme
+    pub fn palette(&self) -> Palette {
+        match self {
+            Theme::Light => Palette {
+                background:  [1.00, 1.00, 1.00],
+                text:        [0.10, 0.10, 0.10],
+                surface:     [0.90, 0.90, 0.92],
+                border:      [0.55, 0.55, 0.58],
+                placeholder: [0.60, 0.60, 0.60],
+                correct:     [0.36, 0.72, 0.36],
+                incorrect:   [0.85, 0.33, 0.31],
+            },
+            Theme::Dark => Palette {
+                background:  [0.12, 0.12, 0.14],
+                text:        [0.88, 0.88, 0.88],
+                surface:     [0.22, 0.22, 0.25],
+                border:      [0.40, 0.40, 0.44],
+                placeholder: [0.50, 0.50, 0.52],
+                correct:     [0.30, 0.60, 0.35],
+                incorrect:   [0.70, 0.30, 0.30],
+            },
+            // The blue and orange of the Okabe-Ito palette
+            Theme::Colorblind => Palette {
+                correct:   [0.00, 0.45, 0.70],
+                incorrect: [0.90, 0.62, 0.00],
+                ..Theme::Light.palette()
+            },
+        }
+    }
+
+    /// Returns the index of the theme in `ALL`
+    fn index(&self) -> usize {
+        Self::ALL.iter().position(|theme| theme == self).unwrap_or(0)
+    }
+}
+
+/// Returns the theme the user interface is currently shown in
+pub fn theme() -> Theme {
+    Theme::ALL[CURRENT.load(Ordering::Relaxed)]
+}
+
+/// Switches the user interface to `theme`
+pub fn set_theme(theme: Theme) {
+    CURRENT.store(theme.index(), Ordering::Relaxed);
+}
+
+/// Returns the colors of the current theme
+pub fn palette() -> Palette {
+    theme().palette()
+}